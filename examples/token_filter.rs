@@ -1,6 +1,9 @@
 use cainam_core::{
     birdeye::api::BirdeyeClient,
-    config::mongodb::{MongoConfig, MongoDbPool},
+    config::{
+        mongodb::{MongoConfig, MongoDbPool},
+        ConfigFile,
+    },
     services::token_filter::TokenFilterService,
 };
 use anyhow::Result;
@@ -17,6 +20,7 @@ const SLEEP_DURATION: Duration = Duration::from_secs(300); // 5 minutes between
 async fn main() -> Result<()> {
     // Load environment variables from .env file
     dotenv().ok();
+    ConfigFile::load(None, None)?.apply();
 
     // Initialize detailed logging
     tracing_subscriber::fmt()
@@ -34,7 +38,7 @@ async fn main() -> Result<()> {
 
     // Initialize MongoDB
     info!("Initializing MongoDB connection...");
-    let mongo_config = MongoConfig::from_env();
+    let mongo_config = MongoConfig::from_env()?;
     let db_pool = match MongoDbPool::create_pool(mongo_config).await {
         Ok(pool) => {
             info!("Successfully connected to MongoDB");
@@ -4,7 +4,7 @@ use cainam_core::{
     cli,
     config::{
         mongodb::{MongoConfig, MongoDbPool, MongoPoolConfig},
-        AgentConfig,
+        AgentConfig, ConfigFile,
     },
     services::token_filter::TokenFilterService,
 };
@@ -15,7 +15,7 @@ use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
-use mongodb::bson::doc;
+use mongodb::bson::{doc, Document};
 
 const SLEEP_DURATION: Duration = Duration::from_secs(180); // 3 minutes
 
@@ -67,6 +67,7 @@ async fn main() -> Result<()> {
 
     // Load environment variables
     dotenv().ok();
+    ConfigFile::load(None, None)?.apply();
 
     // Clear screen and show welcome message
     cli::clear_screen();
@@ -98,7 +99,7 @@ async fn main() -> Result<()> {
     let openai_api_key = std::env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set");
     let filter_service = TokenFilterService::new(
         birdeye.clone(),
-        db_pool,
+        db_pool.clone(),
         &openai_api_key,
     );
     progress.finish_with_message("✓ Filter service ready");
@@ -155,8 +156,9 @@ async fn main() -> Result<()> {
                     println!("Score: {}", format!("{:.2}", token.score).cyan());
                     
                     // Get full token recommendation with reasoning from DB
-                    match db_pool.get_database().unwrap().collection::<Document>("token_recommendations")
-                        .find_one(doc! { "token_address": &token.address }, None)
+                    match db_pool.database(&db_pool.get_config().database).collection::<Document>("token_recommendations")
+                        .find_one(doc! { "token_address": &token.address })
+                        .sort(doc! { "analysis_date": -1 })
                         .await
                     {
                         Ok(Some(doc)) => {
@@ -165,7 +167,7 @@ async fn main() -> Result<()> {
                                 if !kol_ownership.is_empty() {
                                     println!("\n{}", "KOL Ownership:".yellow().bold());
                                     for kol in kol_ownership {
-                                        if let Ok(kol_doc) = kol.as_document() {
+                                        if let Some(kol_doc) = kol.as_document() {
                                             println!("  • {} ({}) - Position: {}", 
                                                 kol_doc.get_str("name").unwrap_or("Unknown"),
                                                 kol_doc.get_str("wallet_address").unwrap_or("Unknown address"),
@@ -1,44 +0,0 @@
--- Enable pgvector extension
-CREATE EXTENSION IF NOT EXISTS vector;
-
--- Create documents table for vector store
-CREATE TABLE IF NOT EXISTS documents (
-    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
-    content TEXT NOT NULL,
-    metadata JSONB DEFAULT '{}',
-    embedding vector(1536), -- Using 1536 dimensions for OpenAI embeddings
-    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
-);
-
--- Create index for vector similarity search
-CREATE INDEX IF NOT EXISTS documents_embedding_idx ON documents 
-USING ivfflat (embedding vector_cosine_ops)
-WITH (lists = 100);
-
--- Create function to perform vector similarity search
-CREATE OR REPLACE FUNCTION vector_similarity_search(
-    query_embedding vector,
-    match_threshold float,
-    match_count int
-)
-RETURNS TABLE (
-    id UUID,
-    content TEXT,
-    metadata JSONB,
-    similarity float
-)
-LANGUAGE plpgsql
-AS $$
-BEGIN
-    RETURN QUERY
-    SELECT
-        d.id,
-        d.content,
-        d.metadata,
-        1 - (d.embedding <=> query_embedding) as similarity
-    FROM documents d
-    WHERE 1 - (d.embedding <=> query_embedding) > match_threshold
-    ORDER BY d.embedding <=> query_embedding
-    LIMIT match_count;
-END;
-$$;
\ No newline at end of file
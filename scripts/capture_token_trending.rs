@@ -1,7 +1,10 @@
 use anyhow::{Context, Result};
 use cainam_core::{
     birdeye::api::{BirdeyeApi, BirdeyeClient},
-    config::mongodb::{MongoConfig, MongoDbPool, MongoPoolConfig},
+    config::{
+        mongodb::{MongoConfig, MongoDbPool, MongoPoolConfig},
+        ConfigFile,
+    },
     models::token_trending::TrendingToken,
 };
 use dotenvy::dotenv;
@@ -25,6 +28,7 @@ async fn main() -> Result<()> {
 
     // Load environment variables
     dotenv().ok();
+    ConfigFile::load(None, None)?.apply();
 
     // Get MongoDB connection details
     let mongodb_uri = dotenvy::var("MONGODB_URI").context("MONGODB_URI must be set")?;
@@ -1,24 +1,31 @@
-use crate::SolanaAgentKit;
+use super::{api_key, check_status};
+use crate::trading::SolanaAgentKit;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct HeliusWebhookResponse {
+    #[serde(rename = "webhookURL")]
     pub webhook_url: String,
+    #[serde(rename = "webhookID")]
     pub webhook_id: String,
 }
 
+/// Creates an enhanced Helius webhook watching the given accounts.
+///
+/// # Arguments
+/// * `agent` - An instance of SolanaAgentKit (with a Helius API key)
+/// * `account_addresses` - Accounts whose transactions trigger the webhook
+/// * `webhook_url` - URL Helius posts the transactions to
 pub async fn create_webhook(
     agent: &SolanaAgentKit,
     account_addresses: Vec<String>,
     webhook_url: String,
-) -> Result<HeliusWebhookResponse, Box<dyn std::error::Error>> {
-    // Get the Helius API key from the agent's configuration
-    let api_key = match agent.config.helius_api_key.as_ref() {
-        Some(key) => key,
-        None => return Err("Missing Helius API key in agent.config.HELIUS_API_KEY".into()),
-    };
-
-    let url = format!("https://api.helius.xyz/v0/webhooks?api-key={}", api_key);
+) -> Result<HeliusWebhookResponse> {
+    let url = format!(
+        "https://api.helius.xyz/v0/webhooks?api-key={}",
+        api_key(agent)?
+    );
 
     let body = serde_json::json!({
         "webhookURL": webhook_url,
@@ -29,11 +36,15 @@ pub async fn create_webhook(
     });
 
     let client = reqwest::Client::new();
-    let response = client.post(url).header("Content-Type", "application/json").json(&body).send().await?;
-
-    let data = response.json::<serde_json::Value>().await?;
-    let webhook_url = data.get("webhookURL").expect("webhookURL field").as_str().expect("webhookURL text");
-    let webhook_id = data.get("webhookID").expect("webhookID field").as_str().expect("webhookID text");
+    let response = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await?;
 
-    Ok(HeliusWebhookResponse { webhook_url: webhook_url.to_string(), webhook_id: webhook_id.to_string() })
+    check_status(response, "create webhook")?
+        .json::<HeliusWebhookResponse>()
+        .await
+        .context("Unexpected create webhook response")
 }
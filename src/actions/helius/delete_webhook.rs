@@ -1,54 +1,41 @@
-use crate::SolanaAgentKit;
+use super::{api_key, check_status};
+use crate::trading::SolanaAgentKit;
+use anyhow::Result;
+use reqwest::StatusCode;
 
 /// Deletes a Helius Webhook by its ID.
 ///
 /// # Arguments
-/// * `agent` - An instance of SolanaAgentKit (with .config.HELIUS_API_KEY)
+/// * `agent` - An instance of SolanaAgentKit (with a Helius API key)
 /// * `webhook_id` - The unique ID of the webhook to delete
 ///
 /// # Returns
 /// The response body from the Helius API (which may contain status or other info)
-pub async fn delete_webhook(
-    agent: &SolanaAgentKit,
-    webhook_id: &str,
-) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-    // Get the Helius API key from the agent's configuration
-    let api_key = match agent.config.helius_api_key.as_ref() {
-        Some(key) => key,
-        None => return Err("Missing Helius API key in agent.config.HELIUS_API_KEY".into()),
-    };
+pub async fn delete_webhook(agent: &SolanaAgentKit, webhook_id: &str) -> Result<serde_json::Value> {
+    let url = format!(
+        "https://api.helius.xyz/v0/webhooks/{}?api-key={}",
+        webhook_id,
+        api_key(agent)?
+    );
 
-    // Construct the URL for the DELETE request
-    let url = format!("https://api.helius.xyz/v0/webhooks/{}?api-key={}", webhook_id, api_key);
-
-    // Create an HTTP client
     let client = reqwest::Client::new();
-
-    // Send the DELETE request
-    let response = client.delete(&url).header("Content-Type", "application/json").send().await?;
-
-    // Check if the request was successful
-    if !response.status().is_success() {
-        return Err(format!(
-            "Failed to delete webhook: {} {}",
-            response.status(),
-            response.status().canonical_reason().unwrap_or("Unknown")
-        )
-        .into());
+    let response = client
+        .delete(&url)
+        .header("Content-Type", "application/json")
+        .send()
+        .await?;
+    let response = check_status(response, "delete webhook")?;
+
+    if response.status() == StatusCode::NO_CONTENT {
+        return Ok(
+            serde_json::json!({"message": "Webhook deleted successfully (no content returned)"}),
+        );
     }
 
-    // Handle different response status codes
-    if response.status().as_u16() == 204 {
-        return Ok(serde_json::json!({"message": "Webhook deleted successfully (no content returned)"}));
-    }
-
-    // Check if the response body is empty
-    let content_length = response.headers().get("Content-Length");
-    if content_length.is_none() || content_length.expect("HeaderValue").to_str()? == "0" {
+    // Helius may answer with an empty body
+    let body = response.text().await?;
+    if body.trim().is_empty() {
         return Ok(serde_json::json!({"message": "Webhook deleted successfully (empty body)"}));
     }
-
-    // Parse the response body as JSON
-    let data: serde_json::Value = response.json().await?;
-    Ok(data)
+    Ok(serde_json::from_str(&body)?)
 }
@@ -1,45 +1,51 @@
-use crate::SolanaAgentKit;
+use super::{api_key, check_status};
+use crate::trading::SolanaAgentKit;
+use anyhow::{anyhow, Result};
 use serde_json::json;
 
+/// Lists the assets (NFTs and fungible tokens) held by a wallet through the
+/// Helius DAS API.
+///
+/// # Arguments
+/// * `agent` - An instance of SolanaAgentKit (with a Helius API key)
+/// * `owner_public_key` - Wallet whose assets are listed
+/// * `limit` - Maximum number of assets to return
 pub async fn get_assets_by_owner(
     agent: &SolanaAgentKit,
     owner_public_key: &str,
     limit: u32,
-) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-    // Get the Helius API key from the agent's configuration
-    let api_key = match agent.config.helius_api_key.as_ref() {
-        Some(key) => key,
-        None => return Err("Missing Helius API key in agent.config.HELIUS_API_KEY".into()),
-    };
-
-    let url = format!("https://mainnet.helius-rpc.com/?api-key={}", api_key);
-
-    let client = reqwest::Client::new();
+) -> Result<serde_json::Value> {
+    let url = format!(
+        "https://mainnet.helius-rpc.com/?api-key={}",
+        api_key(agent)?
+    );
 
     let request_body = json!({
         "jsonrpc": "2.0",
         "id": "get-assets",
         "method": "getAssetsByOwner",
-        "params": json!({
+        "params": {
             "ownerAddress": owner_public_key,
-            "page": 3,
+            "page": 1,
             "limit": limit,
             "displayOptions": { "showFungible": true },
-        }),
+        },
     });
 
-    let response = client.post(&url).header("Content-Type", "application/json").json(&request_body).send().await?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "Failed to fetch: {} - {}",
-            response.status(),
-            response.status().canonical_reason().unwrap_or("Unknown")
-        )
-        .into());
+    let client = reqwest::Client::new();
+    let response = client
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .json(&request_body)
+        .send()
+        .await?;
+
+    let mut data: serde_json::Value = check_status(response, "fetch assets")?.json().await?;
+    if let Some(error) = data.get("error") {
+        return Err(anyhow!("getAssetsByOwner failed: {}", error));
     }
-
-    let data: serde_json::Value = response.json().await?;
-
-    Ok(data)
+    Ok(data
+        .get_mut("result")
+        .map(serde_json::Value::take)
+        .unwrap_or(data))
 }
@@ -1,9 +1,13 @@
-use crate::SolanaAgentKit;
+use super::{api_key, check_status};
+use crate::trading::SolanaAgentKit;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct HeliusWebhookIdResponse {
     pub wallet: String,
+    #[serde(rename = "webhookURL")]
     pub webhook_url: String,
     pub transaction_types: Vec<String>,
     pub account_addresses: Vec<String>,
@@ -13,26 +17,30 @@ pub struct HeliusWebhookIdResponse {
 /// Retrieves a Helius Webhook by ID, returning only the specified fields.
 ///
 /// # Arguments
-/// * `agent` - An instance of SolanaAgentKit (with .config.HELIUS_API_KEY)
-/// * `webhook_id` - The unique ID of the webhook to delete
+/// * `agent` - An instance of SolanaAgentKit (with a Helius API key)
+/// * `webhook_id` - The unique ID of the webhook to fetch
 ///
 /// # Returns
 /// A HeliusWebhook object containing { wallet, webhookURL, transactionTypes, accountAddresses, webhookType }
 pub async fn get_webhook(
     agent: &SolanaAgentKit,
     webhook_id: &str,
-) -> Result<HeliusWebhookIdResponse, Box<dyn std::error::Error>> {
-    // Get the Helius API key from the agent's configuration
-    let api_key = match agent.config.helius_api_key.as_ref() {
-        Some(key) => key,
-        None => return Err("Missing Helius API key in agent.config.HELIUS_API_KEY".into()),
-    };
+) -> Result<HeliusWebhookIdResponse> {
+    let url = format!(
+        "https://api.helius.xyz/v0/webhooks/{}?api-key={}",
+        webhook_id,
+        api_key(agent)?
+    );
 
     let client = reqwest::Client::new();
-    let url = format!("https://api.helius.xyz/v0/webhooks/{}?api-key={}", webhook_id, api_key);
+    let response = client
+        .get(url)
+        .header("Content-Type", "application/json")
+        .send()
+        .await?;
 
-    let response = client.get(url).header("Content-Type", "application/json").send().await?;
-
-    let data = response.json::<HeliusWebhookIdResponse>().await?;
-    Ok(data)
+    check_status(response, "get webhook")?
+        .json::<HeliusWebhookIdResponse>()
+        .await
+        .context("Unexpected webhook response")
 }
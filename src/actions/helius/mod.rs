@@ -1,3 +1,6 @@
+use crate::trading::SolanaAgentKit;
+use anyhow::{anyhow, Result};
+
 mod create_webhook;
 pub use create_webhook::{create_webhook, HeliusWebhookResponse};
 
@@ -12,3 +15,47 @@ pub use transaction_parsing::transaction_parse;
 
 mod get_assets_by_owner;
 pub use get_assets_by_owner::get_assets_by_owner;
+
+/// Helius API key from the agent's configuration
+fn api_key(agent: &SolanaAgentKit) -> Result<&str> {
+    agent
+        .config
+        .helius_api_key
+        .as_deref()
+        .ok_or_else(|| anyhow!("Missing Helius API key, set HELIUS_API_KEY"))
+}
+
+/// Error out on a non-success response
+fn check_status(response: reqwest::Response, action: &str) -> Result<reqwest::Response> {
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow!(
+            "Failed to {}: {} {}",
+            action,
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("Unknown")
+        ));
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SolanaConfig;
+    use solana_sdk::signature::Keypair;
+
+    #[tokio::test]
+    async fn test_missing_api_key() {
+        let agent = SolanaAgentKit::new("http://localhost:8899", Keypair::new());
+        assert!(api_key(&agent).is_err());
+        assert!(get_webhook(&agent, "id").await.is_err());
+        assert!(get_assets_by_owner(&agent, "owner", 10).await.is_err());
+
+        let agent = agent.with_config(SolanaConfig {
+            helius_api_key: Some("key".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(api_key(&agent).unwrap(), "key");
+    }
+}
@@ -1,20 +1,12 @@
-use crate::SolanaAgentKit;
-use serde::{Deserialize, Serialize};
+use super::{api_key, check_status};
+use crate::trading::SolanaAgentKit;
+use anyhow::Result;
 use serde_json::json;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HeliusWebhookIdResponse {
-    pub wallet: String,
-    pub webhook_url: String,
-    pub transaction_types: Vec<String>,
-    pub account_addresses: Vec<String>,
-    pub webhook_type: String,
-}
-
 /// Parse a Solana transaction using the Helius Enhanced Transactions API
 ///
 /// # Arguments
-/// * `agent` - An instance of SolanaAgentKit (with .config.HELIUS_API_KEY)
+/// * `agent` - An instance of SolanaAgentKit (with a Helius API key)
 /// * `transaction_id` - The transaction ID to parse
 ///
 /// # Returns
@@ -22,22 +14,23 @@ pub struct HeliusWebhookIdResponse {
 pub async fn transaction_parse(
     agent: &SolanaAgentKit,
     transaction_id: &str,
-) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-    // Get the Helius API key from the agent's configuration
-    let api_key = match agent.config.helius_api_key.as_ref() {
-        Some(key) => key,
-        None => return Err("Missing Helius API key in agent.config.HELIUS_API_KEY".into()),
-    };
+) -> Result<serde_json::Value> {
+    let url = format!(
+        "https://api.helius.xyz/v0/transactions/?api-key={}",
+        api_key(agent)?
+    );
 
-    let client = reqwest::Client::new();
-    let url = format!("https://api.helius.xyz/v0/transactions/?api-key={}", api_key);
-
-    let body = json!( {
-        "transactions": vec![transaction_id.to_string()],
+    let body = json!({
+        "transactions": [transaction_id],
     });
 
-    let response = client.post(url).header("Content-Type", "application/json").json(&body).send().await?;
+    let client = reqwest::Client::new();
+    let response = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .json(&body)
+        .send()
+        .await?;
 
-    let data = response.json().await?;
-    Ok(data)
+    Ok(check_status(response, "parse transaction")?.json().await?)
 }
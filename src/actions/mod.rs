@@ -0,0 +1,8 @@
+//! On-chain actions of the agent's wallet.
+//!
+//! Each action takes a [`SolanaAgentKit`](crate::trading::SolanaAgentKit) for
+//! its RPC connection, wallet and settings. The Helius actions need
+//! `HELIUS_API_KEY`.
+
+pub mod helius;
+pub mod solana;
@@ -1,34 +1,13 @@
-use crate::{primitives::USDC, SolanaAgentKit};
-use anyhow::{anyhow, Context, Result};
+use super::{ParsedTokenAccount, USDC};
+use crate::trading::SolanaAgentKit;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use solana_client::rpc_request::TokenAccountsFilter;
-use solana_sdk::{instruction::Instruction, pubkey::Pubkey, transaction::Transaction};
-use spl_token::instruction::close_account;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
 use std::str::FromStr;
 
-use serde::{Deserialize, Serialize};
-
-#[derive(serde::Deserialize)]
-pub struct Parsed {
-    pub info: SplToken,
-}
-
-#[derive(serde::Deserialize)]
-pub struct SplToken {
-    pub mint: String,
-    #[serde(rename(deserialize = "tokenAmount"))]
-    pub token_amount: Amount,
-}
-
-#[allow(dead_code)]
-#[derive(serde::Deserialize)]
-pub struct Amount {
-    pub amount: String,
-    #[serde(rename(deserialize = "uiAmountString"))]
-    ui_amount_string: String,
-    #[serde(rename(deserialize = "uiAmount"))]
-    pub ui_amount: f64,
-    pub decimals: u8,
-}
+/// Most accounts closed in one transaction
+const MAX_INSTRUCTIONS: usize = 40;
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct CloseEmptyTokenAccountsData {
@@ -45,7 +24,8 @@ impl CloseEmptyTokenAccountsData {
     }
 }
 
-/// Close Empty SPL Token accounts of the agent.
+/// Close empty SPL Token and Token-2022 accounts of the agent, returning
+/// their rent to the wallet. The USDC account is kept open.
 ///
 /// # Parameters
 ///
@@ -53,74 +33,116 @@ impl CloseEmptyTokenAccountsData {
 ///
 /// # Returns
 ///
-/// Transaction signature and total number of accounts closed or an error if the account doesn't exist.
+/// Transaction signature and number of accounts closed. Both are empty when
+/// there is nothing to close.
 pub async fn close_empty_token_accounts(
     agent: &SolanaAgentKit,
 ) -> Result<CloseEmptyTokenAccountsData> {
-    let max_instructions = 40_u32;
-    let mut transaction: Vec<Instruction> = vec![];
-    let mut closed_size = 0;
-    let token_programs = vec![spl_token::ID, spl_token_2022::ID];
+    let owner = agent.wallet.address;
+    let mut instructions: Vec<Instruction> = vec![];
 
-    for token_program in token_programs {
+    for token_program in [spl_token::ID, spl_token_2022::ID] {
         let accounts = agent
             .connection
-            .get_token_accounts_by_owner(
-                &agent.wallet.address,
-                TokenAccountsFilter::ProgramId(token_program.to_owned()),
-            )
+            .get_token_accounts_by_owner(&owner, TokenAccountsFilter::ProgramId(token_program))
+            .await
             .context("Failed to get token accounts by owner")?;
 
-        closed_size += accounts.len();
-
-        for account in accounts {
-            if transaction.len() >= max_instructions as usize {
+        for account in &accounts {
+            if instructions.len() >= MAX_INSTRUCTIONS {
                 break;
             }
 
-            if let solana_account_decoder::UiAccountData::Json(d) = &account.account.data {
-                let parsed = serde_json::from_value::<Parsed>(d.parsed.clone())
-                    .context("Failed to parse token account data")?;
-
-                if parsed.info.token_amount.amount.parse::<u32>().unwrap_or(0) == 0_u32
-                    && parsed.info.mint != USDC
-                {
-                    let account_pubkey = Pubkey::from_str(&account.pubkey)
-                        .context("Failed to parse account pubkey")?;
-
-                    let instruct = close_account(
-                        &token_program,
-                        &account_pubkey,
-                        &agent.wallet.address,
-                        &agent.wallet.address,
-                        &[&agent.wallet.address],
-                    )
-                    .context("Failed to create close_account instruction")?;
-                    transaction.push(instruct);
-                }
+            let parsed = ParsedTokenAccount::from_keyed(account)?;
+            if parsed.info.token_amount.amount.parse::<u64>().unwrap_or(0) != 0
+                || parsed.info.mint == USDC
+            {
+                continue;
             }
+
+            let account_pubkey =
+                Pubkey::from_str(&account.pubkey).context("Failed to parse account pubkey")?;
+            // The 2022 builder accepts accounts of both token programs
+            let instruction = spl_token_2022::instruction::close_account(
+                &token_program,
+                &account_pubkey,
+                &owner,
+                &owner,
+                &[],
+            )
+            .context("Failed to create close_account instruction")?;
+            instructions.push(instruction);
         }
     }
 
-    if transaction.is_empty() {
+    if instructions.is_empty() {
         return Ok(CloseEmptyTokenAccountsData::default());
     }
 
-    let recent_blockhash = agent
-        .connection
-        .get_latest_blockhash()
-        .context("Failed to get latest blockhash")?;
-    let transaction = Transaction::new_signed_with_payer(
-        &transaction,
-        Some(&agent.wallet.address),
-        &[&agent.wallet.wallet],
-        recent_blockhash,
-    );
+    let outcome = agent
+        .transactions()
+        .send_and_confirm(&instructions, &[])
+        .await
+        .context("Failed to close token accounts")?;
+    Ok(CloseEmptyTokenAccountsData::new(
+        outcome.signature.to_string(),
+        instructions.len(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::solana::test_utils::token_accounts;
+    use crate::config::SolanaConfig;
+    use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
+    use solana_rpc_client::mock_sender::MocksMap;
+    use solana_sdk::signature::Keypair;
+
+    #[tokio::test]
+    async fn test_closes_only_empty_non_usdc_accounts() {
+        let mint = Pubkey::new_unique().to_string();
+        // SPL Token accounts first, then Token-2022 accounts
+        let mocks = MocksMap::from_iter([
+            (
+                RpcRequest::GetTokenAccountsByOwner,
+                token_accounts(&[
+                    (&Pubkey::new_unique().to_string(), &mint, 0, 0.0),
+                    (&Pubkey::new_unique().to_string(), &mint, 10, 0.00001),
+                    (&Pubkey::new_unique().to_string(), USDC, 0, 0.0),
+                ]),
+            ),
+            (
+                RpcRequest::GetTokenAccountsByOwner,
+                token_accounts(&[(&Pubkey::new_unique().to_string(), &mint, 0, 0.0)]),
+            ),
+        ]);
+        let agent = SolanaAgentKit::with_client(
+            RpcClient::new_mock_with_mocks_map("succeeds", mocks),
+            Keypair::new(),
+            SolanaConfig::default(),
+        );
+
+        let data = close_empty_token_accounts(&agent).await.unwrap();
+        assert_eq!(data.closed_size, 2);
+        assert!(!data.signature.is_empty());
+    }
 
-    let signature = agent
-        .connection
-        .send_and_confirm_transaction(&transaction)
-        .context("Failed to send and confirm transaction")?;
-    let data = CloseEmptyTokenAccountsData::new(signature.to_string(), closed_size);
-    Ok(data)
+    #[tokio::test]
+    async fn test_nothing_to_close() {
+        let empty = token_accounts(&[]);
+        let mocks = MocksMap::from_iter([
+            (RpcRequest::GetTokenAccountsByOwner, empty.clone()),
+            (RpcRequest::GetTokenAccountsByOwner, empty),
+        ]);
+        let agent = SolanaAgentKit::with_client(
+            RpcClient::new_mock_with_mocks_map("succeeds", mocks),
+            Keypair::new(),
+            SolanaConfig::default(),
+        );
+
+        let data = close_empty_token_accounts(&agent).await.unwrap();
+        assert_eq!(data.closed_size, 0);
+        assert!(data.signature.is_empty());
+    }
 }
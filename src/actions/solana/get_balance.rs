@@ -1,6 +1,7 @@
-use crate::SolanaAgentKit;
+use super::ParsedTokenAccount;
+use crate::trading::SolanaAgentKit;
 use anyhow::{Context, Result};
-use solana_client::client_error::ClientError;
+use solana_client::rpc_request::TokenAccountsFilter;
 use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
 use std::str::FromStr;
 
@@ -9,53 +10,78 @@ use std::str::FromStr;
 /// # Parameters
 ///
 /// - `agent`: An instance of `SolanaAgentKit`.
-/// - `token_address`: An optional SPL token mint address. If not provided, returns the SOL balance.
+/// - `token_address`: An optional SPL or Token-2022 mint address. If not provided, returns the SOL balance.
 ///
 /// # Returns
 ///
-/// A `Result` that resolves to the balance as a number (in UI units) or an error if the account doesn't exist.
-pub async fn get_balance(agent: &SolanaAgentKit, token_address: Option<String>) -> Result<f64, ClientError> {
+/// The balance in UI units, summed over all of the wallet's accounts for the mint.
+pub async fn get_balance(agent: &SolanaAgentKit, token_address: Option<&str>) -> Result<f64> {
     if let Some(token_address) = token_address {
-        // Get SPL token account balance
-        let pubkey = Pubkey::from_str(&token_address).map_err(|e| {
-            ClientError::from(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!("Invalid token address: {}: {}", token_address, e),
-            ))
-        })?;
-
-        let token_account = agent.connection.get_token_account_balance(&pubkey)?;
-        let ui_amount = token_account.ui_amount.unwrap_or(0.0);
-        return Ok(ui_amount);
+        let mint = Pubkey::from_str(token_address)
+            .with_context(|| format!("Invalid token address: {}", token_address))?;
+
+        let accounts = agent
+            .connection
+            .get_token_accounts_by_owner(&agent.wallet.address, TokenAccountsFilter::Mint(mint))
+            .await
+            .context("Failed to get token accounts by owner")?;
+
+        let mut balance = 0.0;
+        for account in &accounts {
+            let parsed = ParsedTokenAccount::from_keyed(account)?;
+            balance += parsed.info.token_amount.ui_amount.unwrap_or_default();
+        }
+        return Ok(balance);
     }
 
     // Get SOL balance
-    let balance = agent.connection.get_balance(&agent.wallet.address)?;
-    Ok(balance as f64 / LAMPORTS_PER_SOL as f64)
+    let lamports = agent
+        .connection
+        .get_balance(&agent.wallet.address)
+        .await
+        .context("Failed to get SOL balance")?;
+    Ok(lamports as f64 / LAMPORTS_PER_SOL as f64)
 }
 
-
-// Integration Tests:
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::actions::solana::test_utils::token_accounts;
+    use crate::config::SolanaConfig;
+    use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
     use solana_sdk::signature::Keypair;
+    use std::collections::HashMap;
 
     #[tokio::test]
-    async fn test_get_sol_balance() -> Result<()> {
-        // Replace with a testnet/devnet RPC URL and a wallet with known balance
-        let rpc_url = "https://api.devnet.solana.com";
-        let wallet = Keypair::new(); // Create a new keypair for testing
-        let wallet_address = wallet.pubkey().to_string();
-        Ok(())
+    async fn test_get_sol_balance() {
+        // The mock RPC reports 50 lamports
+        let agent = SolanaAgentKit::with_client(
+            RpcClient::new_mock("succeeds".to_string()),
+            Keypair::new(),
+            SolanaConfig::default(),
+        );
+        let balance = get_balance(&agent, None).await.unwrap();
+        assert_eq!(balance, 50.0 / LAMPORTS_PER_SOL as f64);
     }
 
     #[tokio::test]
-    async fn test_get_spl_token_balance() -> Result<()> {
-        // Replace with a testnet/devnet RPC URL and a token with known balance
-        let rpc_url = "https://api.devnet.solana.com";
-        let wallet_address = "A5GecEJ";
-        let token_address = "So11111111111111111111111111111111111111112";
-        Ok(())
+    async fn test_get_spl_token_balance_sums_accounts() {
+        let mint = Pubkey::new_unique().to_string();
+        let mocks = HashMap::from([(
+            RpcRequest::GetTokenAccountsByOwner,
+            token_accounts(&[
+                (&Pubkey::new_unique().to_string(), &mint, 1_500_000, 1.5),
+                (&Pubkey::new_unique().to_string(), &mint, 250_000, 0.25),
+            ]),
+        )]);
+        let agent = SolanaAgentKit::with_client(
+            RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks),
+            Keypair::new(),
+            SolanaConfig::default(),
+        );
+
+        let balance = get_balance(&agent, Some(&mint)).await.unwrap();
+        assert_eq!(balance, 1.75);
+        assert!(get_balance(&agent, Some("not-a-mint")).await.is_err());
     }
 }
@@ -1,5 +1,5 @@
-use crate::SolanaAgentKit;
-use solana_client::client_error::ClientError;
+use crate::trading::SolanaAgentKit;
+use anyhow::{Context, Result};
 
 /// Gets the transactions per second (TPS) from the Solana network.
 ///
@@ -9,22 +9,37 @@ use solana_client::client_error::ClientError;
 ///
 /// # Returns
 ///
-/// A `Result` containing the TPS as a `f64`, or an error if fetching performance samples fails.
-pub async fn get_tps(agent: &SolanaAgentKit) -> Result<f64, ClientError> {
-    // Fetch recent performance samples
-    let limit = 1;
-    let perf_samples = agent.connection.get_recent_performance_samples(Some(limit))?;
+/// The TPS of the most recent performance sample, or 0 when the node has none.
+pub async fn get_tps(agent: &SolanaAgentKit) -> Result<f64> {
+    let perf_samples = agent
+        .connection
+        .get_recent_performance_samples(Some(1))
+        .await
+        .context("Failed to get performance samples")?;
 
-    // Check if there are any samples available
-    if !perf_samples.is_empty() {
-        // Calculate TPS
-        let num_transactions = perf_samples[0].num_transactions;
-        let sample_period_secs = perf_samples[0].sample_period_secs;
+    Ok(perf_samples
+        .first()
+        .filter(|sample| sample.sample_period_secs > 0)
+        .map(|sample| sample.num_transactions as f64 / sample.sample_period_secs as f64)
+        .unwrap_or(0.0))
+}
 
-        let tps = num_transactions as f64 / sample_period_secs as f64;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SolanaConfig;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_sdk::signature::Keypair;
 
-        return Ok(tps);
+    #[tokio::test]
+    async fn test_get_tps() {
+        // The mock sample has 125 transactions over 60 seconds
+        let agent = SolanaAgentKit::with_client(
+            RpcClient::new_mock("succeeds".to_string()),
+            Keypair::new(),
+            SolanaConfig::default(),
+        );
+        let tps = get_tps(&agent).await.unwrap();
+        assert!((tps - 125.0 / 60.0).abs() < 1e-9);
     }
-
-    Ok(0.0)
 }
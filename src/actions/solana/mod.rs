@@ -12,3 +12,87 @@ pub use get_tps::get_tps;
 
 mod transfer;
 pub use transfer::transfer;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use solana_account_decoder::UiAccountData;
+use solana_client::rpc_response::RpcKeyedAccount;
+
+/// USDC mint, whose empty account is kept open
+pub const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+/// `jsonParsed` data of a token account
+#[derive(Deserialize)]
+pub(crate) struct ParsedTokenAccount {
+    pub info: TokenAccountInfo,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TokenAccountInfo {
+    pub mint: String,
+    #[serde(rename = "tokenAmount")]
+    pub token_amount: TokenAmount,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TokenAmount {
+    /// Raw amount in base units
+    pub amount: String,
+    #[serde(rename = "uiAmount")]
+    pub ui_amount: Option<f64>,
+}
+
+impl ParsedTokenAccount {
+    pub fn from_keyed(account: &RpcKeyedAccount) -> Result<Self> {
+        match &account.account.data {
+            UiAccountData::Json(data) => serde_json::from_value(data.parsed.clone())
+                .with_context(|| format!("Failed to parse token account {}", account.pubkey)),
+            _ => Err(anyhow!(
+                "Token account {} was not returned as parsed JSON",
+                account.pubkey
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use serde_json::{json, Value};
+
+    /// `getTokenAccountsByOwner` response with one parsed account per
+    /// `(pubkey, mint, raw amount, ui amount)`
+    pub fn token_accounts(accounts: &[(&str, &str, u64, f64)]) -> Value {
+        let value: Vec<_> = accounts
+            .iter()
+            .map(|(pubkey, mint, amount, ui_amount)| {
+                json!({
+                    "pubkey": pubkey,
+                    "account": {
+                        "lamports": 2_039_280,
+                        "owner": spl_token::ID.to_string(),
+                        "executable": false,
+                        "rentEpoch": 0,
+                        "space": 165,
+                        "data": {
+                            "program": "spl-token",
+                            "space": 165,
+                            "parsed": {
+                                "type": "account",
+                                "info": {
+                                    "mint": mint,
+                                    "tokenAmount": {
+                                        "amount": amount.to_string(),
+                                        "decimals": 6,
+                                        "uiAmount": ui_amount,
+                                        "uiAmountString": ui_amount.to_string(),
+                                    },
+                                },
+                            },
+                        },
+                    },
+                })
+            })
+            .collect();
+        json!({ "context": { "slot": 1 }, "value": value })
+    }
+}
@@ -1,7 +1,10 @@
-use crate::SolanaAgentKit;
-use solana_client::client_error::ClientError;
+use crate::trading::SolanaAgentKit;
+use anyhow::{Context, Result};
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
 
+/// SOL requested from the faucet
+const FAUCET_SOL: u64 = 5;
+
 /// Requests SOL from the Solana faucet (devnet/testnet only).
 ///
 /// # Parameters
@@ -10,17 +13,42 @@ use solana_sdk::native_token::LAMPORTS_PER_SOL;
 ///
 /// # Returns
 ///
-/// A transaction signature as a `String`.
+/// The airdrop transaction signature once it is confirmed.
 ///
 /// # Errors
 ///
 /// Returns an error if the request fails or times out.
-pub async fn request_faucet_funds(agent: &SolanaAgentKit) -> Result<String, ClientError> {
-    // Request airdrop of 5 SOL (5 * LAMPORTS_PER_SOL)
-    let tx = agent.connection.request_airdrop(&agent.wallet.address, 5 * LAMPORTS_PER_SOL)?;
+pub async fn request_faucet_funds(agent: &SolanaAgentKit) -> Result<String> {
+    let signature = agent
+        .connection
+        .request_airdrop(&agent.wallet.address, FAUCET_SOL * LAMPORTS_PER_SOL)
+        .await
+        .context("Failed to request airdrop")?;
+
+    agent
+        .connection
+        .poll_for_signature_with_commitment(&signature, agent.config.transactions.commitment)
+        .await
+        .with_context(|| format!("Airdrop {} was not confirmed", signature))?;
+
+    Ok(signature.to_string())
+}
 
-    // Confirm the transaction
-    agent.connection.confirm_transaction(&tx)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SolanaConfig;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_sdk::signature::{Keypair, Signature};
 
-    Ok(tx.to_string())
+    #[tokio::test]
+    async fn test_request_faucet_funds() {
+        let agent = SolanaAgentKit::with_client(
+            RpcClient::new_mock("succeeds".to_string()),
+            Keypair::new(),
+            SolanaConfig::default(),
+        );
+        let signature = request_faucet_funds(&agent).await.unwrap();
+        assert_eq!(signature, Signature::from([8; 64]).to_string());
+    }
 }
@@ -1,120 +1,182 @@
-use crate::SolanaAgentKit;
-use anyhow::{Context, Result};
-use solana_client::client_error::ClientError;
-use solana_sdk::{program_pack::Pack, pubkey::Pubkey, system_instruction, transaction::Transaction};
-use spl_associated_token_account::get_associated_token_address;
-use spl_token::{instruction::transfer as transfer_instruct, state::Mint};
+use crate::models::money::TokenAmount;
+use crate::trading::{mint_info, SolanaAgentKit};
+use anyhow::{anyhow, Context, Result};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
 use std::str::FromStr;
 
-/// Transfer SOL or SPL tokens to a recipient
+const SOL_DECIMALS: u8 = 9;
+
+/// Transfer SOL, SPL tokens or Token-2022 tokens to a recipient
 ///
 /// `agent` - SolanaAgentKit instance
 /// `to` - Recipient's public key
-/// `amount` - Amount to transfer
-/// `mint` - Optional mint address for SPL tokens
+/// `amount` - Amount to transfer in UI units (SOL or whole tokens)
+/// `mint` - Optional mint address for SPL or Token-2022 tokens
 ///
+/// The recipient's associated token account is created when missing.
 /// Returns the transaction signature.
 pub async fn transfer(
     agent: &SolanaAgentKit,
     to: &str,
-    amount: u64,
-    mint: Option<String>,
-) -> Result<String, ClientError> {
-    match mint {
-        Some(mint) => {
-            // Transfer SPL Token
-            let mint_pubkey = Pubkey::from_str(&mint).map_err(|e| {
-                ClientError::from(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!("Invalid mint address: {}: {}", mint, e),
-                ))
-            })?;
-            let to_pubkey = Pubkey::from_str(to).map_err(|e| {
-                ClientError::from(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!("Invalid recipient address: {}: {}", to, e),
-                ))
-            })?;
-
-            let from_ata = get_associated_token_address(&agent.wallet.address, &mint_pubkey);
-            let to_ata = get_associated_token_address(&to_pubkey, &mint_pubkey);
-
-            let account_info = agent.connection.get_account(&mint_pubkey).map_err(|e| {
-                ClientError::from(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("Failed to get account info for mint {}: {}", mint, e),
-                ))
-            })?;
-            let mint_info = Mint::unpack_from_slice(&account_info.data).map_err(|e| {
-                ClientError::from(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("Failed to unpack mint info for {}: {}", mint, e),
-                ))
-            })?;
-
-            let adjusted_amount = amount * 10u64.pow(mint_info.decimals as u32);
-
-            let transfer_instruction = transfer_instruct(
-                &spl_token::id(),
-                &from_ata,
-                &to_ata,
-                &agent.wallet.address,
-                &[&agent.wallet.address],
-                adjusted_amount,
-            )
-            .map_err(|e| {
-                ClientError::from(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("Failed to create transfer instruction: {}", e),
-                ))
-            })?;
-
-            let recent_blockhash = agent
-                .connection
-                .get_latest_blockhash()
-                .map_err(|e| ClientError::from(e))?;
-
-            let transaction = Transaction::new_signed_with_payer(
-                &[transfer_instruction],
-                Some(&agent.wallet.address),
-                &[&agent.wallet.wallet],
-                recent_blockhash,
-            );
-
-            let signature = agent
-                .connection
-                .send_and_confirm_transaction(&transaction)
-                .map_err(|e| ClientError::from(e))?;
-            Ok(signature.to_string())
-        }
-        None => {
-            // Transfer SOL
-            let to_pubkey = Pubkey::from_str(to).map_err(|e| {
-                ClientError::from(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!("Invalid recipient address: {}: {}", to, e),
-                ))
-            })?;
-            let transfer_instruction =
-                system_instruction::transfer(&agent.wallet.address, &to_pubkey, amount);
-
-            let recent_blockhash = agent
-                .connection
-                .get_latest_blockhash()
-                .map_err(|e| ClientError::from(e))?;
-
-            let transaction = Transaction::new_signed_with_payer(
-                &[transfer_instruction],
-                Some(&agent.wallet.address),
-                &[&agent.wallet.wallet],
-                recent_blockhash,
-            );
-
-            let signature = agent
-                .connection
-                .send_and_confirm_transaction(&transaction)
-                .map_err(|e| ClientError::from(e))?;
-            Ok(signature.to_string())
+    amount: f64,
+    mint: Option<&str>,
+) -> Result<String> {
+    let instructions = transfer_instructions(agent, to, amount, mint).await?;
+    let outcome = agent
+        .transactions()
+        .send_and_confirm(&instructions, &[])
+        .await
+        .context("Transfer failed")?;
+    Ok(outcome.signature.to_string())
+}
+
+async fn transfer_instructions(
+    agent: &SolanaAgentKit,
+    to: &str,
+    amount: f64,
+    mint: Option<&str>,
+) -> Result<Vec<Instruction>> {
+    if !(amount > 0.0 && amount.is_finite()) {
+        return Err(anyhow!("Transfer amount must be positive, got {}", amount));
+    }
+    let to_pubkey =
+        Pubkey::from_str(to).with_context(|| format!("Invalid recipient address: {}", to))?;
+    let from = agent.wallet.address;
+
+    let Some(mint) = mint else {
+        let lamports = TokenAmount::from_f64(amount, SOL_DECIMALS)?.raw();
+        return Ok(vec![solana_system_interface::instruction::transfer(
+            &from, &to_pubkey, lamports,
+        )]);
+    };
+
+    let mint_pubkey =
+        Pubkey::from_str(mint).with_context(|| format!("Invalid mint address: {}", mint))?;
+    let (token_program, decimals) = mint_info(&agent.connection, &mint_pubkey).await?;
+    let raw_amount = TokenAmount::from_f64(amount, decimals)?.raw();
+
+    let from_ata =
+        get_associated_token_address_with_program_id(&from, &mint_pubkey, &token_program);
+    let to_ata =
+        get_associated_token_address_with_program_id(&to_pubkey, &mint_pubkey, &token_program);
+
+    Ok(vec![
+        create_associated_token_account_idempotent(&from, &to_pubkey, &mint_pubkey, &token_program),
+        spl_token_2022::instruction::transfer_checked(
+            &token_program,
+            &from_ata,
+            &mint_pubkey,
+            &to_ata,
+            &from,
+            &[],
+            raw_amount,
+            decimals,
+        )
+        .context("Failed to create transfer instruction")?,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SolanaConfig;
+    use serde_json::json;
+    use solana_account_decoder::{encode_ui_account, UiAccountEncoding};
+    use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
+    use solana_sdk::{
+        account::Account, native_token::LAMPORTS_PER_SOL, program_pack::Pack, signature::Keypair,
+    };
+    use spl_token_2022::{instruction::TokenInstruction, state::Mint};
+    use std::collections::HashMap;
+
+    /// Agent whose RPC returns a mint with 6 decimals owned by `program`
+    fn agent_with_mint(mint: &Pubkey, program: Pubkey) -> SolanaAgentKit {
+        let mut data = vec![0; Mint::LEN];
+        Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
         }
+        .pack_into_slice(&mut data);
+        let account = Account {
+            lamports: 1_461_600,
+            data,
+            owner: program,
+            executable: false,
+            rent_epoch: 0,
+        };
+        let ui_account = encode_ui_account(mint, &account, UiAccountEncoding::Base64, None, None);
+        let mocks = HashMap::from([(
+            RpcRequest::GetAccountInfo,
+            json!({ "context": { "slot": 1 }, "value": ui_account }),
+        )]);
+        SolanaAgentKit::with_client(
+            RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks),
+            Keypair::new(),
+            SolanaConfig::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_sol_transfer() {
+        let agent = agent_with_mint(&Pubkey::new_unique(), spl_token::ID);
+        let to = Pubkey::new_unique();
+
+        let instructions = transfer_instructions(&agent, &to.to_string(), 0.5, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            instructions,
+            vec![solana_system_interface::instruction::transfer(
+                &agent.wallet.address,
+                &to,
+                LAMPORTS_PER_SOL / 2
+            )]
+        );
+        assert!(transfer(&agent, &to.to_string(), 0.5, None).await.is_ok());
+        assert!(transfer(&agent, &to.to_string(), 0.0, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_token_2022_transfer() {
+        let mint = Pubkey::new_unique();
+        let agent = agent_with_mint(&mint, spl_token_2022::ID);
+        let to = Pubkey::new_unique();
+
+        let instructions =
+            transfer_instructions(&agent, &to.to_string(), 1.25, Some(&mint.to_string()))
+                .await
+                .unwrap();
+        assert_eq!(instructions.len(), 2);
+
+        let transfer_ix = &instructions[1];
+        assert_eq!(transfer_ix.program_id, spl_token_2022::ID);
+        assert_eq!(
+            transfer_ix.accounts[2].pubkey,
+            get_associated_token_address_with_program_id(&to, &mint, &spl_token_2022::ID)
+        );
+        assert_eq!(
+            TokenInstruction::unpack(&transfer_ix.data).unwrap(),
+            TokenInstruction::TransferChecked {
+                amount: 1_250_000,
+                decimals: 6
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_non_mint_accounts() {
+        let mint = Pubkey::new_unique();
+        let agent = agent_with_mint(&mint, Pubkey::new_unique());
+        let to = Pubkey::new_unique().to_string();
+
+        let err = transfer_instructions(&agent, &to, 1.0, Some(&mint.to_string()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not a token mint"));
     }
 }
@@ -1,5 +1,5 @@
 pub mod trader;
-// pub mod risk_manager;
+pub mod risk_manager;
 // pub mod portfolio_optimizer;
 pub mod analyst;
 
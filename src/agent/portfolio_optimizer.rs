@@ -19,7 +19,7 @@ impl PortfolioOptimizer {
 
     pub async fn get_allocation(&self, _token: &TokenAnalytics, _signal: &MarketSignal) -> Result<BigDecimal> {
         // For now, return a default allocation
-        Ok(f64_to_decimal(0.1)) // 10% allocation
+        Ok(BigDecimal::new(1.into(), 1)) // 10% allocation
     }
 
     pub async fn get_position_allocation(&self, address: &str) -> Result<BigDecimal> {
@@ -36,7 +36,7 @@ impl PortfolioOptimizer {
             .and_then(|d| d.get_f64("allocation"))
             .unwrap_or(0.0);
 
-        Ok(f64_to_decimal(allocation))
+        Ok(f64_to_decimal(allocation)?)
     }
 
     async fn get_allocation(&self, token_address: &str) -> Result<Option<Allocation>, Error> {
@@ -1,33 +1,34 @@
-use anyhow::Result;
 use crate::models::market_signal::MarketSignal;
-use crate::utils::{decimal_to_f64, f64_to_decimal};
-use std::sync::Arc;
-use rig_mongodb::MongoDbPool;
+use crate::models::money::UsdAmount;
+use anyhow::{anyhow, Result};
+use bigdecimal::BigDecimal;
 
 pub struct RiskManagerAgent {
-    db: Arc<MongoDbPool>,
-    max_position_size: f64,
+    max_position_size: UsdAmount,
     max_drawdown: f64,
 }
 
 impl RiskManagerAgent {
-    pub fn new(db: Arc<MongoDbPool>, max_position_size: f64, max_drawdown: f64) -> Self {
+    pub fn new(max_position_size: UsdAmount, max_drawdown: f64) -> Self {
         Self {
-            db,
             max_position_size,
             max_drawdown,
         }
     }
 
+    pub fn max_drawdown(&self) -> f64 {
+        self.max_drawdown
+    }
+
     pub async fn validate_trade(&self, signal: &MarketSignal) -> Result<bool> {
         // TODO: Implement risk validation logic
         // - Check current exposure
         // - Validate against max drawdown
         // - Check correlation with existing positions
         // - Verify position sizing
-        
-        let min_confidence = f64_to_decimal(0.5);
-        let max_risk = f64_to_decimal(0.7);
+
+        let min_confidence = BigDecimal::new(5.into(), 1);
+        let max_risk = BigDecimal::new(7.into(), 1);
         if signal.confidence < min_confidence || signal.risk_score > max_risk {
             return Ok(false);
         }
@@ -35,17 +36,60 @@ impl RiskManagerAgent {
         Ok(true)
     }
 
-    pub async fn calculate_position_size(&self, signal: &MarketSignal) -> Result<f64> {
+    /// Sizes a position from the signal's confidence and risk score, both of
+    /// which must lie in [0, 1]. The result never exceeds the maximum size.
+    pub async fn calculate_position_size(&self, signal: &MarketSignal) -> Result<UsdAmount> {
         // Calculate optimal position size based on:
         // - Current portfolio value
         // - Risk metrics
         // - Signal confidence
-        let max_size = f64_to_decimal(self.max_position_size);
-        let base_size = max_size.clone() * signal.confidence.clone();
-        let one = f64_to_decimal(1.0);
-        let risk_factor = one - signal.risk_score.clone();
-        let risk_adjusted_size = base_size * risk_factor;
-        
-        Ok(decimal_to_f64(&risk_adjusted_size.min(max_size)))
+        let confidence = unit_interval("confidence", &signal.confidence)?;
+        let risk_score = unit_interval("risk score", &signal.risk_score)?;
+
+        let risk_factor = BigDecimal::from(1) - risk_score;
+        let size = self.max_position_size.scale(&(confidence * risk_factor));
+
+        Ok(size.min(self.max_position_size.clone()))
     }
-}
\ No newline at end of file
+}
+
+fn unit_interval(name: &str, value: &BigDecimal) -> Result<BigDecimal> {
+    if *value < BigDecimal::from(0) || *value > BigDecimal::from(1) {
+        return Err(anyhow!("Signal {} {} is outside [0, 1]", name, value));
+    }
+    Ok(value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::market_signal::{MarketSignalBuilder, SignalType};
+    use std::str::FromStr;
+
+    fn signal(confidence: &str, risk_score: &str) -> MarketSignal {
+        MarketSignalBuilder::new("addr".to_string(), SignalType::Buy, BigDecimal::from(1))
+            .confidence(BigDecimal::from_str(confidence).unwrap())
+            .risk_score(BigDecimal::from_str(risk_score).unwrap())
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_calculate_position_size() {
+        let manager = RiskManagerAgent::new(UsdAmount::new(BigDecimal::from(1000)), 0.2);
+
+        let size = manager
+            .calculate_position_size(&signal("0.8", "0.3"))
+            .await
+            .unwrap();
+        assert_eq!(size, UsdAmount::new(BigDecimal::from(560)));
+
+        assert!(manager
+            .calculate_position_size(&signal("1.5", "0.3"))
+            .await
+            .is_err());
+        assert!(manager
+            .calculate_position_size(&signal("0.8", "-0.1"))
+            .await
+            .is_err());
+    }
+}
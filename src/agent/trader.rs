@@ -1,12 +1,30 @@
 // use crate::models::trade::Trade;
 use crate::{
+    birdeye::api::{BirdeyeApi, BirdeyeClient},
     config::mongodb::MongoDbPool,
-    config::AgentConfig,
+    config::{get_openai_model, AgentConfig, LiveConfig, LiveSettings},
     error::{AgentError, AgentResult},
-    models::market_signal::{MarketSignal, SignalType},
-    services::TokenAnalyticsService,
-    trading::{trading_engine::TradingEngine, SolanaAgentKit},
-    utils::f64_to_decimal,
+    llm::{LlmBackend, OpenAIBackend},
+    logging::metrics::{metrics, timed_step},
+    models::{
+        market_signal::{MarketSignal, SignalType},
+        money::{Price, UsdAmount},
+        token_analytics::TokenAnalytics,
+    },
+    services::{SignalGate, TokenAnalyticsService},
+    signals::{SignalDecision, SignalPolicy},
+    strategy::{self, Strategy, StrategyContext, TradeRecommendation},
+    trading::{
+        exits::{DefaultExits, ExitManager},
+        limit_orders::{LimitOrder, TriggerEngine},
+        order::OrderSide,
+        positions::PositionLedger,
+        scheduler::{ExecutionScheduler, ParentOrder, Schedule},
+        trading_engine::TradingEngine,
+        transactions::TradeLedger,
+        SolanaAgentKit,
+    },
+    utils::{decimal_to_f64, f64_to_decimal},
 };
 use bigdecimal::BigDecimal;
 use bson;
@@ -15,34 +33,84 @@ use std::sync::Arc;
 use tokio::time::sleep;
 use tracing::{error, info};
 
+/// How long a strategy's limit entry waits for its price
+const LIMIT_ENTRY_TTL_SECS: i64 = 24 * 60 * 60;
+
 pub struct TradingAgent {
     analytics_service: Arc<TokenAnalyticsService>,
-    config: AgentConfig,
+    /// Trading and signal settings that may change while the agent runs
+    live: Arc<LiveConfig>,
     running: Arc<AtomicBool>,
-    engine: TradingEngine,
+    engine: Arc<TradingEngine>,
+    exits: Arc<ExitManager>,
+    scheduler: ExecutionScheduler,
+    triggers: TriggerEngine,
+    signal_gate: SignalGate,
+    /// Configured strategy set; `None` uses the built-in signal engine
+    strategy: Option<Arc<dyn Strategy>>,
+    ledger: PositionLedger,
     db_pool: Arc<MongoDbPool>,
 }
 
 impl TradingAgent {
     pub async fn new(
         config: AgentConfig,
+        live: Arc<LiveConfig>,
         analytics_service: Arc<TokenAnalyticsService>,
         db_pool: Arc<MongoDbPool>,
         solana_agent: SolanaAgentKit,
     ) -> AgentResult<Self> {
         info!("Initializing TradingAgent...");
-
-        let engine = TradingEngine::new(
-            config.trade_min_confidence,
-            config.trade_max_amount,
-            solana_agent,
+        let settings = live.current();
+        analytics_service.set_market_config(settings.market.clone());
+
+        let transactions = solana_agent
+            .transactions()
+            .with_ledger(TradeLedger::new(db_pool.clone()));
+        let engine = Arc::new(
+            TradingEngine::new(
+                f64_to_decimal(settings.trading.trade_min_confidence)?,
+                UsdAmount::from_f64(settings.trading.trade_max_amount)?,
+                settings.trading.trade_max_slippage,
+                solana_agent,
+            )
+            .with_transactions(transactions),
         );
+        let exits = Arc::new(ExitManager::new(
+            db_pool.clone(),
+            engine.clone(),
+            DefaultExits {
+                stop_loss_pct: settings.trading.exit_stop_loss_pct,
+                trailing_stop_pct: settings.trading.exit_trailing_stop_pct,
+            },
+        ));
+        let birdeye: Arc<dyn BirdeyeApi> =
+            Arc::new(BirdeyeClient::new(config.birdeye_api_key.clone()));
+        let scheduler = ExecutionScheduler::new(db_pool.clone(), engine.clone(), exits.clone());
+        let triggers = TriggerEngine::new(db_pool.clone(), engine.clone(), exits.clone(), birdeye);
+        let signal_gate = SignalGate::new(db_pool.clone(), SignalPolicy::new_from_env()?).await?;
+
+        let llm: Arc<dyn LlmBackend> = Arc::new(OpenAIBackend::new(
+            &config.openai_api_key,
+            get_openai_model(),
+        ));
+        let strategy = strategy::from_config(&config.strategy, Some(llm))
+            .map_err(|e| AgentError::InvalidConfig("STRATEGIES".into(), e.to_string()))?;
+        if let Some(strategy) = &strategy {
+            info!("Trading with the {} strategy", strategy.name());
+        }
 
         Ok(Self {
             analytics_service,
-            config,
+            live,
             running: Arc::new(AtomicBool::new(false)),
             engine,
+            exits,
+            scheduler,
+            triggers,
+            signal_gate,
+            strategy,
+            ledger: PositionLedger::new(db_pool.clone()),
             db_pool,
         })
     }
@@ -69,14 +137,36 @@ impl TradingAgent {
             info!("24h Volume: ${:.2}", volume);
         }
 
-        // Generate market signals
-        let signal = self
-            .analytics_service
-            .generate_market_signals(&analytics)
+        // Every fresh price is checked against the exit rules of open positions
+        match self
+            .exits
+            .on_price(address, decimal_to_f64(&analytics.price)?)
             .await
-            .map_err(|e| {
-                AgentError::MarketAnalysis(format!("Failed to generate signals: {}", e))
-            })?;
+        {
+            Ok(signatures) if !signatures.is_empty() => {
+                info!(
+                    "Submitted {} exit order(s) for {}",
+                    signatures.len(),
+                    symbol
+                )
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to evaluate exit rules for {}: {}", symbol, e),
+        }
+
+        // Generate market signals
+        let signal = timed_step("generate_signals", async {
+            match &self.strategy {
+                Some(strategy) => self.run_strategy(strategy.as_ref(), analytics).await,
+                None => {
+                    self.analytics_service
+                        .generate_market_signals(&analytics)
+                        .await
+                }
+            }
+        })
+        .await
+        .map_err(|e| AgentError::MarketAnalysis(format!("Failed to generate signals: {}", e)))?;
 
         if let Some(signal) = &signal {
             info!(
@@ -88,6 +178,35 @@ impl TradingAgent {
         Ok(signal)
     }
 
+    /// Evaluate the configured strategy on a fresh snapshot and store its
+    /// strongest signal
+    async fn run_strategy(
+        &self,
+        strategy: &dyn Strategy,
+        analytics: TokenAnalytics,
+    ) -> AgentResult<Option<MarketSignal>> {
+        // The strategy may commit up to the per-trade budget
+        let ctx = StrategyContext::load(
+            &self.analytics_service,
+            &self.ledger,
+            analytics,
+            self.live.current().trading.trade_max_amount,
+        )
+        .await
+        .map_err(|e| AgentError::MarketAnalysis(e.to_string()))?;
+        let signal = strategy
+            .evaluate(&ctx)
+            .await
+            .map_err(|e| AgentError::MarketAnalysis(format!("{}: {}", strategy.name(), e)))?
+            .into_iter()
+            .max_by(|a, b| a.confidence.cmp(&b.confidence));
+
+        match signal {
+            Some(signal) => Ok(Some(self.analytics_service.store_signal(signal).await?)),
+            None => Ok(None),
+        }
+    }
+
     pub async fn process_signal(&self, signal: &MarketSignal) -> AgentResult<Option<String>> {
         let zero = BigDecimal::from(0);
         let action = match signal.signal_type {
@@ -102,10 +221,7 @@ impl TradingAgent {
             _ => return Ok(None),
         };
 
-        // Convert f64 config values to BigDecimal
-        let threshold = f64_to_decimal(self.config.trade_min_confidence);
-
-        if signal.confidence >= threshold {
+        if signal.confidence >= self.engine.get_min_confidence() {
             info!(
                 "Signal meets confidence threshold for {}: {} (confidence: {:.2})",
                 signal.asset_address, action, signal.confidence
@@ -116,11 +232,155 @@ impl TradingAgent {
     }
 
     pub async fn execute_trade(&self, symbol: &str, signal: &MarketSignal) -> AgentResult<String> {
+        let result = self.submit_trade(symbol, signal).await;
+        let outcome = match &result {
+            Ok(id) if id.starts_with("limit_") => "limit",
+            Ok(id) if id.starts_with("scheduled_") => "scheduled",
+            Ok(_) => "executed",
+            Err(_) => "failed",
+        };
+        metrics().record_trade(outcome);
+        result
+    }
+
+    /// Place, schedule or send the order for a signal. Returns the
+    /// transaction signature or the id of the waiting order.
+    async fn submit_trade(&self, symbol: &str, signal: &MarketSignal) -> AgentResult<String> {
         info!("Executing trade for {}", symbol);
-        self.engine
-            .execute_trade(signal)
+        let settings = self.live.current();
+        let is_buy = TradingEngine::order_side(signal) == Some(OrderSide::Buy)
+            && signal.confidence >= self.engine.get_min_confidence();
+        // Strategy signals can carry a plan with exits and an entry style
+        let recommendation = TradeRecommendation::from_signal(signal);
+        let exit_plan = recommendation.as_ref().map(|r| r.exit_plan());
+        let reason = format!("{:?} signal", signal.signal_type);
+
+        if is_buy {
+            let quantity = self
+                .engine
+                .quantity(signal)
+                .await
+                .map_err(|e| AgentError::Trading(format!("Failed to size the buy: {}", e)))?;
+
+            // Limit entries wait for the trigger engine; the position is
+            // opened when the order fills
+            if let Some(limit) = recommendation.as_ref().and_then(|r| r.limit_price()) {
+                let expires_at = bson::DateTime::from_millis(
+                    bson::DateTime::now().timestamp_millis() + LIMIT_ENTRY_TTL_SECS * 1000,
+                );
+                let mut order = LimitOrder::new(
+                    &signal.asset_address,
+                    OrderSide::Buy,
+                    quantity,
+                    limit,
+                    settings.trading.trade_max_slippage,
+                    expires_at,
+                    reason,
+                );
+                if let Some(plan) = exit_plan {
+                    order = order.with_exit_plan(plan);
+                }
+                let order = self.triggers.place(order).await.map_err(|e| {
+                    AgentError::Trading(format!("Failed to place limit order: {}", e))
+                })?;
+                return Ok(format!(
+                    "limit_{}",
+                    order.id.map(|id| id.to_hex()).unwrap_or_default()
+                ));
+            }
+
+            // DCA plans and large buys are split into child orders instead of
+            // one swap. The position is opened by the first child fill.
+            let schedule = recommendation
+                .as_ref()
+                .and_then(|r| r.dca_schedule())
+                .or_else(|| {
+                    (settings.trading.trade_twap_slices > 1).then(|| {
+                        Schedule::twap(
+                            settings.trading.trade_twap_window.as_secs(),
+                            settings.trading.trade_twap_slices,
+                        )
+                    })
+                });
+            if let Some(schedule) = schedule {
+                let mut order = ParentOrder::new(
+                    &signal.asset_address,
+                    OrderSide::Buy,
+                    quantity,
+                    schedule,
+                    reason,
+                    bson::DateTime::now(),
+                );
+                if let Some(plan) = exit_plan {
+                    order = order.with_exit_plan(plan);
+                }
+                let order =
+                    self.scheduler.schedule(order).await.map_err(|e| {
+                        AgentError::Trading(format!("Failed to schedule order: {}", e))
+                    })?;
+                return Ok(format!(
+                    "scheduled_{}",
+                    order.id.map(|id| id.to_hex()).unwrap_or_default()
+                ));
+            }
+        }
+
+        let Some(mut order) = self
+            .engine
+            .signal_order(signal)
             .await
-            .map_err(|e| AgentError::Trading(format!("Trade execution failed: {}", e)))
+            .map_err(|e| AgentError::Trading(format!("Failed to size the order: {}", e)))?
+        else {
+            return Ok("HOLD".to_string());
+        };
+
+        // Sells only sell what the open positions hold
+        if order.side == OrderSide::Sell {
+            let held = self
+                .exits
+                .held(&order.token_address)
+                .await
+                .map_err(|e| AgentError::Trading(format!("Failed to read positions: {}", e)))?;
+            let Some(held) = held else {
+                info!("No open position in {} to sell", order.token_address);
+                return Ok("HOLD".to_string());
+            };
+            order.quantity = order.quantity.checked_min(&held)?;
+        }
+
+        info!(
+            "Executing {} trade for {} with confidence {:.2}",
+            order.side, order.token_address, signal.confidence
+        );
+        let execution = self
+            .engine
+            .submit_order(&order)
+            .await
+            .map_err(|e| AgentError::Trading(format!("Trade execution failed: {}", e)))?;
+
+        // Positions are booked from what filled. Buys become positions with
+        // the planned or default exit rules; sells reduce the oldest first.
+        let fill = execution.fill_or(order.quantity, &Price::new(signal.price.clone())?);
+        let booked = match order.side {
+            OrderSide::Buy => self
+                .exits
+                .open_position(
+                    &order.token_address,
+                    fill.quantity,
+                    &fill.price,
+                    exit_plan.as_ref(),
+                )
+                .await
+                .map(|_| ()),
+            OrderSide::Sell => {
+                self.exits
+                    .reduce_positions(&order.token_address, &fill)
+                    .await
+            }
+        };
+        booked.map_err(|e| AgentError::Trading(format!("Failed to record position: {}", e)))?;
+
+        Ok(execution.signature)
     }
 
     pub async fn post_trade_update(
@@ -144,10 +404,10 @@ impl TradingAgent {
             "timestamp": bson::DateTime::now(),
         };
 
-        // Insert into trades collection
+        // The trades collection is the order ledger; updates are kept apart
         self.db_pool
-            .database("cainam") // Use a constant or config value for database name
-            .collection("trades")
+            .database(&self.db_pool.get_config().database)
+            .collection("trade_updates")
             .insert_one(trade_update)
             .await
             .map_err(|e| AgentError::Database(e.into()))?;
@@ -159,23 +419,63 @@ impl TradingAgent {
     pub async fn run(&self) -> AgentResult<()> {
         info!("Starting trading agent...");
         self.running.store(true, Ordering::SeqCst);
-
-        let tokens = [
-            ("SOL", "So11111111111111111111111111111111111111112"),
-            ("BONK", "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263"),
-        ];
+        let mut updates = self.live.subscribe();
 
         while self.running.load(Ordering::SeqCst) {
-            for (symbol, address) in tokens.iter() {
-                match self.analyze_market(symbol, address).await {
+            // Reloaded settings take effect at the start of a cycle
+            if updates.has_changed().unwrap_or(false) {
+                let settings = updates.borrow_and_update().clone();
+                if let Err(e) = self.apply_settings(&settings) {
+                    error!("Failed to apply reloaded settings: {}", e);
+                }
+            }
+            let settings = self.live.current();
+
+            match self.scheduler.run_due().await {
+                Ok(0) => {}
+                Ok(filled) => info!("Filled {} scheduled child order(s)", filled),
+                Err(e) => error!("Failed to run scheduled orders: {}", e),
+            }
+            match self.triggers.watch().await {
+                Ok(signatures) if !signatures.is_empty() => {
+                    info!("Filled {} limit order(s)", signatures.len())
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed to check limit orders: {}", e),
+            }
+
+            for token in &settings.trading.watchlist {
+                let (symbol, address) = (&token.symbol, &token.address);
+                match timed_step("analyze_market", self.analyze_market(symbol, address)).await {
                     Ok(Some(signal)) => {
-                        let min_confidence = f64_to_decimal(self.config.trade_min_confidence);
-                        if signal.confidence >= min_confidence {
-                            if let Err(e) = self.process_signal(&signal).await {
-                                error!("Error processing signal: {}", e);
-                            }
-                        } else {
+                        // Weak signals must not open an episode, or the
+                        // stronger ones that follow would be merged into it
+                        if signal.confidence < self.engine.get_min_confidence() {
                             info!("Signal confidence too low for trading");
+                            continue;
+                        }
+
+                        match self.signal_gate.admit(&signal).await {
+                            Ok(SignalDecision::Actionable(_)) => {}
+                            Ok(SignalDecision::Merged(episode)) => {
+                                info!(
+                                    "Signal merged into ongoing {:?} episode ({} signals)",
+                                    episode.direction, episode.signal_count
+                                );
+                                continue;
+                            }
+                            Ok(SignalDecision::Suppressed(reason)) => {
+                                info!("Signal suppressed: {}", reason);
+                                continue;
+                            }
+                            Err(e) => {
+                                error!("Failed to record signal episode: {}", e);
+                                continue;
+                            }
+                        }
+
+                        if let Err(e) = self.process_signal(&signal).await {
+                            error!("Error processing signal: {}", e);
                         }
                     }
                     Ok(None) => {
@@ -187,11 +487,15 @@ impl TradingAgent {
                 }
             }
 
+            if let Err(e) = self.update_position_metrics().await {
+                error!("Failed to update position metrics: {}", e);
+            }
+
             info!(
                 "Waiting for next analysis interval ({:?})...",
-                self.config.analysis_interval
+                settings.trading.analysis_interval
             );
-            sleep(self.config.analysis_interval).await;
+            sleep(settings.trading.analysis_interval).await;
             info!("Starting next analysis cycle");
         }
 
@@ -199,6 +503,45 @@ impl TradingAgent {
         Ok(())
     }
 
+    /// Publish the open exposure and realized profit of the position ledger
+    async fn update_position_metrics(&self) -> AgentResult<()> {
+        let open = self
+            .ledger
+            .open_positions(None)
+            .await
+            .map_err(|e| AgentError::Trading(e.to_string()))?;
+        let mut exposure = UsdAmount::zero();
+        for position in &open {
+            exposure = exposure + position.quantity.value_at(&position.entry_price);
+        }
+        let realized = self
+            .ledger
+            .realized_pnl()
+            .await
+            .map_err(|e| AgentError::Trading(e.to_string()))?;
+
+        metrics().set_open_exposure(exposure.to_f64()?);
+        metrics().set_realized_pnl(realized.to_f64()?);
+        Ok(())
+    }
+
+    /// Hand reloaded settings to the services that copied them at startup
+    fn apply_settings(&self, settings: &LiveSettings) -> AgentResult<()> {
+        self.engine.set_limits(
+            f64_to_decimal(settings.trading.trade_min_confidence)?,
+            UsdAmount::from_f64(settings.trading.trade_max_amount)?,
+            settings.trading.trade_max_slippage,
+        );
+        self.exits.set_defaults(DefaultExits {
+            stop_loss_pct: settings.trading.exit_stop_loss_pct,
+            trailing_stop_pct: settings.trading.exit_trailing_stop_pct,
+        });
+        self.analytics_service
+            .set_market_config(settings.market.clone());
+        info!("Applied reloaded trading and signal settings");
+        Ok(())
+    }
+
     pub fn stop(&self) {
         info!("Stopping trading agent...");
         self.running.store(false, Ordering::SeqCst);
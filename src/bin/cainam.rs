@@ -1,21 +1,72 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use bson::oid::ObjectId;
 use cainam_core::{
     birdeye::api::{BirdeyeApi, BirdeyeClient},
     config::{
-        mongodb::{MongoConfig, MongoDbPool, MongoPoolConfig},
-        AgentConfig,
+        file_config::Profile,
+        mongodb::{MongoConfig, MongoDbPool},
+        AgentConfig, ConfigFile, EmbeddingConfig, LiveConfig,
+    },
+    eval::{EvalDataset, EvalReport, EvalRunner, ReportDiff},
+    export::{row_writer, Dataset, ExportFilter, ExportFormat, Exporter},
+    llm::{embedding, LlmBackend, OpenAIBackend},
+    models::{
+        chat_session::{ChatRole, ChatSession},
+        money::TokenAmount,
+    },
+    services::{
+        recommendation_tracker::OutcomeHorizon, signal_tracker::DEFAULT_MIN_SAMPLES,
+        token_analytics::AnalyticsFilter, token_analytics_llm::TokenAnalyticsLLM,
+        token_filter::TokenFilterService, ChatSessionStore, EmbeddingJob, RecommendationTracker,
+        RetentionJob, SignalGate, SignalTracker, TokenAnalyticsService, WalletTrackerService,
+    },
+    signals::SignalPolicy,
+    storage::{migrations::Migrator, Storage, StorageKind},
+    strategy::{self, Backtester},
+    tools::AnalystTools,
+    trading::{
+        exits::{ExitRuleStore, ExitRules, TakeProfitLevel},
+        limit_orders::{LimitOrder, LimitOrderStore},
+        mint_info,
+        order::OrderSide,
+        positions::PositionLedger,
+        scheduler::{ChildStatus, ParentOrder, ParentOrderStore, Schedule},
+        SolanaAgentKit,
     },
-    services::{token_analytics_llm::TokenAnalyticsLLM, TokenAnalyticsService},
 };
 use clap::{Parser, Subcommand};
 use dotenvy::dotenv;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 use tracing::{error, info, Level};
 use tracing_subscriber::FmtSubscriber;
 
+/// How often the monitor rolls up and expires old snapshots
+const RETENTION_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Let the analytics agent send funds from the wallet through its transfer tool
+    #[arg(long, global = true)]
+    allow_transfers: bool,
+
+    /// Where services keep their data: mongo, or memory to run without a database
+    #[arg(long, global = true, default_value = "mongo")]
+    storage: StorageKind,
+
+    /// Config file, defaults to $CAINAM_CONFIG or ./cainam.toml when present
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Settings profile from the config file: dev, paper or live
+    #[arg(long, global = true, value_parser = Profile::from_str)]
+    profile: Option<Profile>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -47,6 +98,641 @@ enum Commands {
         #[arg(default_value = "300")]
         interval: u64,
     },
+
+    /// Measure forward returns of stored recommendations whose horizons have elapsed
+    Reprice,
+
+    /// Show realized recommendation performance grouped by score, label, KOL presence and prompt version
+    Scorecard {
+        /// Horizon to report (1h, 24h, 7d, 30d)
+        #[arg(long, default_value = "24h")]
+        horizon: OutcomeHorizon,
+    },
+
+    /// Measure outcomes of stored market signals and refit their confidence calibration
+    Calibrate {
+        /// Forward horizon a signal's direction is judged at (1h, 24h, 7d, 30d)
+        #[arg(long, default_value = "24h")]
+        horizon: OutcomeHorizon,
+
+        /// Minimum measured signals per signal type
+        #[arg(long, default_value_t = DEFAULT_MIN_SAMPLES)]
+        min_samples: usize,
+    },
+
+    /// Apply, inspect or undo database migrations
+    Db {
+        #[command(subcommand)]
+        command: DbCommands,
+    },
+
+    /// Inspect the layered configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// Offline evaluation of the token filter pipeline
+    Eval {
+        #[command(subcommand)]
+        command: EvalCommands,
+    },
+
+    /// Show or edit the exit rules of open positions
+    Exits {
+        #[command(subcommand)]
+        command: ExitCommands,
+    },
+
+    /// Split orders into DCA/TWAP child orders executed by the trading agent
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommands,
+    },
+
+    /// Limit orders fired by the trading agent when the price crosses
+    Orders {
+        #[command(subcommand)]
+        command: OrderCommands,
+    },
+
+    /// Embed snapshots and recommendations whose embedding is missing or outdated
+    Embed {
+        /// Most documents of each kind to embed
+        #[arg(long, default_value = "1000")]
+        limit: usize,
+
+        /// Only count the documents that need embedding
+        #[arg(long)]
+        status: bool,
+    },
+
+    /// Roll up old snapshots and expire them as set by the retention policies
+    Retention,
+
+    /// Export stored data to CSV, JSON Lines or Parquet
+    Export {
+        dataset: ExportDataset,
+
+        /// Output file, or - for stdout
+        #[arg(long)]
+        out: String,
+
+        /// csv, jsonl or parquet (defaults to the extension of --out)
+        #[arg(long)]
+        format: Option<ExportFormat>,
+
+        /// Only this token
+        #[arg(long)]
+        token: Option<String>,
+
+        /// Start of the range, RFC 3339 or YYYY-MM-DD (inclusive)
+        #[arg(long, value_parser = parse_time, conflicts_with = "hours")]
+        since: Option<bson::DateTime>,
+
+        /// End of the range, RFC 3339 or YYYY-MM-DD (exclusive)
+        #[arg(long, value_parser = parse_time)]
+        until: Option<bson::DateTime>,
+
+        /// Only the last this many hours
+        #[arg(long)]
+        hours: Option<i64>,
+
+        /// Candle length in seconds
+        #[arg(long, default_value = "3600")]
+        interval: i64,
+    },
+
+    /// Find tokens whose current profile resembles a token's latest snapshot
+    Similar {
+        /// Reference token address
+        address: String,
+
+        /// Number of tokens to show
+        #[arg(short, default_value = "10")]
+        k: usize,
+
+        /// Only compare snapshots from the last this many hours
+        #[arg(long, default_value = "24")]
+        hours: i64,
+
+        /// Minimum liquidity in USD
+        #[arg(long)]
+        min_liquidity: Option<f64>,
+
+        /// Minimum 24h volume in USD
+        #[arg(long)]
+        min_volume: Option<f64>,
+    },
+
+    /// Multi-turn chat with the analytics agent
+    Chat {
+        /// Resume the session with this id
+        #[arg(long)]
+        session: Option<ObjectId>,
+
+        /// List recent sessions instead of chatting
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// Replay stored snapshots of a token through the configured strategies
+    Backtest {
+        /// Token address
+        address: String,
+
+        /// Days of history to replay
+        #[arg(long, default_value = "7")]
+        days: i64,
+
+        /// Strategies to run instead of STRATEGIES (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        strategies: Vec<String>,
+
+        /// Fee and slippage per fill, as a fraction
+        #[arg(long, default_value = "0.003")]
+        fee: f64,
+    },
+}
+
+#[derive(Subcommand)]
+enum OrderCommands {
+    /// List pending limit orders
+    List,
+
+    /// Place a limit order
+    Place {
+        token: String,
+        side: Side,
+        /// Size in token units
+        quantity: f64,
+        /// Highest price to buy at, or lowest price to sell at
+        #[arg(long)]
+        limit: f64,
+        /// Minutes until the order expires
+        #[arg(long, default_value = "1440")]
+        expires_mins: i64,
+        /// Slippage past the limit allowed at fire time (defaults to TRADE_MAX_SLIPPAGE)
+        #[arg(long)]
+        slippage: Option<f64>,
+        /// Position a sell reduces
+        #[arg(long)]
+        position: Option<ObjectId>,
+    },
+
+    /// Cancel a pending limit order
+    Cancel { id: ObjectId },
+}
+
+/// `quantity` whole tokens in the base units of the token's mint, whose
+/// decimals are read over SOLANA_RPC_URL
+async fn token_amount(token: &str, quantity: f64) -> Result<TokenAmount> {
+    let rpc_url = std::env::var("SOLANA_RPC_URL")
+        .context("SOLANA_RPC_URL is needed to look up the token's decimals")?;
+    let mint =
+        Pubkey::from_str(token).with_context(|| format!("Invalid token address: {}", token))?;
+    let (_, decimals) = mint_info(&RpcClient::new(rpc_url), &mint).await?;
+    Ok(TokenAmount::from_f64(quantity, decimals)?)
+}
+
+fn print_limit_order(order: &LimitOrder) {
+    println!(
+        "{} {:?} {} {} {} limit ${:.8} (slippage {:.1}%) expires {}",
+        order.id.map(|id| id.to_hex()).unwrap_or_default(),
+        order.status,
+        order.side,
+        order.quantity,
+        order.token_address,
+        order.limit_price,
+        order.max_slippage * 100.0,
+        order.expires_at
+    );
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Side {
+    Buy,
+    Sell,
+}
+
+impl From<Side> for OrderSide {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Buy => OrderSide::Buy,
+            Side::Sell => OrderSide::Sell,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum ScheduleCommands {
+    /// List active and paused parent orders
+    List,
+
+    /// Equal entries at a fixed interval, starting now
+    Dca {
+        token: String,
+        side: Side,
+        /// Total size in token units
+        quantity: f64,
+        #[arg(long)]
+        interval_hours: f64,
+        #[arg(long)]
+        entries: usize,
+        /// Position a sell reduces
+        #[arg(long)]
+        position: Option<ObjectId>,
+    },
+
+    /// Equal slices spread over a window, starting now
+    Twap {
+        token: String,
+        side: Side,
+        /// Total size in token units
+        quantity: f64,
+        #[arg(long)]
+        window_mins: u64,
+        #[arg(long)]
+        slices: usize,
+        /// Position a sell reduces
+        #[arg(long)]
+        position: Option<ObjectId>,
+    },
+
+    /// Stop submitting children until resumed
+    Pause { id: ObjectId },
+
+    /// Continue a paused order; children that came due meanwhile go out next run
+    Resume { id: ObjectId },
+
+    /// Cancel all pending children
+    Cancel { id: ObjectId },
+}
+
+async fn create_parent_order(
+    store: &ParentOrderStore,
+    token: &str,
+    side: Side,
+    quantity: f64,
+    schedule: Schedule,
+    position: Option<ObjectId>,
+) -> Result<()> {
+    let side = OrderSide::from(side);
+    if side == OrderSide::Sell && position.is_none() {
+        anyhow::bail!("A scheduled sell needs --position");
+    }
+
+    let mut order = ParentOrder::new(
+        token,
+        side,
+        token_amount(token, quantity).await?,
+        schedule,
+        "cli",
+        bson::DateTime::now(),
+    );
+    if let Some(position) = position {
+        order = order.with_position(position);
+    }
+    let order = store.insert(&order).await?;
+    print_parent_order(&order)
+}
+
+fn print_parent_order(order: &ParentOrder) -> Result<()> {
+    let filled = order
+        .children
+        .iter()
+        .filter(|c| c.status == ChildStatus::Filled)
+        .count();
+    println!(
+        "{} {:?} {} {} {} — {} — {}/{} children filled{}",
+        order.id.map(|id| id.to_hex()).unwrap_or_default(),
+        order.status,
+        order.side,
+        order.quantity,
+        order.token_address,
+        order.schedule,
+        filled,
+        order.children.len(),
+        order
+            .average_price()?
+            .map(|p| format!(", avg ${}", p))
+            .unwrap_or_default()
+    );
+    Ok(())
+}
+
+#[derive(Subcommand)]
+enum ExitCommands {
+    /// List open positions and their exit rules
+    List,
+
+    /// Set exit rules on a position; options that are left out keep their value
+    Set {
+        /// Position id
+        position: ObjectId,
+
+        /// Stop below the entry price, as a fraction (0 removes the stop)
+        #[arg(long)]
+        stop_loss: Option<f64>,
+
+        /// Trailing stop below the highest price, as a fraction (0 removes it)
+        #[arg(long)]
+        trailing_stop: Option<f64>,
+
+        /// Take-profit level as PRICE:SIZE, SIZE being the fraction of the
+        /// initial position to sell. Repeat for tiers; replaces unfilled levels.
+        #[arg(long = "take-profit", value_parser = parse_take_profit)]
+        take_profit: Vec<TakeProfitLevel>,
+    },
+
+    /// Remove all exit rules from a position
+    Clear {
+        /// Position id
+        position: ObjectId,
+    },
+}
+
+fn parse_take_profit(value: &str) -> Result<TakeProfitLevel, String> {
+    let (price, size) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected PRICE:SIZE, got '{}'", value))?;
+    let price: f64 = price
+        .parse()
+        .map_err(|_| format!("invalid price '{}'", price))?;
+    let size_pct: f64 = size
+        .parse()
+        .map_err(|_| format!("invalid size '{}'", size))?;
+    if !(0.0..=1.0).contains(&size_pct) {
+        return Err(format!("size must be between 0 and 1, got {}", size_pct));
+    }
+    Ok(TakeProfitLevel {
+        price,
+        size_pct,
+        filled: false,
+    })
+}
+
+fn print_exit_rules(rules: &ExitRules) {
+    if let Some(stop) = rules.stop_price {
+        println!("  Stop: ${:.8}", stop);
+    }
+    if let Some(pct) = rules.trailing_stop_pct {
+        println!(
+            "  Trailing stop: {:.1}% below ${:.8}",
+            pct * 100.0,
+            rules.high_water_price
+        );
+    }
+    for level in &rules.take_profit {
+        println!(
+            "  Take profit: ${:.8} sells {:.0}%{}",
+            level.price,
+            level.size_pct * 100.0,
+            if level.filled { " (filled)" } else { "" }
+        );
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ExportDataset {
+    Analytics,
+    Signals,
+    Recommendations,
+    Trades,
+    /// OHLC bars built from token analytics
+    Candles,
+}
+
+fn parse_time(value: &str) -> Result<bson::DateTime, String> {
+    let time = chrono::DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .or_else(|_| {
+            chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+        })
+        .map_err(|_| format!("expected RFC 3339 or YYYY-MM-DD, got '{}'", value))?;
+    Ok(bson::DateTime::from_millis(time.timestamp_millis()))
+}
+
+/// Run `cainam export`, which only needs the database
+async fn run_export(command: &Commands) -> Result<()> {
+    let Commands::Export {
+        dataset,
+        out,
+        format,
+        token,
+        since,
+        until,
+        hours,
+        interval,
+    } = command
+    else {
+        unreachable!("not an export command");
+    };
+    let format = match format {
+        Some(format) => *format,
+        None => ExportFormat::from_path(Path::new(out))
+            .ok_or_else(|| anyhow!("Can't tell the format of '{}', pass --format", out))?,
+    };
+    let dataset = match dataset {
+        ExportDataset::Analytics => Dataset::Analytics,
+        ExportDataset::Signals => Dataset::Signals,
+        ExportDataset::Recommendations => Dataset::Recommendations,
+        ExportDataset::Trades => Dataset::Trades,
+        ExportDataset::Candles => Dataset::Candles {
+            bucket_secs: *interval,
+        },
+    };
+    let filter = ExportFilter {
+        token_address: token.clone(),
+        since: since.or_else(|| {
+            hours.map(|h| {
+                bson::DateTime::from_millis(
+                    bson::DateTime::now().timestamp_millis() - h * 3_600_000,
+                )
+            })
+        }),
+        until: *until,
+    };
+
+    let writer = if out == "-" {
+        row_writer(format, dataset.columns(), std::io::stdout())?
+    } else {
+        row_writer(format, dataset.columns(), std::fs::File::create(out)?)?
+    };
+    let db_pool = connect_mongo().await?;
+    let rows = Exporter::new(&db_pool)
+        .export(dataset, &filter, writer)
+        .await?;
+    if out != "-" {
+        println!("Wrote {} rows to {}", rows, out);
+    }
+    Ok(())
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Apply pending migrations
+    Migrate,
+
+    /// List migrations and whether they are applied
+    Status,
+
+    /// Undo the newest applied migrations
+    Rollback {
+        /// Migrations to undo
+        #[arg(long, default_value = "1")]
+        steps: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Validate every setting and print the effective config, secrets redacted
+    Check,
+
+    /// Override a trading or signal setting in the settings collection; a
+    /// running agent picks it up on its next reload
+    Set {
+        /// Setting name, e.g. TRADE_MIN_CONFIDENCE
+        setting: String,
+
+        value: String,
+    },
+
+    /// Remove an override from the settings collection
+    Unset {
+        /// Setting name, e.g. TRADE_MIN_CONFIDENCE
+        setting: String,
+    },
+
+    /// Show the audit log of settings changes
+    History {
+        /// Number of changes to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum EvalCommands {
+    /// Fill missing outcomes in a dataset from stored token analytics
+    Label {
+        /// Path to the dataset JSON file
+        dataset: String,
+
+        /// Where to write the labeled dataset (defaults to overwriting the input)
+        #[arg(long)]
+        out: Option<String>,
+    },
+
+    /// Replay a dataset through the filter pipeline and score the results
+    Run {
+        /// Path to the dataset JSON file
+        dataset: String,
+
+        /// Model used for the analysis stages
+        #[arg(long, default_value = cainam_core::services::token_filter::MODEL)]
+        model: String,
+
+        /// Minimum realized return (fraction) for a case to count as a success
+        #[arg(long, default_value = "0.0")]
+        threshold: f64,
+
+        /// Tokens per pipeline batch
+        #[arg(long, default_value = "25")]
+        batch_size: usize,
+
+        /// Where to write the JSON report
+        #[arg(long)]
+        out: Option<String>,
+    },
+
+    /// Compare two eval reports
+    Diff {
+        /// Baseline report
+        before: String,
+
+        /// Candidate report
+        after: String,
+    },
+}
+
+async fn connect_mongo() -> Result<Arc<MongoDbPool>> {
+    let mut mongo_config = MongoConfig::from_env()?;
+    mongo_config
+        .app_name
+        .get_or_insert_with(|| "cainam-cli".to_string());
+    MongoDbPool::create_pool(mongo_config).await
+}
+
+/// Run a `cainam db` command, which only needs the database
+async fn run_db_command(command: &DbCommands) -> Result<()> {
+    let db_pool = connect_mongo().await?;
+    let migrator = Migrator::new(&db_pool, &EmbeddingConfig::new_from_env()?)?;
+    match command {
+        DbCommands::Migrate => {
+            let applied = migrator.migrate().await?;
+            if applied.is_empty() {
+                println!("Database is up to date");
+            } else {
+                println!("Applied {} migration(s): {:?}", applied.len(), applied);
+            }
+        }
+        DbCommands::Status => {
+            for status in migrator.status().await? {
+                println!("{}", status);
+            }
+        }
+        DbCommands::Rollback { steps } => {
+            let undone = migrator.rollback(*steps).await?;
+            if undone.is_empty() {
+                println!("No applied migrations to roll back");
+            } else {
+                println!("Rolled back {} migration(s): {:?}", undone.len(), undone);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run a `cainam config` command that reads or writes the settings collection
+async fn run_config_command(command: &ConfigCommands, config_file: ConfigFile) -> Result<()> {
+    let db_pool = connect_mongo().await?;
+    let store = Storage::mongo(&db_pool, &EmbeddingConfig::new_from_env()?.index_name).settings;
+    let live = LiveConfig::load(config_file, Some(store)).await?;
+    let changes = match command {
+        ConfigCommands::Set { setting, value } => {
+            live.set(setting, Some(value.as_str()), "cli").await?
+        }
+        ConfigCommands::Unset { setting } => live.set(setting, None, "cli").await?,
+        ConfigCommands::History { limit } => {
+            for change in live.history(*limit).await? {
+                let status = match &change.error {
+                    Some(error) => format!("rejected: {}", error),
+                    None => "applied".to_string(),
+                };
+                println!(
+                    "{} ({}) {}",
+                    change.timestamp.try_to_rfc3339_string().unwrap_or_default(),
+                    change.source,
+                    status
+                );
+                for setting in &change.changes {
+                    println!("  {}", setting);
+                }
+            }
+            return Ok(());
+        }
+        ConfigCommands::Check => unreachable!("checked before connecting"),
+    };
+    if changes.is_empty() {
+        println!("No settings changed");
+    }
+    for change in &changes {
+        println!("{}", change);
+    }
+    Ok(())
 }
 
 #[tokio::main]
@@ -60,26 +746,86 @@ async fn main() -> Result<()> {
     // Parse command line arguments
     let cli = Cli::parse();
 
+    // Layer cainam.toml and its profile under the environment
+    let config_file = ConfigFile::load(cli.config.as_deref(), cli.profile)?;
+    config_file.apply();
+    if let Commands::Config {
+        command: ConfigCommands::Check,
+    } = &cli.command
+    {
+        print!("{}", config_file);
+        let problems = config_file.validate();
+        if problems.is_empty() {
+            println!("\nConfiguration is valid");
+            return Ok(());
+        }
+        println!();
+        for problem in &problems {
+            println!("  - {}", problem);
+        }
+        return Err(anyhow!("{} configuration problem(s)", problems.len()));
+    }
+
+    if let Commands::Config { command } = &cli.command {
+        return run_config_command(command, config_file).await;
+    }
+
+    // Comparing reports only needs the files on disk
+    if let Commands::Eval {
+        command: EvalCommands::Diff { before, after },
+    } = &cli.command
+    {
+        let diff = ReportDiff::between(&EvalReport::load(before)?, &EvalReport::load(after)?);
+        println!("{}", diff);
+        return Ok(());
+    }
+    if let Commands::Db { command } = &cli.command {
+        return run_db_command(command).await;
+    }
+    if let Commands::Export { .. } = &cli.command {
+        return run_export(&cli.command).await;
+    }
+
     // Initialize services
     let config = AgentConfig::new_from_env()?;
 
-    // Create MongoDB configuration
-    let mongodb_uri = dotenvy::var("MONGODB_URI").expect("MONGODB_URI must be set");
-    let mongodb_database = dotenvy::var("MONGODB_DATABASE").expect("MONGODB_DATABASE must be set");
-    let mongo_config = MongoConfig {
-        uri: mongodb_uri,
-        database: mongodb_database,
-        app_name: Some("cainam-cli".to_string()),
-        pool_config: MongoPoolConfig::default(),
+    let (db_pool, storage) = match cli.storage {
+        StorageKind::Mongo => {
+            let db_pool = connect_mongo().await?;
+            let storage = Storage::mongo(&db_pool, &config.embedding.index_name);
+            (Some(db_pool), storage)
+        }
+        StorageKind::Memory => {
+            info!("Using in-memory storage; nothing is kept after exit");
+            (None, Storage::memory())
+        }
+    };
+    // Commands whose stores have no in-memory implementation
+    let mongo = || {
+        db_pool
+            .clone()
+            .ok_or_else(|| anyhow!("This command needs MongoDB, run it with --storage mongo"))
     };
 
-    let db_pool = MongoDbPool::create_pool(mongo_config).await?;
     let birdeye: Arc<dyn BirdeyeApi> = Arc::new(BirdeyeClient::new(config.birdeye_api_key.clone()));
-    let analytics_service =
-        Arc::new(TokenAnalyticsService::new(db_pool.into(), birdeye.clone(), None).await?);
+    let openai_api_key = config.openai_api_key.clone();
+    let analytics_service = Arc::new(
+        TokenAnalyticsService::from_storage(&storage, birdeye.clone(), None)
+            .await?
+            .with_embeddings(embedding::from_config(&config.embedding, &openai_api_key))
+            .with_retention(config.retention.token_analytics),
+    );
 
-    let openai_api_key = std::env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set");
-    let analytics_llm = TokenAnalyticsLLM::new(analytics_service.clone(), &openai_api_key);
+    let mut tools = AnalystTools::new()
+        .with_analytics(analytics_service.clone())
+        .with_wallet_tracker(Arc::new(WalletTrackerService::from_storage(&storage)))
+        .allow_transfers(cli.allow_transfers);
+    match SolanaAgentKit::new_from_env() {
+        Ok(solana) => tools = tools.with_solana(Arc::new(solana)),
+        Err(e) => info!("On-chain tools disabled: {}", e),
+    }
+    let analytics_llm =
+        TokenAnalyticsLLM::new(analytics_service.clone(), &openai_api_key).with_tools(tools);
 
     // Process commands
     match cli.command {
@@ -89,6 +835,150 @@ async fn main() -> Result<()> {
             println!("\n{}", insights);
         }
 
+        Commands::Embed { limit, status } => {
+            let backend = embedding::from_config(&config.embedding, &openai_api_key);
+            let job = EmbeddingJob::new(mongo()?, backend, config.embedding.batch_size);
+            if !status {
+                let report = job.run(limit).await?;
+                println!(
+                    "Embedded {} snapshots and {} recommendations",
+                    report.analytics, report.recommendations
+                );
+            }
+            let pending = job.pending().await?;
+            println!(
+                "Pending ({}): {} snapshots, {} recommendations",
+                job.version(),
+                pending.analytics,
+                pending.recommendations
+            );
+        }
+
+        Commands::Retention => {
+            let job = RetentionJob::new(mongo()?, &config.retention);
+            for report in job.run().await? {
+                println!(
+                    "{}: {} hourly and {} daily buckets updated",
+                    report.collection, report.hourly, report.daily
+                );
+            }
+        }
+
+        Commands::Chat { session, list } => {
+            let store = ChatSessionStore::new(mongo()?);
+            if list {
+                for session in store.recent(20).await? {
+                    let title: String = session.title().chars().take(60).collect();
+                    println!(
+                        "{}  {}  {:>3} turns  {}",
+                        session.id.map(|id| id.to_hex()).unwrap_or_default(),
+                        session.updated_at,
+                        session.turns.len(),
+                        title
+                    );
+                }
+                return Ok(());
+            }
+
+            let mut session = match session {
+                Some(id) => store
+                    .get(id)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("No chat session {}", id))?,
+                None => ChatSession::new(),
+            };
+            for turn in session.recent_turns() {
+                let speaker = match turn.role {
+                    ChatRole::User => "you",
+                    ChatRole::Assistant => "analyst",
+                };
+                println!("{}> {}\n", speaker, turn.content);
+            }
+
+            let chat = analytics_llm.chat();
+            println!("Type a question, or 'exit' to quit.");
+            let stdin = std::io::stdin();
+            loop {
+                print!("you> ");
+                std::io::stdout().flush()?;
+                let mut input = String::new();
+                if stdin.read_line(&mut input)? == 0 {
+                    break;
+                }
+                let input = input.trim();
+                if input.is_empty() {
+                    continue;
+                }
+                if input == "exit" || input == "quit" {
+                    break;
+                }
+
+                match chat.respond(&mut session, input).await {
+                    Ok(reply) => println!("\nanalyst> {}\n", reply),
+                    Err(e) => {
+                        error!("Chat failed: {}", e);
+                        continue;
+                    }
+                }
+                store.save(&mut session).await?;
+            }
+            if let Some(id) = session.id {
+                println!("Resume with: cainam chat --session {}", id.to_hex());
+            }
+        }
+
+        Commands::Backtest {
+            address,
+            days,
+            strategies,
+            fee,
+        } => {
+            let mut strategy_config = config.strategy.clone();
+            if !strategies.is_empty() {
+                strategy_config.strategies = strategies.iter().map(|s| s.to_lowercase()).collect();
+            }
+            let llm: Arc<dyn LlmBackend> = Arc::new(OpenAIBackend::new(
+                &openai_api_key,
+                cainam_core::config::get_openai_model(),
+            ));
+            let Some(strategy) = strategy::from_config(&strategy_config, Some(llm))? else {
+                println!("No strategies configured; set STRATEGIES or pass --strategies");
+                return Ok(());
+            };
+
+            let end = bson::DateTime::now();
+            let start =
+                bson::DateTime::from_millis(end.timestamp_millis() - days * 24 * 60 * 60 * 1000);
+            let history = analytics_service
+                .get_token_history(&address, start, end)
+                .await?;
+            if history.is_empty() {
+                println!(
+                    "No stored snapshots for {} in the last {} days",
+                    address, days
+                );
+                return Ok(());
+            }
+
+            let backtester = Backtester {
+                fee_pct: fee,
+                ..Default::default()
+            };
+            let report = backtester.run(strategy.as_ref(), &history).await?;
+            println!("\n{}", report);
+            for trade in &report.trades {
+                println!(
+                    "  {} ${:.8} -> {} ${:.8} {:+.2}%{}",
+                    trade.entry_at,
+                    trade.entry_price,
+                    trade.exit_at,
+                    trade.exit_price,
+                    trade.return_pct * 100.0,
+                    if trade.open { " (open)" } else { "" }
+                );
+            }
+        }
+
         Commands::Token { address } => {
             info!("Fetching token analytics for {}", address);
             let overview = birdeye.get_token_overview(&address).await?;
@@ -183,7 +1073,23 @@ async fn main() -> Result<()> {
             );
             println!("Press Ctrl+C to stop.");
 
+            let signal_gate = SignalGate::new(mongo()?, SignalPolicy::new_from_env()?).await?;
+            // Snapshots must be rolled up before they expire
+            let retention = db_pool
+                .clone()
+                .map(|pool| RetentionJob::new(pool, &config.retention));
+            let mut last_retention: Option<std::time::Instant> = None;
+
             loop {
+                if let Some(job) = &retention {
+                    if last_retention.is_none_or(|at| at.elapsed() >= RETENTION_INTERVAL) {
+                        if let Err(e) = job.run().await {
+                            error!("Retention run failed: {}", e);
+                        }
+                        last_retention = Some(std::time::Instant::now());
+                    }
+                }
+
                 // Get trending tokens to compare with monitored tokens
                 if let Ok(trending) = analytics_service.get_trending_tokens(5).await {
                     println!("\nTop Trending Tokens:");
@@ -207,6 +1113,11 @@ async fn main() -> Result<()> {
                                     .generate_market_signals(&analytics)
                                     .await?
                                 {
+                                    // Repeats of an ongoing episode are not reported again
+                                    if !signal_gate.admit(&signal).await?.is_actionable() {
+                                        continue;
+                                    }
+
                                     println!(
                                         "\n[{}] Signal for {} ({}):",
                                         chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
@@ -245,6 +1156,277 @@ async fn main() -> Result<()> {
                 tokio::time::sleep(tokio::time::Duration::from_secs(interval)).await;
             }
         }
+
+        Commands::Reprice => {
+            let tracker =
+                RecommendationTracker::new(mongo()?, analytics_service.clone(), birdeye.clone());
+            let written = tracker.reprice_due().await?;
+            println!("Stored {} recommendation outcomes", written);
+        }
+
+        Commands::Similar {
+            address,
+            k,
+            hours,
+            min_liquidity,
+            min_volume,
+        } => {
+            let filter = AnalyticsFilter {
+                min_liquidity,
+                min_volume_24h: min_volume,
+                ..AnalyticsFilter::last_hours(hours)
+            };
+            let similar = analytics_service
+                .find_similar_tokens(&address, k, &filter)
+                .await?;
+            if similar.is_empty() {
+                println!("No comparable tokens in the last {} hours", hours);
+            }
+            for (rank, token) in similar.iter().enumerate() {
+                let drivers: Vec<String> = token
+                    .drivers
+                    .iter()
+                    .map(|m| format!("{} {:.3} vs {:.3}", m.feature, m.value, m.reference))
+                    .collect();
+                println!(
+                    "{:>2}. {:<10} {}  distance {:.3}\n    closest on: {}",
+                    rank + 1,
+                    token.analytics.token_symbol,
+                    token.analytics.token_address,
+                    token.distance,
+                    drivers.join(", ")
+                );
+            }
+        }
+
+        Commands::Scorecard { horizon } => {
+            let tracker =
+                RecommendationTracker::new(mongo()?, analytics_service.clone(), birdeye.clone());
+            println!("\n{}", tracker.scorecard(horizon).await?);
+        }
+
+        Commands::Calibrate {
+            horizon,
+            min_samples,
+        } => {
+            let tracker = SignalTracker::new(mongo()?, analytics_service.clone());
+            let measured = tracker.measure_outcomes(horizon).await?;
+            let fitted = tracker.fit_calibration(horizon, min_samples).await?;
+
+            println!("\nMeasured {} signal outcomes", measured);
+            if fitted.is_empty() {
+                println!(
+                    "No signal type has {} outcomes yet; confidence stays uncalibrated",
+                    min_samples
+                );
+            }
+            for calibration in fitted {
+                println!(
+                    "\n{:?} ({} samples, base rate {:.1}%):",
+                    calibration.signal_type,
+                    calibration.samples,
+                    calibration.base_rate * 100.0
+                );
+                let model = &calibration.calibrator;
+                for (raw, probability) in model.knots.iter().zip(&model.values) {
+                    println!("  raw {:.3} -> {:.3}", raw, probability);
+                }
+            }
+        }
+
+        Commands::Db { .. } | Commands::Config { .. } | Commands::Export { .. } => {
+            unreachable!("handled before service initialization")
+        }
+
+        Commands::Eval { command } => match command {
+            EvalCommands::Label { dataset, out } => {
+                let mut data = EvalDataset::load(&dataset)?;
+                let filled = data.label_from_analytics(&analytics_service).await?;
+                data.save(out.as_deref().unwrap_or(&dataset))?;
+                println!("Labeled {} outcomes ({} cases)", filled, data.cases.len());
+            }
+
+            EvalCommands::Run {
+                dataset,
+                model,
+                threshold,
+                batch_size,
+                out,
+            } => {
+                let data = EvalDataset::load(&dataset)?;
+                let backend = Arc::new(OpenAIBackend::new(&openai_api_key, &model));
+                let filter = TokenFilterService::with_backend(birdeye.clone(), &storage, backend);
+
+                let report = EvalRunner::new(&filter)
+                    .batch_size(batch_size)
+                    .success_threshold(threshold)
+                    .run(&data)
+                    .await?;
+
+                if let Some(path) = out {
+                    report.save(&path)?;
+                    info!("Eval report written to {}", path);
+                }
+                println!("\n{}", report);
+            }
+
+            EvalCommands::Diff { .. } => unreachable!("handled before service initialization"),
+        },
+
+        Commands::Exits { command } => {
+            let ledger = PositionLedger::new(mongo()?);
+            let store = ExitRuleStore::new(mongo()?);
+
+            match command {
+                ExitCommands::List => {
+                    let positions = ledger.open_positions(None).await?;
+                    if positions.is_empty() {
+                        println!("No open positions");
+                    }
+                    for position in positions {
+                        let Some(id) = position.id else { continue };
+                        println!(
+                            "\n{} {} — {} left of {} @ ${:.8}",
+                            id,
+                            position.token_address,
+                            position.quantity,
+                            position.initial_quantity,
+                            position.entry_price
+                        );
+                        match store.get(id).await? {
+                            Some(rules) if !rules.is_empty() => print_exit_rules(&rules),
+                            _ => println!("  No exit rules"),
+                        }
+                    }
+                }
+
+                ExitCommands::Set {
+                    position,
+                    stop_loss,
+                    trailing_stop,
+                    take_profit,
+                } => {
+                    let open = ledger.get(position).await?;
+                    if !open.is_open() {
+                        anyhow::bail!("Position {} is closed", position);
+                    }
+
+                    let mut rules = match store.get(position).await? {
+                        Some(rules) => rules,
+                        None => ExitRules::new(position, &open)?,
+                    };
+                    if let Some(pct) = stop_loss {
+                        rules.stop_price =
+                            (pct > 0.0).then_some(open.entry_price.to_f64()? * (1.0 - pct));
+                    }
+                    if let Some(pct) = trailing_stop {
+                        rules.trailing_stop_pct = (pct > 0.0).then_some(pct);
+                    }
+                    if !take_profit.is_empty() {
+                        rules.take_profit.retain(|level| level.filled);
+                        for level in take_profit {
+                            rules = rules.with_take_profit(level.price, level.size_pct);
+                        }
+                    }
+
+                    store.save(&rules).await?;
+                    println!("Exit rules for {}:", position);
+                    print_exit_rules(&rules);
+                }
+
+                ExitCommands::Clear { position } => {
+                    if store.remove(position).await? {
+                        println!("Removed exit rules from {}", position);
+                    } else {
+                        println!("Position {} has no exit rules", position);
+                    }
+                }
+            }
+        }
+
+        Commands::Schedule { command } => {
+            let store = ParentOrderStore::new(mongo()?);
+
+            match command {
+                ScheduleCommands::List => {
+                    let orders = store.open().await?;
+                    if orders.is_empty() {
+                        println!("No scheduled orders");
+                    }
+                    for order in &orders {
+                        print_parent_order(order)?;
+                    }
+                }
+                ScheduleCommands::Dca {
+                    token,
+                    side,
+                    quantity,
+                    interval_hours,
+                    entries,
+                    position,
+                } => {
+                    let schedule = Schedule::dca(interval_hours, entries);
+                    create_parent_order(&store, &token, side, quantity, schedule, position).await?;
+                }
+                ScheduleCommands::Twap {
+                    token,
+                    side,
+                    quantity,
+                    window_mins,
+                    slices,
+                    position,
+                } => {
+                    let schedule = Schedule::twap(window_mins * 60, slices);
+                    create_parent_order(&store, &token, side, quantity, schedule, position).await?;
+                }
+                ScheduleCommands::Pause { id } => print_parent_order(&store.pause(id).await?)?,
+                ScheduleCommands::Resume { id } => print_parent_order(&store.resume(id).await?)?,
+                ScheduleCommands::Cancel { id } => print_parent_order(&store.cancel(id).await?)?,
+            }
+        }
+
+        Commands::Orders { command } => {
+            let store = LimitOrderStore::new(mongo()?);
+
+            match command {
+                OrderCommands::List => {
+                    let orders = store.pending(None).await?;
+                    if orders.is_empty() {
+                        println!("No pending limit orders");
+                    }
+                    for order in &orders {
+                        print_limit_order(order);
+                    }
+                }
+                OrderCommands::Place {
+                    token,
+                    side,
+                    quantity,
+                    limit,
+                    expires_mins,
+                    slippage,
+                    position,
+                } => {
+                    let expires_at = bson::DateTime::from_millis(
+                        bson::DateTime::now().timestamp_millis() + expires_mins * 60 * 1000,
+                    );
+                    let mut order = LimitOrder::new(
+                        &token,
+                        side.into(),
+                        token_amount(&token, quantity).await?,
+                        limit,
+                        slippage.unwrap_or(config.trading.trade_max_slippage),
+                        expires_at,
+                        "cli",
+                    );
+                    if let Some(position) = position {
+                        order = order.with_position(position);
+                    }
+                    print_limit_order(&store.insert(&order).await?);
+                }
+                OrderCommands::Cancel { id } => print_limit_order(&store.cancel(id).await?),
+            }
+        }
     }
 
     Ok(())
@@ -1,4 +1,5 @@
 use super::BIRDEYE_API_URL;
+use crate::logging::metrics::metrics;
 use crate::models::market_data::{ApiResponse, TokenMarketResponse};
 use crate::models::token_info::TokenExtensions;
 use crate::models::token_trending::{TrendingToken, TrendingTokenData};
@@ -7,6 +8,7 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Instant;
 use tracing::{debug, error};
 use serde_json;
 
@@ -342,13 +344,20 @@ impl BirdeyeClient {
         let url = format!("{}{}", BIRDEYE_API_URL, endpoint);
         debug!("Making GET request to: {}", url);
 
+        let start = Instant::now();
         let response = self
             .client
             .get(&url)
             .header("X-API-KEY", &self.api_key)
             .send()
-            .await
-            .context(format!("Failed to send GET request to {}", url))?;
+            .await;
+        metrics().observe_request(
+            "birdeye",
+            endpoint,
+            start.elapsed(),
+            response.as_ref().is_ok_and(|r| r.status().is_success()),
+        );
+        let response = response.context(format!("Failed to send GET request to {}", url))?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -0,0 +1,119 @@
+use crate::error::{AgentError, AgentResult};
+use std::env;
+use std::str::FromStr;
+
+/// Service that turns text into embedding vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingProvider {
+    OpenAI,
+    /// OpenAI-compatible `/v1/embeddings` endpoint, e.g. Ollama or llama.cpp
+    Local,
+}
+
+impl FromStr for EmbeddingProvider {
+    type Err = AgentError;
+
+    fn from_str(s: &str) -> AgentResult<Self> {
+        match s.to_lowercase().as_str() {
+            "openai" => Ok(EmbeddingProvider::OpenAI),
+            "local" => Ok(EmbeddingProvider::Local),
+            _ => Err(AgentError::InvalidConfig(
+                "EMBEDDING_PROVIDER".into(),
+                format!("unknown provider '{}', expected openai or local", s),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EmbeddingConfig {
+    pub provider: EmbeddingProvider,
+    pub model: String,
+    /// Endpoint of the local provider
+    pub url: String,
+    /// Length of the vectors, which must match the Atlas vector index
+    pub dimensions: usize,
+    /// Documents embedded per request
+    pub batch_size: usize,
+    /// Atlas vector search index over the `embedding` field
+    pub index_name: String,
+}
+
+impl EmbeddingConfig {
+    pub fn new_from_env() -> AgentResult<Self> {
+        let defaults = Self::default();
+        let config = Self {
+            provider: env::var("EMBEDDING_PROVIDER")
+                .map(|v| v.parse())
+                .unwrap_or(Ok(defaults.provider))?,
+            model: env::var("EMBEDDING_MODEL").unwrap_or(defaults.model),
+            url: env::var("EMBEDDING_URL").unwrap_or(defaults.url),
+            dimensions: parse_env("EMBEDDING_DIMENSIONS", defaults.dimensions)?,
+            batch_size: parse_env("EMBEDDING_BATCH_SIZE", defaults.batch_size)?,
+            index_name: env::var("MONGODB_VECTOR_INDEX_NAME").unwrap_or(defaults.index_name),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> AgentResult<()> {
+        if self.model.is_empty() {
+            return Err(AgentError::InvalidConfig(
+                "embedding model".into(),
+                "cannot be empty".into(),
+            ));
+        }
+        if self.dimensions == 0 || self.batch_size == 0 {
+            return Err(AgentError::InvalidConfig(
+                "embedding dimensions and batch size".into(),
+                "must be at least 1".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            provider: EmbeddingProvider::OpenAI,
+            model: rig::providers::openai::TEXT_EMBEDDING_3_SMALL.to_string(),
+            url: "http://localhost:11434/v1/embeddings".to_string(),
+            dimensions: 1536,
+            batch_size: 64,
+            index_name: crate::vector_store::DEFAULT_INDEX_NAME.to_string(),
+        }
+    }
+}
+
+fn parse_env<T: FromStr>(key: &str, default: T) -> AgentResult<T> {
+    match env::var(key) {
+        Ok(val) => val.parse().map_err(|_| {
+            AgentError::InvalidConfig(key.to_string(), "must be a valid number".to_string())
+        }),
+        Err(_) => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_and_validation() {
+        assert_eq!(
+            "Local".parse::<EmbeddingProvider>().unwrap(),
+            EmbeddingProvider::Local
+        );
+        assert!("cohere".parse::<EmbeddingProvider>().is_err());
+
+        let config = EmbeddingConfig::default();
+        assert!(config.validate().is_ok());
+        assert!(EmbeddingConfig {
+            dimensions: 0,
+            ..config
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,643 @@
+//! Layered configuration from `cainam.toml`.
+//!
+//! Settings are named after the environment variables they replace, in lower
+//! case. Sections only group them:
+//!
+//! ```toml
+//! profile = "paper"
+//!
+//! [trading]
+//! trade_min_confidence = 0.8
+//! strategies = ["momentum", "breakout"]
+//!
+//! [secrets]
+//! openai_api_key_file = "/run/secrets/openai_api_key"
+//!
+//! [profiles.live.trading]
+//! trade_max_amount = 500
+//! ```
+//!
+//! Later layers win: the base sections, then `[profiles.<profile>]`, then
+//! environment variables (`.env` included). A `<key>_file` setting or a
+//! `<KEY>_FILE` variable reads the value from a file, for mounted secrets.
+
+use super::agent_config::AgentConfig;
+use super::embedding_config::EmbeddingConfig;
+use super::market_config::MarketConfig;
+use super::retention_config::RetentionConfig;
+use super::strategy_config::StrategyConfig;
+use super::transaction_config::TransactionConfig;
+use crate::error::{AgentError, AgentResult};
+use crate::signals::state::SignalPolicy;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{env, fs};
+use toml::value::{Table, Value};
+
+/// Read from the working directory when no path is given
+pub const DEFAULT_CONFIG_FILE: &str = "cainam.toml";
+/// Path of the config file, unless one is passed explicitly
+pub const CONFIG_FILE_VAR: &str = "CAINAM_CONFIG";
+/// Profile to use, unless one is passed explicitly
+pub const PROFILE_VAR: &str = "CAINAM_PROFILE";
+
+const SECRET_FILE_SUFFIX: &str = "_FILE";
+
+/// Every setting the agent reads, by environment variable name
+pub const KNOWN_KEYS: &[&str] = &[
+    "ANALYSIS_INTERVAL",
+    "ANTHROPIC_MODEL",
+    "BASE_CONFIDENCE",
+    "BIRDEYE_API_KEY",
+    "BIRDEYE_API_URL",
+    "BREAKOUT_BUFFER",
+    "BREAKOUT_LOOKBACK",
+    "CONFIG_RELOAD_SECS",
+    "EMBEDDING_BATCH_SIZE",
+    "EMBEDDING_DIMENSIONS",
+    "EMBEDDING_MODEL",
+    "EMBEDDING_PROVIDER",
+    "EMBEDDING_URL",
+    "EXIT_STOP_LOSS_PCT",
+    "EXIT_TRAILING_STOP_PCT",
+    "HELIUS_API_KEY",
+    "JUPITER_API_URL",
+    "MEAN_REVERSION_PERIOD",
+    "MEAN_REVERSION_Z",
+    "METRICS_ADDR",
+    "MOMENTUM_LOOKBACK",
+    "MOMENTUM_THRESHOLD",
+    "MONGODB_APP_NAME",
+    "MONGODB_CONNECT_TIMEOUT_MS",
+    "MONGODB_DATABASE",
+    "MONGODB_MAX_POOL_SIZE",
+    "MONGODB_MIN_POOL_SIZE",
+    "MONGODB_URI",
+    "MONGODB_VECTOR_INDEX_NAME",
+    "OPENAI_API_KEY",
+    "OPENAI_MODEL",
+    "PRICE_CHANGE_THRESHOLD",
+    "PRICE_WEIGHT",
+    "RETENTION_ANALYTICS_DAILY_DAYS",
+    "RETENTION_ANALYTICS_HOURLY_DAYS",
+    "RETENTION_ANALYTICS_RAW_DAYS",
+    "RETENTION_TRENDING_DAILY_DAYS",
+    "RETENTION_TRENDING_HOURLY_DAYS",
+    "RETENTION_TRENDING_RAW_DAYS",
+    "RUST_LOG",
+    "SIGNAL_COOLDOWN_SECS",
+    "SIGNAL_EPISODE_GAP_SECS",
+    "SIGNAL_FLIP_CONFIRMATIONS",
+    "SOLANA_PRIVATE_KEY",
+    "SOLANA_RPC_URL",
+    "STRATEGIES",
+    "STRATEGY_ENSEMBLE",
+    "STRATEGY_ENSEMBLE_THRESHOLD",
+    "STRATEGY_WEIGHTS",
+    "TRADE_DRY_RUN",
+    "TRADE_MAX_AMOUNT",
+    "TRADE_MAX_SLIPPAGE",
+    "TRADE_MIN_CONFIDENCE",
+    "TRADE_QUOTE_MINT",
+    "TRADE_TWAP_SLICES",
+    "TRADE_TWAP_WINDOW",
+    "TWITTER_BEARER_TOKEN",
+    "TX_ATTEMPT_TIMEOUT_SECS",
+    "TX_COMMITMENT",
+    "TX_COMPUTE_UNIT_LIMIT",
+    "TX_MAX_PRIORITY_FEE",
+    "TX_MAX_RESENDS",
+    "TX_MIN_PRIORITY_FEE",
+    "TX_POLL_INTERVAL_MS",
+    "TX_PRIORITY_FEE_PERCENTILE",
+    "VOLUME_SURGE_THRESHOLD",
+    "VOLUME_WEIGHT",
+    "WATCHLIST",
+];
+
+/// Settings whose values are never printed
+const SECRET_KEYS: &[&str] = &[
+    "BIRDEYE_API_KEY",
+    "HELIUS_API_KEY",
+    "MONGODB_URI",
+    "OPENAI_API_KEY",
+    "SOLANA_PRIVATE_KEY",
+    "TWITTER_BEARER_TOKEN",
+];
+
+/// Deployment the settings are for. Paper trading needs an RPC endpoint and
+/// live trading also needs a wallet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Profile {
+    #[default]
+    Dev,
+    Paper,
+    Live,
+}
+
+impl Profile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Profile::Dev => "dev",
+            Profile::Paper => "paper",
+            Profile::Live => "live",
+        }
+    }
+
+    /// Settings that must be set for this profile
+    fn required_keys(&self) -> Vec<&'static str> {
+        let mut keys = vec!["MONGODB_URI", "MONGODB_DATABASE"];
+        if *self != Profile::Dev {
+            keys.push("SOLANA_RPC_URL");
+        }
+        if *self == Profile::Live {
+            keys.push("SOLANA_PRIVATE_KEY");
+        }
+        keys
+    }
+}
+
+impl FromStr for Profile {
+    type Err = AgentError;
+
+    fn from_str(s: &str) -> AgentResult<Self> {
+        match s {
+            "dev" => Ok(Profile::Dev),
+            "paper" => Ok(Profile::Paper),
+            "live" => Ok(Profile::Live),
+            _ => Err(AgentError::InvalidConfig(
+                "profile".into(),
+                format!("unknown profile '{}', expected dev, paper or live", s),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Layer a setting's value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    File,
+    Profile,
+    Env,
+    SecretFile(PathBuf),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File => write!(f, "file"),
+            Source::Profile => write!(f, "profile"),
+            Source::Env => write!(f, "env"),
+            Source::SecretFile(path) => write!(f, "secret {}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub value: String,
+    pub source: Source,
+}
+
+/// Settings resolved from the config file, its profile and the environment
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub path: Option<PathBuf>,
+    pub profile: Profile,
+    settings: BTreeMap<&'static str, Setting>,
+    /// Problems found while reading the layers, as (setting, message)
+    problems: Vec<(String, String)>,
+}
+
+impl ConfigFile {
+    /// Loads `path`, else `$CAINAM_CONFIG`, else `./cainam.toml` when it
+    /// exists. `profile` takes precedence over `$CAINAM_PROFILE` and the
+    /// file's `profile` key.
+    pub fn load(path: Option<&Path>, profile: Option<Profile>) -> AgentResult<Self> {
+        let path = match path
+            .map(Path::to_path_buf)
+            .or_else(|| env::var_os(CONFIG_FILE_VAR).map(PathBuf::from))
+        {
+            Some(path) => Some(path),
+            None => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+        };
+        let text = match &path {
+            Some(path) => Some(fs::read_to_string(path).map_err(|e| {
+                AgentError::Config(format!("Failed to read {}: {}", path.display(), e))
+            })?),
+            None => None,
+        };
+        let profile = match profile {
+            Some(profile) => Some(profile),
+            None => env::var(PROFILE_VAR).ok().map(|p| p.parse()).transpose()?,
+        };
+        Self::resolve(path, text.as_deref(), profile, |key| env::var(key).ok())
+    }
+
+    /// Reads the file again for the same profile. Environment variables
+    /// keep the precedence they had at load, since `apply` has exported the
+    /// file's own settings since. Problems in the file are an error.
+    pub fn reload(&self) -> AgentResult<Self> {
+        let text = match &self.path {
+            Some(path) => Some(fs::read_to_string(path).map_err(|e| {
+                AgentError::Config(format!("Failed to read {}: {}", path.display(), e))
+            })?),
+            None => None,
+        };
+        let pinned: BTreeMap<&str, String> = self
+            .settings
+            .iter()
+            .filter(|(_, setting)| setting.source == Source::Env)
+            .map(|(key, setting)| (*key, setting.value.clone()))
+            .collect();
+        let config = Self::resolve(
+            self.path.clone(),
+            text.as_deref(),
+            Some(self.profile),
+            |key| pinned.get(key).cloned(),
+        )?;
+        match config.problems.first() {
+            Some((setting, message)) => {
+                Err(AgentError::InvalidConfig(setting.clone(), message.clone()))
+            }
+            None => Ok(config),
+        }
+    }
+
+    pub(crate) fn resolve(
+        path: Option<PathBuf>,
+        text: Option<&str>,
+        profile: Option<Profile>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> AgentResult<Self> {
+        let mut table = match text {
+            Some(text) => toml::from_str::<Table>(text).map_err(|e| {
+                let name = path.as_deref().unwrap_or(Path::new(DEFAULT_CONFIG_FILE));
+                AgentError::Config(format!("Failed to parse {}: {}", name.display(), e))
+            })?,
+            None => Table::new(),
+        };
+        let profile = match (profile, table.remove("profile")) {
+            (Some(profile), _) => profile,
+            (None, Some(Value::String(name))) => name.parse()?,
+            (None, Some(_)) => {
+                return Err(AgentError::InvalidConfig(
+                    "profile".into(),
+                    "must be a string".into(),
+                ))
+            }
+            (None, None) => Profile::default(),
+        };
+        let profiles = table.remove("profiles");
+
+        let mut config = Self {
+            path,
+            profile,
+            settings: BTreeMap::new(),
+            problems: Vec::new(),
+        };
+        config.merge(&table, "", &Source::File);
+        match profiles {
+            Some(Value::Table(profiles)) => {
+                for (name, layer) in &profiles {
+                    let section = format!("profiles.{}", name);
+                    match (name.parse::<Profile>(), layer) {
+                        (Ok(p), Value::Table(layer)) if p == profile => {
+                            config.merge(layer, &section, &Source::Profile)
+                        }
+                        (Ok(_), Value::Table(_)) => {}
+                        (Ok(_), _) => config.problem(&section, "must be a table".into()),
+                        (Err(_), _) => config.problem(
+                            &section,
+                            "is not a profile, expected dev, paper or live".into(),
+                        ),
+                    }
+                }
+            }
+            Some(_) => config.problem("profiles", "must be a table".into()),
+            None => {}
+        }
+
+        // Environment variables win over the file
+        for key in KNOWN_KEYS {
+            if let Some(value) = env(key) {
+                config.set(key, value, Source::Env);
+            } else if let Some(path) = env(&format!("{}{}", key, SECRET_FILE_SUFFIX)) {
+                config.read_secret(key, PathBuf::from(path));
+            }
+        }
+        Ok(config)
+    }
+
+    fn merge(&mut self, table: &Table, section: &str, source: &Source) {
+        for (name, value) in table {
+            let location = if section.is_empty() {
+                name.clone()
+            } else {
+                format!("{}.{}", section, name)
+            };
+            if let Value::Table(inner) = value {
+                self.merge(inner, &location, source);
+                continue;
+            }
+
+            let name = name.to_ascii_uppercase();
+            if let Some(key) = name.strip_suffix(SECRET_FILE_SUFFIX).and_then(known_key) {
+                match value {
+                    Value::String(path) => self.read_secret(key, PathBuf::from(path)),
+                    _ => self.problem(&location, "must be a file path".into()),
+                }
+                continue;
+            }
+            let Some(key) = known_key(&name) else {
+                self.problem(&location, "is not a known setting".into());
+                continue;
+            };
+            match scalar(value) {
+                Some(value) => self.set(key, value, source.clone()),
+                None => self.problem(&location, "must be a string, number, bool or list".into()),
+            }
+        }
+    }
+
+    fn read_secret(&mut self, key: &'static str, path: PathBuf) {
+        match fs::read_to_string(&path) {
+            Ok(secret) => {
+                let secret = secret.trim_end_matches(['\r', '\n']).to_string();
+                self.set(key, secret, Source::SecretFile(path));
+            }
+            Err(e) => self.problem(key, format!("failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    fn set(&mut self, key: &'static str, value: String, source: Source) {
+        self.settings.insert(key, Setting { value, source });
+    }
+
+    fn problem(&mut self, setting: &str, message: String) {
+        self.problems.push((setting.to_string(), message));
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Setting> {
+        self.settings.get(key)
+    }
+
+    /// Exports the file's settings to the environment, so every
+    /// `new_from_env` constructor reads the layered values. Variables that
+    /// are already set are left alone.
+    pub fn apply(&self) {
+        env::set_var(PROFILE_VAR, self.profile.as_str());
+        for (key, setting) in &self.settings {
+            if setting.source != Source::Env {
+                env::set_var(key, &setting.value);
+            }
+        }
+    }
+
+    /// Checks every section against the environment, which `apply` must
+    /// have been called on, and returns all problems rather than the first
+    pub fn validate(&self) -> Vec<AgentError> {
+        let mut problems: Vec<AgentError> = self
+            .problems
+            .iter()
+            .map(|(setting, message)| AgentError::InvalidConfig(setting.clone(), message.clone()))
+            .collect();
+
+        problems.extend(
+            self.profile
+                .required_keys()
+                .into_iter()
+                .filter(|key| env::var(key).map_or(true, |value| value.is_empty()))
+                .map(|key| AgentError::MissingEnvVar(key.to_string())),
+        );
+
+        let sections = [
+            StrategyConfig::new_from_env().err(),
+            EmbeddingConfig::new_from_env().err(),
+            RetentionConfig::new_from_env().err(),
+            TransactionConfig::new_from_env().err(),
+            MarketConfig::new_from_env()
+                .and_then(|config| config.validate())
+                .err(),
+            SignalPolicy::new_from_env().err(),
+        ];
+        let sections_valid = sections.iter().all(Option::is_none);
+        problems.extend(sections.into_iter().flatten());
+
+        // The agent config nests the sections above, so only its own values
+        // are left to check once they are valid
+        if sections_valid {
+            match AgentConfig::load_from_env() {
+                Ok(config) => problems.extend(config.problems()),
+                Err(e) => problems.push(e),
+            }
+        }
+        problems
+    }
+
+    /// Like `validate`, but as one error listing every problem
+    pub fn ensure_valid(&self) -> AgentResult<()> {
+        let problems = self.validate();
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let list: Vec<String> = problems.iter().map(|p| format!("  - {}", p)).collect();
+        Err(AgentError::Config(format!(
+            "{} problem(s) in profile {}:\n{}",
+            problems.len(),
+            self.profile,
+            list.join("\n")
+        )))
+    }
+}
+
+/// The effective settings with secrets redacted. Unset settings use the
+/// defaults of the sections that read them.
+impl fmt::Display for ConfigFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "profile: {}", self.profile)?;
+        match &self.path {
+            Some(path) => writeln!(f, "file: {}", path.display())?,
+            None => writeln!(f, "file: none")?,
+        }
+        let width = KNOWN_KEYS.iter().map(|key| key.len()).max().unwrap_or(0);
+        for key in KNOWN_KEYS {
+            match self.settings.get(key) {
+                Some(setting) => {
+                    let value = if SECRET_KEYS.contains(key) {
+                        "<redacted>"
+                    } else {
+                        setting.value.as_str()
+                    };
+                    writeln!(f, "{:width$} = {}  ({})", key, value, setting.source)?
+                }
+                None => writeln!(f, "{:width$}   (default)", key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn known_key(name: &str) -> Option<&'static str> {
+    KNOWN_KEYS.iter().copied().find(|key| *key == name)
+}
+
+/// Setting value in the form the environment variable takes. Lists become
+/// comma separated.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Integer(i) => Some(i.to_string()),
+        Value::Float(x) => Some(x.to_string()),
+        Value::Boolean(b) => Some(b.to_string()),
+        Value::Array(items) => items
+            .iter()
+            .map(scalar)
+            .collect::<Option<Vec<_>>>()
+            .map(|items| items.join(",")),
+        Value::Datetime(_) | Value::Table(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const FILE: &str = r#"
+        profile = "paper"
+
+        [trading]
+        trade_min_confidence = 0.7
+        trade_max_amount = 100
+        strategies = ["momentum", "breakout"]
+
+        [profiles.live.trading]
+        trade_max_amount = 500
+
+        [profiles.paper.trading]
+        trade_max_amount = 50
+    "#;
+
+    fn resolve(
+        text: &str,
+        profile: Option<Profile>,
+        env: &[(&str, &str)],
+    ) -> AgentResult<ConfigFile> {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        ConfigFile::resolve(None, Some(text), profile, |key| env.get(key).cloned())
+    }
+
+    fn value<'a>(config: &'a ConfigFile, key: &str) -> Option<&'a str> {
+        config.get(key).map(|setting| setting.value.as_str())
+    }
+
+    #[test]
+    fn test_layers() {
+        let config = resolve(FILE, None, &[("TRADE_MIN_CONFIDENCE", "0.9")]).unwrap();
+        assert_eq!(config.profile, Profile::Paper);
+        assert_eq!(value(&config, "TRADE_MAX_AMOUNT"), Some("50"));
+        assert_eq!(
+            config.get("TRADE_MAX_AMOUNT").unwrap().source,
+            Source::Profile
+        );
+        assert_eq!(value(&config, "TRADE_MIN_CONFIDENCE"), Some("0.9"));
+        assert_eq!(
+            config.get("TRADE_MIN_CONFIDENCE").unwrap().source,
+            Source::Env
+        );
+        assert_eq!(value(&config, "STRATEGIES"), Some("momentum,breakout"));
+
+        let live = resolve(FILE, Some(Profile::Live), &[]).unwrap();
+        assert_eq!(value(&live, "TRADE_MAX_AMOUNT"), Some("500"));
+        assert_eq!(value(&live, "TRADE_MIN_CONFIDENCE"), Some("0.7"));
+    }
+
+    #[test]
+    fn test_reports_every_file_problem() {
+        let text = r#"
+            [trading]
+            trade_min_confidense = 0.7
+            trade_max_amount = { value = 1 }
+            trade_twap_window = 1979-05-27T07:32:00Z
+
+            [profiles.staging]
+            trade_max_amount = 1
+
+            [secrets]
+            openai_api_key_file = "/nonexistent/openai"
+        "#;
+        let config = resolve(text, None, &[]).unwrap();
+        let settings: Vec<&str> = config.problems.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(
+            settings,
+            [
+                "OPENAI_API_KEY",
+                "trading.trade_max_amount.value",
+                "trading.trade_min_confidense",
+                "trading.trade_twap_window",
+                "profiles.staging",
+            ]
+        );
+
+        assert!(resolve("profile = \"prod\"", None, &[]).is_err());
+        assert!(resolve("[trading", None, &[]).is_err());
+    }
+
+    #[test]
+    fn test_reload_keeps_env_precedence() {
+        let path = env::temp_dir().join(format!("cainam-reload-{}.toml", std::process::id()));
+        fs::write(&path, FILE).unwrap();
+        let vars: HashMap<String, String> =
+            HashMap::from([("TRADE_MIN_CONFIDENCE".to_string(), "0.9".to_string())]);
+        let config = ConfigFile::resolve(Some(path.clone()), Some(FILE), None, |key| {
+            vars.get(key).cloned()
+        })
+        .unwrap();
+
+        fs::write(&path, FILE.replace("= 50", "= 75").replace("0.7", "0.6")).unwrap();
+        let reloaded = config.reload();
+        fs::write(&path, "[trading\n").unwrap();
+        let broken = config.reload();
+        fs::remove_file(&path).unwrap();
+
+        let reloaded = reloaded.unwrap();
+        assert_eq!(reloaded.profile, Profile::Paper);
+        assert_eq!(value(&reloaded, "TRADE_MAX_AMOUNT"), Some("75"));
+        assert_eq!(value(&reloaded, "TRADE_MIN_CONFIDENCE"), Some("0.9"));
+        assert!(broken.is_err());
+    }
+
+    #[test]
+    fn test_secret_files_are_redacted() {
+        let path = env::temp_dir().join(format!("cainam-secret-{}", std::process::id()));
+        fs::write(&path, "sk-test\n").unwrap();
+        let text = format!(
+            "[secrets]\nopenai_api_key_file = {:?}\n",
+            path.display().to_string()
+        );
+        let config = resolve(&text, None, &[("BIRDEYE_API_KEY", "be-test")]).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(value(&config, "OPENAI_API_KEY"), Some("sk-test"));
+        assert_eq!(
+            config.get("OPENAI_API_KEY").unwrap().source,
+            Source::SecretFile(path)
+        );
+        let report = config.to_string();
+        assert!(!report.contains("sk-test"));
+        assert!(!report.contains("be-test"));
+        assert!(report.contains("<redacted>"));
+    }
+}
@@ -0,0 +1,410 @@
+//! Trading and signal parameters that change without a restart.
+//!
+//! `LiveConfig` holds the current `LiveSettings` behind a watch channel, so
+//! readers take a snapshot and never see a half-applied change. A reload
+//! reads the config file again, puts the values of the Mongo `settings`
+//! document on top, validates the result and swaps it in. Every reload that
+//! changes a value is written to the audit log, rejected ones included.
+
+use super::file_config::ConfigFile;
+use super::market_config::MarketConfig;
+use super::trading_config::TradingConfig;
+use crate::error::{AgentError, AgentResult};
+use crate::models::config_change::{ConfigChange, SettingChange};
+use crate::storage::SettingsRepository;
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Seconds between reloads of a running agent's settings (0 disables)
+pub const RELOAD_INTERVAL_VAR: &str = "CONFIG_RELOAD_SECS";
+
+const DEFAULT_RELOAD_SECS: u64 = 30;
+
+/// Settings that take effect without a restart
+pub const LIVE_KEYS: &[&str] = &[
+    "ANALYSIS_INTERVAL",
+    "BASE_CONFIDENCE",
+    "EXIT_STOP_LOSS_PCT",
+    "EXIT_TRAILING_STOP_PCT",
+    "PRICE_CHANGE_THRESHOLD",
+    "PRICE_WEIGHT",
+    "TRADE_MAX_AMOUNT",
+    "TRADE_MAX_SLIPPAGE",
+    "TRADE_MIN_CONFIDENCE",
+    "TRADE_TWAP_SLICES",
+    "TRADE_TWAP_WINDOW",
+    "VOLUME_SURGE_THRESHOLD",
+    "VOLUME_WEIGHT",
+    "WATCHLIST",
+];
+
+/// One consistent set of the live settings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LiveSettings {
+    pub market: MarketConfig,
+    pub trading: TradingConfig,
+}
+
+impl LiveSettings {
+    pub fn new_from_env() -> AgentResult<Self> {
+        Self::from_settings(|key| env::var(key).ok())
+    }
+
+    /// Reads both sections from `get` and validates them
+    pub fn from_settings(get: impl Fn(&str) -> Option<String>) -> AgentResult<Self> {
+        let settings = Self {
+            market: MarketConfig::from_settings(&get)?,
+            trading: TradingConfig::from_settings(&get)?,
+        };
+        settings.market.validate()?;
+        settings.trading.validate()?;
+        Ok(settings)
+    }
+
+    fn values(&self) -> BTreeMap<&'static str, String> {
+        self.market
+            .settings()
+            .into_iter()
+            .chain(self.trading.settings())
+            .collect()
+    }
+
+    /// Settings whose value differs in `next`
+    pub fn changes(&self, next: &Self) -> Vec<SettingChange> {
+        changed_values(self, &next.values())
+    }
+}
+
+/// Settings of `current` that `values` gives another value
+fn changed_values(current: &LiveSettings, values: &BTreeMap<&str, String>) -> Vec<SettingChange> {
+    current
+        .values()
+        .into_iter()
+        .filter_map(|(setting, old_value)| {
+            let new_value = values.get(setting)?;
+            (*new_value != old_value).then(|| SettingChange {
+                setting: setting.to_string(),
+                old_value,
+                new_value: new_value.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Watched, atomically swapped handle to the live settings
+pub struct LiveConfig {
+    current: watch::Sender<Arc<LiveSettings>>,
+    /// Read again on every reload
+    file: Option<ConfigFile>,
+    /// Holds the `settings` document and the audit log
+    store: Option<Arc<dyn SettingsRepository>>,
+    /// Error of the last rejected reload, so a bad value is reported once.
+    /// Held for the whole reload, which serializes reloads.
+    rejected: Mutex<Option<String>>,
+}
+
+impl LiveConfig {
+    /// Fixed settings; reloads read the environment
+    pub fn new(settings: LiveSettings) -> Self {
+        let (current, _) = watch::channel(Arc::new(settings));
+        Self {
+            current,
+            file: None,
+            store: None,
+            rejected: Mutex::new(None),
+        }
+    }
+
+    /// Settings from `file`, with the `settings` document of `store` on top
+    pub async fn load(
+        file: ConfigFile,
+        store: Option<Arc<dyn SettingsRepository>>,
+    ) -> AgentResult<Self> {
+        let mut live = Self::new(LiveSettings::default());
+        live.file = Some(file);
+        live.store = store;
+        let values = live.read_sources().await?;
+        live.current
+            .send_replace(Arc::new(LiveSettings::from_settings(|key| {
+                values.get(key).cloned()
+            })?));
+        Ok(live)
+    }
+
+    /// Snapshot of the settings in effect
+    pub fn current(&self) -> Arc<LiveSettings> {
+        self.current.borrow().clone()
+    }
+
+    /// Receiver notified whenever a reload changes the settings
+    pub fn subscribe(&self) -> watch::Receiver<Arc<LiveSettings>> {
+        self.current.subscribe()
+    }
+
+    /// Value of every live setting that any source sets
+    async fn read_sources(&self) -> AgentResult<BTreeMap<&'static str, String>> {
+        let file = self.file.as_ref().map(ConfigFile::reload).transpose()?;
+        let overrides = match &self.store {
+            Some(store) => store.overrides().await?,
+            None => BTreeMap::new(),
+        };
+
+        let mut values = BTreeMap::new();
+        for key in LIVE_KEYS {
+            let value = overrides.get(*key).cloned().or_else(|| match &file {
+                Some(file) => file.get(key).map(|setting| setting.value.clone()),
+                None => env::var(key).ok(),
+            });
+            if let Some(value) = value {
+                values.insert(*key, value);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Reads the sources again and applies them when they are valid.
+    /// Returns the settings that changed. `source` is recorded in the audit
+    /// log as what triggered the reload.
+    pub async fn reload(&self, source: &str) -> AgentResult<Vec<SettingChange>> {
+        let mut rejected = self.rejected.lock().await;
+        let current = self.current();
+        let (values, next) = match self.read_sources().await {
+            Ok(values) => {
+                let next = LiveSettings::from_settings(|key| values.get(key).cloned());
+                (values, next)
+            }
+            Err(e) => (BTreeMap::new(), Err(e)),
+        };
+
+        let next = match next {
+            Ok(next) => next,
+            Err(e) => {
+                let error = e.to_string();
+                if rejected.as_deref() != Some(error.as_str()) {
+                    warn!("Rejected settings reload ({}): {}", source, error);
+                    let changes = changed_values(&current, &values);
+                    self.audit(ConfigChange::rejected(source, changes, error.clone()))
+                        .await;
+                    *rejected = Some(error);
+                }
+                return Err(e);
+            }
+        };
+        *rejected = None;
+
+        let changes = current.changes(&next);
+        if !changes.is_empty() {
+            self.current.send_replace(Arc::new(next));
+            for change in &changes {
+                info!("Setting changed ({}): {}", source, change);
+            }
+            self.audit(ConfigChange::applied(source, changes.clone()))
+                .await;
+        }
+        Ok(changes)
+    }
+
+    /// Sets a value in the `settings` document, or removes it with `None`,
+    /// and reloads. A value the settings would not validate with is
+    /// rejected before it is stored.
+    pub async fn set(
+        &self,
+        setting: &str,
+        value: Option<&str>,
+        source: &str,
+    ) -> AgentResult<Vec<SettingChange>> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| AgentError::Config("No settings store to write overrides to".into()))?;
+        let name = setting.to_ascii_uppercase();
+        let key = LIVE_KEYS
+            .iter()
+            .copied()
+            .find(|key| *key == name)
+            .ok_or_else(|| {
+                AgentError::InvalidConfig(
+                    setting.to_string(),
+                    "is not a live setting, changing it needs a restart".into(),
+                )
+            })?;
+
+        if let Some(value) = value {
+            let mut values = self.read_sources().await?;
+            values.insert(key, value.to_string());
+            LiveSettings::from_settings(|key| values.get(key).cloned())?;
+        }
+        store.set_override(key, value).await?;
+        self.reload(source).await
+    }
+
+    /// Audited reloads, newest first
+    pub async fn history(&self, limit: usize) -> AgentResult<Vec<ConfigChange>> {
+        match &self.store {
+            Some(store) => store.changes(limit).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn audit(&self, change: ConfigChange) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.record_change(&change).await {
+                warn!("Failed to write settings audit record: {}", e);
+            }
+        }
+    }
+
+    /// Reloads every `interval` until the handle is dropped
+    pub fn watch(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let live = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            // The first tick completes immediately
+            ticks.tick().await;
+            loop {
+                ticks.tick().await;
+                let Some(live) = live.upgrade() else {
+                    break;
+                };
+                // Rejections are logged and audited by the reload
+                let _ = live.reload("watch").await;
+            }
+        })
+    }
+}
+
+/// Interval for `LiveConfig::watch` from `CONFIG_RELOAD_SECS`, or `None`
+/// when reloading is disabled
+pub fn reload_interval() -> AgentResult<Option<Duration>> {
+    let secs = match env::var(RELOAD_INTERVAL_VAR) {
+        Ok(value) => value.trim().parse::<u64>().map_err(|_| {
+            AgentError::InvalidConfig(
+                RELOAD_INTERVAL_VAR.into(),
+                "must be a valid number of seconds".into(),
+            )
+        })?,
+        Err(_) => DEFAULT_RELOAD_SECS,
+    };
+    Ok((secs > 0).then_some(Duration::from_secs(secs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::MemorySettingsRepository;
+    use std::fs;
+    use std::path::PathBuf;
+
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, text: &str) -> Self {
+            let path = env::temp_dir().join(format!("cainam-{}-{}.toml", name, std::process::id()));
+            fs::write(&path, text).unwrap();
+            Self(path)
+        }
+
+        fn config(&self) -> ConfigFile {
+            let text = fs::read_to_string(&self.0).unwrap();
+            ConfigFile::resolve(Some(self.0.clone()), Some(&text), None, |_| None).unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reload_from_file() {
+        let file = TempFile::new("reload", "[trading]\ntrade_min_confidence = 0.7\n");
+        let store = Arc::new(MemorySettingsRepository::default());
+        let live = LiveConfig::load(file.config(), Some(store.clone()))
+            .await
+            .unwrap();
+        let updates = live.subscribe();
+        assert_eq!(live.current().trading.trade_min_confidence, 0.7);
+
+        // Nothing changed, nothing is applied or audited
+        assert!(live.reload("test").await.unwrap().is_empty());
+        assert!(!updates.has_changed().unwrap());
+
+        fs::write(
+            &file.0,
+            "[trading]\ntrade_min_confidence = 0.6\nwatchlist = [\"WIF:EKpQ\"]\n",
+        )
+        .unwrap();
+        let changes = live.reload("test").await.unwrap();
+        let changed: Vec<&str> = changes.iter().map(|c| c.setting.as_str()).collect();
+        assert_eq!(changed, ["TRADE_MIN_CONFIDENCE", "WATCHLIST"]);
+        assert!(updates.has_changed().unwrap());
+        assert_eq!(live.current().trading.watchlist[0].symbol, "WIF");
+
+        let history = live.history(10).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].applied);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_reload_is_rejected_once() {
+        let file = TempFile::new("reject", "[trading]\ntrade_max_amount = 50\n");
+        let store = Arc::new(MemorySettingsRepository::default());
+        let live = LiveConfig::load(file.config(), Some(store.clone()))
+            .await
+            .unwrap();
+
+        fs::write(&file.0, "[trading]\ntrade_max_amount = -5\n").unwrap();
+        assert!(live.reload("test").await.is_err());
+        assert!(live.reload("test").await.is_err());
+        assert_eq!(live.current().trading.trade_max_amount, 50.0);
+
+        let history = live.history(10).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(!history[0].applied);
+        assert_eq!(history[0].changes[0].new_value, "-5");
+    }
+
+    #[tokio::test]
+    async fn test_settings_document_overrides_file() {
+        let file = TempFile::new("overrides", "[signals]\nprice_change_threshold = 0.05\n");
+        let store = Arc::new(MemorySettingsRepository::default());
+        let live = LiveConfig::load(file.config(), Some(store.clone()))
+            .await
+            .unwrap();
+
+        let changes = live
+            .set("price_change_threshold", Some("0.1"), "test")
+            .await
+            .unwrap();
+        assert_eq!(changes[0].setting, "PRICE_CHANGE_THRESHOLD");
+        assert_eq!(
+            live.current().market.price_change_threshold.to_string(),
+            "0.1"
+        );
+
+        // Invalid and restart-only values never reach the document
+        assert!(live
+            .set("VOLUME_WEIGHT", Some("0.9"), "test")
+            .await
+            .is_err());
+        assert!(live
+            .set("OPENAI_API_KEY", Some("sk"), "test")
+            .await
+            .is_err());
+        assert_eq!(store.overrides().await.unwrap().len(), 1);
+
+        live.set("PRICE_CHANGE_THRESHOLD", None, "test")
+            .await
+            .unwrap();
+        assert_eq!(
+            live.current().market.price_change_threshold.to_string(),
+            "0.05"
+        );
+    }
+}
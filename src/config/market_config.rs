@@ -1,9 +1,9 @@
 use crate::error::{AgentError, AgentResult};
-use crate::utils::f64_to_decimal;
 use bigdecimal::BigDecimal;
 use std::env;
+use std::str::FromStr;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MarketConfig {
     pub price_change_threshold: BigDecimal,
     pub volume_surge_threshold: BigDecimal,
@@ -14,15 +14,47 @@ pub struct MarketConfig {
 
 impl MarketConfig {
     pub fn new_from_env() -> AgentResult<Self> {
+        Self::from_settings(|key| env::var(key).ok())
+    }
+
+    /// Reads the settings by environment variable name from `get`, with
+    /// defaults for the unset ones
+    pub fn from_settings(get: impl Fn(&str) -> Option<String>) -> AgentResult<Self> {
+        let defaults = Self::default();
         Ok(Self {
-            price_change_threshold: parse_decimal_env("PRICE_CHANGE_THRESHOLD", 0.05)?,
-            volume_surge_threshold: parse_decimal_env("VOLUME_SURGE_THRESHOLD", 1.0)?,
-            base_confidence: parse_decimal_env("BASE_CONFIDENCE", 0.5)?,
-            price_weight: parse_decimal_env("PRICE_WEIGHT", 0.3)?,
-            volume_weight: parse_decimal_env("VOLUME_WEIGHT", 0.2)?,
+            price_change_threshold: parse_decimal(
+                &get,
+                "PRICE_CHANGE_THRESHOLD",
+                defaults.price_change_threshold,
+            )?,
+            volume_surge_threshold: parse_decimal(
+                &get,
+                "VOLUME_SURGE_THRESHOLD",
+                defaults.volume_surge_threshold,
+            )?,
+            base_confidence: parse_decimal(&get, "BASE_CONFIDENCE", defaults.base_confidence)?,
+            price_weight: parse_decimal(&get, "PRICE_WEIGHT", defaults.price_weight)?,
+            volume_weight: parse_decimal(&get, "VOLUME_WEIGHT", defaults.volume_weight)?,
         })
     }
 
+    /// Every setting in the form `from_settings` reads it
+    pub fn settings(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "PRICE_CHANGE_THRESHOLD",
+                self.price_change_threshold.to_string(),
+            ),
+            (
+                "VOLUME_SURGE_THRESHOLD",
+                self.volume_surge_threshold.to_string(),
+            ),
+            ("BASE_CONFIDENCE", self.base_confidence.to_string()),
+            ("PRICE_WEIGHT", self.price_weight.to_string()),
+            ("VOLUME_WEIGHT", self.volume_weight.to_string()),
+        ]
+    }
+
     pub fn validate(&self) -> AgentResult<()> {
         // Validate thresholds are positive
         if self.price_change_threshold <= BigDecimal::from(0) {
@@ -54,27 +86,28 @@ impl MarketConfig {
 impl Default for MarketConfig {
     fn default() -> Self {
         Self {
-            price_change_threshold: f64_to_decimal(0.05),
-            volume_surge_threshold: f64_to_decimal(1.0),
-            base_confidence: f64_to_decimal(0.5),
-            price_weight: f64_to_decimal(0.3),
-            volume_weight: f64_to_decimal(0.2),
+            price_change_threshold: BigDecimal::new(5.into(), 2),
+            volume_surge_threshold: BigDecimal::from(1),
+            base_confidence: BigDecimal::new(5.into(), 1),
+            price_weight: BigDecimal::new(3.into(), 1),
+            volume_weight: BigDecimal::new(2.into(), 1),
         }
     }
 }
 
-fn parse_decimal_env(key: &str, default: f64) -> AgentResult<BigDecimal> {
-    match env::var(key) {
-        Ok(val) => val
-            .parse::<f64>()
-            .map_err(|_| {
-                AgentError::InvalidConfig(
-                    key.to_string(),
-                    "must be a valid decimal number".to_string(),
-                )
-            })
-            .map(f64_to_decimal),
-        Err(_) => Ok(f64_to_decimal(default)),
+fn parse_decimal(
+    get: &impl Fn(&str) -> Option<String>,
+    key: &str,
+    default: BigDecimal,
+) -> AgentResult<BigDecimal> {
+    match get(key) {
+        Some(val) => BigDecimal::from_str(val.trim()).map_err(|_| {
+            AgentError::InvalidConfig(
+                key.to_string(),
+                "must be a valid decimal number".to_string(),
+            )
+        }),
+        None => Ok(default),
     }
 }
 
@@ -85,9 +118,9 @@ mod tests {
     #[test]
     fn test_market_config_defaults() {
         let config = MarketConfig::default();
-        assert_eq!(config.price_change_threshold, f64_to_decimal(0.05));
-        assert_eq!(config.volume_surge_threshold, f64_to_decimal(1.0));
-        assert_eq!(config.base_confidence, f64_to_decimal(0.5));
+        assert_eq!(config.price_change_threshold.to_string(), "0.05");
+        assert_eq!(config.volume_surge_threshold.to_string(), "1");
+        assert_eq!(config.base_confidence.to_string(), "0.5");
     }
 
     #[test]
@@ -97,14 +130,18 @@ mod tests {
         assert!(config.validate().is_ok());
 
         // Invalid: negative threshold
-        let mut invalid_config = MarketConfig::default();
-        invalid_config.price_change_threshold = f64_to_decimal(-0.1);
+        let invalid_config = MarketConfig {
+            price_change_threshold: BigDecimal::from_str("-0.1").unwrap(),
+            ..Default::default()
+        };
         assert!(invalid_config.validate().is_err());
 
         // Invalid: weights sum > 1
-        let mut invalid_weights = MarketConfig::default();
-        invalid_weights.price_weight = f64_to_decimal(0.6);
-        invalid_weights.volume_weight = f64_to_decimal(0.5);
+        let invalid_weights = MarketConfig {
+            price_weight: BigDecimal::from_str("0.6").unwrap(),
+            volume_weight: BigDecimal::from_str("0.5").unwrap(),
+            ..Default::default()
+        };
         assert!(invalid_weights.validate().is_err());
     }
 }
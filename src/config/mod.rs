@@ -1,15 +1,32 @@
 pub mod agent_config;
 pub mod birdeye_config;
+pub mod embedding_config;
+pub mod file_config;
+pub mod live_config;
 pub mod logging_config;
 pub mod market_config;
 pub mod mongodb;
+pub mod retention_config;
+pub mod solana_config;
+pub mod strategy_config;
+pub mod trading_config;
+pub mod transaction_config;
 
 pub use self::agent_config::AgentConfig;
-use rig::providers::openai::{GPT_4O, GPT_4O_MINI, O3_MINI, O1_PREVIEW};
-use rig::providers::anthropic::completion::CLAUDE_3_7_SONNET;
+pub use self::embedding_config::EmbeddingConfig;
+pub use self::file_config::ConfigFile;
+pub use self::live_config::{LiveConfig, LiveSettings};
+pub use self::retention_config::{RetentionConfig, RetentionPolicy};
+pub use self::solana_config::SolanaConfig;
+pub use self::strategy_config::StrategyConfig;
+pub use self::transaction_config::TransactionConfig;
+use rig::providers::openai::{GPT_4O, GPT_4O_MINI, O1_PREVIEW, O3_MINI};
 
 pub const DEFAULT_MODEL: &str = GPT_4O_MINI;
 
+/// Not yet exported by the pinned rig release
+const CLAUDE_3_7_SONNET: &str = "claude-3-7-sonnet-latest";
+
 pub fn get_openai_model() -> &'static str {
     match std::env::var("OPENAI_MODEL").as_deref() {
         Ok("gpt-4o") => GPT_4O,
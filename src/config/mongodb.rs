@@ -74,16 +74,17 @@ impl Default for MongoConfig {
 }
 
 impl MongoConfig {
-    pub fn from_env() -> Self {
-        let uri = env::var("MONGODB_URI").expect("MONGODB_URI must be set");
-        let database = env::var("MONGODB_DATABASE").expect("MONGODB_DATABASE must be set");
+    pub fn from_env() -> Result<Self> {
+        let uri = env::var("MONGODB_URI").map_err(|_| anyhow!("MONGODB_URI must be set"))?;
+        let database =
+            env::var("MONGODB_DATABASE").map_err(|_| anyhow!("MONGODB_DATABASE must be set"))?;
 
-        Self {
+        Ok(Self {
             uri,
             database,
-            app_name: None,
-            pool_config: MongoPoolConfig::default(),
-        }
+            app_name: env::var("MONGODB_APP_NAME").ok(),
+            pool_config: MongoPoolConfig::from_env(),
+        })
     }
 }
 
@@ -0,0 +1,123 @@
+use crate::error::{AgentError, AgentResult};
+use std::env;
+
+/// How long each resolution of a time-series collection is kept, in days.
+/// `None` keeps the data forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Snapshots as inserted
+    pub raw_days: Option<u32>,
+    /// Hourly rollups
+    pub hourly_days: Option<u32>,
+    /// Daily rollups
+    pub daily_days: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// Raw snapshots only, never expired or rolled up
+    pub fn keep_raw() -> Self {
+        Self {
+            raw_days: None,
+            hourly_days: None,
+            daily_days: None,
+        }
+    }
+
+    /// Read `<PREFIX>_RAW_DAYS`, `<PREFIX>_HOURLY_DAYS` and `<PREFIX>_DAILY_DAYS`.
+    /// `forever` keeps a resolution without expiry.
+    pub fn from_env(prefix: &str) -> AgentResult<Self> {
+        let defaults = Self::default();
+        let policy = Self {
+            raw_days: parse_days(&format!("{}_RAW_DAYS", prefix), defaults.raw_days)?,
+            hourly_days: parse_days(&format!("{}_HOURLY_DAYS", prefix), defaults.hourly_days)?,
+            daily_days: parse_days(&format!("{}_DAILY_DAYS", prefix), defaults.daily_days)?,
+        };
+        policy.validate(prefix)?;
+        Ok(policy)
+    }
+
+    /// Coarser resolutions must be kept at least as long as finer ones, or
+    /// history would have gaps
+    pub fn validate(&self, name: &str) -> AgentResult<()> {
+        let kept = |days: Option<u32>| days.unwrap_or(u32::MAX);
+        if kept(self.raw_days) > kept(self.hourly_days)
+            || kept(self.hourly_days) > kept(self.daily_days)
+        {
+            return Err(AgentError::InvalidConfig(
+                name.to_string(),
+                "coarser resolutions must be kept at least as long as finer ones".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether anything expires, and so rollups are needed
+    pub fn expires(&self) -> bool {
+        self.raw_days.is_some()
+    }
+}
+
+/// Raw for 7 days, hourly for 90, daily forever
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            raw_days: Some(7),
+            hourly_days: Some(90),
+            daily_days: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RetentionConfig {
+    pub token_analytics: RetentionPolicy,
+    pub token_trending: RetentionPolicy,
+}
+
+impl RetentionConfig {
+    pub fn new_from_env() -> AgentResult<Self> {
+        Ok(Self {
+            token_analytics: RetentionPolicy::from_env("RETENTION_ANALYTICS")?,
+            token_trending: RetentionPolicy::from_env("RETENTION_TRENDING")?,
+        })
+    }
+}
+
+fn parse_days(key: &str, default: Option<u32>) -> AgentResult<Option<u32>> {
+    match env::var(key) {
+        Ok(val) if val.eq_ignore_ascii_case("forever") => Ok(None),
+        Ok(val) => match val.parse::<u32>() {
+            Ok(days) if days > 0 => Ok(Some(days)),
+            _ => Err(AgentError::InvalidConfig(
+                key.to_string(),
+                "must be a positive number of days or 'forever'".to_string(),
+            )),
+        },
+        Err(_) => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_validation() {
+        assert!(RetentionPolicy::default().validate("default").is_ok());
+        assert!(RetentionPolicy::keep_raw().validate("raw").is_ok());
+        assert!(RetentionPolicy {
+            raw_days: Some(30),
+            hourly_days: Some(7),
+            daily_days: None,
+        }
+        .validate("short hourly")
+        .is_err());
+        assert!(RetentionPolicy {
+            raw_days: Some(7),
+            hourly_days: None,
+            daily_days: Some(365),
+        }
+        .validate("hourly forever, daily expiring")
+        .is_err());
+    }
+}
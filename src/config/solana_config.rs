@@ -0,0 +1,49 @@
+use super::transaction_config::TransactionConfig;
+use crate::error::{AgentError, AgentResult};
+use crate::trading::swap::{JUPITER_API_URL, USDC_MINT};
+use std::env;
+
+/// Settings of the on-chain toolkit
+#[derive(Debug, Clone)]
+pub struct SolanaConfig {
+    /// Enables the Helius asset, webhook and transaction parsing actions
+    pub helius_api_key: Option<String>,
+    pub transactions: TransactionConfig,
+    /// Jupiter swap API that builds the trade instructions
+    pub jupiter_api_url: String,
+    /// Mint that buys pay with and sells receive
+    pub quote_mint: String,
+    /// Log orders and return a mock signature instead of sending them
+    pub dry_run: bool,
+}
+
+impl SolanaConfig {
+    pub fn new_from_env() -> AgentResult<Self> {
+        let dry_run = match env::var("TRADE_DRY_RUN") {
+            Ok(val) => val.parse().map_err(|_| {
+                AgentError::InvalidConfig("TRADE_DRY_RUN".into(), "must be true or false".into())
+            })?,
+            Err(_) => false,
+        };
+        Ok(Self {
+            helius_api_key: env::var("HELIUS_API_KEY").ok().filter(|key| !key.is_empty()),
+            transactions: TransactionConfig::new_from_env()?,
+            jupiter_api_url: env::var("JUPITER_API_URL")
+                .unwrap_or_else(|_| JUPITER_API_URL.to_string()),
+            quote_mint: env::var("TRADE_QUOTE_MINT").unwrap_or_else(|_| USDC_MINT.to_string()),
+            dry_run,
+        })
+    }
+}
+
+impl Default for SolanaConfig {
+    fn default() -> Self {
+        Self {
+            helius_api_key: None,
+            transactions: TransactionConfig::default(),
+            jupiter_api_url: JUPITER_API_URL.to_string(),
+            quote_mint: USDC_MINT.to_string(),
+            dry_run: false,
+        }
+    }
+}
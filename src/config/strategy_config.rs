@@ -0,0 +1,180 @@
+use crate::error::{AgentError, AgentResult};
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+
+/// How an ensemble turns member signals into one decision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnsembleMode {
+    /// Share of member weight that agrees on a direction
+    Vote,
+    /// Weighted mean of signed member confidences
+    Weighted,
+}
+
+impl FromStr for EnsembleMode {
+    type Err = AgentError;
+
+    fn from_str(s: &str) -> AgentResult<Self> {
+        match s {
+            "vote" => Ok(EnsembleMode::Vote),
+            "weighted" => Ok(EnsembleMode::Weighted),
+            _ => Err(AgentError::InvalidConfig(
+                "STRATEGY_ENSEMBLE".into(),
+                format!("unknown mode '{}', expected vote or weighted", s),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StrategyConfig {
+    /// Strategies the trading agent runs. Several are combined in an
+    /// ensemble; none leaves trading to the built-in signal engine.
+    pub strategies: Vec<String>,
+    /// Ensemble weight per strategy (default 1.0)
+    pub weights: HashMap<String, f64>,
+    pub ensemble_mode: EnsembleMode,
+    /// Minimum agreement (vote) or score (weighted) for the ensemble to act
+    pub ensemble_threshold: f64,
+    /// Snapshots the momentum return is measured over
+    pub momentum_lookback: usize,
+    /// Smallest return (fraction) that counts as momentum
+    pub momentum_threshold: f64,
+    /// Snapshots in the mean-reversion average
+    pub mean_reversion_period: usize,
+    /// Standard deviations from the average that trigger a reversion trade
+    pub mean_reversion_z: f64,
+    /// Hourly candles whose range a breakout must leave
+    pub breakout_lookback: usize,
+    /// Fraction past the range high/low that confirms a breakout
+    pub breakout_buffer: f64,
+}
+
+impl StrategyConfig {
+    pub fn new_from_env() -> AgentResult<Self> {
+        let config = Self {
+            strategies: env::var("STRATEGIES")
+                .map(|v| parse_list(&v))
+                .unwrap_or_default(),
+            weights: match env::var("STRATEGY_WEIGHTS") {
+                Ok(v) => parse_weights(&v)?,
+                Err(_) => HashMap::new(),
+            },
+            ensemble_mode: env::var("STRATEGY_ENSEMBLE")
+                .map(|v| v.parse())
+                .unwrap_or(Ok(EnsembleMode::Vote))?,
+            ensemble_threshold: parse_env("STRATEGY_ENSEMBLE_THRESHOLD", 0.5)?,
+            momentum_lookback: parse_env("MOMENTUM_LOOKBACK", 12)?,
+            momentum_threshold: parse_env("MOMENTUM_THRESHOLD", 0.05)?,
+            mean_reversion_period: parse_env("MEAN_REVERSION_PERIOD", 20)?,
+            mean_reversion_z: parse_env("MEAN_REVERSION_Z", 2.0)?,
+            breakout_lookback: parse_env("BREAKOUT_LOOKBACK", 24)?,
+            breakout_buffer: parse_env("BREAKOUT_BUFFER", 0.01)?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> AgentResult<()> {
+        if !(0.0..=1.0).contains(&self.ensemble_threshold) {
+            return Err(AgentError::InvalidConfig(
+                "ensemble_threshold".into(),
+                "must be between 0.0 and 1.0".into(),
+            ));
+        }
+        for (name, value) in [
+            ("momentum_lookback", self.momentum_lookback),
+            ("mean_reversion_period", self.mean_reversion_period),
+            ("breakout_lookback", self.breakout_lookback),
+        ] {
+            if value < 2 {
+                return Err(AgentError::InvalidConfig(
+                    name.into(),
+                    "must be at least 2".into(),
+                ));
+            }
+        }
+        if self.momentum_threshold <= 0.0 || self.mean_reversion_z <= 0.0 {
+            return Err(AgentError::InvalidConfig(
+                "strategy thresholds".into(),
+                "must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn weight(&self, strategy: &str) -> f64 {
+        self.weights.get(strategy).copied().unwrap_or(1.0)
+    }
+}
+
+impl Default for StrategyConfig {
+    fn default() -> Self {
+        Self {
+            strategies: Vec::new(),
+            weights: HashMap::new(),
+            ensemble_mode: EnsembleMode::Vote,
+            ensemble_threshold: 0.5,
+            momentum_lookback: 12,
+            momentum_threshold: 0.05,
+            mean_reversion_period: 20,
+            mean_reversion_z: 2.0,
+            breakout_lookback: 24,
+            breakout_buffer: 0.01,
+        }
+    }
+}
+
+/// Comma-separated names, trimmed and lowercased
+pub fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// `name:weight` pairs separated by commas
+fn parse_weights(value: &str) -> AgentResult<HashMap<String, f64>> {
+    parse_list(value)
+        .into_iter()
+        .map(|pair| {
+            let (name, weight) = pair.split_once(':').ok_or_else(|| {
+                AgentError::InvalidConfig(
+                    "STRATEGY_WEIGHTS".into(),
+                    format!("expected name:weight, got '{}'", pair),
+                )
+            })?;
+            let weight = weight.parse::<f64>().map_err(|_| {
+                AgentError::InvalidConfig(
+                    "STRATEGY_WEIGHTS".into(),
+                    format!("invalid weight in '{}'", pair),
+                )
+            })?;
+            Ok((name.to_string(), weight))
+        })
+        .collect()
+}
+
+fn parse_env<T: FromStr>(key: &str, default: T) -> AgentResult<T> {
+    match env::var(key) {
+        Ok(val) => val.parse().map_err(|_| {
+            AgentError::InvalidConfig(key.to_string(), "must be a valid number".to_string())
+        }),
+        Err(_) => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_weights() {
+        let weights = parse_weights("Momentum:2, breakout:0.5").unwrap();
+        assert_eq!(weights["momentum"], 2.0);
+        assert_eq!(weights["breakout"], 0.5);
+        assert!(parse_weights("momentum").is_err());
+    }
+}
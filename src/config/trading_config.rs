@@ -0,0 +1,290 @@
+use crate::error::{AgentError, AgentResult};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Analyzed when `WATCHLIST` is unset
+const DEFAULT_WATCHLIST: &str = "SOL:So11111111111111111111111111111111111111112,\
+                                 BONK:DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+
+/// A token the trading agent analyzes every interval
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchedToken {
+    pub symbol: String,
+    pub address: String,
+}
+
+impl FromStr for WatchedToken {
+    type Err = AgentError;
+
+    fn from_str(s: &str) -> AgentResult<Self> {
+        match s.trim().split_once(':') {
+            Some((symbol, address)) if !symbol.trim().is_empty() && !address.trim().is_empty() => {
+                Ok(Self {
+                    symbol: symbol.trim().to_string(),
+                    address: address.trim().to_string(),
+                })
+            }
+            _ => Err(AgentError::InvalidConfig(
+                "WATCHLIST".into(),
+                format!("'{}' is not SYMBOL:ADDRESS", s.trim()),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for WatchedToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.symbol, self.address)
+    }
+}
+
+/// Trading settings of the agent. They can change while it runs, see
+/// `LiveConfig`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TradingConfig {
+    pub analysis_interval: Duration,
+    /// Minimum calibrated probability that a signal's direction is right
+    pub trade_min_confidence: f64,
+    pub trade_max_amount: f64,
+    pub trade_max_slippage: f64,
+    /// Number of TWAP slices a buy is split into (1 sends it as one swap)
+    pub trade_twap_slices: usize,
+    /// Window the TWAP slices are spread over
+    pub trade_twap_window: Duration,
+    /// Stop below entry attached to new positions, as a fraction (0 disables)
+    pub exit_stop_loss_pct: f64,
+    /// Trailing stop attached to new positions, as a fraction (0 disables)
+    pub exit_trailing_stop_pct: f64,
+    /// Tokens analyzed every interval
+    pub watchlist: Vec<WatchedToken>,
+}
+
+impl TradingConfig {
+    /// Reads the environment without checking the values' ranges
+    pub fn new_from_env() -> AgentResult<Self> {
+        Self::from_settings(|key| env::var(key).ok())
+    }
+
+    /// Reads the settings by environment variable name from `get`, with
+    /// defaults for the unset ones
+    pub fn from_settings(get: impl Fn(&str) -> Option<String>) -> AgentResult<Self> {
+        Ok(Self {
+            analysis_interval: parse_duration_secs(&get, "ANALYSIS_INTERVAL", 300)?,
+            trade_min_confidence: parse_f64(&get, "TRADE_MIN_CONFIDENCE", 0.8)?,
+            trade_max_amount: parse_f64(&get, "TRADE_MAX_AMOUNT", 100.0)?,
+            trade_max_slippage: parse_f64(&get, "TRADE_MAX_SLIPPAGE", 0.05)?,
+            trade_twap_slices: parse_usize(&get, "TRADE_TWAP_SLICES", 1)?,
+            trade_twap_window: parse_duration_secs(&get, "TRADE_TWAP_WINDOW", 3600)?,
+            exit_stop_loss_pct: parse_f64(&get, "EXIT_STOP_LOSS_PCT", 0.15)?,
+            exit_trailing_stop_pct: parse_f64(&get, "EXIT_TRAILING_STOP_PCT", 0.0)?,
+            watchlist: parse_watchlist(
+                &get("WATCHLIST").unwrap_or_else(|| DEFAULT_WATCHLIST.to_string()),
+            )?,
+        })
+    }
+
+    pub fn validate(&self) -> AgentResult<()> {
+        match self.problems().into_iter().next() {
+            Some(problem) => Err(problem),
+            None => Ok(()),
+        }
+    }
+
+    /// Every invalid value, where `validate` stops at the first
+    pub fn problems(&self) -> Vec<AgentError> {
+        let mut problems = Vec::new();
+
+        if !(0.0..=1.0).contains(&self.trade_min_confidence) {
+            problems.push(AgentError::InvalidConfig(
+                "trade_min_confidence".into(),
+                "must be between 0.0 and 1.0".into(),
+            ));
+        }
+        if self.trade_max_amount <= 0.0 {
+            problems.push(AgentError::InvalidConfig(
+                "trade_max_amount".into(),
+                "must be greater than 0".into(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.trade_max_slippage) {
+            problems.push(AgentError::InvalidConfig(
+                "trade_max_slippage".into(),
+                "must be between 0.0 and 1.0".into(),
+            ));
+        }
+
+        if self.trade_twap_slices == 0 {
+            problems.push(AgentError::InvalidConfig(
+                "trade_twap_slices".into(),
+                "must be at least 1".into(),
+            ));
+        }
+
+        for (name, value) in [
+            ("exit_stop_loss_pct", self.exit_stop_loss_pct),
+            ("exit_trailing_stop_pct", self.exit_trailing_stop_pct),
+        ] {
+            if !(0.0..1.0).contains(&value) {
+                problems.push(AgentError::InvalidConfig(
+                    name.into(),
+                    "must be at least 0.0 and below 1.0".into(),
+                ));
+            }
+        }
+
+        if self.analysis_interval.is_zero() {
+            problems.push(AgentError::InvalidConfig(
+                "analysis_interval".into(),
+                "must be at least 1 second".into(),
+            ));
+        }
+        if self.watchlist.is_empty() {
+            problems.push(AgentError::InvalidConfig(
+                "watchlist".into(),
+                "must list at least one token".into(),
+            ));
+        }
+
+        problems
+    }
+
+    /// Every setting in the form `from_settings` reads it
+    pub fn settings(&self) -> Vec<(&'static str, String)> {
+        let watchlist: Vec<String> = self.watchlist.iter().map(|t| t.to_string()).collect();
+        vec![
+            (
+                "ANALYSIS_INTERVAL",
+                self.analysis_interval.as_secs().to_string(),
+            ),
+            (
+                "TRADE_MIN_CONFIDENCE",
+                self.trade_min_confidence.to_string(),
+            ),
+            ("TRADE_MAX_AMOUNT", self.trade_max_amount.to_string()),
+            ("TRADE_MAX_SLIPPAGE", self.trade_max_slippage.to_string()),
+            ("TRADE_TWAP_SLICES", self.trade_twap_slices.to_string()),
+            (
+                "TRADE_TWAP_WINDOW",
+                self.trade_twap_window.as_secs().to_string(),
+            ),
+            ("EXIT_STOP_LOSS_PCT", self.exit_stop_loss_pct.to_string()),
+            (
+                "EXIT_TRAILING_STOP_PCT",
+                self.exit_trailing_stop_pct.to_string(),
+            ),
+            ("WATCHLIST", watchlist.join(",")),
+        ]
+    }
+}
+
+impl Default for TradingConfig {
+    fn default() -> Self {
+        Self::from_settings(|_| None).expect("default trading settings are valid")
+    }
+}
+
+/// Comma separated `SYMBOL:ADDRESS` entries
+fn parse_watchlist(value: &str) -> AgentResult<Vec<WatchedToken>> {
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(WatchedToken::from_str)
+        .collect()
+}
+
+/// Helper function to parse a duration from seconds
+fn parse_duration_secs(
+    get: &impl Fn(&str) -> Option<String>,
+    key: &str,
+    default: u64,
+) -> AgentResult<Duration> {
+    let secs = get(key)
+        .map(|v| v.trim().parse::<u64>())
+        .unwrap_or(Ok(default))
+        .map_err(|_| {
+            AgentError::InvalidConfig(
+                key.to_string(),
+                "must be a valid number of seconds".to_string(),
+            )
+        })?;
+
+    Ok(Duration::from_secs(secs))
+}
+
+/// Helper function to parse a usize value
+fn parse_usize(
+    get: &impl Fn(&str) -> Option<String>,
+    key: &str,
+    default: usize,
+) -> AgentResult<usize> {
+    get(key)
+        .map(|v| v.trim().parse::<usize>())
+        .unwrap_or(Ok(default))
+        .map_err(|_| {
+            AgentError::InvalidConfig(key.to_string(), "must be a valid whole number".to_string())
+        })
+}
+
+/// Helper function to parse an f64 value
+fn parse_f64(get: &impl Fn(&str) -> Option<String>, key: &str, default: f64) -> AgentResult<f64> {
+    get(key)
+        .map(|v| v.trim().parse::<f64>())
+        .unwrap_or(Ok(default))
+        .map_err(|_| {
+            AgentError::InvalidConfig(key.to_string(), "must be a valid number".to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn from(settings: &[(&str, &str)]) -> AgentResult<TradingConfig> {
+        let settings: HashMap<&str, &str> = settings.iter().copied().collect();
+        TradingConfig::from_settings(|key| settings.get(key).map(|v| v.to_string()))
+    }
+
+    #[test]
+    fn test_watchlist() {
+        let config = TradingConfig::default();
+        assert_eq!(config.watchlist.len(), 2);
+        assert_eq!(config.watchlist[0].symbol, "SOL");
+
+        let config = from(&[("WATCHLIST", " WIF:EKpQ , JUP:JUPy,")]).unwrap();
+        assert_eq!(
+            config.watchlist,
+            [
+                WatchedToken {
+                    symbol: "WIF".into(),
+                    address: "EKpQ".into()
+                },
+                WatchedToken {
+                    symbol: "JUP".into(),
+                    address: "JUPy".into()
+                },
+            ]
+        );
+        assert!(from(&[("WATCHLIST", "WIF")]).is_err());
+        assert!(from(&[("WATCHLIST", "")]).unwrap().validate().is_err());
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let config = from(&[
+            ("TRADE_MIN_CONFIDENCE", "0.65"),
+            ("TRADE_TWAP_WINDOW", "600"),
+            ("WATCHLIST", "WIF:EKpQ"),
+        ])
+        .unwrap();
+        let settings: HashMap<&str, String> = config.settings().into_iter().collect();
+        assert_eq!(settings["TRADE_MIN_CONFIDENCE"], "0.65");
+        assert_eq!(settings["WATCHLIST"], "WIF:EKpQ");
+
+        let reread = TradingConfig::from_settings(|key| settings.get(key).cloned()).unwrap();
+        assert_eq!(reread, config);
+    }
+}
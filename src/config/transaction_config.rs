@@ -0,0 +1,98 @@
+use crate::error::{AgentError, AgentResult};
+use solana_commitment_config::{CommitmentConfig, CommitmentLevel};
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How transactions are priced, sent and confirmed
+#[derive(Debug, Clone)]
+pub struct TransactionConfig {
+    /// Commitment a transaction must reach to count as executed
+    pub commitment: CommitmentConfig,
+    /// Times a transaction is re-signed with a fresh blockhash after expiring
+    pub max_resends: usize,
+    /// Compute units requested per transaction
+    pub compute_unit_limit: u32,
+    /// Percentile of recent prioritization fees to pay, in [0, 1]
+    pub priority_fee_percentile: f64,
+    /// Bounds of the compute-unit price, in micro-lamports
+    pub min_priority_fee: u64,
+    pub max_priority_fee: u64,
+    /// Delay between signature status checks
+    pub poll_interval: Duration,
+    /// Longest wait for one attempt, in case the RPC node stops advancing
+    pub attempt_timeout: Duration,
+}
+
+impl TransactionConfig {
+    pub fn new_from_env() -> AgentResult<Self> {
+        let commitment = match env::var("TX_COMMITMENT") {
+            Ok(level) => CommitmentLevel::from_str(&level).map_err(|_| {
+                AgentError::InvalidConfig(
+                    "TX_COMMITMENT".into(),
+                    "must be processed, confirmed or finalized".into(),
+                )
+            })?,
+            Err(_) => CommitmentLevel::Confirmed,
+        };
+
+        let config = Self {
+            commitment: CommitmentConfig { commitment },
+            max_resends: parse_env("TX_MAX_RESENDS", 3)?,
+            compute_unit_limit: parse_env("TX_COMPUTE_UNIT_LIMIT", 200_000)?,
+            priority_fee_percentile: parse_env("TX_PRIORITY_FEE_PERCENTILE", 0.75)?,
+            min_priority_fee: parse_env("TX_MIN_PRIORITY_FEE", 0)?,
+            max_priority_fee: parse_env("TX_MAX_PRIORITY_FEE", 1_000_000)?,
+            poll_interval: Duration::from_millis(parse_env("TX_POLL_INTERVAL_MS", 500)?),
+            attempt_timeout: Duration::from_secs(parse_env("TX_ATTEMPT_TIMEOUT_SECS", 90)?),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> AgentResult<()> {
+        if !(0.0..=1.0).contains(&self.priority_fee_percentile) {
+            return Err(AgentError::InvalidConfig(
+                "priority_fee_percentile".into(),
+                "must be between 0.0 and 1.0".into(),
+            ));
+        }
+        if self.min_priority_fee > self.max_priority_fee {
+            return Err(AgentError::InvalidConfig(
+                "min_priority_fee".into(),
+                "must not exceed max_priority_fee".into(),
+            ));
+        }
+        if self.compute_unit_limit == 0 {
+            return Err(AgentError::InvalidConfig(
+                "compute_unit_limit".into(),
+                "must be greater than 0".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for TransactionConfig {
+    fn default() -> Self {
+        Self {
+            commitment: CommitmentConfig::confirmed(),
+            max_resends: 3,
+            compute_unit_limit: 200_000,
+            priority_fee_percentile: 0.75,
+            min_priority_fee: 0,
+            max_priority_fee: 1_000_000,
+            poll_interval: Duration::from_millis(500),
+            attempt_timeout: Duration::from_secs(90),
+        }
+    }
+}
+
+fn parse_env<T: FromStr>(key: &str, default: T) -> AgentResult<T> {
+    match env::var(key) {
+        Ok(val) => val.parse().map_err(|_| {
+            AgentError::InvalidConfig(key.to_string(), "must be a valid number".to_string())
+        }),
+        Err(_) => Ok(default),
+    }
+}
@@ -84,6 +84,12 @@ impl From<MongoError> for AgentError {
     }
 }
 
+impl From<bson::ser::Error> for AgentError {
+    fn from(err: bson::ser::Error) -> Self {
+        AgentError::Conversion(err.to_string())
+    }
+}
+
 impl From<ParseFloatError> for AgentError {
     fn from(err: ParseFloatError) -> Self {
         AgentError::Parse(err.to_string())
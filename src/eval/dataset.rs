@@ -0,0 +1,160 @@
+use crate::birdeye::api::TokenV3Response;
+use crate::error::AgentResult;
+use crate::services::token_analytics::TokenAnalyticsService;
+use anyhow::{Context, Result};
+use bigdecimal::ToPrimitive;
+use bson::DateTime;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::{debug, info};
+
+/// How far from the target time a stored snapshot may be and still be used
+/// as the realized price for a horizon.
+const OUTCOME_TOLERANCE_SECS: i64 = 2 * 60 * 60;
+
+/// Forward horizons the harness scores against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Horizon {
+    #[serde(rename = "24h")]
+    H24,
+    #[serde(rename = "7d")]
+    D7,
+}
+
+impl Horizon {
+    pub const ALL: [Horizon; 2] = [Horizon::H24, Horizon::D7];
+
+    pub fn as_secs(&self) -> i64 {
+        match self {
+            Horizon::H24 => 24 * 60 * 60,
+            Horizon::D7 => 7 * 24 * 60 * 60,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Horizon::H24 => "24h",
+            Horizon::D7 => "7d",
+        }
+    }
+}
+
+/// Realized forward returns for a case, as fractions (0.25 = +25%)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EvalOutcome {
+    pub return_24h: Option<f64>,
+    pub return_7d: Option<f64>,
+}
+
+impl EvalOutcome {
+    pub fn get(&self, horizon: Horizon) -> Option<f64> {
+        match horizon {
+            Horizon::H24 => self.return_24h,
+            Horizon::D7 => self.return_7d,
+        }
+    }
+
+    fn set(&mut self, horizon: Horizon, value: Option<f64>) {
+        match horizon {
+            Horizon::H24 => self.return_24h = value,
+            Horizon::D7 => self.return_7d = value,
+        }
+    }
+}
+
+/// A historical pipeline input together with what happened afterwards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalCase {
+    /// Token data exactly as BirdEye returned it at capture time
+    pub token: TokenV3Response,
+    /// Unix timestamp (seconds) of when the token data was captured
+    pub captured_at: i64,
+    #[serde(default)]
+    pub outcome: EvalOutcome,
+}
+
+/// A fixed, labeled set of pipeline inputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalDataset {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub cases: Vec<EvalCase>,
+}
+
+impl EvalDataset {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read eval dataset {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse eval dataset {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let data = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, data)
+            .with_context(|| format!("Failed to write eval dataset {}", path.display()))
+    }
+
+    /// Number of cases with a realized outcome for `horizon`
+    pub fn labeled(&self, horizon: Horizon) -> usize {
+        self.cases
+            .iter()
+            .filter(|c| c.outcome.get(horizon).is_some())
+            .count()
+    }
+
+    /// Fill in missing outcomes from the prices stored in `token_analytics`.
+    ///
+    /// Cases that already carry an outcome are left untouched, so hand-labeled
+    /// datasets stay stable. Returns the number of outcomes that were filled.
+    pub async fn label_from_analytics(
+        &mut self,
+        analytics: &TokenAnalyticsService,
+    ) -> AgentResult<usize> {
+        let mut filled = 0;
+
+        for case in self.cases.iter_mut() {
+            if case.token.price <= 0.0 {
+                continue;
+            }
+
+            for horizon in Horizon::ALL {
+                if case.outcome.get(horizon).is_some() {
+                    continue;
+                }
+
+                let target = case.captured_at + horizon.as_secs();
+                let history = analytics
+                    .get_token_history(
+                        &case.token.address,
+                        DateTime::from_millis((target - OUTCOME_TOLERANCE_SECS) * 1000),
+                        DateTime::from_millis((target + OUTCOME_TOLERANCE_SECS) * 1000),
+                    )
+                    .await?;
+
+                let realized = history
+                    .iter()
+                    .min_by_key(|h| (h.timestamp.timestamp_millis() / 1000 - target).abs())
+                    .and_then(|h| h.price.to_f64());
+
+                if let Some(price) = realized {
+                    case.outcome
+                        .set(horizon, Some(price / case.token.price - 1.0));
+                    filled += 1;
+                } else {
+                    debug!(
+                        "No stored price for {} at {} horizon",
+                        case.token.symbol,
+                        horizon.label()
+                    );
+                }
+            }
+        }
+
+        info!("Labeled {} outcomes in dataset {}", filled, self.name);
+        Ok(filled)
+    }
+}
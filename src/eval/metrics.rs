@@ -0,0 +1,325 @@
+use super::dataset::Horizon;
+use super::report::EvalRow;
+use serde::{Deserialize, Serialize};
+
+/// Number of equal-width score buckets used for the calibration table
+const CALIBRATION_BUCKETS: usize = 10;
+
+/// One row of the calibration table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+    /// Mean predicted score of the cases in the bucket
+    pub mean_score: f64,
+    /// Share of cases in the bucket whose return met the success threshold
+    pub hit_rate: f64,
+}
+
+/// Quality metrics of one run against one forward horizon
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HorizonMetrics {
+    /// Cases with a realized outcome for the horizon
+    pub labeled: usize,
+    /// Labeled cases whose return met the success threshold
+    pub positives: usize,
+    /// Labeled cases the pipeline passed
+    pub passed: usize,
+    /// Labeled cases that were passed and met the success threshold
+    pub true_positives: usize,
+    pub precision: Option<f64>,
+    pub recall: Option<f64>,
+    pub f1: Option<f64>,
+    /// Mean squared error between score and the 0/1 outcome
+    pub brier_score: Option<f64>,
+    /// Count-weighted mean gap between score and hit rate per bucket
+    pub expected_calibration_error: Option<f64>,
+    pub calibration: Vec<CalibrationBucket>,
+    /// Spearman rank correlation between score and realized return
+    pub spearman: Option<f64>,
+    pub mean_return_passed: Option<f64>,
+    pub mean_return_rejected: Option<f64>,
+}
+
+/// Score a run against one horizon. A case counts as a success when its
+/// realized return is at least `success_threshold`.
+pub fn compute_metrics(
+    rows: &[EvalRow],
+    horizon: Horizon,
+    success_threshold: f64,
+) -> HorizonMetrics {
+    let labeled: Vec<(&EvalRow, f64)> = rows
+        .iter()
+        .filter_map(|row| row.outcome.get(horizon).map(|r| (row, r)))
+        .filter(|(_, r)| r.is_finite())
+        .collect();
+
+    if labeled.is_empty() {
+        return HorizonMetrics::default();
+    }
+
+    let positives = labeled
+        .iter()
+        .filter(|(_, r)| *r >= success_threshold)
+        .count();
+    let passed = labeled.iter().filter(|(row, _)| row.passed).count();
+    let true_positives = labeled
+        .iter()
+        .filter(|(row, r)| row.passed && *r >= success_threshold)
+        .count();
+
+    let precision = ratio(true_positives, passed);
+    let recall = ratio(true_positives, positives);
+    let f1 = match (precision, recall) {
+        (Some(p), Some(r)) if p + r > 0.0 => Some(2.0 * p * r / (p + r)),
+        (Some(_), Some(_)) => Some(0.0),
+        _ => None,
+    };
+
+    let brier_score = Some(
+        labeled
+            .iter()
+            .map(|(row, r)| {
+                let outcome = if *r >= success_threshold { 1.0 } else { 0.0 };
+                (clamp_score(row.score) - outcome).powi(2)
+            })
+            .sum::<f64>()
+            / labeled.len() as f64,
+    );
+
+    let calibration = calibration_table(&labeled, success_threshold);
+    let expected_calibration_error = Some(
+        calibration
+            .iter()
+            .map(|b| b.count as f64 * (b.mean_score - b.hit_rate).abs())
+            .sum::<f64>()
+            / labeled.len() as f64,
+    );
+
+    let scores: Vec<f64> = labeled.iter().map(|(row, _)| row.score).collect();
+    let returns: Vec<f64> = labeled.iter().map(|(_, r)| *r).collect();
+
+    HorizonMetrics {
+        labeled: labeled.len(),
+        positives,
+        passed,
+        true_positives,
+        precision,
+        recall,
+        f1,
+        brier_score,
+        expected_calibration_error,
+        calibration,
+        spearman: spearman(&scores, &returns),
+        mean_return_passed: mean(
+            labeled
+                .iter()
+                .filter(|(row, _)| row.passed)
+                .map(|(_, r)| *r),
+        ),
+        mean_return_rejected: mean(
+            labeled
+                .iter()
+                .filter(|(row, _)| !row.passed)
+                .map(|(_, r)| *r),
+        ),
+    }
+}
+
+fn calibration_table(
+    labeled: &[(&EvalRow, f64)],
+    success_threshold: f64,
+) -> Vec<CalibrationBucket> {
+    let width = 1.0 / CALIBRATION_BUCKETS as f64;
+    let mut buckets = Vec::new();
+
+    for i in 0..CALIBRATION_BUCKETS {
+        let in_bucket: Vec<&(&EvalRow, f64)> = labeled
+            .iter()
+            .filter(|(row, _)| {
+                let idx = ((clamp_score(row.score) / width) as usize).min(CALIBRATION_BUCKETS - 1);
+                idx == i
+            })
+            .collect();
+
+        if in_bucket.is_empty() {
+            continue;
+        }
+
+        let count = in_bucket.len();
+        let mean_score = in_bucket
+            .iter()
+            .map(|(row, _)| clamp_score(row.score))
+            .sum::<f64>()
+            / count as f64;
+        let hits = in_bucket
+            .iter()
+            .filter(|(_, r)| *r >= success_threshold)
+            .count();
+
+        buckets.push(CalibrationBucket {
+            lower: i as f64 * width,
+            upper: (i + 1) as f64 * width,
+            count,
+            mean_score,
+            hit_rate: hits as f64 / count as f64,
+        });
+    }
+
+    buckets
+}
+
+/// Spearman rank correlation with average ranks for ties.
+/// Returns `None` when there are fewer than two points or either side is constant.
+pub fn spearman(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() != ys.len() || xs.len() < 2 {
+        return None;
+    }
+
+    let rx = average_ranks(xs);
+    let ry = average_ranks(ys);
+    pearson(&rx, &ry)
+}
+
+fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+
+    let mut cov = 0.0;
+    let mut var_x = 0.0;
+    let mut var_y = 0.0;
+    for (x, y) in xs.iter().zip(ys) {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+
+    Some(cov / (var_x.sqrt() * var_y.sqrt()))
+}
+
+fn average_ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && values[order[j + 1]] == values[order[i]] {
+            j += 1;
+        }
+        // Ranks are 1-based; tied values share the mean of their positions
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        for idx in &order[i..=j] {
+            ranks[*idx] = rank;
+        }
+        i = j + 1;
+    }
+
+    ranks
+}
+
+fn ratio(num: usize, den: usize) -> Option<f64> {
+    if den == 0 {
+        None
+    } else {
+        Some(num as f64 / den as f64)
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0usize), |(s, c), v| (s + v, c + 1));
+    if count == 0 {
+        None
+    } else {
+        Some(sum / count as f64)
+    }
+}
+
+fn clamp_score(score: f64) -> f64 {
+    score.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::dataset::EvalOutcome;
+
+    fn row(address: &str, score: f64, passed: bool, return_24h: Option<f64>) -> EvalRow {
+        EvalRow {
+            address: address.to_string(),
+            symbol: address.to_uppercase(),
+            score,
+            passed,
+            outcome: EvalOutcome {
+                return_24h,
+                return_7d: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_precision_recall() {
+        let rows = vec![
+            row("a", 0.9, true, Some(0.5)),
+            row("b", 0.8, true, Some(-0.2)),
+            row("c", 0.3, false, Some(0.1)),
+            row("d", 0.1, false, Some(-0.4)),
+            row("e", 0.7, true, None),
+        ];
+
+        let metrics = compute_metrics(&rows, Horizon::H24, 0.0);
+        assert_eq!(metrics.labeled, 4);
+        assert_eq!(metrics.positives, 2);
+        assert_eq!(metrics.passed, 2);
+        assert_eq!(metrics.true_positives, 1);
+        assert_eq!(metrics.precision, Some(0.5));
+        assert_eq!(metrics.recall, Some(0.5));
+        assert_eq!(metrics.f1, Some(0.5));
+        assert_eq!(metrics.mean_return_passed, Some(0.15));
+    }
+
+    #[test]
+    fn test_unlabeled_horizon_is_empty() {
+        let rows = vec![row("a", 0.9, true, Some(0.5))];
+        let metrics = compute_metrics(&rows, Horizon::D7, 0.0);
+        assert_eq!(metrics, HorizonMetrics::default());
+    }
+
+    #[test]
+    fn test_calibration_buckets() {
+        let rows = vec![
+            row("a", 0.95, true, Some(0.5)),
+            row("b", 0.92, true, Some(-0.5)),
+            row("c", 0.05, false, Some(-0.1)),
+        ];
+
+        let metrics = compute_metrics(&rows, Horizon::H24, 0.0);
+        assert_eq!(metrics.calibration.len(), 2);
+        assert_eq!(metrics.calibration[1].count, 2);
+        assert_eq!(metrics.calibration[1].hit_rate, 0.5);
+        assert!(metrics.expected_calibration_error.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_spearman() {
+        assert!((spearman(&[1.0, 2.0, 3.0], &[10.0, 20.0, 30.0]).unwrap() - 1.0).abs() < 1e-12);
+        assert!((spearman(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]).unwrap() + 1.0).abs() < 1e-12);
+        assert_eq!(spearman(&[1.0, 1.0, 1.0], &[3.0, 2.0, 1.0]), None);
+        assert_eq!(spearman(&[1.0], &[3.0]), None);
+    }
+
+    #[test]
+    fn test_average_ranks_with_ties() {
+        assert_eq!(
+            average_ranks(&[5.0, 1.0, 5.0, 3.0]),
+            vec![3.5, 1.0, 3.5, 2.0]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Offline evaluation of the token filter pipeline.
+//!
+//! A dataset of historical BirdEye token snapshots is replayed through
+//! [`TokenFilterService::analyze_tokens`] and the resulting scores and
+//! pass/fail verdicts are compared with the returns the tokens realized
+//! afterwards.
+
+pub mod dataset;
+pub mod metrics;
+pub mod report;
+
+pub use dataset::{EvalCase, EvalDataset, EvalOutcome, Horizon};
+pub use metrics::{compute_metrics, CalibrationBucket, HorizonMetrics};
+pub use report::{EvalReport, EvalRow, ReportDiff};
+
+use crate::services::token_filter::{TokenFilterService, PROMPT_VERSION};
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::{BTreeMap, HashMap};
+use tracing::info;
+
+/// Default number of tokens sent to the pipeline per batch, matching the
+/// page size the live pipeline works with.
+pub const DEFAULT_BATCH_SIZE: usize = 25;
+
+pub struct EvalRunner<'a> {
+    filter: &'a TokenFilterService,
+    batch_size: usize,
+    success_threshold: f64,
+}
+
+impl<'a> EvalRunner<'a> {
+    pub fn new(filter: &'a TokenFilterService) -> Self {
+        Self {
+            filter,
+            batch_size: DEFAULT_BATCH_SIZE,
+            success_threshold: 0.0,
+        }
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Minimum realized return for a case to count as a success
+    pub fn success_threshold(mut self, threshold: f64) -> Self {
+        self.success_threshold = threshold;
+        self
+    }
+
+    pub async fn run(&self, dataset: &EvalDataset) -> Result<EvalReport> {
+        info!(
+            "Replaying {} cases from {} with model {}",
+            dataset.cases.len(),
+            dataset.name,
+            self.filter.model()
+        );
+
+        let mut rows = Vec::with_capacity(dataset.cases.len());
+
+        for (i, batch) in dataset.cases.chunks(self.batch_size).enumerate() {
+            info!("Eval batch {} ({} tokens)", i + 1, batch.len());
+
+            let tokens = batch.iter().map(|c| c.token.clone()).collect();
+            let analysis = self.filter.analyze_tokens(tokens).await?;
+
+            let market_scores: HashMap<&str, f64> = analysis
+                .market
+                .filtered_tokens
+                .iter()
+                .map(|t| (t.address.as_str(), t.score))
+                .collect();
+            let final_scores: HashMap<&str, f64> = analysis
+                .metadata
+                .filtered_tokens
+                .iter()
+                .map(|t| (t.address.as_str(), t.score))
+                .collect();
+
+            for case in batch {
+                let address = case.token.address.as_str();
+                let (score, passed) = match final_scores.get(address) {
+                    Some(score) => (*score, true),
+                    None => (market_scores.get(address).copied().unwrap_or(0.0), false),
+                };
+
+                rows.push(EvalRow {
+                    address: case.token.address.clone(),
+                    symbol: case.token.symbol.clone(),
+                    score,
+                    passed,
+                    outcome: case.outcome.clone(),
+                });
+            }
+        }
+
+        rows.sort_by(|a, b| a.address.cmp(&b.address));
+
+        let metrics: BTreeMap<String, HorizonMetrics> = Horizon::ALL
+            .iter()
+            .map(|h| {
+                (
+                    h.label().to_string(),
+                    compute_metrics(&rows, *h, self.success_threshold),
+                )
+            })
+            .collect();
+
+        Ok(EvalReport {
+            dataset: dataset.name.clone(),
+            model: self.filter.model().to_string(),
+            prompt_version: PROMPT_VERSION.to_string(),
+            generated_at: Utc::now().timestamp(),
+            success_threshold: self.success_threshold,
+            metrics,
+            rows,
+        })
+    }
+}
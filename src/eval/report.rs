@@ -0,0 +1,303 @@
+use super::dataset::{EvalOutcome, Horizon};
+use super::metrics::HorizonMetrics;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Pipeline verdict for one dataset case
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalRow {
+    pub address: String,
+    pub symbol: String,
+    /// Final score, or the market-stage score for tokens dropped at the
+    /// metadata stage, or 0.0 for tokens dropped at the market stage
+    pub score: f64,
+    /// Whether the token made it through both analysis stages
+    pub passed: bool,
+    pub outcome: EvalOutcome,
+}
+
+/// Result of replaying a dataset through the filter pipeline.
+///
+/// Rows are sorted by address and metrics are keyed by horizon label so two
+/// reports serialize in the same order and can be diffed as text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub dataset: String,
+    pub model: String,
+    pub prompt_version: String,
+    /// Unix timestamp (seconds) of the run
+    pub generated_at: i64,
+    pub success_threshold: f64,
+    pub metrics: BTreeMap<String, HorizonMetrics>,
+    pub rows: Vec<EvalRow>,
+}
+
+impl EvalReport {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read eval report {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse eval report {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write eval report {}", path.display()))
+    }
+
+    pub fn horizon(&self, horizon: Horizon) -> Option<&HorizonMetrics> {
+        self.metrics.get(horizon.label())
+    }
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Dataset: {} | model: {} | prompt: {} | success >= {:+.1}%",
+            self.dataset,
+            self.model,
+            self.prompt_version,
+            self.success_threshold * 100.0
+        )?;
+        writeln!(
+            f,
+            "Cases: {} ({} passed)",
+            self.rows.len(),
+            self.rows.iter().filter(|r| r.passed).count()
+        )?;
+
+        for (label, m) in &self.metrics {
+            writeln!(
+                f,
+                "\n[{}] labeled={} positives={} passed={}",
+                label, m.labeled, m.positives, m.passed
+            )?;
+            writeln!(f, "  precision: {}", fmt_opt(m.precision))?;
+            writeln!(f, "  recall:    {}", fmt_opt(m.recall))?;
+            writeln!(f, "  f1:        {}", fmt_opt(m.f1))?;
+            writeln!(f, "  brier:     {}", fmt_opt(m.brier_score))?;
+            writeln!(f, "  ece:       {}", fmt_opt(m.expected_calibration_error))?;
+            writeln!(f, "  spearman:  {}", fmt_opt(m.spearman))?;
+            for b in &m.calibration {
+                writeln!(
+                    f,
+                    "  [{:.1}-{:.1}) n={:<4} score={:.3} hit={:.3}",
+                    b.lower, b.upper, b.count, b.mean_score, b.hit_rate
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Change of one metric between two reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricDelta {
+    pub horizon: String,
+    pub metric: String,
+    pub before: Option<f64>,
+    pub after: Option<f64>,
+}
+
+impl MetricDelta {
+    pub fn delta(&self) -> Option<f64> {
+        Some(self.after? - self.before?)
+    }
+}
+
+/// A case whose pass/fail verdict changed between two reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerdictFlip {
+    pub address: String,
+    pub symbol: String,
+    pub before_passed: bool,
+    pub after_passed: bool,
+    pub before_score: f64,
+    pub after_score: f64,
+}
+
+type MetricGetter = fn(&HorizonMetrics) -> Option<f64>;
+
+/// Differences between a baseline report and a candidate report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportDiff {
+    pub before: String,
+    pub after: String,
+    pub metrics: Vec<MetricDelta>,
+    pub flips: Vec<VerdictFlip>,
+}
+
+impl ReportDiff {
+    pub fn between(before: &EvalReport, after: &EvalReport) -> Self {
+        let mut metrics = Vec::new();
+        let labels: std::collections::BTreeSet<&String> =
+            before.metrics.keys().chain(after.metrics.keys()).collect();
+
+        for label in labels {
+            let b = before.metrics.get(label);
+            let a = after.metrics.get(label);
+            let pick = |m: Option<&HorizonMetrics>, f: MetricGetter| m.and_then(f);
+
+            let fields: [(&str, MetricGetter); 6] = [
+                ("precision", |m| m.precision),
+                ("recall", |m| m.recall),
+                ("f1", |m| m.f1),
+                ("brier_score", |m| m.brier_score),
+                ("expected_calibration_error", |m| {
+                    m.expected_calibration_error
+                }),
+                ("spearman", |m| m.spearman),
+            ];
+
+            for (name, getter) in fields {
+                metrics.push(MetricDelta {
+                    horizon: label.clone(),
+                    metric: name.to_string(),
+                    before: pick(b, getter),
+                    after: pick(a, getter),
+                });
+            }
+        }
+
+        let baseline: BTreeMap<&str, &EvalRow> = before
+            .rows
+            .iter()
+            .map(|r| (r.address.as_str(), r))
+            .collect();
+        let flips = after
+            .rows
+            .iter()
+            .filter_map(|row| {
+                let prev = baseline.get(row.address.as_str())?;
+                (prev.passed != row.passed).then(|| VerdictFlip {
+                    address: row.address.clone(),
+                    symbol: row.symbol.clone(),
+                    before_passed: prev.passed,
+                    after_passed: row.passed,
+                    before_score: prev.score,
+                    after_score: row.score,
+                })
+            })
+            .collect();
+
+        Self {
+            before: format!(
+                "{}@{}/{}",
+                before.dataset, before.model, before.prompt_version
+            ),
+            after: format!("{}@{}/{}", after.dataset, after.model, after.prompt_version),
+            metrics,
+            flips,
+        }
+    }
+}
+
+impl fmt::Display for ReportDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} -> {}", self.before, self.after)?;
+        for m in &self.metrics {
+            writeln!(
+                f,
+                "[{}] {:<28} {:>8} -> {:>8} ({})",
+                m.horizon,
+                m.metric,
+                fmt_opt(m.before),
+                fmt_opt(m.after),
+                m.delta()
+                    .map(|d| format!("{:+.4}", d))
+                    .unwrap_or_else(|| "n/a".to_string())
+            )?;
+        }
+
+        if !self.flips.is_empty() {
+            writeln!(f, "\nVerdict changes:")?;
+            for flip in &self.flips {
+                writeln!(
+                    f,
+                    "  {} ({}): {} ({:.2}) -> {} ({:.2})",
+                    flip.symbol,
+                    flip.address,
+                    if flip.before_passed { "pass" } else { "fail" },
+                    flip.before_score,
+                    if flip.after_passed { "pass" } else { "fail" },
+                    flip.after_score
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn fmt_opt(value: Option<f64>) -> String {
+    value
+        .map(|v| format!("{:.4}", v))
+        .unwrap_or_else(|| "n/a".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(model: &str, rows: Vec<EvalRow>, precision: Option<f64>) -> EvalReport {
+        let mut metrics = BTreeMap::new();
+        metrics.insert(
+            "24h".to_string(),
+            HorizonMetrics {
+                precision,
+                ..Default::default()
+            },
+        );
+        EvalReport {
+            dataset: "fixture".to_string(),
+            model: model.to_string(),
+            prompt_version: "v1".to_string(),
+            generated_at: 0,
+            success_threshold: 0.0,
+            metrics,
+            rows,
+        }
+    }
+
+    fn row(address: &str, score: f64, passed: bool) -> EvalRow {
+        EvalRow {
+            address: address.to_string(),
+            symbol: address.to_uppercase(),
+            score,
+            passed,
+            outcome: EvalOutcome::default(),
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_flips_and_deltas() {
+        let before = report(
+            "a",
+            vec![row("x", 0.8, true), row("y", 0.2, false)],
+            Some(0.5),
+        );
+        let after = report(
+            "b",
+            vec![row("x", 0.4, false), row("y", 0.3, false)],
+            Some(0.75),
+        );
+
+        let diff = ReportDiff::between(&before, &after);
+        assert_eq!(diff.flips.len(), 1);
+        assert_eq!(diff.flips[0].address, "x");
+
+        let precision = diff
+            .metrics
+            .iter()
+            .find(|m| m.metric == "precision")
+            .unwrap();
+        assert_eq!(precision.delta(), Some(0.25));
+    }
+}
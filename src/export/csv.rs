@@ -0,0 +1,105 @@
+use super::{Column, RowWriter, Value};
+use anyhow::{anyhow, Result};
+use std::io::{BufWriter, Write};
+
+/// RFC 4180 CSV with a header row. Timestamps are written as RFC 3339 and
+/// decimals in full, so no precision is lost.
+pub struct CsvWriter<W: Write> {
+    out: BufWriter<W>,
+    columns: usize,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(columns: Vec<Column>, out: W) -> Result<Self> {
+        let mut out = BufWriter::new(out);
+        let header: Vec<String> = columns.iter().map(|c| escape(c.name)).collect();
+        writeln!(out, "{}", header.join(","))?;
+        Ok(Self {
+            out,
+            columns: columns.len(),
+        })
+    }
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub(super) fn timestamp_text(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Text(text) => escape(text),
+        Value::Int(i) => i.to_string(),
+        Value::Float(f) if f.is_finite() => f.to_string(),
+        Value::Float(_) => String::new(),
+        Value::Bool(b) => b.to_string(),
+        Value::Decimal(d) => d.to_string(),
+        Value::Timestamp(millis) => timestamp_text(*millis),
+    }
+}
+
+impl<W: Write> RowWriter for CsvWriter<W> {
+    fn write_row(&mut self, row: &[Value]) -> Result<()> {
+        if row.len() != self.columns {
+            return Err(anyhow!(
+                "row has {} values for {} columns",
+                row.len(),
+                self.columns
+            ));
+        }
+        let cells: Vec<String> = row.iter().map(cell).collect();
+        writeln!(self.out, "{}", cells.join(","))?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ColumnType;
+    use super::*;
+    use bigdecimal::BigDecimal;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_csv_cells() {
+        let columns = vec![
+            Column::new("name", ColumnType::Text),
+            Column::new("price", ColumnType::Decimal),
+            Column::new("at", ColumnType::Timestamp),
+        ];
+        let mut out = Vec::new();
+        let mut writer = Box::new(CsvWriter::new(columns, &mut out).unwrap());
+        writer
+            .write_row(&[
+                Value::Text("say \"hi\", ok".to_string()),
+                Value::Decimal(BigDecimal::from_str("0.000000000000000000123").unwrap()),
+                Value::Timestamp(1_500),
+            ])
+            .unwrap();
+        writer
+            .write_row(&[Value::Null, Value::Null, Value::Null])
+            .unwrap();
+        assert!(writer.write_row(&[Value::Null]).is_err());
+        writer.finish().unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "name,price,at\n\"say \"\"hi\"\", ok\",0.000000000000000000123,1970-01-01T00:00:01.500Z\n,,\n"
+        );
+    }
+}
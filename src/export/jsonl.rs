@@ -0,0 +1,60 @@
+use super::csv::timestamp_text;
+use super::{Column, RowWriter, Value};
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value as JsonValue};
+use std::io::{BufWriter, Write};
+
+/// One JSON object per line. Decimals are strings so that readers don't
+/// parse them into floats; timestamps are RFC 3339.
+pub struct JsonlWriter<W: Write> {
+    out: BufWriter<W>,
+    columns: Vec<Column>,
+}
+
+impl<W: Write> JsonlWriter<W> {
+    pub fn new(columns: Vec<Column>, out: W) -> Self {
+        Self {
+            out: BufWriter::new(out),
+            columns,
+        }
+    }
+}
+
+fn json(value: &Value) -> JsonValue {
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Text(text) => text.clone().into(),
+        Value::Int(i) => (*i).into(),
+        // Non-finite floats become null
+        Value::Float(f) => (*f).into(),
+        Value::Bool(b) => (*b).into(),
+        Value::Decimal(d) => d.to_string().into(),
+        Value::Timestamp(millis) => timestamp_text(*millis).into(),
+    }
+}
+
+impl<W: Write> RowWriter for JsonlWriter<W> {
+    fn write_row(&mut self, row: &[Value]) -> Result<()> {
+        if row.len() != self.columns.len() {
+            return Err(anyhow!(
+                "row has {} values for {} columns",
+                row.len(),
+                self.columns.len()
+            ));
+        }
+        let object: Map<String, JsonValue> = self
+            .columns
+            .iter()
+            .zip(row)
+            .map(|(column, value)| (column.name.to_string(), json(value)))
+            .collect();
+        serde_json::to_writer(&mut self.out, &object)?;
+        self.out.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,418 @@
+//! Export of stored analytics, signals, recommendations, trades and candles
+//! to CSV, JSON Lines and Parquet for analysis outside the agent.
+//!
+//! Documents are streamed from a MongoDB cursor into the writer one row at a
+//! time, so memory use doesn't depend on the size of the exported range.
+//! Decimal amounts keep their exact value: as text in CSV and JSON Lines,
+//! and as `DECIMAL(76, 38)` in Parquet.
+
+mod csv;
+mod jsonl;
+mod parquet;
+mod rows;
+
+use crate::config::mongodb::MongoDbPool;
+use crate::models::market_signal::MarketSignal;
+use crate::models::token_analytics::TokenAnalytics;
+use crate::services::wallet_tracker::TokenRecommendation;
+use crate::trading::transactions::TradeRecord;
+use anyhow::{anyhow, Result};
+use bigdecimal::BigDecimal;
+use bson::{doc, DateTime, Document};
+use futures::TryStreamExt;
+use mongodb::Database;
+use serde::de::DeserializeOwned;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::info;
+
+pub use rows::{CandleBuilder, ExportRow};
+
+/// Scale of decimal columns in Parquet, wide enough for the smallest token
+/// prices
+pub const DECIMAL_SCALE: i64 = 38;
+/// Precision of decimal columns in Parquet, the most 32 bytes can hold
+pub const DECIMAL_PRECISION: i32 = 76;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Format implied by a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "jsonl" | "ndjson" => Ok(ExportFormat::Jsonl),
+            "parquet" => Ok(ExportFormat::Parquet),
+            _ => Err(anyhow!(
+                "unknown format '{}', expected csv, jsonl or parquet",
+                s
+            )),
+        }
+    }
+}
+
+/// What to export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dataset {
+    Analytics,
+    Signals,
+    Recommendations,
+    Trades,
+    /// OHLC bars of `bucket_secs` built from token analytics
+    Candles {
+        bucket_secs: i64,
+    },
+}
+
+impl Dataset {
+    fn collection(&self) -> &'static str {
+        match self {
+            Dataset::Analytics | Dataset::Candles { .. } => "token_analytics",
+            Dataset::Signals => "market_signals",
+            Dataset::Recommendations => "token_recommendations",
+            Dataset::Trades => "trades",
+        }
+    }
+
+    /// Fields holding the token and the time of a document
+    fn fields(&self) -> (&'static str, &'static str) {
+        match self {
+            Dataset::Analytics | Dataset::Candles { .. } => ("token_address", "timestamp"),
+            Dataset::Signals => ("asset_address", "timestamp"),
+            Dataset::Recommendations => ("token_address", "analysis_date"),
+            Dataset::Trades => ("token_address", "created_at"),
+        }
+    }
+
+    pub fn columns(&self) -> Vec<Column> {
+        match self {
+            Dataset::Analytics => TokenAnalytics::columns(),
+            Dataset::Signals => MarketSignal::columns(),
+            Dataset::Recommendations => TokenRecommendation::columns(),
+            Dataset::Trades => TradeRecord::columns(),
+            Dataset::Candles { .. } => CandleBuilder::columns(),
+        }
+    }
+}
+
+/// Restricts the exported documents. Unset fields don't restrict.
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub token_address: Option<String>,
+    /// Documents at or after this time
+    pub since: Option<DateTime>,
+    /// Documents before this time
+    pub until: Option<DateTime>,
+}
+
+impl ExportFilter {
+    fn to_query(&self, dataset: Dataset) -> Document {
+        let (token_field, time_field) = dataset.fields();
+        let mut query = doc! {};
+        if let Some(address) = &self.token_address {
+            query.insert(token_field, address);
+        }
+        // Recommendation dates are stored as RFC 3339 strings whose fraction
+        // digits vary, so they're converted to dates before comparing
+        if let Dataset::Recommendations = dataset {
+            let date = doc! { "$toDate": format!("${}", time_field) };
+            let mut bounds = Vec::new();
+            if let Some(since) = self.since {
+                bounds.push(doc! { "$gte": [date.clone(), since] });
+            }
+            if let Some(until) = self.until {
+                bounds.push(doc! { "$lt": [date, until] });
+            }
+            if !bounds.is_empty() {
+                query.insert("$expr", doc! { "$and": bounds });
+            }
+            return query;
+        }
+        let mut range = doc! {};
+        if let Some(since) = self.since {
+            range.insert("$gte", since);
+        }
+        if let Some(until) = self.until {
+            range.insert("$lt", until);
+        }
+        if !range.is_empty() {
+            query.insert(time_field, range);
+        }
+        query
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Text,
+    Int,
+    Float,
+    Bool,
+    /// Exact decimal amount
+    Decimal,
+    /// Milliseconds since the Unix epoch, UTC
+    Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    pub kind: ColumnType,
+}
+
+impl Column {
+    pub const fn new(name: &'static str, kind: ColumnType) -> Self {
+        Self { name, kind }
+    }
+}
+
+/// A cell of an exported row
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Decimal(BigDecimal),
+    Timestamp(i64),
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(value.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<BigDecimal> for Value {
+    fn from(value: BigDecimal) -> Self {
+        Value::Decimal(value)
+    }
+}
+
+impl From<&BigDecimal> for Value {
+    fn from(value: &BigDecimal) -> Self {
+        Value::Decimal(value.clone())
+    }
+}
+
+impl From<DateTime> for Value {
+    fn from(value: DateTime) -> Self {
+        Value::Timestamp(value.timestamp_millis())
+    }
+}
+
+impl From<chrono::DateTime<chrono::Utc>> for Value {
+    fn from(value: chrono::DateTime<chrono::Utc>) -> Self {
+        Value::Timestamp(value.timestamp_millis())
+    }
+}
+
+/// Sink for the rows of one export
+pub trait RowWriter {
+    /// Write a row with one value per column
+    fn write_row(&mut self, row: &[Value]) -> Result<()>;
+
+    /// Flush buffered rows and write any trailer
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// Writer of `format` for rows of `columns`
+pub fn row_writer<W: Write + Send + 'static>(
+    format: ExportFormat,
+    columns: Vec<Column>,
+    out: W,
+) -> Result<Box<dyn RowWriter>> {
+    Ok(match format {
+        ExportFormat::Csv => Box::new(csv::CsvWriter::new(columns, out)?),
+        ExportFormat::Jsonl => Box::new(jsonl::JsonlWriter::new(columns, out)),
+        ExportFormat::Parquet => Box::new(parquet::ParquetWriter::new(columns, out)?),
+    })
+}
+
+pub struct Exporter {
+    db: Database,
+}
+
+impl Exporter {
+    pub fn new(db_pool: &MongoDbPool) -> Self {
+        Self {
+            db: db_pool.database(&db_pool.get_config().database),
+        }
+    }
+
+    /// Stream the documents of `dataset` matching `filter` into `writer`,
+    /// oldest first. Returns the number of rows written.
+    pub async fn export(
+        &self,
+        dataset: Dataset,
+        filter: &ExportFilter,
+        mut writer: Box<dyn RowWriter>,
+    ) -> Result<u64> {
+        let rows = match dataset {
+            Dataset::Analytics => {
+                self.stream::<TokenAnalytics>(dataset, filter, writer.as_mut())
+                    .await?
+            }
+            Dataset::Signals => {
+                self.stream::<MarketSignal>(dataset, filter, writer.as_mut())
+                    .await?
+            }
+            Dataset::Recommendations => {
+                self.stream::<TokenRecommendation>(dataset, filter, writer.as_mut())
+                    .await?
+            }
+            Dataset::Trades => {
+                self.stream::<TradeRecord>(dataset, filter, writer.as_mut())
+                    .await?
+            }
+            Dataset::Candles { bucket_secs } => {
+                self.stream_candles(bucket_secs, filter, writer.as_mut())
+                    .await?
+            }
+        };
+        writer.finish()?;
+        info!("Exported {} rows of {}", rows, dataset.collection());
+        Ok(rows)
+    }
+
+    async fn stream<T>(
+        &self,
+        dataset: Dataset,
+        filter: &ExportFilter,
+        writer: &mut dyn RowWriter,
+    ) -> Result<u64>
+    where
+        T: ExportRow + DeserializeOwned + Send + Sync,
+    {
+        let (_, time_field) = dataset.fields();
+        let mut cursor = self
+            .db
+            .collection::<T>(dataset.collection())
+            .find(filter.to_query(dataset))
+            .sort(doc! { time_field: 1 })
+            .projection(doc! { "embedding": 0 })
+            .await?;
+        let mut rows = 0;
+        while let Some(document) = cursor.try_next().await? {
+            writer.write_row(&document.row())?;
+            rows += 1;
+        }
+        Ok(rows)
+    }
+
+    /// Candles need each token's snapshots in order, so they are read by
+    /// token, then time
+    async fn stream_candles(
+        &self,
+        bucket_secs: i64,
+        filter: &ExportFilter,
+        writer: &mut dyn RowWriter,
+    ) -> Result<u64> {
+        let dataset = Dataset::Candles { bucket_secs };
+        let mut cursor = self
+            .db
+            .collection::<TokenAnalytics>(dataset.collection())
+            .find(filter.to_query(dataset))
+            .sort(doc! { "token_address": 1, "timestamp": 1 })
+            .projection(doc! { "embedding": 0 })
+            .await?;
+        let mut builder = CandleBuilder::new(bucket_secs)?;
+        let mut rows = 0;
+        while let Some(snapshot) = cursor.try_next().await? {
+            if let Some(candle) = builder.push(&snapshot) {
+                writer.write_row(&candle)?;
+                rows += 1;
+            }
+        }
+        if let Some(candle) = builder.finish() {
+            writer.write_row(&candle)?;
+            rows += 1;
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_and_filter() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("out/signals.PARQUET")),
+            Some(ExportFormat::Parquet)
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("signals")), None);
+        assert!("xlsx".parse::<ExportFormat>().is_err());
+
+        let filter = ExportFilter {
+            token_address: Some("mint".to_string()),
+            since: Some(DateTime::from_millis(0)),
+            until: None,
+        };
+        assert_eq!(
+            filter.to_query(Dataset::Signals),
+            doc! { "asset_address": "mint", "timestamp": { "$gte": DateTime::from_millis(0) } }
+        );
+        assert_eq!(
+            filter.to_query(Dataset::Recommendations),
+            doc! {
+                "token_address": "mint",
+                "$expr": { "$and": [
+                    { "$gte": [{ "$toDate": "$analysis_date" }, DateTime::from_millis(0)] },
+                ] },
+            }
+        );
+    }
+}
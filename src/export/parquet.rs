@@ -0,0 +1,413 @@
+//! Parquet output through the `parquet` crate's file writer.
+//!
+//! Every column is optional. Rows are buffered per column up to
+//! `ROW_GROUP_SIZE` and then written as one row group, so memory stays
+//! bounded.
+//!
+//! Column types map to `BYTE_ARRAY` (UTF8), `INT64`, `DOUBLE`, `BOOLEAN`,
+//! `FIXED_LEN_BYTE_ARRAY(32)` (`DECIMAL(76, 38)`) and `INT64`
+//! (`TIMESTAMP_MILLIS`).
+
+use super::{Column, ColumnType, RowWriter, Value, DECIMAL_PRECISION, DECIMAL_SCALE};
+use anyhow::{anyhow, Result};
+use bigdecimal::BigDecimal;
+use parquet::basic::{ConvertedType, Repetition, Type as PhysicalType};
+use parquet::data_type::{
+    BoolType, ByteArray, ByteArrayType, DataType, DoubleType, FixedLenByteArray,
+    FixedLenByteArrayType, Int64Type,
+};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{SerializedColumnWriter, SerializedFileWriter};
+use parquet::schema::types::Type;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Rows buffered before a row group is written
+pub const ROW_GROUP_SIZE: usize = 50_000;
+const DECIMAL_BYTES: usize = 32;
+
+/// Values of one column in the current row group
+enum Values {
+    Text(Vec<ByteArray>),
+    Int(Vec<i64>),
+    Float(Vec<f64>),
+    Bool(Vec<bool>),
+    Decimal(Vec<FixedLenByteArray>),
+}
+
+struct ColumnBuffer {
+    /// Definition level of each row: 1 when the value is present
+    levels: Vec<i16>,
+    values: Values,
+}
+
+pub struct ParquetWriter<W: Write + Send> {
+    out: SerializedFileWriter<W>,
+    columns: Vec<Column>,
+    buffers: Vec<ColumnBuffer>,
+    rows: usize,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    pub fn new(columns: Vec<Column>, out: W) -> Result<Self> {
+        let fields = columns
+            .iter()
+            .map(|column| field(column).map(Arc::new))
+            .collect::<Result<Vec<_>>>()?;
+        let schema = Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()?;
+        let properties = WriterProperties::builder()
+            .set_created_by("cainam-core".to_string())
+            .set_max_row_group_size(ROW_GROUP_SIZE)
+            .build();
+
+        Ok(Self {
+            out: SerializedFileWriter::new(out, Arc::new(schema), Arc::new(properties))?,
+            buffers: columns.iter().map(|c| ColumnBuffer::new(c.kind)).collect(),
+            columns,
+            rows: 0,
+        })
+    }
+
+    fn flush_row_group(&mut self) -> Result<()> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+        let mut group = self.out.next_row_group()?;
+        for (buffer, column) in self.buffers.iter_mut().zip(&self.columns) {
+            let mut writer = group
+                .next_column()?
+                .ok_or_else(|| anyhow!("no column writer for {}", column.name))?;
+            buffer.write(&mut writer)?;
+            writer.close()?;
+            *buffer = ColumnBuffer::new(column.kind);
+        }
+        group.close()?;
+        self.rows = 0;
+        Ok(())
+    }
+}
+
+/// Schema field of an optional column
+fn field(column: &Column) -> Result<Type> {
+    let builder = match column.kind {
+        ColumnType::Text => Type::primitive_type_builder(column.name, PhysicalType::BYTE_ARRAY)
+            .with_converted_type(ConvertedType::UTF8),
+        ColumnType::Int => Type::primitive_type_builder(column.name, PhysicalType::INT64),
+        ColumnType::Float => Type::primitive_type_builder(column.name, PhysicalType::DOUBLE),
+        ColumnType::Bool => Type::primitive_type_builder(column.name, PhysicalType::BOOLEAN),
+        ColumnType::Decimal => {
+            Type::primitive_type_builder(column.name, PhysicalType::FIXED_LEN_BYTE_ARRAY)
+                .with_length(DECIMAL_BYTES as i32)
+                .with_converted_type(ConvertedType::DECIMAL)
+                .with_precision(DECIMAL_PRECISION)
+                .with_scale(DECIMAL_SCALE as i32)
+        }
+        ColumnType::Timestamp => Type::primitive_type_builder(column.name, PhysicalType::INT64)
+            .with_converted_type(ConvertedType::TIMESTAMP_MILLIS),
+    };
+    Ok(builder.with_repetition(Repetition::OPTIONAL).build()?)
+}
+
+/// Unscaled `DECIMAL(76, 38)` value as 32 big-endian two's complement bytes.
+/// Values with more fraction digits than the scale are rejected rather than
+/// truncated.
+fn decimal_bytes(value: &BigDecimal) -> Result<[u8; DECIMAL_BYTES]> {
+    let scaled = value.with_scale(DECIMAL_SCALE);
+    let (unscaled, _) = scaled.as_bigint_and_exponent();
+    let bytes = unscaled.to_signed_bytes_be();
+    if scaled != *value
+        || bytes.len() > DECIMAL_BYTES
+        || unscaled.to_string().trim_start_matches('-').len() > DECIMAL_PRECISION as usize
+    {
+        return Err(anyhow!(
+            "{} doesn't fit DECIMAL({}, {})",
+            value,
+            DECIMAL_PRECISION,
+            DECIMAL_SCALE
+        ));
+    }
+    let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+    let mut out = [fill; DECIMAL_BYTES];
+    out[DECIMAL_BYTES - bytes.len()..].copy_from_slice(&bytes);
+    Ok(out)
+}
+
+impl ColumnBuffer {
+    fn new(kind: ColumnType) -> Self {
+        let values = match kind {
+            ColumnType::Text => Values::Text(Vec::new()),
+            ColumnType::Int | ColumnType::Timestamp => Values::Int(Vec::new()),
+            ColumnType::Float => Values::Float(Vec::new()),
+            ColumnType::Bool => Values::Bool(Vec::new()),
+            ColumnType::Decimal => Values::Decimal(Vec::new()),
+        };
+        Self {
+            levels: Vec::new(),
+            values,
+        }
+    }
+
+    fn push(&mut self, column: &Column, value: &Value) -> Result<()> {
+        let present = match (&mut self.values, value) {
+            (_, Value::Null) => false,
+            (Values::Float(_), Value::Float(f)) if !f.is_finite() => false,
+            (Values::Text(values), Value::Text(text)) => {
+                values.push(text.as_bytes().to_vec().into());
+                true
+            }
+            (Values::Int(values), Value::Int(i)) if column.kind == ColumnType::Int => {
+                values.push(*i);
+                true
+            }
+            (Values::Int(values), Value::Timestamp(i)) if column.kind == ColumnType::Timestamp => {
+                values.push(*i);
+                true
+            }
+            (Values::Float(values), Value::Float(f)) => {
+                values.push(*f);
+                true
+            }
+            (Values::Bool(values), Value::Bool(b)) => {
+                values.push(*b);
+                true
+            }
+            (Values::Decimal(values), Value::Decimal(d)) => {
+                values.push(decimal_bytes(d)?.to_vec().into());
+                true
+            }
+            (_, value) => {
+                return Err(anyhow!(
+                    "column {} of type {:?} can't hold {:?}",
+                    column.name,
+                    column.kind,
+                    value
+                ))
+            }
+        };
+        self.levels.push(present.into());
+        Ok(())
+    }
+
+    /// Keep the first `rows` rows
+    fn truncate(&mut self, rows: usize) {
+        self.levels.truncate(rows);
+        let present = self.levels.iter().filter(|level| **level == 1).count();
+        match &mut self.values {
+            Values::Text(values) => values.truncate(present),
+            Values::Int(values) => values.truncate(present),
+            Values::Float(values) => values.truncate(present),
+            Values::Bool(values) => values.truncate(present),
+            Values::Decimal(values) => values.truncate(present),
+        }
+    }
+
+    fn write(&self, writer: &mut SerializedColumnWriter<'_>) -> Result<()> {
+        match &self.values {
+            Values::Text(values) => write_batch::<ByteArrayType>(writer, values, &self.levels),
+            Values::Int(values) => write_batch::<Int64Type>(writer, values, &self.levels),
+            Values::Float(values) => write_batch::<DoubleType>(writer, values, &self.levels),
+            Values::Bool(values) => write_batch::<BoolType>(writer, values, &self.levels),
+            Values::Decimal(values) => {
+                write_batch::<FixedLenByteArrayType>(writer, values, &self.levels)
+            }
+        }
+    }
+}
+
+fn write_batch<T: DataType>(
+    writer: &mut SerializedColumnWriter<'_>,
+    values: &[T::T],
+    levels: &[i16],
+) -> Result<()> {
+    writer
+        .typed::<T>()
+        .write_batch(values, Some(levels), None)?;
+    Ok(())
+}
+
+impl<W: Write + Send> RowWriter for ParquetWriter<W> {
+    fn write_row(&mut self, row: &[Value]) -> Result<()> {
+        if row.len() != self.columns.len() {
+            return Err(anyhow!(
+                "row has {} values for {} columns",
+                row.len(),
+                self.columns.len()
+            ));
+        }
+        let pushed = self
+            .buffers
+            .iter_mut()
+            .zip(&self.columns)
+            .zip(row)
+            .try_for_each(|((buffer, column), value)| buffer.push(column, value));
+        if let Err(e) = pushed {
+            // Drop what was buffered of the rejected row so the columns stay
+            // aligned
+            let rows = self.rows;
+            self.buffers.iter_mut().for_each(|b| b.truncate(rows));
+            return Err(e);
+        }
+        self.rows += 1;
+        if self.rows >= ROW_GROUP_SIZE {
+            self.flush_row_group()?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.flush_row_group()?;
+        self.out.into_inner()?.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::num_bigint::BigInt;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+    use std::str::FromStr;
+
+    fn unscaled(bytes: &[u8]) -> BigInt {
+        BigInt::from_signed_bytes_be(bytes)
+    }
+
+    #[test]
+    fn test_decimal_bytes() {
+        let tick = "0.00000000000000000000000000000000000001";
+        let negative = decimal_bytes(&BigDecimal::from_str(&format!("-{}", tick)).unwrap());
+        assert_eq!(negative.unwrap(), [0xff; 32]);
+        let price = decimal_bytes(&BigDecimal::from_str("1.5").unwrap()).unwrap();
+        assert_eq!(
+            unscaled(&price),
+            BigInt::from(15) * BigInt::from(10).pow(37)
+        );
+        // A price with more fraction digits than DECIMAL(38, 18) held
+        let small = BigDecimal::from_str("0.000000001234567890123456789").unwrap();
+        let bytes = decimal_bytes(&small).unwrap();
+        assert_eq!(BigDecimal::new(unscaled(&bytes), DECIMAL_SCALE), small);
+
+        assert!(decimal_bytes(&BigDecimal::from_str("1e38").unwrap()).is_err());
+        // Digits past the scale are an error, not truncated away
+        assert!(decimal_bytes(&BigDecimal::from_str(&format!("{}1", tick)).unwrap()).is_err());
+    }
+
+    fn read(name: &str, out: Vec<u8>) -> (i64, Vec<Vec<Field>>) {
+        let path =
+            std::env::temp_dir().join(format!("cainam-{}-{}.parquet", name, std::process::id()));
+        std::fs::write(&path, out).unwrap();
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.unwrap()
+                    .get_column_iter()
+                    .map(|(_, field)| field.clone())
+                    .collect()
+            })
+            .collect();
+        (reader.metadata().file_metadata().num_rows(), rows)
+    }
+
+    #[test]
+    fn test_rejected_row_is_dropped() {
+        let columns = vec![
+            Column::new("token", ColumnType::Text),
+            Column::new("price", ColumnType::Decimal),
+            Column::new("hit", ColumnType::Bool),
+        ];
+        let mut out = Vec::new();
+        let mut writer = Box::new(ParquetWriter::new(columns, &mut out).unwrap());
+        writer
+            .write_row(&[
+                "a".into(),
+                Value::Decimal(BigDecimal::from(2)),
+                Value::Bool(true),
+            ])
+            .unwrap();
+        assert!(writer
+            .write_row(&["b".into(), Value::Null, Value::Int(1)])
+            .is_err());
+        assert!(writer
+            .write_row(&[Value::Int(1), Value::Null, Value::Null])
+            .is_err());
+        writer
+            .write_row(&["c".into(), Value::Null, Value::Bool(false)])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let (rows, read) = read("rejected", out);
+        assert_eq!(rows, 2);
+        assert_eq!(read[1][0], Field::Str("c".to_string()));
+        assert_eq!(read[1][2], Field::Bool(false));
+    }
+
+    #[test]
+    fn test_round_trip_with_parquet_reader() {
+        let columns = vec![
+            Column::new("token", ColumnType::Text),
+            Column::new("count", ColumnType::Int),
+            Column::new("score", ColumnType::Float),
+            Column::new("hit", ColumnType::Bool),
+            Column::new("price", ColumnType::Decimal),
+            Column::new("at", ColumnType::Timestamp),
+        ];
+        let mut out = Vec::new();
+        let mut writer = Box::new(ParquetWriter::new(columns, &mut out).unwrap());
+        writer
+            .write_row(&[
+                "a".into(),
+                Value::Int(-3),
+                Value::Float(0.5),
+                Value::Bool(true),
+                Value::Decimal(BigDecimal::from_str("1.5").unwrap()),
+                Value::Timestamp(1_700_000_000_000),
+            ])
+            .unwrap();
+        writer
+            .write_row(&[
+                "b".into(),
+                Value::Null,
+                Value::Float(f64::NAN),
+                Value::Bool(false),
+                Value::Null,
+                Value::Null,
+            ])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let (count, rows) = read("round-trip", out);
+        assert_eq!(count, 2);
+        assert_eq!(rows[0][0], Field::Str("a".to_string()));
+        assert_eq!(rows[0][1], Field::Long(-3));
+        assert_eq!(rows[0][2], Field::Double(0.5));
+        assert_eq!(rows[0][3], Field::Bool(true));
+        match &rows[0][4] {
+            Field::Decimal(d) => {
+                assert_eq!((d.precision(), d.scale()), (76, 38));
+                assert_eq!(
+                    BigDecimal::new(unscaled(d.data()), DECIMAL_SCALE),
+                    BigDecimal::from_str("1.5").unwrap()
+                );
+            }
+            other => panic!("expected a decimal, got {:?}", other),
+        }
+        assert_eq!(rows[0][5], Field::TimestampMillis(1_700_000_000_000));
+        assert_eq!(
+            rows[1],
+            vec![
+                Field::Str("b".to_string()),
+                Field::Null,
+                Field::Null,
+                Field::Bool(false),
+                Field::Null,
+                Field::Null,
+            ]
+        );
+    }
+}
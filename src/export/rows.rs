@@ -0,0 +1,396 @@
+use super::{Column, ColumnType, Value};
+use crate::models::market_signal::MarketSignal;
+use crate::models::token_analytics::TokenAnalytics;
+use crate::services::recommendation_tracker::OutcomeHorizon;
+use crate::services::wallet_tracker::TokenRecommendation;
+use crate::trading::transactions::TradeRecord;
+use anyhow::{anyhow, Result};
+use bigdecimal::BigDecimal;
+use ColumnType::*;
+
+/// A stored document flattened into typed columns
+pub trait ExportRow {
+    fn columns() -> Vec<Column>;
+
+    /// One value per column, in column order
+    fn row(&self) -> Vec<Value>;
+}
+
+fn id(id: Option<bson::oid::ObjectId>) -> Value {
+    id.map(|id| id.to_hex()).into()
+}
+
+impl ExportRow for TokenAnalytics {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::new("id", Text),
+            Column::new("timestamp", Timestamp),
+            Column::new("token_address", Text),
+            Column::new("token_symbol", Text),
+            Column::new("token_name", Text),
+            Column::new("decimals", Int),
+            Column::new("price", Decimal),
+            Column::new("price_change_24h", Decimal),
+            Column::new("price_change_7d", Decimal),
+            Column::new("volume_24h", Decimal),
+            Column::new("volume_change_24h", Decimal),
+            Column::new("market_cap", Decimal),
+            Column::new("fully_diluted_market_cap", Decimal),
+            Column::new("circulating_supply", Decimal),
+            Column::new("total_supply", Decimal),
+            Column::new("liquidity", Decimal),
+            Column::new("liquidity_change_24h", Decimal),
+            Column::new("trades_24h", Int),
+            Column::new("average_trade_size", Decimal),
+            Column::new("holder_count", Int),
+            Column::new("active_wallets_24h", Int),
+            Column::new("whale_transactions_24h", Int),
+            Column::new("rsi_14", Decimal),
+            Column::new("macd", Decimal),
+            Column::new("macd_signal", Decimal),
+            Column::new("bollinger_upper", Decimal),
+            Column::new("bollinger_lower", Decimal),
+        ]
+    }
+
+    fn row(&self) -> Vec<Value> {
+        vec![
+            id(self.id),
+            self.timestamp.into(),
+            self.token_address.as_str().into(),
+            self.token_symbol.as_str().into(),
+            self.token_name.as_str().into(),
+            i32::from(self.decimals).into(),
+            (&self.price).into(),
+            self.price_change_24h.as_ref().into(),
+            self.price_change_7d.as_ref().into(),
+            self.volume_24h.as_ref().into(),
+            self.volume_change_24h.as_ref().into(),
+            self.market_cap.as_ref().into(),
+            self.fully_diluted_market_cap.as_ref().into(),
+            self.circulating_supply.as_ref().into(),
+            self.total_supply.as_ref().into(),
+            self.liquidity.as_ref().into(),
+            self.liquidity_change_24h.as_ref().into(),
+            self.trades_24h.into(),
+            self.average_trade_size.as_ref().into(),
+            self.holder_count.into(),
+            self.active_wallets_24h.into(),
+            self.whale_transactions_24h.into(),
+            self.rsi_14.as_ref().into(),
+            self.macd.as_ref().into(),
+            self.macd_signal.as_ref().into(),
+            self.bollinger_upper.as_ref().into(),
+            self.bollinger_lower.as_ref().into(),
+        ]
+    }
+}
+
+impl ExportRow for MarketSignal {
+    fn columns() -> Vec<Column> {
+        let mut columns = vec![
+            Column::new("id", Text),
+            Column::new("timestamp", Timestamp),
+            Column::new("asset_address", Text),
+            Column::new("signal_type", Text),
+            Column::new("price", Decimal),
+            Column::new("confidence", Decimal),
+            Column::new("raw_confidence", Decimal),
+            Column::new("risk_score", Decimal),
+            Column::new("sentiment_score", Decimal),
+            Column::new("price_change_24h", Decimal),
+            Column::new("volume_change_24h", Decimal),
+            Column::new("volume_change", Decimal),
+        ];
+        // Realized return and hit at each horizon, once measured
+        const RETURNS: [&str; 4] = ["return_1h", "return_24h", "return_7d", "return_30d"];
+        const HITS: [&str; 4] = ["hit_1h", "hit_24h", "hit_7d", "hit_30d"];
+        columns.extend(RETURNS.iter().map(|name| Column::new(name, Float)));
+        columns.extend(HITS.iter().map(|name| Column::new(name, Bool)));
+        columns
+    }
+
+    fn row(&self) -> Vec<Value> {
+        let outcome = |horizon: &OutcomeHorizon| {
+            self.outcomes
+                .as_ref()
+                .and_then(|outcomes| outcomes.get(horizon.label()))
+        };
+        let mut row = vec![
+            id(self.id),
+            self.timestamp.into(),
+            self.asset_address.as_str().into(),
+            format!("{:?}", self.signal_type).into(),
+            (&self.price).into(),
+            (&self.confidence).into(),
+            self.raw_confidence.as_ref().into(),
+            (&self.risk_score).into(),
+            self.sentiment_score.as_ref().into(),
+            self.price_change_24h.as_ref().into(),
+            self.volume_change_24h.as_ref().into(),
+            (&self.volume_change).into(),
+        ];
+        row.extend(
+            OutcomeHorizon::ALL
+                .iter()
+                .map(|horizon| outcome(horizon).map(|o| o.forward_return).into()),
+        );
+        row.extend(
+            OutcomeHorizon::ALL
+                .iter()
+                .map(|horizon| outcome(horizon).map(|o| o.hit).into()),
+        );
+        row
+    }
+}
+
+impl ExportRow for TokenRecommendation {
+    fn columns() -> Vec<Column> {
+        let mut columns = vec![
+            Column::new("id", Text),
+            Column::new("analysis_date", Timestamp),
+            Column::new("token_address", Text),
+            Column::new("symbol", Text),
+            Column::new("name", Text),
+            Column::new("recommendation", Text),
+            Column::new("overall_score", Float),
+            Column::new("market_score", Float),
+            Column::new("social_score", Float),
+            Column::new("dev_score", Float),
+            Column::new("risk_score", Float),
+            Column::new("price", Float),
+            Column::new("liquidity", Float),
+            Column::new("market_cap", Float),
+            Column::new("volume_24h", Float),
+            Column::new("holders", Int),
+            Column::new("strengths", Text),
+            Column::new("risks", Text),
+            Column::new("prompt_version", Text),
+        ];
+        // Realized return at each horizon, once measured
+        const RETURNS: [&str; 4] = ["return_1h", "return_24h", "return_7d", "return_30d"];
+        columns.extend(RETURNS.iter().map(|name| Column::new(name, Float)));
+        columns
+    }
+
+    fn row(&self) -> Vec<Value> {
+        let mut row = vec![
+            id(self.id),
+            self.analysis_date.into(),
+            self.token_address.as_str().into(),
+            self.symbol.as_str().into(),
+            self.name.as_str().into(),
+            self.recommendation.as_str().into(),
+            self.overall_score.into(),
+            self.market_score.into(),
+            self.social_score.into(),
+            self.dev_score.into(),
+            self.risk_score.into(),
+            self.price.into(),
+            self.liquidity.into(),
+            self.market_cap.into(),
+            self.volume_24h.into(),
+            self.holders.into(),
+            self.strengths.join("; ").into(),
+            self.risks.join("; ").into(),
+            self.prompt_version.clone().into(),
+        ];
+        row.extend(OutcomeHorizon::ALL.iter().map(|horizon| {
+            self.outcomes
+                .as_ref()
+                .and_then(|outcomes| outcomes.get(horizon.label()))
+                .map(|outcome| outcome.forward_return)
+                .into()
+        }));
+        row
+    }
+}
+
+impl ExportRow for TradeRecord {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::new("id", Text),
+            Column::new("created_at", Timestamp),
+            Column::new("updated_at", Timestamp),
+            Column::new("token_address", Text),
+            Column::new("side", Text),
+            Column::new("quantity", Decimal),
+            Column::new("status", Text),
+            Column::new("reason", Text),
+            Column::new("signature", Text),
+            Column::new("slot", Int),
+            Column::new("attempts", Int),
+            Column::new("priority_fee", Int),
+            Column::new("error", Text),
+        ]
+    }
+
+    fn row(&self) -> Vec<Value> {
+        // Slots and fees fit in i64 by a wide margin
+        let unsigned = |v: Option<u64>| v.map(|v| v as i64).into();
+        vec![
+            id(self.id),
+            self.created_at.into(),
+            self.updated_at.into(),
+            self.token_address.as_str().into(),
+            self.side.to_string().into(),
+            self.quantity.to_decimal().into(),
+            format!("{:?}", self.status).into(),
+            self.reason.as_str().into(),
+            self.signature.clone().into(),
+            unsigned(self.slot),
+            i64::from(self.attempts).into(),
+            unsigned(self.priority_fee),
+            self.error.clone().into(),
+        ]
+    }
+}
+
+struct OpenCandle {
+    token_address: String,
+    bucket: i64,
+    open: BigDecimal,
+    high: BigDecimal,
+    low: BigDecimal,
+    close: BigDecimal,
+    volume_24h: Option<BigDecimal>,
+    samples: i64,
+}
+
+/// Builds OHLC bars with exact prices from snapshots ordered by token, then
+/// time, holding only the bar being built
+pub struct CandleBuilder {
+    bucket_secs: i64,
+    current: Option<OpenCandle>,
+}
+
+impl CandleBuilder {
+    pub fn new(bucket_secs: i64) -> Result<Self> {
+        if bucket_secs <= 0 {
+            return Err(anyhow!("candle interval must be at least one second"));
+        }
+        Ok(Self {
+            bucket_secs,
+            current: None,
+        })
+    }
+
+    pub fn columns() -> Vec<Column> {
+        vec![
+            Column::new("token_address", Text),
+            Column::new("timestamp", Timestamp),
+            Column::new("open", Decimal),
+            Column::new("high", Decimal),
+            Column::new("low", Decimal),
+            Column::new("close", Decimal),
+            Column::new("volume_24h", Decimal),
+            Column::new("samples", Int),
+        ]
+    }
+
+    /// Add the next snapshot, returning the previous bar once it is complete
+    pub fn push(&mut self, snapshot: &TokenAnalytics) -> Option<Vec<Value>> {
+        let secs = snapshot.timestamp.timestamp_millis().div_euclid(1000);
+        let bucket = secs - secs.rem_euclid(self.bucket_secs);
+        let price = &snapshot.price;
+
+        if let Some(candle) = self.current.as_mut() {
+            if candle.token_address == snapshot.token_address && candle.bucket == bucket {
+                if *price > candle.high {
+                    candle.high = price.clone();
+                }
+                if *price < candle.low {
+                    candle.low = price.clone();
+                }
+                candle.close = price.clone();
+                if snapshot.volume_24h.is_some() {
+                    candle.volume_24h = snapshot.volume_24h.clone();
+                }
+                candle.samples += 1;
+                return None;
+            }
+        }
+
+        let done = self.finish();
+        self.current = Some(OpenCandle {
+            token_address: snapshot.token_address.clone(),
+            bucket,
+            open: price.clone(),
+            high: price.clone(),
+            low: price.clone(),
+            close: price.clone(),
+            volume_24h: snapshot.volume_24h.clone(),
+            samples: 1,
+        });
+        done
+    }
+
+    /// The bar being built, if any
+    pub fn finish(&mut self) -> Option<Vec<Value>> {
+        let candle = self.current.take()?;
+        Some(vec![
+            candle.token_address.into(),
+            Value::Timestamp(candle.bucket * 1000),
+            candle.open.into(),
+            candle.high.into(),
+            candle.low.into(),
+            candle.close.into(),
+            candle.volume_24h.into(),
+            candle.samples.into(),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn snapshot(address: &str, secs: i64, price: &str) -> TokenAnalytics {
+        TokenAnalytics {
+            token_address: address.to_string(),
+            price: BigDecimal::from_str(price).unwrap(),
+            ..TokenAnalytics::test_snapshot(secs, 0.0)
+        }
+    }
+
+    #[test]
+    fn test_rows_match_columns() {
+        let analytics = snapshot("a", 0, "1");
+        assert_eq!(analytics.row().len(), TokenAnalytics::columns().len());
+        assert_eq!(
+            TokenRecommendation::columns().len(),
+            19 + OutcomeHorizon::ALL.len()
+        );
+    }
+
+    #[test]
+    fn test_candle_builder() {
+        let mut builder = CandleBuilder::new(60).unwrap();
+        assert!(builder.push(&snapshot("a", 0, "0.000000000123")).is_none());
+        assert!(builder.push(&snapshot("a", 30, "0.000000000125")).is_none());
+        assert!(builder.push(&snapshot("a", 59, "0.000000000121")).is_none());
+
+        let first = builder.push(&snapshot("a", 61, "0.0000000002")).unwrap();
+        let decimal = |s: &str| Value::Decimal(BigDecimal::from_str(s).unwrap());
+        assert_eq!(
+            first,
+            vec![
+                "a".into(),
+                Value::Timestamp(0),
+                decimal("0.000000000123"),
+                decimal("0.000000000125"),
+                decimal("0.000000000121"),
+                decimal("0.000000000121"),
+                Value::Null,
+                Value::Int(3),
+            ]
+        );
+
+        // A new token starts a new bar in the same bucket
+        let second = builder.push(&snapshot("b", 62, "5")).unwrap();
+        assert_eq!(second[1], Value::Timestamp(60_000));
+        assert_eq!(builder.finish().unwrap()[0], "b".into());
+        assert!(builder.finish().is_none());
+    }
+}
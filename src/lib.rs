@@ -1,14 +1,23 @@
+pub mod actions;
 pub mod agent;
 pub mod birdeye;
 pub mod cli;
 pub mod config;
 pub mod error;
+pub mod eval;
+pub mod export;
+pub mod llm;
 pub mod logging;
 pub mod models;
 pub mod services;
+pub mod signals;
+pub mod storage;
+pub mod strategy;
+pub mod tools;
 pub mod trading;
 pub mod twitter;
 pub mod utils;
+pub mod vector_store;
 
 // Re-export commonly used types
 pub use crate::config::{
@@ -19,10 +28,3 @@ pub use crate::config::{
     AgentConfig,
 };
 
-pub mod services {
-    pub mod token_filter;
-    pub mod wallet_tracker;
-    
-    pub use token_filter::TokenFilterService;
-    pub use wallet_tracker::WalletTrackerService;
-}
@@ -0,0 +1,199 @@
+use crate::config::embedding_config::{EmbeddingConfig, EmbeddingProvider};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use rig::embeddings::EmbeddingModel;
+use rig::providers::openai::Client as OpenAIClient;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Turns documents into embedding vectors for vector search.
+#[async_trait]
+pub trait EmbeddingBackend: Send + Sync {
+    /// Identifier of the model producing the vectors
+    fn model(&self) -> &str;
+
+    /// Length of the vectors
+    fn dimensions(&self) -> usize;
+
+    /// Embed each text, returning the vectors in input order
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Backend for the configured provider
+pub fn from_config(config: &EmbeddingConfig, openai_api_key: &str) -> Arc<dyn EmbeddingBackend> {
+    match config.provider {
+        EmbeddingProvider::OpenAI => Arc::new(OpenAIEmbeddings::new(
+            openai_api_key,
+            &config.model,
+            config.dimensions,
+        )),
+        EmbeddingProvider::Local => Arc::new(LocalEmbeddings::new(
+            &config.url,
+            &config.model,
+            config.dimensions,
+        )),
+    }
+}
+
+/// OpenAI embeddings API
+pub struct OpenAIEmbeddings {
+    model: rig::providers::openai::EmbeddingModel,
+    name: String,
+    dimensions: usize,
+}
+
+impl OpenAIEmbeddings {
+    pub fn new(api_key: &str, model: &str, dimensions: usize) -> Self {
+        Self {
+            model: OpenAIClient::new(api_key).embedding_model_with_ndims(model, dimensions),
+            name: model.to_string(),
+            dimensions,
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingBackend for OpenAIEmbeddings {
+    fn model(&self) -> &str {
+        &self.name
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        debug!("Embedding {} documents with {}", texts.len(), self.name);
+        let embeddings = self
+            .model
+            .embed_texts(texts.to_vec())
+            .await
+            .map_err(|e| anyhow!("OpenAI embedding request failed: {}", e))?;
+        check_dimensions(
+            embeddings
+                .into_iter()
+                .map(|e| e.vec.into_iter().map(|v| v as f32).collect())
+                .collect(),
+            texts.len(),
+            self.dimensions,
+        )
+    }
+}
+
+/// Self-hosted server exposing an OpenAI-compatible `/v1/embeddings` route
+pub struct LocalEmbeddings {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    dimensions: usize,
+}
+
+impl LocalEmbeddings {
+    pub fn new(url: &str, model: &str, dimensions: usize) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.to_string(),
+            model: model.to_string(),
+            dimensions,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Vectors of an `/v1/embeddings` response, in input order
+fn parse_response(response: EmbeddingsResponse) -> Vec<Vec<f32>> {
+    let mut data = response.data;
+    data.sort_by_key(|d| d.index);
+    data.into_iter().map(|d| d.embedding).collect()
+}
+
+#[async_trait]
+impl EmbeddingBackend for LocalEmbeddings {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        debug!("Embedding {} documents at {}", texts.len(), self.url);
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&serde_json::json!({ "model": self.model, "input": texts }))
+            .send()
+            .await
+            .with_context(|| format!("Embedding server at {} unreachable", self.url))?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Embedding server returned {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            ));
+        }
+        let body: EmbeddingsResponse = response
+            .json()
+            .await
+            .context("Unexpected embedding server response")?;
+        check_dimensions(parse_response(body), texts.len(), self.dimensions)
+    }
+}
+
+/// One vector per text, each of the configured length
+fn check_dimensions(
+    vectors: Vec<Vec<f32>>,
+    expected: usize,
+    dimensions: usize,
+) -> Result<Vec<Vec<f32>>> {
+    if vectors.len() != expected {
+        return Err(anyhow!(
+            "Expected {} embeddings, got {}",
+            expected,
+            vectors.len()
+        ));
+    }
+    if let Some(v) = vectors.iter().find(|v| v.len() != dimensions) {
+        return Err(anyhow!(
+            "Embedding has {} dimensions, expected {} (check EMBEDDING_DIMENSIONS)",
+            v.len(),
+            dimensions
+        ));
+    }
+    Ok(vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response_orders_by_index() {
+        let body: EmbeddingsResponse = serde_json::from_str(
+            r#"{"data": [
+                {"index": 1, "embedding": [0.0, 1.0]},
+                {"index": 0, "embedding": [1.0, 0.0]}
+            ]}"#,
+        )
+        .unwrap();
+        let vectors = parse_response(body);
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        assert!(check_dimensions(vectors.clone(), 2, 2).is_ok());
+        assert!(check_dimensions(vectors.clone(), 3, 2).is_err());
+        assert!(check_dimensions(vectors, 2, 3).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+pub mod embedding;
+
+use crate::logging::metrics::timed;
+use anyhow::Result;
+use async_trait::async_trait;
+use rig::{
+    completion::{CompletionModel, CompletionRequest},
+    message::{AssistantContent, Message, UserContent},
+    one_or_many::OneOrMany,
+    providers::openai::Client as OpenAIClient,
+};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tracing::{debug, error};
+
+/// A single-prompt completion backend used by the LLM driven services.
+///
+/// Services depend on this trait instead of a concrete provider client so the
+/// model can be swapped (e.g. for evaluation runs) and scripted in tests.
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    /// Identifier of the model answering the prompts
+    fn model(&self) -> &str;
+
+    /// Send a prompt and return the text of the first response choice
+    async fn complete(&self, prompt: &str) -> Result<String>;
+}
+
+/// OpenAI completion backend
+pub struct OpenAIBackend {
+    client: OpenAIClient,
+    model: String,
+}
+
+impl OpenAIBackend {
+    pub fn new(api_key: &str, model: &str) -> Self {
+        Self {
+            client: OpenAIClient::new(api_key),
+            model: model.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OpenAIBackend {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        debug!("Using model: {}", self.model);
+
+        let request = CompletionRequest {
+            prompt: Message::User {
+                content: OneOrMany::one(UserContent::text(prompt.to_string())),
+            },
+            chat_history: vec![],
+            preamble: None,
+            tools: vec![],
+            temperature: None,
+            additional_params: None,
+            documents: vec![],
+            max_tokens: None,
+        };
+
+        let model = self.client.completion_model(&self.model);
+        let completion = match timed("openai", &self.model, model.completion(request)).await {
+            Ok(c) => {
+                debug!("Got successful completion response");
+                c
+            }
+            Err(e) => {
+                error!("Completion request failed: {:?}", e);
+                return Err(anyhow::anyhow!("Failed to get completion: {}", e));
+            }
+        };
+
+        completion
+            .choice
+            .into_iter()
+            .find_map(|content| match content {
+                AssistantContent::Text(text) => Some(text.text),
+                _other => None,
+            })
+            .ok_or_else(|| anyhow::anyhow!("No text response from completion"))
+    }
+}
+
+/// Backend that replays a fixed list of responses in order.
+///
+/// Every prompt it receives is recorded so tests can assert on what was sent.
+pub struct ScriptedBackend {
+    model: String,
+    responses: Mutex<VecDeque<String>>,
+    prompts: Mutex<Vec<String>>,
+}
+
+impl ScriptedBackend {
+    pub fn new<I, S>(responses: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            model: "scripted".to_string(),
+            responses: Mutex::new(responses.into_iter().map(Into::into).collect()),
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Prompts received so far, oldest first
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl LlmBackend for ScriptedBackend {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        self.prompts.lock().unwrap().push(prompt.to_string());
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| anyhow::anyhow!("Scripted backend has no responses left"))
+    }
+}
+
+/// Strip the markdown code fences models like to wrap JSON answers in.
+pub fn strip_code_fences(response: &str) -> &str {
+    response
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_code_fences() {
+        assert_eq!(strip_code_fences("```json\n{\"a\": 1}\n```"), "{\"a\": 1}");
+        assert_eq!(strip_code_fences("  {\"a\": 1}  "), "{\"a\": 1}");
+    }
+
+    #[tokio::test]
+    async fn test_scripted_backend_replays_in_order() {
+        let backend = ScriptedBackend::new(["first", "second"]);
+
+        assert_eq!(backend.complete("a").await.unwrap(), "first");
+        assert_eq!(backend.complete("b").await.unwrap(), "second");
+        assert!(backend.complete("c").await.is_err());
+        assert_eq!(backend.prompts(), vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,403 @@
+//! Process-wide counters, gauges and histograms, served in the Prometheus
+//! text format on `/metrics`.
+
+use crate::error::{AgentError, AgentResult};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{debug, info, warn};
+
+/// Address the metrics endpoint listens on when `METRICS_ADDR` is unset
+pub const DEFAULT_METRICS_ADDR: &str = "127.0.0.1:9464";
+
+/// Address of the metrics endpoint
+pub const METRICS_ADDR_VAR: &str = "METRICS_ADDR";
+
+/// Pause after a failed accept, so a persistent error doesn't spin
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Upper bounds in seconds, sized for HTTP calls and pipeline steps
+const LATENCY_BUCKETS: &[f64] = &[0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Cumulative count per bucket of `LATENCY_BUCKETS`
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; LATENCY_BUCKETS.len()];
+        }
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// One metric name with a value per label set
+struct Family<T> {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    values: Mutex<BTreeMap<Vec<String>, T>>,
+}
+
+impl<T: Default> Family<T> {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn with(&self, labels: &[&str], update: impl FnOnce(&mut T)) {
+        debug_assert_eq!(labels.len(), self.labels.len(), "{}", self.name);
+        let key = labels.iter().map(|l| l.to_string()).collect();
+        let mut values = self.values.lock().unwrap();
+        update(values.entry(key).or_default());
+    }
+
+    fn label_pairs(&self, values: &[String], extra: Option<(&str, &str)>) -> String {
+        let mut pairs: Vec<String> = self
+            .labels
+            .iter()
+            .zip(values)
+            .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+            .collect();
+        if let Some((name, value)) = extra {
+            pairs.push(format!("{}=\"{}\"", name, value));
+        }
+        if pairs.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", pairs.join(","))
+        }
+    }
+
+    fn header(&self, out: &mut String, kind: &str) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} {}", self.name, kind);
+    }
+}
+
+impl Family<f64> {
+    fn render(&self, out: &mut String, kind: &str) {
+        self.header(out, kind);
+        for (labels, value) in self.values.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "{}{} {}",
+                self.name,
+                self.label_pairs(labels, None),
+                value
+            );
+        }
+    }
+}
+
+impl Family<Histogram> {
+    fn render(&self, out: &mut String) {
+        self.header(out, "histogram");
+        for (labels, histogram) in self.values.lock().unwrap().iter() {
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.buckets) {
+                let le = bound.to_string();
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{} {}",
+                    self.name,
+                    self.label_pairs(labels, Some(("le", &le))),
+                    count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{} {}",
+                self.name,
+                self.label_pairs(labels, Some(("le", "+Inf"))),
+                histogram.count
+            );
+            let pairs = self.label_pairs(labels, None);
+            let _ = writeln!(out, "{}_sum{} {}", self.name, pairs, histogram.sum);
+            let _ = writeln!(out, "{}_count{} {}", self.name, pairs, histogram.count);
+        }
+    }
+}
+
+/// Label values may contain anything; quotes, backslashes and newlines are
+/// escaped as the text format requires
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub struct Metrics {
+    request_seconds: Family<Histogram>,
+    request_errors: Family<f64>,
+    signals: Family<f64>,
+    trades: Family<f64>,
+    open_exposure: Family<f64>,
+    realized_pnl: Family<f64>,
+    step_seconds: Family<Histogram>,
+    cache_lookups: Family<f64>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            request_seconds: Family::new(
+                "cainam_request_duration_seconds",
+                "Latency of calls to external services",
+                &["service", "endpoint"],
+            ),
+            request_errors: Family::new(
+                "cainam_request_errors_total",
+                "Failed calls to external services",
+                &["service", "endpoint"],
+            ),
+            signals: Family::new(
+                "cainam_signals_total",
+                "Market signals stored",
+                &["signal_type"],
+            ),
+            trades: Family::new(
+                "cainam_trades_total",
+                "Trades attempted by the trading agent",
+                &["outcome"],
+            ),
+            open_exposure: Family::new(
+                "cainam_open_exposure_usd",
+                "Cost basis of open positions",
+                &[],
+            ),
+            realized_pnl: Family::new(
+                "cainam_realized_pnl_usd",
+                "Realized profit of all positions",
+                &[],
+            ),
+            step_seconds: Family::new(
+                "cainam_pipeline_step_duration_seconds",
+                "Duration of analysis and filter pipeline steps",
+                &["step"],
+            ),
+            cache_lookups: Family::new(
+                "cainam_cache_lookups_total",
+                "In-memory cache lookups by result",
+                &["cache", "result"],
+            ),
+        }
+    }
+
+    /// Record one call to an external service. `endpoint` is a path without
+    /// its query string so the label set stays small.
+    pub fn observe_request(&self, service: &str, endpoint: &str, elapsed: Duration, ok: bool) {
+        let endpoint = endpoint.split('?').next().unwrap_or(endpoint);
+        self.request_seconds
+            .with(&[service, endpoint], |h| h.observe(elapsed.as_secs_f64()));
+        if !ok {
+            self.request_errors
+                .with(&[service, endpoint], |v| *v += 1.0);
+        }
+    }
+
+    pub fn record_signal(&self, signal_type: &str) {
+        self.signals.with(&[signal_type], |v| *v += 1.0);
+    }
+
+    /// `outcome` is e.g. `executed`, `scheduled`, `limit` or `failed`
+    pub fn record_trade(&self, outcome: &str) {
+        self.trades.with(&[outcome], |v| *v += 1.0);
+    }
+
+    pub fn set_open_exposure(&self, usd: f64) {
+        self.open_exposure.with(&[], |v| *v = usd);
+    }
+
+    pub fn set_realized_pnl(&self, usd: f64) {
+        self.realized_pnl.with(&[], |v| *v = usd);
+    }
+
+    pub fn observe_step(&self, step: &str, elapsed: Duration) {
+        self.step_seconds
+            .with(&[step], |h| h.observe(elapsed.as_secs_f64()));
+    }
+
+    pub fn record_cache_lookup(&self, cache: &str, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        self.cache_lookups.with(&[cache, result], |v| *v += 1.0);
+    }
+
+    /// Every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.request_seconds.render(&mut out);
+        self.request_errors.render(&mut out, "counter");
+        self.signals.render(&mut out, "counter");
+        self.trades.render(&mut out, "counter");
+        self.open_exposure.render(&mut out, "gauge");
+        self.realized_pnl.render(&mut out, "gauge");
+        self.step_seconds.render(&mut out);
+        self.cache_lookups.render(&mut out, "counter");
+        out
+    }
+}
+
+/// The process-wide registry
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+/// Await a call to an external service, recording its latency and whether
+/// it failed
+pub async fn timed<T, E>(
+    service: &str,
+    endpoint: &str,
+    call: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let start = Instant::now();
+    let result = call.await;
+    metrics().observe_request(service, endpoint, start.elapsed(), result.is_ok());
+    result
+}
+
+/// Await one pipeline step, recording its duration
+pub async fn timed_step<T>(step: &str, work: impl Future<Output = T>) -> T {
+    let start = Instant::now();
+    let output = work.await;
+    metrics().observe_step(step, start.elapsed());
+    output
+}
+
+/// Address for `serve` from `METRICS_ADDR`, or `None` when it is set empty
+pub fn metrics_addr() -> AgentResult<Option<SocketAddr>> {
+    let addr = env::var(METRICS_ADDR_VAR).unwrap_or_else(|_| DEFAULT_METRICS_ADDR.to_string());
+    if addr.trim().is_empty() {
+        return Ok(None);
+    }
+    addr.trim().parse().map(Some).map_err(|_| {
+        AgentError::InvalidConfig(
+            METRICS_ADDR_VAR.into(),
+            "must be a socket address such as 127.0.0.1:9464".into(),
+        )
+    })
+}
+
+/// Serve `GET /metrics` on `addr` until the task is dropped. Any other
+/// request gets a 404.
+pub async fn serve(addr: SocketAddr) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    info!(
+        "Serving metrics on http://{}/metrics",
+        listener.local_addr()?
+    );
+
+    loop {
+        // Accept errors such as running out of file descriptors are
+        // transient; the endpoint stays up
+        let (mut stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!("Failed to accept metrics connection: {}", e);
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let read = match stream.read(&mut buf).await {
+                Ok(read) => read,
+                Err(e) => {
+                    debug!("Failed to read metrics request from {}: {}", peer, e);
+                    return;
+                }
+            };
+            let request = String::from_utf8_lossy(&buf[..read]);
+            let response = match request.lines().next() {
+                Some(line) if line.starts_with("GET /metrics ") => {
+                    let body = metrics().render();
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\n\
+                         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                }
+                _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string(),
+            };
+            if let Err(e) = stream.write_all(response.as_bytes()).await {
+                debug!("Failed to write metrics response to {}: {}", peer, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_counters_and_histograms() {
+        let metrics = Metrics::new();
+        metrics.observe_request(
+            "birdeye",
+            "/defi/token_overview?address=So111",
+            Duration::from_millis(200),
+            true,
+        );
+        metrics.observe_request(
+            "birdeye",
+            "/defi/token_overview?address=DezX",
+            Duration::from_secs(3),
+            false,
+        );
+        metrics.record_signal("StrongBuy");
+        metrics.set_open_exposure(125.5);
+
+        let text = metrics.render();
+        let labels = "service=\"birdeye\",endpoint=\"/defi/token_overview\"";
+        assert!(text.contains("# TYPE cainam_request_duration_seconds histogram"));
+        assert!(text.contains(&format!(
+            "cainam_request_duration_seconds_bucket{{{},le=\"0.25\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "cainam_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 2",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "cainam_request_duration_seconds_count{{{}}} 2",
+            labels
+        )));
+        assert!(text.contains(&format!("cainam_request_errors_total{{{}}} 1", labels)));
+        assert!(text.contains("cainam_signals_total{signal_type=\"StrongBuy\"} 1"));
+        assert!(text.contains("cainam_open_exposure_usd 125.5"));
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        let metrics = Metrics::new();
+        metrics.record_trade("failed: \"slippage\"");
+        assert!(metrics
+            .render()
+            .contains("cainam_trades_total{outcome=\"failed: \\\"slippage\\\"\"} 1"));
+    }
+}
@@ -9,6 +9,7 @@ use tracing::{error, info, warn};
 use tracing_subscriber::{fmt, EnvFilter};
 
 pub mod market_metrics;
+pub mod metrics;
 pub mod performance_metrics;
 
 #[derive(Debug, Serialize)]
@@ -153,8 +154,8 @@ mod tests {
 
         assert_eq!(parsed["symbol"], "SOL");
         assert_eq!(parsed["price"], 100.0);
-        assert_eq!(parsed["volume_24h"], 1000000.0);
-        assert_eq!(parsed["signal_type"], "BUY");
+        assert_eq!(parsed["volume24h"], 1000000.0);
+        assert_eq!(parsed["signalType"], "BUY");
         assert_eq!(parsed["confidence"], 0.8);
     }
 
@@ -1,35 +1,30 @@
-use crate::{
+use anyhow::{Context, Result};
+use bigdecimal::BigDecimal;
+use bson::DateTime;
+use cainam_core::{
     agent::trader::TradingAgent,
     birdeye::api::{BirdeyeApi, BirdeyeClient},
-    config::AgentConfig,
+    config::{
+        live_config,
+        mongodb::{MongoConfig, MongoDbPool, MongoPoolConfig},
+        AgentConfig, ConfigFile, LiveConfig, SolanaConfig,
+    },
+    logging::{self, metrics},
     models::market_signal::{MarketSignal, SignalType},
     services::token_analytics::TokenAnalyticsService,
+    storage::Storage,
     trading::SolanaAgentKit,
     utils::f64_to_decimal,
 };
-use anyhow::Result;
-use bson::DateTime;
-use config::mongodb::{MongoConfig, MongoDbPool, MongoPoolConfig};
 use solana_sdk::signature::Keypair;
 use std::io::{self, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tracing::{error, info};
 
-mod agent;
-mod birdeye;
-mod config;
-mod error;
-mod logging;
-mod models;
-mod services;
-mod trading;
-mod twitter;
-mod utils;
-
 async fn handle_user_input(
     trader: Arc<TradingAgent>,
-    config: AgentConfig,
+    live: Arc<LiveConfig>,
     running: Arc<AtomicBool>,
 ) {
     println!("\n=== Cainam Trading Agent ===");
@@ -109,30 +104,40 @@ async fn handle_user_input(
                         tokio::spawn({
                             let trader = trader.clone();
                             let symbol = parts[1].clone();
+                            let min_confidence = live.current().trading.trade_min_confidence;
                             async move {
                                 let signal = MarketSignal {
                                     id: None,
                                     asset_address: symbol.clone(),
                                     signal_type: signal_type.clone(),
-                                    confidence: f64_to_decimal(0.8),
-                                    risk_score: f64_to_decimal(0.2),
-                                    sentiment_score: Some(f64_to_decimal(0.6)),
-                                    volume_change_24h: Some(f64_to_decimal(0.15)),
-                                    price_change_24h: Some(f64_to_decimal(
+                                    confidence: BigDecimal::new(8.into(), 1),
+                                    risk_score: BigDecimal::new(2.into(), 1),
+                                    sentiment_score: Some(BigDecimal::new(6.into(), 1)),
+                                    volume_change_24h: Some(BigDecimal::new(15.into(), 2)),
+                                    price_change_24h: Some(BigDecimal::new(
                                         if signal_type == SignalType::StrongBuy {
-                                            0.05
+                                            5.into()
                                         } else {
-                                            -0.05
+                                            (-5).into()
                                         },
+                                        2,
                                     )),
-                                    price: f64_to_decimal(10.0),
-                                    volume_change: f64_to_decimal(0.2),
+                                    price: BigDecimal::from(10),
+                                    volume_change: BigDecimal::new(2.into(), 1),
                                     timestamp: DateTime::now(),
                                     metadata: None,
                                     created_at: None,
+                                    raw_confidence: None,
+                                    outcomes: None,
                                 };
 
-                                let min_confidence = f64_to_decimal(config.trade_min_confidence);
+                                let min_confidence = match f64_to_decimal(min_confidence) {
+                                    Ok(min_confidence) => min_confidence,
+                                    Err(e) => {
+                                        println!("Invalid minimum confidence: {}", e);
+                                        return;
+                                    }
+                                };
                                 if signal.confidence >= min_confidence {
                                     match trader.execute_trade(&symbol, &signal).await {
                                         Ok(signature) => {
@@ -157,11 +162,22 @@ async fn handle_user_input(
                         });
                     }
                     "status" => {
+                        let settings = live.current();
                         println!("\nTrading Agent Status:");
                         println!("  State: Active");
-                        println!("  Analysis Interval: {:?}", config.analysis_interval);
-                        println!("  Min Confidence: {:.2}", config.trade_min_confidence);
-                        println!("  Max Trade Amount: {:.2}", config.trade_max_amount);
+                        println!(
+                            "  Analysis Interval: {:?}",
+                            settings.trading.analysis_interval
+                        );
+                        println!(
+                            "  Min Confidence: {:.2}",
+                            settings.trading.trade_min_confidence
+                        );
+                        println!(
+                            "  Max Trade Amount: {:.2}",
+                            settings.trading.trade_max_amount
+                        );
+                        println!("  Watchlist: {} token(s)", settings.trading.watchlist.len());
                     }
                     "exit" => {
                         println!("\nShutting down trading agent...");
@@ -206,6 +222,12 @@ async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
     println!("Loading env file...");
 
+    // Layer cainam.toml under the environment and report every problem at once
+    let config_file = ConfigFile::load(None, None)?;
+    config_file.apply();
+    config_file.ensure_valid()?;
+    println!("Loaded {} profile", config_file.profile);
+
     // Initialize MongoDB connection pool using rig-mongodb
     let db_pool = init_mongodb().await?;
     println!("Initialized MongoDB connection pool");
@@ -213,10 +235,11 @@ async fn main() -> Result<()> {
     // Initialize Solana agent
     let rpc_url = std::env::var("SOLANA_RPC_URL")
         .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
-    let private_key =
-        std::env::var("SOLANA_PRIVATE_KEY").expect("SOLANA_PRIVATE_KEY not found in environment");
+    let private_key = std::env::var("SOLANA_PRIVATE_KEY")
+        .context("SOLANA_PRIVATE_KEY not found in environment")?;
     let keypair = Keypair::from_base58_string(&private_key);
-    let solana_agent = SolanaAgentKit::new(&rpc_url, keypair);
+    let solana_agent =
+        SolanaAgentKit::new(&rpc_url, keypair).with_config(SolanaConfig::new_from_env()?);
 
     // Load configuration from environment
     let config = AgentConfig::new_from_env()?;
@@ -226,10 +249,29 @@ async fn main() -> Result<()> {
     let token_analytics_service =
         Arc::new(TokenAnalyticsService::new(db_pool.clone(), birdeye.clone(), None).await?);
 
+    // Trading and signal settings are reloaded from cainam.toml and the
+    // settings collection while the agent runs
+    let settings_store = Storage::mongo(&db_pool, &config.embedding.index_name).settings;
+    let live = Arc::new(LiveConfig::load(config_file, Some(settings_store)).await?);
+    if let Some(interval) = live_config::reload_interval()? {
+        live.watch(interval);
+        println!("Reloading trading settings every {:?}", interval);
+    }
+
+    // Prometheus scrapes /metrics; an empty METRICS_ADDR turns it off
+    if let Some(addr) = metrics::metrics_addr()? {
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(addr).await {
+                error!("Metrics endpoint stopped: {}", e);
+            }
+        });
+    }
+
     // Initialize trading agent
     let trader = Arc::new(
         TradingAgent::new(
-            config.clone(),
+            config,
+            live.clone(),
             token_analytics_service,
             db_pool.clone(),
             solana_agent,
@@ -250,7 +292,7 @@ async fn main() -> Result<()> {
     });
 
     // Handle user input in a separate task
-    let input_handle = tokio::spawn(handle_user_input(trader.clone(), config, running.clone()));
+    let input_handle = tokio::spawn(handle_user_input(trader.clone(), live, running.clone()));
 
     // Wait for either task to complete
     tokio::select! {
@@ -0,0 +1,85 @@
+use crate::models::token_analytics::TokenAnalytics;
+use bigdecimal::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+/// OHLC bar built from stored analytics snapshots
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    /// Bucket start, Unix seconds
+    pub timestamp: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Rolling 24h volume at the last snapshot of the bucket
+    pub volume_24h: Option<f64>,
+}
+
+impl Candle {
+    /// Group snapshots (oldest first) into fixed buckets of `bucket_secs`.
+    /// Buckets without snapshots are skipped.
+    pub fn from_snapshots(snapshots: &[TokenAnalytics], bucket_secs: i64) -> Vec<Candle> {
+        let mut candles: Vec<Candle> = Vec::new();
+
+        for snapshot in snapshots {
+            let Some(price) = snapshot.price.to_f64() else {
+                continue;
+            };
+            let secs = snapshot.timestamp.timestamp_millis() / 1000;
+            let bucket = secs - secs.rem_euclid(bucket_secs);
+            let volume = snapshot.volume_24h.as_ref().and_then(|v| v.to_f64());
+
+            match candles.last_mut() {
+                Some(candle) if candle.timestamp == bucket => {
+                    candle.high = candle.high.max(price);
+                    candle.low = candle.low.min(price);
+                    candle.close = price;
+                    candle.volume_24h = volume.or(candle.volume_24h);
+                }
+                _ => candles.push(Candle {
+                    timestamp: bucket,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume_24h: volume,
+                }),
+            }
+        }
+
+        candles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(secs: i64, price: f64) -> TokenAnalytics {
+        TokenAnalytics::test_snapshot(secs, price)
+    }
+
+    #[test]
+    fn test_from_snapshots_buckets_ohlc() {
+        let snapshots = vec![
+            snapshot(3600, 2.0),
+            snapshot(4000, 3.0),
+            snapshot(5000, 1.0),
+            snapshot(7300, 4.0),
+        ];
+
+        let candles = Candle::from_snapshots(&snapshots, 3600);
+        assert_eq!(candles.len(), 2);
+        assert_eq!(
+            (
+                candles[0].open,
+                candles[0].high,
+                candles[0].low,
+                candles[0].close
+            ),
+            (2.0, 3.0, 1.0, 1.0)
+        );
+        assert_eq!(candles[1].timestamp, 7200);
+        assert_eq!(candles[1].close, 4.0);
+    }
+}
@@ -0,0 +1,182 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Most tokens a session remembers
+const MAX_TOKEN_REFERENCES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChatRole {
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatTurn {
+    pub role: ChatRole,
+    pub content: String,
+    pub timestamp: DateTime,
+}
+
+/// A token discussed in a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenReference {
+    pub address: String,
+    pub symbol: Option<String>,
+    pub last_mentioned: DateTime,
+}
+
+/// Multi-turn conversation with the analytics agent.
+///
+/// Every turn is kept, but only the turns after `summarized_turns` are sent
+/// to the model; older ones are represented by `summary`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatSession {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    pub turns: Vec<ChatTurn>,
+    pub summary: Option<String>,
+    pub summarized_turns: u32,
+    /// Tokens discussed, most recently mentioned first
+    pub tokens: Vec<TokenReference>,
+}
+
+impl ChatSession {
+    pub fn new() -> Self {
+        let now = DateTime::now();
+        Self {
+            id: None,
+            created_at: now,
+            updated_at: now,
+            turns: Vec::new(),
+            summary: None,
+            summarized_turns: 0,
+            tokens: Vec::new(),
+        }
+    }
+
+    pub fn collection_name() -> &'static str {
+        "chat_sessions"
+    }
+
+    /// First user message, used to label the session in listings
+    pub fn title(&self) -> &str {
+        self.turns
+            .iter()
+            .find(|t| t.role == ChatRole::User)
+            .map(|t| t.content.as_str())
+            .unwrap_or("(empty)")
+    }
+
+    pub fn push(&mut self, role: ChatRole, content: impl Into<String>) {
+        self.updated_at = DateTime::now();
+        self.turns.push(ChatTurn {
+            role,
+            content: content.into(),
+            timestamp: self.updated_at,
+        });
+    }
+
+    /// Turns not yet folded into the summary
+    pub fn recent_turns(&self) -> &[ChatTurn] {
+        let start = (self.summarized_turns as usize).min(self.turns.len());
+        &self.turns[start..]
+    }
+
+    /// Mark a token as the most recently discussed one. A known symbol is
+    /// kept when the new mention has none.
+    pub fn remember_token(&mut self, address: &str, symbol: Option<String>) {
+        let previous = self
+            .tokens
+            .iter()
+            .position(|t| t.address == address)
+            .map(|i| self.tokens.remove(i));
+        let symbol = symbol.or_else(|| previous.and_then(|p| p.symbol));
+        self.tokens.insert(
+            0,
+            TokenReference {
+                address: address.to_string(),
+                symbol,
+                last_mentioned: DateTime::now(),
+            },
+        );
+        self.tokens.truncate(MAX_TOKEN_REFERENCES);
+    }
+
+    /// Earlier conversation and discussed tokens, to prefix the next prompt
+    /// with so references like "that one" can be resolved
+    pub fn context(&self) -> Option<String> {
+        if self.summary.is_none() && self.tokens.is_empty() {
+            return None;
+        }
+
+        let mut context = String::new();
+        if let Some(summary) = &self.summary {
+            let _ = writeln!(
+                context,
+                "Summary of the earlier conversation:\n{}\n",
+                summary
+            );
+        }
+        if !self.tokens.is_empty() {
+            let _ = writeln!(
+                context,
+                "Tokens discussed so far, most recent first (\"it\" or \"that one\" usually \
+                 means the first):"
+            );
+            for token in &self.tokens {
+                let _ = writeln!(
+                    context,
+                    "- {} {}",
+                    token.symbol.as_deref().unwrap_or("(unknown symbol)"),
+                    token.address
+                );
+            }
+        }
+        Some(context.trim_end().to_string())
+    }
+}
+
+impl Default for ChatSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_references_and_context() {
+        let mut session = ChatSession::new();
+        assert!(session.context().is_none());
+
+        session.remember_token("bonk", Some("BONK".to_string()));
+        session.remember_token("wif", None);
+        session.remember_token("bonk", None);
+        assert_eq!(session.tokens.len(), 2);
+        assert_eq!(session.tokens[0].address, "bonk");
+        assert_eq!(session.tokens[0].symbol.as_deref(), Some("BONK"));
+
+        session.summary = Some("Looked at memecoins".to_string());
+        let context = session.context().unwrap();
+        assert!(context.contains("Looked at memecoins"));
+        assert!(context.find("BONK bonk").unwrap() < context.find("(unknown symbol) wif").unwrap());
+    }
+
+    #[test]
+    fn test_recent_turns() {
+        let mut session = ChatSession::new();
+        session.push(ChatRole::User, "hi");
+        session.push(ChatRole::Assistant, "hello");
+        session.push(ChatRole::User, "how is BONK?");
+        assert_eq!(session.title(), "hi");
+
+        session.summarized_turns = 2;
+        assert_eq!(session.recent_turns().len(), 1);
+        assert_eq!(session.recent_turns()[0].content, "how is BONK?");
+    }
+}
@@ -0,0 +1,61 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// New value of one setting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettingChange {
+    /// Environment variable name of the setting
+    pub setting: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+impl fmt::Display for SettingChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.setting, self.old_value, self.new_value
+        )
+    }
+}
+
+/// Audit record of a settings reload that changed something. Reloads that
+/// fail validation are recorded with their error and not applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigChange {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub timestamp: DateTime,
+    /// What triggered the reload, e.g. `watch` or `cli`
+    pub source: String,
+    pub changes: Vec<SettingChange>,
+    pub applied: bool,
+    pub error: Option<String>,
+}
+
+impl ConfigChange {
+    pub fn applied(source: &str, changes: Vec<SettingChange>) -> Self {
+        Self {
+            id: None,
+            timestamp: DateTime::now(),
+            source: source.to_string(),
+            changes,
+            applied: true,
+            error: None,
+        }
+    }
+
+    pub fn rejected(source: &str, changes: Vec<SettingChange>, error: String) -> Self {
+        Self {
+            applied: false,
+            error: Some(error),
+            ..Self::applied(source, changes)
+        }
+    }
+
+    pub fn collection_name() -> &'static str {
+        "config_audit"
+    }
+}
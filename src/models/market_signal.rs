@@ -1,11 +1,11 @@
-use crate::utils::f64_to_decimal;
 use bigdecimal::BigDecimal;
 use bson::{self, DateTime, Document};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SignalType {
     Buy,
     Sell,
@@ -45,12 +45,46 @@ pub struct MarketSignal {
     pub timestamp: DateTime,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<Document>,
+    /// Uncalibrated confidence, kept so the calibration can be refitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_confidence: Option<BigDecimal>,
+    /// Realized move after the signal at each measured horizon, keyed by
+    /// the horizon label (e.g. "24h"); filled in by the signal tracker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcomes: Option<BTreeMap<String, SignalOutcome>>,
+}
+
+impl MarketSignal {
+    /// Whether the signal predicts a price increase. Spikes and surges follow
+    /// the direction of the 24h price change.
+    pub fn expects_rise(&self) -> bool {
+        match self.signal_type {
+            SignalType::Buy | SignalType::StrongBuy => true,
+            SignalType::Sell | SignalType::StrongSell | SignalType::PriceDrop => false,
+            SignalType::PriceSpike | SignalType::VolumeSurge | SignalType::Hold => self
+                .price_change_24h
+                .as_ref()
+                .is_none_or(|change| *change >= BigDecimal::from(0)),
+        }
+    }
+}
+
+/// Forward move of the token price after a signal, at one horizon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalOutcome {
+    pub evaluated_at: DateTime,
+    pub price: f64,
+    /// Return from the signal price to the horizon price (0.1 = +10%)
+    pub forward_return: f64,
+    /// Whether the price moved in the direction the signal predicted
+    pub hit: bool,
 }
 
 pub struct MarketSignalBuilder {
     asset_address: String,
     signal_type: SignalType,
     confidence: Option<BigDecimal>,
+    raw_confidence: Option<BigDecimal>,
     risk_score: Option<BigDecimal>,
     sentiment_score: Option<BigDecimal>,
     volume_change_24h: Option<BigDecimal>,
@@ -67,6 +101,7 @@ impl MarketSignalBuilder {
             asset_address,
             signal_type,
             confidence: None,
+            raw_confidence: None,
             risk_score: None,
             sentiment_score: None,
             volume_change_24h: None,
@@ -83,6 +118,11 @@ impl MarketSignalBuilder {
         self
     }
 
+    pub fn raw_confidence(mut self, raw_confidence: BigDecimal) -> Self {
+        self.raw_confidence = Some(raw_confidence);
+        self
+    }
+
     pub fn risk_score(mut self, risk_score: BigDecimal) -> Self {
         self.risk_score = Some(risk_score);
         self
@@ -123,8 +163,12 @@ impl MarketSignalBuilder {
             id: None,
             asset_address: self.asset_address,
             signal_type: self.signal_type,
-            confidence: self.confidence.unwrap_or_else(|| f64_to_decimal(0.5)),
-            risk_score: self.risk_score.unwrap_or_else(|| f64_to_decimal(0.5)),
+            confidence: self
+                .confidence
+                .unwrap_or_else(|| BigDecimal::new(5.into(), 1)),
+            risk_score: self
+                .risk_score
+                .unwrap_or_else(|| BigDecimal::new(5.into(), 1)),
             sentiment_score: self.sentiment_score,
             volume_change_24h: self.volume_change_24h,
             price_change_24h: self.price_change_24h,
@@ -133,6 +177,8 @@ impl MarketSignalBuilder {
             timestamp: self.timestamp.unwrap_or_else(DateTime::now),
             metadata: self.metadata.map(|v| bson::to_document(&v).unwrap()),
             created_at: None,
+            raw_confidence: self.raw_confidence,
+            outcomes: None,
         }
     }
 }
@@ -140,39 +186,40 @@ impl MarketSignalBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::f64_to_decimal;
     use serde_json::json;
 
     #[test]
     fn test_market_signal_builder() {
-        let price = f64_to_decimal(100.0);
+        let price = f64_to_decimal(100.0).unwrap();
         let signal = MarketSignalBuilder::new(
             "test_address".to_string(),
             SignalType::PriceSpike,
             price.clone(),
         )
-        .confidence(f64_to_decimal(0.8))
-        .risk_score(f64_to_decimal(0.3))
-        .volume_change_24h(f64_to_decimal(0.15))
-        .price_change_24h(f64_to_decimal(0.05))
+        .confidence(f64_to_decimal(0.8).unwrap())
+        .risk_score(f64_to_decimal(0.3).unwrap())
+        .volume_change_24h(f64_to_decimal(0.15).unwrap())
+        .price_change_24h(f64_to_decimal(0.05).unwrap())
         .metadata(json!({"source": "test"}))
         .build();
 
         assert_eq!(signal.asset_address, "test_address");
         assert_eq!(signal.price, price);
-        assert_eq!(signal.confidence, f64_to_decimal(0.8));
-        assert_eq!(signal.risk_score, f64_to_decimal(0.3));
+        assert_eq!(signal.confidence, f64_to_decimal(0.8).unwrap());
+        assert_eq!(signal.risk_score, f64_to_decimal(0.3).unwrap());
         assert!(signal.metadata.is_some());
     }
 
     #[test]
     fn test_market_signal_builder_defaults() {
-        let price = f64_to_decimal(100.0);
+        let price = f64_to_decimal(100.0).unwrap();
         let signal =
             MarketSignalBuilder::new("test_address".to_string(), SignalType::Hold, price.clone())
                 .build();
 
-        assert_eq!(signal.confidence, f64_to_decimal(0.5)); // Default confidence
-        assert_eq!(signal.risk_score, f64_to_decimal(0.5)); // Default risk score
+        assert_eq!(signal.confidence, f64_to_decimal(0.5).unwrap()); // Default confidence
+        assert_eq!(signal.risk_score, f64_to_decimal(0.5).unwrap()); // Default risk score
         assert_eq!(signal.volume_change, BigDecimal::from(0)); // Default volume change
         assert!(signal.metadata.is_none());
     }
@@ -1,8 +1,13 @@
 use bson::{self, oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
 
+pub mod candle;
+pub mod chat_session;
+pub mod config_change;
 pub mod market_data;
 pub mod market_signal;
+pub mod money;
+pub mod signal_calibration;
 pub mod token_analytics;
 pub mod token_info;
 pub mod token_trending;
@@ -0,0 +1,455 @@
+//! Typed money amounts with exact decimal arithmetic.
+//!
+//! [`UsdAmount`] and [`Price`] wrap a `BigDecimal`, [`TokenAmount`] holds raw
+//! base units with the mint's decimals. Operations that can't produce a
+//! meaningful value, such as dividing by a zero price or overflowing `u64`,
+//! return errors instead of zeros.
+//!
+//! All three are stored as BSON Decimal128. Documents written earlier with
+//! decimal strings or doubles still deserialize.
+
+use crate::error::{AgentError, AgentResult};
+use crate::utils::f64_to_decimal;
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
+use bson::{Bson, Decimal128};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::{Add, Neg, Sub};
+use std::str::FromStr;
+
+/// An amount in US dollars, negative for losses
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UsdAmount(BigDecimal);
+
+impl UsdAmount {
+    pub fn new(value: BigDecimal) -> Self {
+        Self(value)
+    }
+
+    pub fn zero() -> Self {
+        Self(BigDecimal::zero())
+    }
+
+    pub fn from_f64(value: f64) -> AgentResult<Self> {
+        Ok(Self(f64_to_decimal(value)?))
+    }
+
+    pub fn value(&self) -> &BigDecimal {
+        &self.0
+    }
+
+    pub fn to_f64(&self) -> AgentResult<f64> {
+        crate::utils::decimal_to_f64(&self.0)
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.0 > BigDecimal::zero()
+    }
+
+    /// This amount multiplied by `factor`, such as a confidence
+    pub fn scale(&self, factor: &BigDecimal) -> Self {
+        Self(&self.0 * factor)
+    }
+
+    /// Whole tokens this amount buys at `price`
+    pub fn quantity_at(&self, price: &Price) -> AgentResult<BigDecimal> {
+        if price.is_zero() {
+            return Err(AgentError::Validation(format!(
+                "can't size {} USD at a zero price",
+                self
+            )));
+        }
+        Ok(&self.0 / &price.0)
+    }
+}
+
+impl Add for UsdAmount {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl Sub for UsdAmount {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl Neg for UsdAmount {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+/// Price of one whole token in US dollars, never negative
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Price(BigDecimal);
+
+impl Price {
+    pub fn new(value: BigDecimal) -> AgentResult<Self> {
+        if value < BigDecimal::zero() {
+            return Err(AgentError::Validation(format!(
+                "price can't be negative: {}",
+                value
+            )));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn from_f64(value: f64) -> AgentResult<Self> {
+        Self::new(f64_to_decimal(value)?)
+    }
+
+    /// Average price paid for `quantity` whole tokens costing `cost`
+    pub fn per_unit(cost: &UsdAmount, quantity: &BigDecimal) -> AgentResult<Self> {
+        if quantity.is_zero() {
+            return Err(AgentError::Validation(
+                "can't price a zero quantity".to_string(),
+            ));
+        }
+        Self::new(&cost.0 / quantity)
+    }
+
+    pub fn value(&self) -> &BigDecimal {
+        &self.0
+    }
+
+    pub fn to_f64(&self) -> AgentResult<f64> {
+        crate::utils::decimal_to_f64(&self.0)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// Value of `quantity` whole tokens at this price
+    pub fn value_of(&self, quantity: &BigDecimal) -> UsdAmount {
+        UsdAmount(&self.0 * quantity)
+    }
+}
+
+/// A token quantity in the mint's base units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TokenAmount {
+    raw: u64,
+    decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(raw: u64, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    /// Base units of `amount` whole tokens, rounded down to the smallest unit
+    pub fn from_decimal(amount: &BigDecimal, decimals: u8) -> AgentResult<Self> {
+        if *amount < BigDecimal::zero() {
+            return Err(AgentError::Validation(format!(
+                "token amount can't be negative: {}",
+                amount
+            )));
+        }
+        let (units, _) = amount.with_scale(decimals.into()).as_bigint_and_exponent();
+        let raw = units.to_u64().ok_or_else(|| {
+            AgentError::Validation(format!(
+                "{} with {} decimals overflows the token amount",
+                amount, decimals
+            ))
+        })?;
+        Ok(Self { raw, decimals })
+    }
+
+    pub fn from_f64(amount: f64, decimals: u8) -> AgentResult<Self> {
+        Self::from_decimal(&f64_to_decimal(amount)?, decimals)
+    }
+
+    pub fn raw(&self) -> u64 {
+        self.raw
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// The amount in whole tokens
+    pub fn to_decimal(self) -> BigDecimal {
+        BigDecimal::new(self.raw.into(), self.decimals.into())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw == 0
+    }
+
+    pub fn checked_add(&self, other: &Self) -> AgentResult<Self> {
+        let (a, b) = self.aligned(other)?;
+        let raw = a
+            .raw
+            .checked_add(b.raw)
+            .ok_or_else(|| AgentError::Validation(format!("{} + {} overflows", self, other)))?;
+        Ok(Self { raw, ..a })
+    }
+
+    pub fn checked_sub(&self, other: &Self) -> AgentResult<Self> {
+        let (a, b) = self.aligned(other)?;
+        let raw = a
+            .raw
+            .checked_sub(b.raw)
+            .ok_or_else(|| AgentError::Validation(format!("{} - {} is negative", self, other)))?;
+        Ok(Self { raw, ..a })
+    }
+
+    pub fn checked_min(&self, other: &Self) -> AgentResult<Self> {
+        let (a, b) = self.aligned(other)?;
+        Ok(if a.raw <= b.raw { a } else { b })
+    }
+
+    /// `fraction` of this amount, rounded down to the smallest unit
+    pub fn share(&self, fraction: &BigDecimal) -> AgentResult<Self> {
+        Self::from_decimal(&(self.to_decimal() * fraction), self.decimals)
+    }
+
+    /// This amount cut into `parts` equal amounts. The last one also takes
+    /// the units that don't divide evenly, so the parts add up exactly.
+    pub fn split(&self, parts: usize) -> Vec<Self> {
+        let parts = parts.max(1) as u64;
+        let base = self.raw / parts;
+        (0..parts)
+            .map(|i| Self {
+                raw: if i + 1 == parts {
+                    base + self.raw % parts
+                } else {
+                    base
+                },
+                ..*self
+            })
+            .collect()
+    }
+
+    /// Value of this amount at `price`
+    pub fn value_at(&self, price: &Price) -> UsdAmount {
+        price.value_of(&self.to_decimal())
+    }
+
+    /// Both amounts at the larger of their decimals, which is exact. Amounts
+    /// stored as f64 before carry the decimals their value printed with.
+    fn aligned(&self, other: &Self) -> AgentResult<(Self, Self)> {
+        let decimals = self.decimals.max(other.decimals);
+        Ok((self.rescaled(decimals)?, other.rescaled(decimals)?))
+    }
+
+    fn rescaled(&self, decimals: u8) -> AgentResult<Self> {
+        let raw = 10u64
+            .checked_pow((decimals - self.decimals).into())
+            .and_then(|factor| self.raw.checked_mul(factor))
+            .ok_or_else(|| {
+                AgentError::Validation(format!(
+                    "{} with {} decimals overflows the token amount",
+                    self, decimals
+                ))
+            })?;
+        Ok(Self { raw, decimals })
+    }
+}
+
+impl fmt::Display for UsdAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for Price {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_decimal(), f)
+    }
+}
+
+/// Significant digits Decimal128 holds
+const DECIMAL128_DIGITS: u64 = 34;
+
+/// Stored as Decimal128, rounded to the 34 significant digits it holds.
+/// Quotients such as average prices can carry many more.
+fn serialize_decimal<S: Serializer>(value: &BigDecimal, serializer: S) -> Result<S::Ok, S::Error> {
+    let value = if value.digits() > DECIMAL128_DIGITS {
+        value.with_prec(DECIMAL128_DIGITS)
+    } else {
+        value.clone()
+    };
+    Decimal128::from_str(&value.to_string())
+        .map_err(serde::ser::Error::custom)?
+        .serialize(serializer)
+}
+
+/// A decimal stored as Decimal128, or as a string or number by older writers
+fn deserialize_decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigDecimal, D::Error> {
+    decimal_from_bson(Bson::deserialize(deserializer)?)
+}
+
+fn decimal_from_bson<E: de::Error>(value: Bson) -> Result<BigDecimal, E> {
+    let parsed = match value {
+        Bson::Decimal128(value) => BigDecimal::from_str(&value.to_string()).ok(),
+        Bson::String(value) => BigDecimal::from_str(&value).ok(),
+        Bson::Double(value) => f64_to_decimal(value).ok(),
+        Bson::Int32(value) => Some(value.into()),
+        Bson::Int64(value) => Some(value.into()),
+        other => {
+            return Err(de::Error::custom(format!(
+                "expected a decimal, got {}",
+                other
+            )))
+        }
+    };
+    parsed.ok_or_else(|| de::Error::custom("not a finite decimal"))
+}
+
+impl Serialize for UsdAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_decimal(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for UsdAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_decimal(deserializer).map(Self)
+    }
+}
+
+impl Serialize for Price {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_decimal(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Price {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::new(deserialize_decimal(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+/// Stored in whole tokens with one fractional digit per decimal, which
+/// Decimal128 keeps, so the decimals survive the round trip
+impl Serialize for TokenAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_decimal(&self.to_decimal(), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TokenAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let stored = Bson::deserialize(deserializer)?;
+        // Doubles from before amounts were exact convert with 15 significant
+        // digits, whose trailing zeros aren't decimals of the mint
+        let legacy = matches!(stored, Bson::Double(_));
+        let mut amount = decimal_from_bson(stored)?;
+        if legacy {
+            amount = amount.normalized();
+        }
+        let (_, scale) = amount.as_bigint_and_exponent();
+        let decimals = u8::try_from(scale.max(0))
+            .map_err(|_| de::Error::custom(format!("too many decimals in {}", amount)))?;
+        Self::from_decimal(&amount, decimals).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let budget = UsdAmount::new(decimal("100"));
+        let price = Price::new(decimal("0.25")).unwrap();
+        assert_eq!(budget.quantity_at(&price).unwrap(), decimal("400"));
+        assert!(budget
+            .quantity_at(&Price::new(decimal("0")).unwrap())
+            .is_err());
+        assert!(Price::new(decimal("-1")).is_err());
+        assert!(Price::from_f64(f64::NAN).is_err());
+        assert!(UsdAmount::from_f64(f64::INFINITY).is_err());
+
+        // 0.1 + 0.2 is exact
+        let sum = UsdAmount::new(decimal("0.1")) + UsdAmount::new(decimal("0.2"));
+        assert_eq!(sum, UsdAmount::new(decimal("0.3")));
+
+        let amount = TokenAmount::from_decimal(&decimal("1.23456789"), 6).unwrap();
+        assert_eq!((amount.raw(), amount.decimals()), (1_234_567, 6));
+        assert_eq!(amount.to_decimal(), decimal("1.234567"));
+        assert!(TokenAmount::from_decimal(&decimal("-1"), 6).is_err());
+        assert!(TokenAmount::from_decimal(&decimal("1e20"), 9).is_err());
+        assert!(amount.checked_sub(&TokenAmount::new(2_000_000, 6)).is_err());
+        // Combined at the larger decimals
+        assert_eq!(
+            amount.checked_add(&TokenAmount::new(1, 9)).unwrap(),
+            TokenAmount::new(1_234_567_001, 9)
+        );
+        assert_eq!(
+            TokenAmount::new(3, 0).checked_min(&amount).unwrap(),
+            TokenAmount::new(1_234_567, 6)
+        );
+        assert!(TokenAmount::new(u64::MAX, 0)
+            .checked_add(&TokenAmount::new(1, 9))
+            .is_err());
+        assert_eq!(
+            TokenAmount::new(10, 0).split(3),
+            vec![
+                TokenAmount::new(3, 0),
+                TokenAmount::new(3, 0),
+                TokenAmount::new(4, 0)
+            ]
+        );
+        assert_eq!(
+            amount.share(&decimal("0.5")).unwrap(),
+            TokenAmount::new(617_283, 6)
+        );
+        assert!(amount.share(&decimal("-0.5")).is_err());
+        assert!(TokenAmount::new(u64::MAX, 0)
+            .checked_add(&TokenAmount::new(1, 0))
+            .is_err());
+        assert_eq!(
+            amount.value_at(&price),
+            UsdAmount::new(decimal("0.30864175"))
+        );
+    }
+
+    #[test]
+    fn test_decimal128_round_trip() {
+        let price = Price::new(decimal("0.000012345678901234567890123")).unwrap();
+        let amount = TokenAmount::new(1_500_000_000, 9);
+        let stored = bson::to_bson(&price).unwrap();
+        assert!(matches!(stored, Bson::Decimal128(_)));
+        assert_eq!(bson::from_bson::<Price>(stored).unwrap(), price);
+
+        let stored = bson::to_bson(&amount).unwrap();
+        assert_eq!(stored.to_string(), "1.500000000");
+        assert_eq!(bson::from_bson::<TokenAmount>(stored).unwrap(), amount);
+
+        // Values written before as strings and doubles
+        let legacy: UsdAmount = bson::from_bson(Bson::String("12.5".to_string())).unwrap();
+        assert_eq!(legacy, UsdAmount::new(decimal("12.5")));
+        let legacy: TokenAmount = bson::from_bson(Bson::Double(2.5)).unwrap();
+        assert_eq!(legacy, TokenAmount::new(25, 1));
+        let legacy: Price = bson::from_bson(Bson::Double(2.0)).unwrap();
+        assert_eq!(legacy, Price::new(decimal("2")).unwrap());
+        assert!(bson::from_bson::<Price>(Bson::Double(-2.0)).is_err());
+
+        // 10 @ 1.0 and 20 @ 1.1 average to 1.0666..., past Decimal128's digits
+        let cost = UsdAmount::new(decimal("10") + decimal("22"));
+        let average = Price::per_unit(&cost, &decimal("30")).unwrap();
+        let stored = bson::to_bson(&average).unwrap();
+        assert_eq!(stored.to_string(), "1.066666666666666666666666666666667");
+    }
+}
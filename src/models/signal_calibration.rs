@@ -0,0 +1,135 @@
+use crate::models::market_signal::SignalType;
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// Monotone mapping from a raw signal score to an observed hit rate,
+/// fitted with the pool-adjacent-violators algorithm.
+///
+/// `knots` holds the mean raw score of each pooled block and `values` its hit
+/// rate; predictions interpolate linearly between knots and are flat outside.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IsotonicCalibrator {
+    pub knots: Vec<f64>,
+    pub values: Vec<f64>,
+}
+
+impl IsotonicCalibrator {
+    /// Fit on `(raw_score, hit)` pairs. Non-finite scores are ignored.
+    pub fn fit(samples: &[(f64, bool)]) -> Self {
+        let mut points: Vec<(f64, f64)> = samples
+            .iter()
+            .filter(|(x, _)| x.is_finite())
+            .map(|(x, hit)| (*x, if *hit { 1.0 } else { 0.0 }))
+            .collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // (sum of scores, sum of outcomes, weight) per block; adjacent blocks
+        // with equal hit rates are pooled so the knots stay strictly increasing
+        let mut blocks: Vec<(f64, f64, f64)> = Vec::with_capacity(points.len());
+        for (x, y) in points {
+            blocks.push((x, y, 1.0));
+            while blocks.len() > 1 {
+                let (sx, sy, w) = blocks[blocks.len() - 1];
+                let (px, py, pw) = blocks[blocks.len() - 2];
+                if py / pw < sy / w {
+                    break;
+                }
+                blocks.pop();
+                *blocks.last_mut().unwrap() = (px + sx, py + sy, pw + w);
+            }
+        }
+
+        Self {
+            knots: blocks.iter().map(|(sx, _, w)| sx / w).collect(),
+            values: blocks.iter().map(|(_, sy, w)| sy / w).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.knots.is_empty()
+    }
+
+    /// Calibrated probability for a raw score, or the raw score clamped to
+    /// [0, 1] when the calibrator has not been fitted
+    pub fn predict(&self, raw: f64) -> f64 {
+        if self.is_empty() {
+            return raw.clamp(0.0, 1.0);
+        }
+
+        let last = self.knots.len() - 1;
+        if raw <= self.knots[0] {
+            return self.values[0];
+        }
+        if raw >= self.knots[last] {
+            return self.values[last];
+        }
+
+        let i = self.knots.partition_point(|k| *k <= raw);
+        let (x0, x1) = (self.knots[i - 1], self.knots[i]);
+        let (y0, y1) = (self.values[i - 1], self.values[i]);
+        y0 + (y1 - y0) * (raw - x0) / (x1 - x0)
+    }
+}
+
+/// Stored calibration for one signal type, kept in `signal_calibration`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalCalibration {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub signal_type: SignalType,
+    /// Forward horizon the outcomes were measured at, e.g. "24h"
+    pub horizon: String,
+    /// Number of signals with outcomes the calibrator was fitted on
+    pub samples: usize,
+    /// Share of those signals that moved in the expected direction
+    pub base_rate: f64,
+    pub calibrator: IsotonicCalibrator,
+    pub fitted_at: DateTime,
+}
+
+impl SignalCalibration {
+    pub fn collection_name() -> &'static str {
+        "signal_calibration"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_pools_violators() {
+        let samples = [
+            (0.1, false),
+            (0.2, true),
+            (0.3, false),
+            (0.8, true),
+            (0.9, true),
+        ];
+        let model = IsotonicCalibrator::fit(&samples);
+
+        assert_eq!(model.values, vec![0.0, 0.5, 1.0]);
+        assert!((model.knots[1] - 0.25).abs() < 1e-12);
+        assert!(model.values.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_predict_interpolates_and_clamps() {
+        let model = IsotonicCalibrator {
+            knots: vec![0.2, 0.6],
+            values: vec![0.1, 0.5],
+        };
+
+        assert_eq!(model.predict(0.0), 0.1);
+        assert_eq!(model.predict(1.0), 0.5);
+        assert!((model.predict(0.4) - 0.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_unfitted_predict_clamps_raw() {
+        let model = IsotonicCalibrator::default();
+        assert_eq!(model.predict(1.7), 1.0);
+        assert_eq!(model.predict(-0.2), 0.0);
+        assert_eq!(model.predict(0.42), 0.42);
+    }
+}
@@ -65,6 +65,56 @@ pub struct TokenAnalytics {
     // Vector embedding for similarity search
     #[serde(skip_serializing_if = "Option::is_none")]
     pub embedding: Option<Vec<f32>>, // type: "knnVector", dimensions: 1536
+
+    // Representation and model the embedding was built with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_version: Option<String>, // type: "string"
+}
+
+#[cfg(test)]
+impl TokenAnalytics {
+    /// Snapshot of token `addr` with only its price and time set. Tests
+    /// fill in the fields they need with struct update syntax.
+    pub(crate) fn test_snapshot(secs: i64, price: f64) -> Self {
+        use std::str::FromStr;
+
+        Self {
+            id: None,
+            token_address: "addr".to_string(),
+            token_name: "Token".to_string(),
+            token_symbol: "TKN".to_string(),
+            decimals: 9,
+            logo_uri: None,
+            price: BigDecimal::from_str(&price.to_string()).unwrap(),
+            price_change_24h: None,
+            price_change_7d: None,
+            volume_24h: None,
+            volume_change_24h: None,
+            volume_by_price_24h: None,
+            market_cap: None,
+            fully_diluted_market_cap: None,
+            circulating_supply: None,
+            total_supply: None,
+            liquidity: None,
+            liquidity_change_24h: None,
+            trades_24h: None,
+            average_trade_size: None,
+            holder_count: None,
+            active_wallets_24h: None,
+            whale_transactions_24h: None,
+            rsi_14: None,
+            macd: None,
+            macd_signal: None,
+            bollinger_upper: None,
+            bollinger_lower: None,
+            timestamp: DateTime::from_millis(secs * 1000),
+            created_at: None,
+            last_trade_time: None,
+            metadata: None,
+            embedding: None,
+            embedding_version: None,
+        }
+    }
 }
 
 // MongoDB Atlas Search Vector Index Definition (for reference):
@@ -0,0 +1,321 @@
+//! Multi-turn chat with the analytics agent.
+//!
+//! Sessions are stored in `chat_sessions` so they can be resumed by id. Once
+//! a session has more than `summarize_after` unsummarized turns, the older
+//! ones are condensed into the session summary and only the most recent
+//! `keep_recent` turns are sent to the model verbatim.
+
+use crate::config::mongodb::MongoDbPool;
+use crate::error::{AgentError, AgentResult};
+use crate::models::chat_session::{ChatRole, ChatSession, ChatTurn};
+use crate::services::token_analytics::TokenAnalyticsService;
+use crate::tools::{prompt_with_tools, MAX_TOOL_TURNS};
+use bson::{doc, oid::ObjectId};
+use futures::TryStreamExt;
+use mongodb::{options::FindOptions, Collection};
+use rig::{
+    agent::Agent,
+    completion::{CompletionModel, Message, Prompt},
+};
+use solana_sdk::pubkey::Pubkey;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// Unsummarized turns that trigger a summary
+pub const DEFAULT_SUMMARIZE_AFTER: usize = 16;
+
+/// Turns kept verbatim after summarizing
+pub const DEFAULT_KEEP_RECENT: usize = 6;
+
+/// Persistence of chat sessions
+pub struct ChatSessionStore {
+    sessions: Collection<ChatSession>,
+}
+
+impl ChatSessionStore {
+    pub fn new(db_pool: Arc<MongoDbPool>) -> Self {
+        let db = db_pool.database(&db_pool.get_config().database);
+        Self {
+            sessions: db.collection(ChatSession::collection_name()),
+        }
+    }
+
+    /// Insert a new session, or replace an existing one
+    pub async fn save(&self, session: &mut ChatSession) -> AgentResult<()> {
+        match session.id {
+            Some(id) => {
+                self.sessions
+                    .replace_one(doc! { "_id": id }, &*session)
+                    .await?;
+            }
+            None => {
+                let result = self.sessions.insert_one(&*session).await?;
+                session.id = result.inserted_id.as_object_id();
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get(&self, id: ObjectId) -> AgentResult<Option<ChatSession>> {
+        Ok(self.sessions.find_one(doc! { "_id": id }).await?)
+    }
+
+    /// Most recently active sessions first
+    pub async fn recent(&self, limit: i64) -> AgentResult<Vec<ChatSession>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "updated_at": -1 })
+            .limit(limit)
+            .build();
+        Ok(self
+            .sessions
+            .find(doc! {})
+            .with_options(options)
+            .await?
+            .try_collect()
+            .await?)
+    }
+}
+
+/// Analytics agent answering within a [`ChatSession`]
+pub struct AnalystChat<M: CompletionModel> {
+    agent: Agent<M>,
+    summarizer: Agent<M>,
+    analytics: Option<Arc<TokenAnalyticsService>>,
+    summarize_after: usize,
+    keep_recent: usize,
+}
+
+impl<M: CompletionModel> AnalystChat<M> {
+    /// `agent` answers the user, with tools if it has any. `summarizer`
+    /// condenses old turns and should have no tools.
+    pub fn new(agent: Agent<M>, summarizer: Agent<M>) -> Self {
+        Self {
+            agent,
+            summarizer,
+            analytics: None,
+            summarize_after: DEFAULT_SUMMARIZE_AFTER,
+            keep_recent: DEFAULT_KEEP_RECENT,
+        }
+    }
+
+    /// Resolve the symbols of mentioned tokens from stored analytics
+    pub fn with_analytics(mut self, analytics: Arc<TokenAnalyticsService>) -> Self {
+        self.analytics = Some(analytics);
+        self
+    }
+
+    pub fn with_summary_window(mut self, summarize_after: usize, keep_recent: usize) -> Self {
+        self.summarize_after = summarize_after.max(1);
+        self.keep_recent = keep_recent.min(self.summarize_after);
+        self
+    }
+
+    /// Answer `input` in the context of `session` and record both turns
+    pub async fn respond(&self, session: &mut ChatSession, input: &str) -> AgentResult<String> {
+        self.track_tokens(session, input).await;
+
+        let prompt = match session.context() {
+            Some(context) => format!("{}\n\n{}", context, input),
+            None => input.to_string(),
+        };
+        let history = session.recent_turns().iter().map(to_message).collect();
+
+        let reply = prompt_with_tools(&self.agent, prompt, history, MAX_TOOL_TURNS)
+            .await
+            .map_err(|e| AgentError::MarketAnalysis(format!("Chat failed: {}", e)))?;
+
+        session.push(ChatRole::User, input);
+        session.push(ChatRole::Assistant, reply.clone());
+        self.track_tokens(session, &reply).await;
+
+        if let Err(e) = self.summarize(session).await {
+            // The turns are still there; summarizing is retried next turn
+            warn!("Failed to summarize chat session: {}", e);
+        }
+        Ok(reply)
+    }
+
+    /// Remember tokens mentioned by address, or by a symbol found in the
+    /// stored analytics
+    async fn track_tokens(&self, session: &mut ChatSession, text: &str) {
+        for word in text.split(|c: char| !c.is_ascii_alphanumeric() && c != '$') {
+            let word = word.trim_start_matches('$');
+            if (32..=44).contains(&word.len()) && Pubkey::from_str(word).is_ok() {
+                let symbol = match &self.analytics {
+                    Some(analytics) => analytics
+                        .get_token_analytics(word)
+                        .await
+                        .ok()
+                        .flatten()
+                        .map(|a| a.token_symbol),
+                    None => None,
+                };
+                session.remember_token(word, symbol);
+            } else if is_symbol_like(word) {
+                let Some(analytics) = &self.analytics else {
+                    continue;
+                };
+                if let Ok(Some(token)) = analytics.find_by_symbol(word).await {
+                    session.remember_token(&token.token_address, Some(token.token_symbol));
+                }
+            }
+        }
+    }
+
+    /// Fold old turns into the summary once there are too many
+    async fn summarize(&self, session: &mut ChatSession) -> AgentResult<()> {
+        let recent = session.recent_turns();
+        if recent.len() <= self.summarize_after {
+            return Ok(());
+        }
+        let fold = recent.len() - self.keep_recent;
+
+        let mut prompt = String::new();
+        if let Some(summary) = &session.summary {
+            let _ = writeln!(prompt, "Previous summary:\n{}\n", summary);
+        }
+        let _ = writeln!(prompt, "Conversation:");
+        for turn in &recent[..fold] {
+            let speaker = match turn.role {
+                ChatRole::User => "User",
+                ChatRole::Assistant => "Analyst",
+            };
+            let _ = writeln!(prompt, "{}: {}", speaker, turn.content);
+        }
+
+        debug!("Summarizing {} chat turns", fold);
+        let summary = self
+            .summarizer
+            .prompt(prompt)
+            .await
+            .map_err(|e| AgentError::MarketAnalysis(format!("Failed to summarize: {}", e)))?;
+
+        session.summary = Some(summary.trim().to_string());
+        session.summarized_turns += fold as u32;
+        info!(
+            "Summarized chat session up to turn {}",
+            session.summarized_turns
+        );
+        Ok(())
+    }
+}
+
+fn to_message(turn: &ChatTurn) -> Message {
+    match turn.role {
+        ChatRole::User => Message::user(turn.content.clone()),
+        ChatRole::Assistant => Message::assistant(turn.content.clone()),
+    }
+}
+
+/// Upper-case words like "BONK" that may be token symbols
+fn is_symbol_like(word: &str) -> bool {
+    (2..=10).contains(&word.len())
+        && word.chars().any(|c| c.is_ascii_uppercase())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::test_utils::ScriptedModel;
+    use rig::agent::AgentBuilder;
+    use rig::message::AssistantContent;
+
+    const BONK: &str = "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263";
+
+    fn chat(model: &ScriptedModel) -> AnalystChat<ScriptedModel> {
+        AnalystChat::new(
+            AgentBuilder::new(model.clone()).build(),
+            AgentBuilder::new(model.clone()).build(),
+        )
+    }
+
+    fn script(model: &ScriptedModel, replies: &[&str]) {
+        model
+            .choices
+            .lock()
+            .unwrap()
+            .extend(replies.iter().map(|r| AssistantContent::text(*r)));
+    }
+
+    #[tokio::test]
+    async fn test_history_and_token_references() {
+        let model = ScriptedModel::default();
+        script(&model, &["BONK looks strong", "It has the most volume"]);
+        let chat = chat(&model);
+        let mut session = ChatSession::new();
+
+        chat.respond(&mut session, &format!("How is {}?", BONK))
+            .await
+            .unwrap();
+        chat.respond(&mut session, "Compare that one with WIF")
+            .await
+            .unwrap();
+
+        assert_eq!(session.turns.len(), 4);
+        assert_eq!(session.tokens[0].address, BONK);
+
+        let requests = model.requests.lock().unwrap();
+        let (prompt, history, _) = &requests[1];
+        // Earlier turns are replayed, and the prompt names the token "that one" means
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1], Message::assistant("BONK looks strong"));
+        let Message::User { content } = prompt else {
+            panic!("expected a user prompt");
+        };
+        let rig::message::UserContent::Text(text) = content.first() else {
+            panic!("expected a text prompt");
+        };
+        assert!(text.text.contains(BONK));
+        assert!(text.text.ends_with("Compare that one with WIF"));
+    }
+
+    #[tokio::test]
+    async fn test_old_turns_are_summarized() {
+        let model = ScriptedModel::default();
+        script(&model, &["one", "two", "Talked about BONK", "three"]);
+        let chat = chat(&model).with_summary_window(3, 1);
+        let mut session = ChatSession::new();
+
+        chat.respond(&mut session, "a").await.unwrap();
+        assert!(session.summary.is_none());
+        chat.respond(&mut session, "b").await.unwrap();
+        assert_eq!(session.summary.as_deref(), Some("Talked about BONK"));
+        assert_eq!(session.summarized_turns, 3);
+        assert_eq!(session.recent_turns().len(), 1);
+
+        chat.respond(&mut session, "c").await.unwrap();
+        let requests = model.requests.lock().unwrap();
+        // The summarizer saw the folded turns
+        let Message::User { content } = &requests[2].0 else {
+            panic!("expected a user prompt");
+        };
+        let rig::message::UserContent::Text(text) = content.first() else {
+            panic!("expected a text prompt");
+        };
+        assert!(text.text.contains("User: a\nAnalyst: one\nUser: b"));
+        // Later prompts carry the summary and only the recent turns
+        let (prompt, history, _) = &requests[3];
+        assert_eq!(history.len(), 1);
+        let Message::User { content } = prompt else {
+            panic!("expected a user prompt");
+        };
+        let rig::message::UserContent::Text(text) = content.first() else {
+            panic!("expected a text prompt");
+        };
+        assert!(text.text.contains("Talked about BONK"));
+    }
+
+    #[test]
+    fn test_symbol_like() {
+        assert!(is_symbol_like("BONK"));
+        assert!(is_symbol_like("JUP2"));
+        assert!(!is_symbol_like("Bonk"));
+        assert!(!is_symbol_like("A"));
+        assert!(!is_symbol_like("2024"));
+    }
+}
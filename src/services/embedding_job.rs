@@ -0,0 +1,312 @@
+//! Backfill of the `embedding` field of stored snapshots and recommendations.
+//!
+//! Each document is rendered into a canonical text built from its key
+//! metrics, which is embedded by the configured [`EmbeddingBackend`]. The
+//! representation version and model are stored in `embedding_version`, so
+//! documents are re-embedded whenever either changes.
+
+use crate::config::mongodb::MongoDbPool;
+use crate::error::{AgentError, AgentResult};
+use crate::llm::embedding::EmbeddingBackend;
+use crate::models::token_analytics::TokenAnalytics;
+use crate::services::wallet_tracker::TokenRecommendation;
+use crate::utils::decimal_to_f64;
+use bigdecimal::BigDecimal;
+use bson::{doc, oid::ObjectId, Document};
+use futures::TryStreamExt;
+use mongodb::{options::FindOptions, Collection};
+use serde::de::DeserializeOwned;
+use std::fmt::Write;
+use std::sync::Arc;
+use tracing::info;
+
+/// Bump when the canonical texts below change
+pub const REPRESENTATION_VERSION: u32 = 1;
+
+/// Documents embedded by one run
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddingReport {
+    pub analytics: usize,
+    pub recommendations: usize,
+}
+
+pub struct EmbeddingJob {
+    analytics: Collection<TokenAnalytics>,
+    recommendations: Collection<TokenRecommendation>,
+    backend: Arc<dyn EmbeddingBackend>,
+    batch_size: usize,
+}
+
+impl EmbeddingJob {
+    pub fn new(
+        db_pool: Arc<MongoDbPool>,
+        backend: Arc<dyn EmbeddingBackend>,
+        batch_size: usize,
+    ) -> Self {
+        let db = db_pool.database(&db_pool.get_config().database);
+        Self {
+            analytics: db.collection("token_analytics"),
+            recommendations: db.collection("token_recommendations"),
+            backend,
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    /// Value of `embedding_version` for documents embedded by this job
+    pub fn version(&self) -> String {
+        embedding_version(self.backend.model())
+    }
+
+    /// Embed documents with a missing or outdated embedding, newest first,
+    /// up to `limit` of each kind
+    pub async fn run(&self, limit: usize) -> AgentResult<EmbeddingReport> {
+        let version = self.version();
+        let report = EmbeddingReport {
+            analytics: self
+                .backfill(&self.analytics, &version, "timestamp", limit, |a| {
+                    (a.id, analytics_text(a))
+                })
+                .await?,
+            recommendations: self
+                .backfill(
+                    &self.recommendations,
+                    &version,
+                    "analysis_date",
+                    limit,
+                    |r| (r.id, recommendation_text(r)),
+                )
+                .await?,
+        };
+        info!(
+            "Embedded {} snapshots and {} recommendations ({})",
+            report.analytics, report.recommendations, version
+        );
+        Ok(report)
+    }
+
+    /// Documents still needing an embedding
+    pub async fn pending(&self) -> AgentResult<EmbeddingReport> {
+        let filter = outdated_filter(&self.version());
+        Ok(EmbeddingReport {
+            analytics: self.analytics.count_documents(filter.clone()).await? as usize,
+            recommendations: self.recommendations.count_documents(filter).await? as usize,
+        })
+    }
+
+    async fn backfill<T, F>(
+        &self,
+        collection: &Collection<T>,
+        version: &str,
+        sort_field: &str,
+        limit: usize,
+        render: F,
+    ) -> AgentResult<usize>
+    where
+        T: DeserializeOwned + Send + Sync,
+        F: Fn(&T) -> (Option<ObjectId>, String),
+    {
+        let mut embedded = 0;
+        while embedded < limit {
+            let options = FindOptions::builder()
+                .sort(doc! { sort_field: -1 })
+                .limit(self.batch_size.min(limit - embedded) as i64)
+                .projection(doc! { "embedding": 0 })
+                .build();
+            let batch: Vec<T> = collection
+                .find(outdated_filter(version))
+                .with_options(options)
+                .await?
+                .try_collect()
+                .await?;
+            if batch.is_empty() {
+                break;
+            }
+
+            let (ids, texts): (Vec<_>, Vec<_>) = batch.iter().map(&render).unzip();
+            let vectors = self
+                .backend
+                .embed(&texts)
+                .await
+                .map_err(|e| AgentError::ApiError(format!("Embedding failed: {}", e)))?;
+
+            for (id, vector) in ids.into_iter().zip(vectors) {
+                let Some(id) = id else { continue };
+                collection
+                    .update_one(
+                        doc! { "_id": id },
+                        doc! { "$set": { "embedding": vector, "embedding_version": version } },
+                    )
+                    .await?;
+            }
+            embedded += batch.len();
+        }
+        Ok(embedded)
+    }
+}
+
+/// `embedding_version` for vectors from `model`
+pub fn embedding_version(model: &str) -> String {
+    format!("v{}:{}", REPRESENTATION_VERSION, model)
+}
+
+fn outdated_filter(version: &str) -> Document {
+    doc! { "embedding_version": { "$ne": version } }
+}
+
+/// Canonical text of a snapshot
+pub fn analytics_text(a: &TokenAnalytics) -> String {
+    let mut text = format!(
+        "Token {} ({}). Price {} USD",
+        a.token_symbol,
+        a.token_name,
+        decimal_to_f64(&a.price)
+            .map(compact)
+            .unwrap_or_else(|_| a.price.to_string())
+    );
+    let mut field = |label: &str, value: Option<String>| {
+        if let Some(value) = value {
+            let _ = write!(text, "; {} {}", label, value);
+        }
+    };
+    field("24h price change", pct(&a.price_change_24h));
+    field("7d price change", pct(&a.price_change_7d));
+    field("24h volume", usd(&a.volume_24h));
+    field("24h volume change", pct(&a.volume_change_24h));
+    field("liquidity", usd(&a.liquidity));
+    field("24h liquidity change", pct(&a.liquidity_change_24h));
+    field("market cap", usd(&a.market_cap));
+    field("24h trades", a.trades_24h.map(|t| t.to_string()));
+    field("holders", a.holder_count.map(|h| h.to_string()));
+    field(
+        "24h active wallets",
+        a.active_wallets_24h.map(|w| w.to_string()),
+    );
+    field(
+        "24h whale transactions",
+        a.whale_transactions_24h.map(|w| w.to_string()),
+    );
+    field(
+        "RSI 14",
+        a.rsi_14
+            .as_ref()
+            .and_then(|r| decimal_to_f64(r).ok())
+            .map(|r| format!("{:.0}", r)),
+    );
+    if let (Some(macd), Some(signal)) = (&a.macd, &a.macd_signal) {
+        let side = if macd >= signal { "above" } else { "below" };
+        field("MACD", Some(format!("{} signal line", side)));
+    }
+    text.push('.');
+    text
+}
+
+/// Canonical text of a recommendation
+pub fn recommendation_text(r: &TokenRecommendation) -> String {
+    let mut text = format!(
+        "Recommendation {} for {} ({}). Overall score {:.2}, market {:.2}, social {:.2}, \
+         development {:.2}, risk {:.2}. Price {} USD; liquidity {} USD; market cap {} USD; \
+         24h volume {} USD; holders {}.",
+        r.recommendation,
+        r.symbol,
+        r.name,
+        r.overall_score,
+        r.market_score,
+        r.social_score,
+        r.dev_score,
+        r.risk_score,
+        compact(r.price),
+        compact(r.liquidity),
+        compact(r.market_cap),
+        compact(r.volume_24h),
+        r.holders
+    );
+    if !r.strengths.is_empty() {
+        let _ = write!(text, " Strengths: {}.", r.strengths.join("; "));
+    }
+    if !r.risks.is_empty() {
+        let _ = write!(text, " Risks: {}.", r.risks.join("; "));
+    }
+    if let Some(kols) = r.kol_ownership.as_ref().filter(|k| !k.is_empty()) {
+        let names: Vec<_> = kols.iter().map(|k| k.name.as_str()).collect();
+        let _ = write!(text, " Held by KOLs: {}.", names.join(", "));
+    }
+    if let Some(reasoning) = &r.decision_reasoning {
+        let _ = write!(text, " Reasoning: {}", reasoning.final_reasoning);
+    }
+    text
+}
+
+/// Values out of the f64 range are left out of the text
+fn pct(value: &Option<BigDecimal>) -> Option<String> {
+    value
+        .as_ref()
+        .and_then(|v| decimal_to_f64(v).ok())
+        .map(|v| format!("{:+.1}%", v))
+}
+
+fn usd(value: &Option<BigDecimal>) -> Option<String> {
+    value
+        .as_ref()
+        .and_then(|v| decimal_to_f64(v).ok())
+        .map(|v| format!("{} USD", compact(v)))
+}
+
+/// Four significant digits with a K/M/B suffix, so small changes in a value
+/// don't change the text
+fn compact(value: f64) -> String {
+    let abs = value.abs();
+    let (scaled, suffix) = if abs >= 1e9 {
+        (value / 1e9, "B")
+    } else if abs >= 1e6 {
+        (value / 1e6, "M")
+    } else if abs >= 1e3 {
+        (value / 1e3, "K")
+    } else {
+        (value, "")
+    };
+    if scaled == 0.0 {
+        return "0".to_string();
+    }
+    let digits = (3 - scaled.abs().log10().floor() as i32).max(0) as usize;
+    format!("{:.*}{}", digits, scaled, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::token_analytics::TokenAnalytics;
+    use crate::utils::f64_to_decimal;
+
+    #[test]
+    fn test_compact() {
+        assert_eq!(compact(1_234_567.0), "1.235M");
+        assert_eq!(compact(0.00002345), "0.00002345");
+        assert_eq!(compact(950.0), "950.0");
+        assert_eq!(compact(0.0), "0");
+    }
+
+    #[test]
+    fn test_analytics_text() {
+        let mut snapshot = TokenAnalytics::test_snapshot(0, 0.00002345);
+        snapshot.volume_24h = Some(f64_to_decimal(1_234_567.0).unwrap());
+        snapshot.price_change_24h = Some(f64_to_decimal(-3.21).unwrap());
+        snapshot.macd = Some(f64_to_decimal(0.2).unwrap());
+        snapshot.macd_signal = Some(f64_to_decimal(0.1).unwrap());
+
+        let text = analytics_text(&snapshot);
+        assert_eq!(
+            text,
+            "Token TKN (Token). Price 0.00002345 USD; 24h price change -3.2%; \
+             24h volume 1.235M USD; MACD above signal line."
+        );
+
+        // Ids, timestamps and embeddings don't change the representation
+        snapshot.embedding = Some(vec![1.0]);
+        snapshot.timestamp = bson::DateTime::now();
+        assert_eq!(analytics_text(&snapshot), text);
+        assert_eq!(
+            embedding_version("m"),
+            format!("v{}:m", REPRESENTATION_VERSION)
+        );
+    }
+}
@@ -1,4 +1,20 @@
+pub mod analyst_chat;
+pub mod embedding_job;
+pub mod recommendation_tracker;
+pub mod retention;
+pub mod signal_gate;
+pub mod signal_tracker;
 pub mod token_analytics;
 pub mod token_analytics_llm;
 pub mod token_filter;
+pub mod token_similarity;
+pub mod wallet_tracker;
+pub use analyst_chat::{AnalystChat, ChatSessionStore};
+pub use embedding_job::EmbeddingJob;
+pub use recommendation_tracker::RecommendationTracker;
+pub use retention::RetentionJob;
+pub use signal_gate::SignalGate;
+pub use signal_tracker::SignalTracker;
 pub use token_analytics::TokenAnalyticsService;
+pub use token_filter::TokenFilterService;
+pub use wallet_tracker::WalletTrackerService;
@@ -0,0 +1,569 @@
+//! Outcome tracking for stored token recommendations.
+//!
+//! Every document in `token_recommendations` is re-priced once each of the
+//! [`OutcomeHorizon`]s has elapsed since its `analysis_date`. The forward
+//! return and maximum drawdown are stored under `outcomes.<horizon>` and the
+//! results can be aggregated into a [`Scorecard`].
+
+use crate::birdeye::BirdeyeApi;
+use crate::config::mongodb::MongoDbPool;
+use crate::services::token_analytics::TokenAnalyticsService;
+use crate::services::wallet_tracker::{RecommendationOutcome, TokenRecommendation};
+use anyhow::{anyhow, Context, Result};
+use bigdecimal::ToPrimitive;
+use bson::{doc, DateTime};
+use chrono::{Duration as ChronoDuration, Utc};
+use futures::TryStreamExt;
+use mongodb::Collection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// How far from the horizon a stored snapshot may be and still be used as the
+/// horizon price. Also bounds how late a live quote may be taken.
+const OUTCOME_TOLERANCE_SECS: i64 = 30 * 60;
+
+/// Number of equal-width score buckets in the scorecard
+const SCORE_BUCKETS: usize = 5;
+
+/// Fixed horizons a recommendation is re-priced at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum OutcomeHorizon {
+    #[serde(rename = "1h")]
+    H1,
+    #[serde(rename = "24h")]
+    H24,
+    #[serde(rename = "7d")]
+    D7,
+    #[serde(rename = "30d")]
+    D30,
+}
+
+impl OutcomeHorizon {
+    pub const ALL: [OutcomeHorizon; 4] = [
+        OutcomeHorizon::H1,
+        OutcomeHorizon::H24,
+        OutcomeHorizon::D7,
+        OutcomeHorizon::D30,
+    ];
+
+    pub fn as_secs(&self) -> i64 {
+        match self {
+            OutcomeHorizon::H1 => 60 * 60,
+            OutcomeHorizon::H24 => 24 * 60 * 60,
+            OutcomeHorizon::D7 => 7 * 24 * 60 * 60,
+            OutcomeHorizon::D30 => 30 * 24 * 60 * 60,
+        }
+    }
+
+    /// Key used under `outcomes` on the recommendation document
+    pub fn label(&self) -> &'static str {
+        match self {
+            OutcomeHorizon::H1 => "1h",
+            OutcomeHorizon::H24 => "24h",
+            OutcomeHorizon::D7 => "7d",
+            OutcomeHorizon::D30 => "30d",
+        }
+    }
+}
+
+impl FromStr for OutcomeHorizon {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        OutcomeHorizon::ALL
+            .into_iter()
+            .find(|h| h.label() == s)
+            .ok_or_else(|| anyhow!("Unknown horizon '{}', expected one of 1h, 24h, 7d, 30d", s))
+    }
+}
+
+impl fmt::Display for OutcomeHorizon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Re-prices stored recommendations and aggregates their outcomes
+pub struct RecommendationTracker {
+    collection: Collection<TokenRecommendation>,
+    analytics: Arc<TokenAnalyticsService>,
+    birdeye: Arc<dyn BirdeyeApi>,
+}
+
+impl RecommendationTracker {
+    pub fn new(
+        db_pool: Arc<MongoDbPool>,
+        analytics: Arc<TokenAnalyticsService>,
+        birdeye: Arc<dyn BirdeyeApi>,
+    ) -> Self {
+        let db = db_pool.database(&db_pool.get_config().database);
+        Self {
+            collection: db.collection("token_recommendations"),
+            analytics,
+            birdeye,
+        }
+    }
+
+    /// Measure every horizon that has elapsed but has no stored outcome yet.
+    ///
+    /// Horizon prices come from the `token_analytics` history; a live BirdEye
+    /// quote is used only when the horizon passed less than the tolerance ago.
+    /// Horizons that can no longer be priced are skipped and retried on the
+    /// next run. Returns the number of outcomes written.
+    pub async fn reprice_due(&self) -> Result<usize> {
+        let missing: Vec<_> = OutcomeHorizon::ALL
+            .iter()
+            .map(|h| doc! { format!("outcomes.{}", h.label()): { "$exists": false } })
+            .collect();
+        let filter = doc! { "price": { "$gt": 0.0 }, "$or": missing };
+
+        let recommendations: Vec<TokenRecommendation> = self
+            .collection
+            .find(filter)
+            .await
+            .context("Failed to query token recommendations")?
+            .try_collect()
+            .await
+            .context("Failed to collect token recommendations")?;
+
+        let now = Utc::now();
+        let mut written = 0;
+
+        for rec in &recommendations {
+            let Some(id) = rec.id else { continue };
+
+            for horizon in OutcomeHorizon::ALL {
+                let done = rec
+                    .outcomes
+                    .as_ref()
+                    .is_some_and(|o| o.contains_key(horizon.label()));
+                let target = rec.analysis_date + ChronoDuration::seconds(horizon.as_secs());
+                if done || target > now {
+                    continue;
+                }
+
+                match self.measure(rec, horizon).await {
+                    Ok(Some(outcome)) => {
+                        self.collection
+                            .update_one(
+                                doc! { "_id": id },
+                                doc! { "$set": {
+                                    format!("outcomes.{}", horizon.label()): bson::to_bson(&outcome)?
+                                } },
+                            )
+                            .await
+                            .context("Failed to store recommendation outcome")?;
+                        debug!(
+                            "{} {}: return {:+.2}%, drawdown {:.2}%",
+                            rec.symbol,
+                            horizon,
+                            outcome.forward_return * 100.0,
+                            outcome.max_drawdown * 100.0
+                        );
+                        written += 1;
+                    }
+                    Ok(None) => debug!("No price for {} at {} horizon", rec.symbol, horizon),
+                    Err(e) => warn!(
+                        "Failed to price {} at {} horizon: {}",
+                        rec.symbol, horizon, e
+                    ),
+                }
+            }
+        }
+
+        info!(
+            "Re-priced {} outcomes across {} recommendations",
+            written,
+            recommendations.len()
+        );
+        Ok(written)
+    }
+
+    async fn measure(
+        &self,
+        rec: &TokenRecommendation,
+        horizon: OutcomeHorizon,
+    ) -> Result<Option<RecommendationOutcome>> {
+        let start = rec.analysis_date.timestamp();
+        let target = start + horizon.as_secs();
+
+        let history = self
+            .analytics
+            .get_token_history(
+                &rec.token_address,
+                DateTime::from_millis(start * 1000),
+                DateTime::from_millis((target + OUTCOME_TOLERANCE_SECS) * 1000),
+            )
+            .await?;
+
+        let points: Vec<(i64, f64)> = history
+            .iter()
+            .filter_map(|h| Some((h.timestamp.timestamp_millis() / 1000, h.price.to_f64()?)))
+            .collect();
+
+        let stored = points
+            .iter()
+            .filter(|(ts, _)| (ts - target).abs() <= OUTCOME_TOLERANCE_SECS)
+            .min_by_key(|(ts, _)| (ts - target).abs())
+            .map(|(_, price)| *price);
+
+        let horizon_price = match stored {
+            Some(price) => price,
+            None if Utc::now().timestamp() - target <= OUTCOME_TOLERANCE_SECS => {
+                self.birdeye
+                    .get_token_overview(&rec.token_address)
+                    .await?
+                    .price
+            }
+            None => return Ok(None),
+        };
+
+        let path: Vec<f64> = points
+            .iter()
+            .filter(|(ts, _)| *ts <= target)
+            .map(|(_, price)| *price)
+            .collect();
+
+        Ok(compute_outcome(rec.price, &path, horizon_price))
+    }
+
+    /// Aggregate stored outcomes for `horizon` into a scorecard
+    pub async fn scorecard(&self, horizon: OutcomeHorizon) -> Result<Scorecard> {
+        let filter = doc! { format!("outcomes.{}", horizon.label()): { "$exists": true } };
+        let recommendations: Vec<TokenRecommendation> = self
+            .collection
+            .find(filter)
+            .await
+            .context("Failed to query token recommendations")?
+            .try_collect()
+            .await
+            .context("Failed to collect token recommendations")?;
+
+        Ok(Scorecard::build(&recommendations, horizon))
+    }
+}
+
+/// Forward return and drawdown from `entry` along `path` (prices between the
+/// recommendation and the horizon, in time order) to `horizon_price`.
+/// Returns `None` when the entry or horizon price is not usable.
+pub fn compute_outcome(
+    entry: f64,
+    path: &[f64],
+    horizon_price: f64,
+) -> Option<RecommendationOutcome> {
+    if !entry.is_finite() || entry <= 0.0 || !horizon_price.is_finite() || horizon_price <= 0.0 {
+        return None;
+    }
+
+    let prices: Vec<f64> = path
+        .iter()
+        .copied()
+        .filter(|p| p.is_finite() && *p > 0.0)
+        .chain(std::iter::once(horizon_price))
+        .collect();
+
+    Some(RecommendationOutcome {
+        evaluated_at: Utc::now(),
+        price: horizon_price,
+        forward_return: horizon_price / entry - 1.0,
+        max_drawdown: max_drawdown(entry, &prices),
+        samples: prices.len(),
+    })
+}
+
+/// Largest peak-to-trough decline, as a positive fraction, starting from `entry`
+pub fn max_drawdown(entry: f64, prices: &[f64]) -> f64 {
+    let mut peak = entry;
+    let mut worst = 0.0_f64;
+    for price in prices {
+        peak = peak.max(*price);
+        worst = worst.max(1.0 - price / peak);
+    }
+    worst
+}
+
+/// Coarse label for the free-text `recommendation` written by the LLM
+pub fn recommendation_label(text: &str) -> &'static str {
+    let text = text.to_lowercase();
+    if text.contains("strong buy") {
+        "strong buy"
+    } else if text.contains("avoid") || text.contains("sell") {
+        "avoid"
+    } else if text.contains("buy") {
+        "buy"
+    } else if text.contains("hold") || text.contains("watch") || text.contains("monitor") {
+        "hold"
+    } else {
+        "other"
+    }
+}
+
+fn score_bucket(score: f64) -> String {
+    let width = 1.0 / SCORE_BUCKETS as f64;
+    let idx = ((score.clamp(0.0, 1.0) / width) as usize).min(SCORE_BUCKETS - 1);
+    format!("{:.1}-{:.1}", idx as f64 * width, (idx + 1) as f64 * width)
+}
+
+/// Aggregated outcomes of one group of recommendations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScorecardRow {
+    pub group: String,
+    pub count: usize,
+    pub mean_return: f64,
+    pub median_return: f64,
+    /// Share of recommendations with a positive forward return
+    pub hit_rate: f64,
+    pub mean_drawdown: f64,
+}
+
+/// Recommendation outcomes at one horizon, grouped several ways
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scorecard {
+    pub horizon: OutcomeHorizon,
+    pub total: usize,
+    pub by_score: Vec<ScorecardRow>,
+    pub by_recommendation: Vec<ScorecardRow>,
+    pub by_kol_presence: Vec<ScorecardRow>,
+    pub by_prompt_version: Vec<ScorecardRow>,
+}
+
+impl Scorecard {
+    /// Build a scorecard from recommendations; those without an outcome for
+    /// `horizon` are ignored.
+    pub fn build(recommendations: &[TokenRecommendation], horizon: OutcomeHorizon) -> Self {
+        let measured: Vec<(&TokenRecommendation, &RecommendationOutcome)> = recommendations
+            .iter()
+            .filter_map(|r| Some((r, r.outcomes.as_ref()?.get(horizon.label())?)))
+            .collect();
+
+        Self {
+            horizon,
+            total: measured.len(),
+            by_score: group_rows(&measured, |r| score_bucket(r.overall_score)),
+            by_recommendation: group_rows(&measured, |r| {
+                recommendation_label(&r.recommendation).to_string()
+            }),
+            by_kol_presence: group_rows(&measured, |r| {
+                let has_kol = r.kol_ownership.as_ref().is_some_and(|k| !k.is_empty());
+                if has_kol { "with KOL" } else { "no KOL" }.to_string()
+            }),
+            by_prompt_version: group_rows(&measured, |r| {
+                r.prompt_version
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string())
+            }),
+        }
+    }
+}
+
+fn group_rows(
+    measured: &[(&TokenRecommendation, &RecommendationOutcome)],
+    key: impl Fn(&TokenRecommendation) -> String,
+) -> Vec<ScorecardRow> {
+    let mut groups: BTreeMap<String, Vec<&RecommendationOutcome>> = BTreeMap::new();
+    for (rec, outcome) in measured {
+        groups.entry(key(rec)).or_default().push(outcome);
+    }
+
+    groups
+        .into_iter()
+        .map(|(group, outcomes)| {
+            let count = outcomes.len();
+            let mut returns: Vec<f64> = outcomes.iter().map(|o| o.forward_return).collect();
+            returns.sort_by(|a, b| a.total_cmp(b));
+            let median_return = if count % 2 == 0 {
+                (returns[count / 2 - 1] + returns[count / 2]) / 2.0
+            } else {
+                returns[count / 2]
+            };
+
+            ScorecardRow {
+                group,
+                count,
+                mean_return: returns.iter().sum::<f64>() / count as f64,
+                median_return,
+                hit_rate: returns.iter().filter(|r| **r > 0.0).count() as f64 / count as f64,
+                mean_drawdown: outcomes.iter().map(|o| o.max_drawdown).sum::<f64>() / count as f64,
+            }
+        })
+        .collect()
+}
+
+impl fmt::Display for Scorecard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Scorecard at {} ({} recommendations)",
+            self.horizon, self.total
+        )?;
+
+        let sections = [
+            ("Score", &self.by_score),
+            ("Recommendation", &self.by_recommendation),
+            ("KOL presence", &self.by_kol_presence),
+            ("Prompt version", &self.by_prompt_version),
+        ];
+
+        for (title, rows) in sections {
+            writeln!(f, "\n{}:", title)?;
+            writeln!(
+                f,
+                "  {:<14} {:>5} {:>9} {:>9} {:>7} {:>9}",
+                "group", "n", "mean", "median", "hit", "drawdown"
+            )?;
+            for row in rows {
+                writeln!(
+                    f,
+                    "  {:<14} {:>5} {:>+8.2}% {:>+8.2}% {:>6.1}% {:>8.2}%",
+                    row.group,
+                    row.count,
+                    row.mean_return * 100.0,
+                    row.median_return * 100.0,
+                    row.hit_rate * 100.0,
+                    row.mean_drawdown * 100.0
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::wallet_tracker::KolOwnership;
+
+    fn recommendation(
+        score: f64,
+        text: &str,
+        kol: bool,
+        prompt_version: Option<&str>,
+        forward_return: Option<f64>,
+    ) -> TokenRecommendation {
+        let outcomes = forward_return.map(|r| {
+            let mut map = BTreeMap::new();
+            map.insert(
+                "24h".to_string(),
+                RecommendationOutcome {
+                    evaluated_at: Utc::now(),
+                    price: 1.0 + r,
+                    forward_return: r,
+                    max_drawdown: 0.1,
+                    samples: 2,
+                },
+            );
+            map
+        });
+
+        TokenRecommendation {
+            id: None,
+            token_address: "addr".to_string(),
+            symbol: "TKN".to_string(),
+            name: "Token".to_string(),
+            decimals: 9,
+            logo_uri: None,
+            analysis_date: Utc::now(),
+            overall_score: score,
+            market_score: score,
+            social_score: score,
+            dev_score: score,
+            risk_score: 0.5,
+            price: 1.0,
+            liquidity: 0.0,
+            market_cap: 0.0,
+            volume_24h: 0.0,
+            holders: 0,
+            strengths: vec![],
+            risks: vec![],
+            recommendation: text.to_string(),
+            kol_ownership: kol.then(|| {
+                vec![KolOwnership {
+                    kol_id: "kol".to_string(),
+                    name: "KOL".to_string(),
+                    wallet_address: "wallet".to_string(),
+                    position_size: 1.0,
+                    entry_time: Utc::now(),
+                }]
+            }),
+            decision_reasoning: None,
+            prompt_version: prompt_version.map(str::to_string),
+            outcomes,
+            embedding: None,
+            embedding_version: None,
+        }
+    }
+
+    #[test]
+    fn test_max_drawdown() {
+        assert_eq!(max_drawdown(1.0, &[1.2, 0.9, 1.5]), 0.25);
+        assert_eq!(max_drawdown(1.0, &[1.1, 1.2]), 0.0);
+        assert_eq!(max_drawdown(2.0, &[1.0]), 0.5);
+    }
+
+    #[test]
+    fn test_compute_outcome() {
+        let outcome = compute_outcome(2.0, &[2.5, 1.0], 3.0).unwrap();
+        assert_eq!(outcome.forward_return, 0.5);
+        assert_eq!(outcome.max_drawdown, 0.6);
+        assert_eq!(outcome.samples, 3);
+
+        assert!(compute_outcome(0.0, &[], 1.0).is_none());
+        assert!(compute_outcome(1.0, &[], f64::NAN).is_none());
+    }
+
+    #[test]
+    fn test_recommendation_label() {
+        assert_eq!(recommendation_label("STRONG BUY - momentum"), "strong buy");
+        assert_eq!(recommendation_label("Buy on dips"), "buy");
+        assert_eq!(recommendation_label("Avoid, liquidity too thin"), "avoid");
+        assert_eq!(recommendation_label("Hold and monitor"), "hold");
+        assert_eq!(recommendation_label("n/a"), "other");
+    }
+
+    #[test]
+    fn test_horizon_parse() {
+        assert_eq!("7d".parse::<OutcomeHorizon>().unwrap(), OutcomeHorizon::D7);
+        assert!("2d".parse::<OutcomeHorizon>().is_err());
+    }
+
+    #[test]
+    fn test_scorecard_groups() {
+        let recs = vec![
+            recommendation(0.9, "Strong Buy", true, Some("v1"), Some(0.4)),
+            recommendation(0.85, "Buy", false, Some("v1"), Some(-0.2)),
+            recommendation(0.3, "Avoid", false, None, Some(-0.1)),
+            recommendation(0.5, "Buy", false, Some("v1"), None),
+        ];
+
+        let card = Scorecard::build(&recs, OutcomeHorizon::H24);
+        assert_eq!(card.total, 3);
+
+        let top = card.by_score.iter().find(|r| r.group == "0.8-1.0").unwrap();
+        assert_eq!(top.count, 2);
+        assert!((top.mean_return - 0.1).abs() < 1e-12);
+        assert_eq!(top.hit_rate, 0.5);
+
+        let kol = card
+            .by_kol_presence
+            .iter()
+            .find(|r| r.group == "with KOL")
+            .unwrap();
+        assert_eq!(kol.count, 1);
+
+        let versions: Vec<&str> = card
+            .by_prompt_version
+            .iter()
+            .map(|r| r.group.as_str())
+            .collect();
+        assert_eq!(versions, vec!["unknown", "v1"]);
+
+        assert!(Scorecard::build(&recs, OutcomeHorizon::D7)
+            .by_score
+            .is_empty());
+    }
+}
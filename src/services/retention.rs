@@ -0,0 +1,390 @@
+//! Retention of the time-series collections.
+//!
+//! Raw snapshots are rolled up into hourly buckets, and hourly buckets into
+//! daily ones, with aggregation pipelines merging into `<collection>_hourly`
+//! and `<collection>_daily`. Each bucket keeps the last snapshot of its
+//! period, stamped with the bucket start, plus the open, high and low price
+//! and the number of snapshots it covers. Every resolution then expires
+//! through a TTL index on `timestamp` as set by its [`RetentionPolicy`].
+//!
+//! Rollups are incremental: each run recomputes buckets from the newest
+//! bucket already stored. The job must run more often than raw snapshots
+//! expire, or snapshots are deleted before they are rolled up.
+
+use crate::config::mongodb::MongoDbPool;
+use crate::config::{RetentionConfig, RetentionPolicy};
+use crate::error::{AgentError, AgentResult};
+use crate::storage::Resolution;
+use bson::{doc, DateTime, Document};
+use futures::TryStreamExt;
+use mongodb::{error::ErrorKind, Database, IndexModel};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+/// Name of the TTL index on each resolution
+pub const TTL_INDEX: &str = "retention_ttl";
+
+/// A time-series collection under a retention policy
+#[derive(Debug, Clone)]
+pub struct RetainedCollection {
+    pub name: &'static str,
+    /// Field identifying the token of a snapshot
+    pub key: &'static str,
+    pub policy: RetentionPolicy,
+}
+
+impl RetainedCollection {
+    pub fn token_analytics(policy: RetentionPolicy) -> Self {
+        Self {
+            name: "token_analytics",
+            key: "token_address",
+            policy,
+        }
+    }
+
+    pub fn token_trending(policy: RetentionPolicy) -> Self {
+        Self {
+            name: "token_trending",
+            key: "address",
+            policy,
+        }
+    }
+}
+
+/// Buckets written by one run, per collection
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    pub collection: String,
+    pub hourly: u64,
+    pub daily: u64,
+}
+
+fn ceil_to(millis: i64, bucket_millis: i64) -> i64 {
+    -(-millis).div_euclid(bucket_millis) * bucket_millis
+}
+
+/// Oldest time kept at `resolution` under `policy`, aligned up to the next
+/// bucket of the coarser resolution so that adjacent reads don't overlap.
+/// `None` when the resolution is kept forever.
+fn horizon(policy: &RetentionPolicy, resolution: Resolution, now: DateTime) -> Option<i64> {
+    let (days, align) = match resolution {
+        Resolution::Raw => (policy.raw_days?, Resolution::Hourly),
+        Resolution::Hourly => (policy.hourly_days?, Resolution::Daily),
+        Resolution::Daily => return None,
+    };
+    let oldest = now.timestamp_millis() - i64::from(days) * Resolution::Daily.bucket_millis();
+    Some(ceil_to(oldest, align.bucket_millis()))
+}
+
+/// Split `[start, end]` into the finest resolution still kept for each part,
+/// oldest part first
+pub fn history_segments(
+    policy: &RetentionPolicy,
+    now: DateTime,
+    start: DateTime,
+    end: DateTime,
+) -> Vec<(Resolution, DateTime, DateTime)> {
+    let (start, end) = (start.timestamp_millis(), end.timestamp_millis());
+    let Some(raw_from) = horizon(policy, Resolution::Raw, now) else {
+        return vec![(
+            Resolution::Raw,
+            DateTime::from_millis(start),
+            DateTime::from_millis(end),
+        )];
+    };
+    let hourly_from = horizon(policy, Resolution::Hourly, now).map(|h| h.min(raw_from));
+
+    let mut segments = Vec::new();
+    let mut push = |resolution, from: i64, until: i64| {
+        if from <= until {
+            segments.push((
+                resolution,
+                DateTime::from_millis(from),
+                DateTime::from_millis(until),
+            ));
+        }
+    };
+    if let Some(hourly_from) = hourly_from {
+        push(Resolution::Daily, start, end.min(hourly_from - 1));
+    }
+    push(
+        Resolution::Hourly,
+        start.max(hourly_from.unwrap_or(i64::MIN)),
+        end.min(raw_from - 1),
+    );
+    push(Resolution::Raw, start.max(raw_from), end);
+    segments
+}
+
+/// Pipeline merging snapshots of `source` taken at or after `from` into
+/// buckets of `target`. Works on raw snapshots and on finer rollups alike.
+fn rollup_pipeline(
+    key: &str,
+    resolution: Resolution,
+    from: DateTime,
+    target: &str,
+) -> Vec<Document> {
+    let unit = match resolution {
+        Resolution::Daily => "day",
+        _ => "hour",
+    };
+    let price = |field: &str| doc! { "$ifNull": [format!("${}", field), "$price"] };
+    vec![
+        doc! { "$match": { "timestamp": { "$gte": from } } },
+        doc! { "$sort": { "timestamp": 1 } },
+        doc! {
+            "$group": {
+                "_id": {
+                    "key": format!("${}", key),
+                    "bucket": { "$dateTrunc": { "date": "$timestamp", "unit": unit } },
+                },
+                "last": { "$last": "$$ROOT" },
+                "price_open": { "$first": price("price_open") },
+                "price_high": { "$max": { "$toDecimal": price("price_high") } },
+                "price_low": { "$min": { "$toDecimal": price("price_low") } },
+                "samples": { "$sum": { "$ifNull": ["$samples", 1] } },
+            }
+        },
+        doc! {
+            "$replaceWith": {
+                "$mergeObjects": ["$last", {
+                    "timestamp": "$_id.bucket",
+                    "price_open": "$price_open",
+                    "price_high": { "$toString": "$price_high" },
+                    "price_low": { "$toString": "$price_low" },
+                    "samples": "$samples",
+                }]
+            }
+        },
+        // Buckets keep their own _id; embeddings describe single snapshots
+        doc! { "$unset": ["_id", "embedding", "embedding_version"] },
+        doc! {
+            "$merge": {
+                "into": target,
+                "on": [key, "timestamp"],
+                "whenMatched": "replace",
+                "whenNotMatched": "insert",
+            }
+        },
+    ]
+}
+
+pub struct RetentionJob {
+    db: Database,
+    collections: Vec<RetainedCollection>,
+}
+
+impl RetentionJob {
+    pub fn new(db_pool: Arc<MongoDbPool>, config: &RetentionConfig) -> Self {
+        let db = db_pool.database(&db_pool.get_config().database);
+        Self {
+            db,
+            collections: vec![
+                RetainedCollection::token_analytics(config.token_analytics),
+                RetainedCollection::token_trending(config.token_trending),
+            ],
+        }
+    }
+
+    /// Roll up new snapshots and apply the TTL of every resolution
+    pub async fn run(&self) -> AgentResult<Vec<RetentionReport>> {
+        let mut reports = Vec::new();
+        for collection in &self.collections {
+            let mut report = RetentionReport {
+                collection: collection.name.to_string(),
+                ..Default::default()
+            };
+            if collection.policy.expires() {
+                report.hourly = self
+                    .roll_up(collection, Resolution::Raw, Resolution::Hourly)
+                    .await?;
+                report.daily = self
+                    .roll_up(collection, Resolution::Hourly, Resolution::Daily)
+                    .await?;
+            }
+            self.apply_ttl(collection).await?;
+            info!(
+                "Retention of {}: {} hourly and {} daily buckets updated",
+                report.collection, report.hourly, report.daily
+            );
+            reports.push(report);
+        }
+        Ok(reports)
+    }
+
+    /// Start of the newest stored bucket, which may have been partial
+    async fn newest_bucket(&self, target: &str) -> AgentResult<DateTime> {
+        let newest = self
+            .db
+            .collection::<Document>(target)
+            .find_one(doc! {})
+            .sort(doc! { "timestamp": -1 })
+            .projection(doc! { "timestamp": 1 })
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(newest
+            .and_then(|d| d.get_datetime("timestamp").ok().copied())
+            .unwrap_or(DateTime::MIN))
+    }
+
+    async fn roll_up(
+        &self,
+        collection: &RetainedCollection,
+        from: Resolution,
+        to: Resolution,
+    ) -> AgentResult<u64> {
+        let source = from.collection(collection.name);
+        let target = to.collection(collection.name);
+        let since = self.newest_bucket(&target).await?;
+
+        self.db
+            .collection::<Document>(&source)
+            .aggregate(rollup_pipeline(collection.key, to, since, &target))
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect::<Vec<_>>()
+            .await
+            .map_err(AgentError::Database)?;
+
+        // Every bucket from `since` on was written by this run
+        self.db
+            .collection::<Document>(&target)
+            .count_documents(doc! { "timestamp": { "$gte": since } })
+            .await
+            .map_err(AgentError::Database)
+    }
+
+    /// Create, update or drop the TTL index of each resolution to match the
+    /// policy
+    async fn apply_ttl(&self, collection: &RetainedCollection) -> AgentResult<()> {
+        let policy = &collection.policy;
+        for (resolution, days) in [
+            (Resolution::Raw, policy.raw_days),
+            (Resolution::Hourly, policy.hourly_days),
+            (Resolution::Daily, policy.daily_days),
+        ] {
+            let name = resolution.collection(collection.name);
+            let expire_after = days.map(|d| Duration::from_secs(u64::from(d) * 86_400));
+            let current = self.ttl_index(&name).await?;
+            if current.as_ref().map(|i| i.expire_after) == Some(expire_after) {
+                continue;
+            }
+
+            let command = match (current, expire_after) {
+                (None, None) => continue,
+                (Some(_), None) => doc! { "dropIndexes": &name, "index": TTL_INDEX },
+                (None, Some(expire)) => doc! {
+                    "createIndexes": &name,
+                    "indexes": [{
+                        "key": { "timestamp": 1 },
+                        "name": TTL_INDEX,
+                        "expireAfterSeconds": expire.as_secs() as i64,
+                    }]
+                },
+                (Some(_), Some(expire)) => doc! {
+                    "collMod": &name,
+                    "index": { "name": TTL_INDEX, "expireAfterSeconds": expire.as_secs() as i64 },
+                },
+            };
+            self.db
+                .run_command(command)
+                .await
+                .map_err(AgentError::Database)?;
+            info!("Set expiry of {} to {:?} days", name, days);
+        }
+        Ok(())
+    }
+
+    async fn ttl_index(&self, collection: &str) -> AgentResult<Option<TtlIndex>> {
+        let indexes: Vec<IndexModel> = match self
+            .db
+            .collection::<Document>(collection)
+            .list_indexes()
+            .await
+        {
+            Ok(cursor) => cursor.try_collect().await.map_err(AgentError::Database)?,
+            // The collection doesn't exist yet
+            Err(e) if matches!(e.kind.as_ref(), ErrorKind::Command(c) if c.code == 26) => {
+                return Ok(None)
+            }
+            Err(e) => return Err(AgentError::Database(e)),
+        };
+        Ok(indexes
+            .into_iter()
+            .filter_map(|i| i.options)
+            .find(|o| o.name.as_deref() == Some(TTL_INDEX))
+            .map(|o| TtlIndex {
+                expire_after: o.expire_after,
+            }))
+    }
+}
+
+struct TtlIndex {
+    expire_after: Option<Duration>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3_600_000;
+    const DAY: i64 = 24 * HOUR;
+
+    fn at(millis: i64) -> DateTime {
+        DateTime::from_millis(millis)
+    }
+
+    #[test]
+    fn test_history_segments() {
+        let now = at(1000 * DAY + 90 * 60_000);
+        let policy = RetentionPolicy::default();
+
+        // Recent history is read raw
+        let recent = history_segments(&policy, now, at(999 * DAY), now);
+        assert_eq!(recent, vec![(Resolution::Raw, at(999 * DAY), now)]);
+
+        // Raw is kept from the hour after now - 7 days, hourly from the day
+        // after now - 90 days
+        let all = history_segments(&policy, now, at(0), now);
+        assert_eq!(
+            all,
+            vec![
+                (Resolution::Daily, at(0), at(911 * DAY - 1)),
+                (
+                    Resolution::Hourly,
+                    at(911 * DAY),
+                    at(993 * DAY + 2 * HOUR - 1)
+                ),
+                (Resolution::Raw, at(993 * DAY + 2 * HOUR), now),
+            ]
+        );
+
+        assert_eq!(
+            history_segments(&RetentionPolicy::keep_raw(), now, at(0), now),
+            vec![(Resolution::Raw, at(0), now)]
+        );
+        assert!(history_segments(&policy, now, now, at(0)).is_empty());
+    }
+
+    #[test]
+    fn test_rollup_pipeline() {
+        let pipeline = rollup_pipeline(
+            "address",
+            Resolution::Daily,
+            at(DAY),
+            "token_trending_daily",
+        );
+        let group = pipeline[2].get_document("$group").unwrap();
+        assert_eq!(
+            group.get_document("_id").unwrap(),
+            &doc! {
+                "key": "$address",
+                "bucket": { "$dateTrunc": { "date": "$timestamp", "unit": "day" } },
+            }
+        );
+        let merge = pipeline.last().unwrap().get_document("$merge").unwrap();
+        assert_eq!(merge.get_str("into").unwrap(), "token_trending_daily");
+        assert_eq!(ceil_to(DAY + 5, DAY), 2 * DAY);
+    }
+}
@@ -0,0 +1,90 @@
+//! Shared gate between signal generation and anything that acts on signals.
+
+use crate::config::mongodb::MongoDbPool;
+use crate::error::{AgentError, AgentResult};
+use crate::models::market_signal::MarketSignal;
+use crate::signals::{SignalDecision, SignalEpisode, SignalPolicy, SignalStateStore};
+use bson::{doc, DateTime};
+use futures::TryStreamExt;
+use mongodb::Collection;
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// Folds generated signals into episodes and persists them to
+/// `signal_episodes`, so only the first signal of an episode is actionable.
+/// Open episodes and recent cooldowns are restored on startup.
+pub struct SignalGate {
+    store: Mutex<SignalStateStore>,
+    episodes: Collection<SignalEpisode>,
+}
+
+impl SignalGate {
+    pub async fn new(db_pool: Arc<MongoDbPool>, policy: SignalPolicy) -> AgentResult<Self> {
+        let db = db_pool.database(&db_pool.get_config().database);
+        let episodes: Collection<SignalEpisode> = db.collection(SignalEpisode::collection_name());
+
+        let cooldown_start =
+            DateTime::now().timestamp_millis() - policy.cooldown.as_millis() as i64;
+        let recent: Vec<SignalEpisode> = episodes
+            .find(doc! { "$or": [
+                { "ended_at": null },
+                { "started_at": { "$gte": DateTime::from_millis(cooldown_start) } },
+            ] })
+            .sort(doc! { "started_at": 1 })
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)?;
+
+        let mut store = SignalStateStore::new(policy);
+        for episode in recent {
+            store.restore(episode);
+        }
+
+        Ok(Self {
+            store: Mutex::new(store),
+            episodes,
+        })
+    }
+
+    /// Record a signal and decide whether it should be acted on
+    pub async fn admit(&self, signal: &MarketSignal) -> AgentResult<SignalDecision> {
+        let (decision, closed) = {
+            let mut store = self.store.lock().unwrap();
+            let decision = store.observe(signal, DateTime::now());
+            (decision, store.take_closed())
+        };
+
+        for episode in &closed {
+            self.save(episode).await?;
+        }
+        match &decision {
+            SignalDecision::Actionable(episode) | SignalDecision::Merged(episode) => {
+                self.save(episode).await?
+            }
+            SignalDecision::Suppressed(reason) => {
+                debug!(
+                    "Suppressed {:?} for {}: {}",
+                    signal.signal_type, signal.asset_address, reason
+                )
+            }
+        }
+
+        Ok(decision)
+    }
+
+    /// The ongoing episode for a token, if any
+    pub fn active(&self, token_address: &str) -> Option<SignalEpisode> {
+        self.store.lock().unwrap().active(token_address).cloned()
+    }
+
+    async fn save(&self, episode: &SignalEpisode) -> AgentResult<()> {
+        self.episodes
+            .replace_one(doc! { "_id": episode.id }, episode)
+            .upsert(true)
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(())
+    }
+}
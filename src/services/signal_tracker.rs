@@ -0,0 +1,275 @@
+//! Outcome measurement and confidence calibration for market signals.
+//!
+//! Signals persisted to `market_signals` by [`TokenAnalyticsService`] are
+//! re-priced once the forward horizon has elapsed. The realized hits are
+//! then used to fit an [`IsotonicCalibrator`] per [`SignalType`], which is
+//! stored in `signal_calibration` and picked up by the analytics service so
+//! that a signal's confidence is the observed probability of its direction
+//! being right.
+
+use crate::config::mongodb::MongoDbPool;
+use crate::error::{AgentError, AgentResult};
+use crate::models::market_signal::{MarketSignal, SignalOutcome, SignalType};
+use crate::models::signal_calibration::{IsotonicCalibrator, SignalCalibration};
+use crate::services::recommendation_tracker::OutcomeHorizon;
+use crate::services::token_analytics::TokenAnalyticsService;
+use bigdecimal::ToPrimitive;
+use bson::{doc, DateTime};
+use futures::TryStreamExt;
+use mongodb::Collection;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// How far from the horizon a stored snapshot may be and still be used as the
+/// horizon price
+const OUTCOME_TOLERANCE_SECS: i64 = 30 * 60;
+
+/// Minimum number of measured signals before a type gets its own calibration
+pub const DEFAULT_MIN_SAMPLES: usize = 30;
+
+pub struct SignalTracker {
+    signals: Collection<MarketSignal>,
+    calibrations: Collection<SignalCalibration>,
+    analytics: Arc<TokenAnalyticsService>,
+}
+
+impl SignalTracker {
+    pub fn new(db_pool: Arc<MongoDbPool>, analytics: Arc<TokenAnalyticsService>) -> Self {
+        let db = db_pool.database(&db_pool.get_config().database);
+        Self {
+            signals: db.collection("market_signals"),
+            calibrations: db.collection(SignalCalibration::collection_name()),
+            analytics,
+        }
+    }
+
+    /// Store the forward return of every signal whose horizon has elapsed and
+    /// that has no outcome at that horizon yet. Returns the number of
+    /// outcomes written.
+    pub async fn measure_outcomes(&self, horizon: OutcomeHorizon) -> AgentResult<usize> {
+        let cutoff = DateTime::now().timestamp_millis() - horizon.as_secs() * 1000;
+        let key = format!("outcomes.{}", horizon.label());
+        let filter = doc! {
+            &key: { "$exists": false },
+            "timestamp": { "$lte": DateTime::from_millis(cutoff) },
+        };
+
+        let pending: Vec<MarketSignal> = self
+            .signals
+            .find(filter)
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)?;
+
+        let mut written = 0;
+        for signal in &pending {
+            let Some(id) = signal.id else { continue };
+            let Some(outcome) = self.measure(signal, horizon).await? else {
+                debug!("No price for signal {} at {} horizon", id, horizon);
+                continue;
+            };
+
+            self.signals
+                .update_one(
+                    doc! { "_id": id },
+                    doc! { "$set": { &key: bson::to_bson(&outcome)? } },
+                )
+                .await
+                .map_err(AgentError::Database)?;
+            written += 1;
+        }
+
+        info!(
+            "Measured {} of {} pending signal outcomes at {}",
+            written,
+            pending.len(),
+            horizon
+        );
+        Ok(written)
+    }
+
+    async fn measure(
+        &self,
+        signal: &MarketSignal,
+        horizon: OutcomeHorizon,
+    ) -> AgentResult<Option<SignalOutcome>> {
+        let entry = signal.price.to_f64().unwrap_or_default();
+        if entry <= 0.0 {
+            return Ok(None);
+        }
+
+        let target = signal.timestamp.timestamp_millis() / 1000 + horizon.as_secs();
+        let history = self
+            .analytics
+            .get_token_history(
+                &signal.asset_address,
+                DateTime::from_millis((target - OUTCOME_TOLERANCE_SECS) * 1000),
+                DateTime::from_millis((target + OUTCOME_TOLERANCE_SECS) * 1000),
+            )
+            .await?;
+
+        let price = history
+            .iter()
+            .min_by_key(|h| (h.timestamp.timestamp_millis() / 1000 - target).abs())
+            .and_then(|h| h.price.to_f64());
+
+        Ok(price.map(|price| {
+            let forward_return = price / entry - 1.0;
+            SignalOutcome {
+                evaluated_at: DateTime::now(),
+                price,
+                forward_return,
+                hit: if signal.expects_rise() {
+                    forward_return > 0.0
+                } else {
+                    forward_return < 0.0
+                },
+            }
+        }))
+    }
+
+    /// Fit a calibrator per signal type from the measured outcomes at
+    /// `horizon`, store it, and make the analytics service use it.
+    /// Types with fewer than `min_samples` outcomes are left uncalibrated.
+    pub async fn fit_calibration(
+        &self,
+        horizon: OutcomeHorizon,
+        min_samples: usize,
+    ) -> AgentResult<Vec<SignalCalibration>> {
+        let measured: Vec<MarketSignal> = self
+            .signals
+            .find(doc! { format!("outcomes.{}", horizon.label()): { "$exists": true } })
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)?;
+
+        let mut fitted = Vec::new();
+        for (signal_type, samples) in calibration_samples(&measured, horizon) {
+            if samples.len() < min_samples {
+                debug!(
+                    "Skipping calibration for {:?}: {} of {} samples",
+                    signal_type,
+                    samples.len(),
+                    min_samples
+                );
+                continue;
+            }
+
+            let calibration = SignalCalibration {
+                id: None,
+                signal_type: signal_type.clone(),
+                horizon: horizon.label().to_string(),
+                samples: samples.len(),
+                base_rate: samples.iter().filter(|(_, hit)| *hit).count() as f64
+                    / samples.len() as f64,
+                calibrator: IsotonicCalibrator::fit(&samples),
+                fitted_at: DateTime::now(),
+            };
+
+            self.calibrations
+                .replace_one(
+                    doc! { "signal_type": bson::to_bson(&signal_type)? },
+                    &calibration,
+                )
+                .upsert(true)
+                .await
+                .map_err(AgentError::Database)?;
+            fitted.push(calibration);
+        }
+
+        fitted.sort_by_key(|c| format!("{:?}", c.signal_type));
+        self.analytics.reload_calibration().await?;
+        Ok(fitted)
+    }
+}
+
+/// `(raw confidence, hit)` pairs per signal type at `horizon`. Signals
+/// stored before the raw confidence was recorded use their stored
+/// confidence instead.
+fn calibration_samples(
+    signals: &[MarketSignal],
+    horizon: OutcomeHorizon,
+) -> HashMap<SignalType, Vec<(f64, bool)>> {
+    let mut samples: HashMap<SignalType, Vec<(f64, bool)>> = HashMap::new();
+    for signal in signals {
+        let Some(outcome) = signal
+            .outcomes
+            .as_ref()
+            .and_then(|outcomes| outcomes.get(horizon.label()))
+        else {
+            continue;
+        };
+        let raw = signal
+            .raw_confidence
+            .as_ref()
+            .unwrap_or(&signal.confidence)
+            .to_f64()
+            .unwrap_or_default();
+        samples
+            .entry(signal.signal_type.clone())
+            .or_default()
+            .push((raw, outcome.hit));
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::market_signal::MarketSignalBuilder;
+    use crate::utils::f64_to_decimal;
+    use std::collections::BTreeMap;
+
+    fn signal(
+        signal_type: SignalType,
+        raw: Option<f64>,
+        hit: Option<(&str, bool)>,
+    ) -> MarketSignal {
+        let mut builder = MarketSignalBuilder::new(
+            "addr".to_string(),
+            signal_type,
+            f64_to_decimal(1.0).unwrap(),
+        )
+        .confidence(f64_to_decimal(0.9).unwrap());
+        if let Some(raw) = raw {
+            builder = builder.raw_confidence(f64_to_decimal(raw).unwrap());
+        }
+        let mut signal = builder.build();
+        signal.outcomes = hit.map(|(horizon, hit)| {
+            BTreeMap::from([(
+                horizon.to_string(),
+                SignalOutcome {
+                    evaluated_at: DateTime::now(),
+                    price: 1.0,
+                    forward_return: 0.0,
+                    hit,
+                },
+            )])
+        });
+        signal
+    }
+
+    #[test]
+    fn test_calibration_samples_group_by_type() {
+        let signals = vec![
+            signal(SignalType::PriceSpike, Some(0.5), Some(("24h", true))),
+            signal(SignalType::PriceSpike, None, Some(("24h", false))),
+            signal(SignalType::VolumeSurge, Some(0.6), Some(("24h", true))),
+            signal(SignalType::VolumeSurge, Some(0.7), None),
+            // Measured at another horizon only
+            signal(SignalType::VolumeSurge, Some(0.8), Some(("7d", false))),
+        ];
+
+        let samples = calibration_samples(&signals, OutcomeHorizon::H24);
+        assert_eq!(
+            samples[&SignalType::PriceSpike],
+            vec![(0.5, true), (0.9, false)]
+        );
+        assert_eq!(samples[&SignalType::VolumeSurge], vec![(0.6, true)]);
+    }
+}
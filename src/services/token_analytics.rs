@@ -2,25 +2,46 @@ use crate::birdeye::api::TokenOverviewResponse;
 use crate::birdeye::BirdeyeApi;
 use crate::config::market_config::MarketConfig;
 use crate::config::mongodb::MongoDbPool;
+use crate::config::RetentionPolicy;
 use crate::error::{AgentError, AgentResult};
+use crate::llm::embedding::EmbeddingBackend;
 use crate::logging::market_metrics::MarketSignalLog;
+use crate::logging::metrics::metrics;
 use crate::logging::performance_metrics::PerformanceMetrics;
 use crate::logging::{log_market_metrics, log_market_signal, log_performance, RequestLogger};
 use crate::models::market_signal::{MarketSignal, MarketSignalBuilder, SignalType};
+use crate::models::money::Price;
+use crate::models::signal_calibration::IsotonicCalibrator;
 use crate::models::token_analytics::TokenAnalytics;
-use crate::utils::f64_to_decimal;
+use crate::services::embedding_job::embedding_version;
+use crate::services::retention::history_segments;
+use crate::services::token_similarity::{rank_similar, token_features, SimilarToken};
+use crate::services::wallet_tracker::KolOwnership;
+use crate::signals::{indicators, Composite, SignalCombiner, SignalContext};
+use crate::storage::{
+    AnalyticsQuery, AnalyticsRepository, AnalyticsSort, RecommendationRepository, Resolution,
+    SignalRepository, Storage,
+};
+use crate::utils::{decimal_to_f64, f64_to_decimal};
+use crate::vector_store::DEFAULT_INDEX_NAME;
 use bigdecimal::{BigDecimal, ToPrimitive};
 use bson::{doc, DateTime};
 use chrono::{Duration as ChronoDuration, Utc};
-use futures::StreamExt;
-use mongodb::{
-    options::{FindOneOptions, FindOptions},
-    Collection,
-};
 use serde_json::json;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
 use std::time::Instant;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// How far back snapshots are loaded for the signal detectors
+const SIGNAL_HISTORY_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Snapshots fetched per requested result, to leave room for the metric
+/// filters and for repeated snapshots of the same token
+const SEARCH_OVERSAMPLE: usize = 5;
+
+/// Snapshots loaded when comparing token profiles
+const SIMILARITY_SCAN_LIMIT: usize = 5000;
 
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -32,10 +53,63 @@ pub struct MarketMetrics {
     pub confidence: Option<f64>,
 }
 
+/// Restricts which snapshots an analytics search may return
+#[derive(Debug, Clone, Default)]
+pub struct AnalyticsFilter {
+    /// Only snapshots taken within this window
+    pub window: Option<ChronoDuration>,
+    pub token_address: Option<String>,
+    pub min_liquidity: Option<f64>,
+    pub min_volume_24h: Option<f64>,
+    pub min_market_cap: Option<f64>,
+}
+
+impl AnalyticsFilter {
+    pub fn last_hours(hours: i64) -> Self {
+        Self {
+            window: Some(ChronoDuration::hours(hours)),
+            ..Default::default()
+        }
+    }
+
+    /// Conditions the storage can evaluate. Metrics are stored as decimal
+    /// strings, so they are checked by `matches` instead.
+    pub fn to_query(&self) -> AnalyticsQuery {
+        AnalyticsQuery {
+            token_address: self.token_address.clone(),
+            since: self
+                .window
+                .map(|window| DateTime::from_millis((Utc::now() - window).timestamp_millis())),
+            ..Default::default()
+        }
+    }
+
+    /// Whether a snapshot meets the metric thresholds. Missing metrics fail
+    /// any threshold set on them.
+    pub fn matches(&self, analytics: &TokenAnalytics) -> bool {
+        let at_least = |value: &Option<BigDecimal>, min: Option<f64>| match min {
+            Some(min) => value
+                .as_ref()
+                .and_then(|v| v.to_f64())
+                .is_some_and(|v| v >= min),
+            None => true,
+        };
+        at_least(&analytics.liquidity, self.min_liquidity)
+            && at_least(&analytics.volume_24h, self.min_volume_24h)
+            && at_least(&analytics.market_cap, self.min_market_cap)
+    }
+}
+
 pub struct TokenAnalyticsService {
-    collection: Collection<TokenAnalytics>,
+    analytics: Arc<dyn AnalyticsRepository>,
+    signals: Arc<dyn SignalRepository>,
+    recommendations: Arc<dyn RecommendationRepository>,
+    calibration: RwLock<HashMap<SignalType, IsotonicCalibrator>>,
+    combiner: SignalCombiner,
     birdeye: Arc<dyn BirdeyeApi>,
-    market_config: MarketConfig,
+    market_config: RwLock<MarketConfig>,
+    embeddings: Option<Arc<dyn EmbeddingBackend>>,
+    retention: RetentionPolicy,
 }
 
 impl TokenAnalyticsService {
@@ -44,21 +118,89 @@ impl TokenAnalyticsService {
         birdeye: Arc<dyn BirdeyeApi>,
         market_config: Option<MarketConfig>,
     ) -> AgentResult<Self> {
-        let db = pool.database(&pool.get_config().database);
-        let collection = db.collection("token_analytics");
-        debug!(
-            "Initializing TokenAnalyticsService with collection: {:?}",
-            collection
-        );
+        let storage = Storage::mongo(&pool, DEFAULT_INDEX_NAME);
+        Self::from_storage(&storage, birdeye, market_config).await
+    }
 
-        Ok(Self {
-            collection,
+    /// Create the service over explicit repositories, e.g. `Storage::memory()`
+    pub async fn from_storage(
+        storage: &Storage,
+        birdeye: Arc<dyn BirdeyeApi>,
+        market_config: Option<MarketConfig>,
+    ) -> AgentResult<Self> {
+        let service = Self {
+            analytics: storage.analytics.clone(),
+            signals: storage.signals.clone(),
+            recommendations: storage.recommendations.clone(),
+            calibration: RwLock::new(HashMap::new()),
+            combiner: SignalCombiner::default(),
             birdeye,
-            market_config: market_config.unwrap_or_default(),
-        })
+            market_config: RwLock::new(market_config.unwrap_or_default()),
+            embeddings: None,
+            retention: RetentionPolicy::keep_raw(),
+        };
+
+        // Signals fall back to the clamped raw confidence until a calibration is fitted
+        if let Err(e) = service.reload_calibration().await {
+            warn!("Failed to load signal calibration: {}", e);
+        }
+
+        Ok(service)
+    }
+
+    /// Rank analytics searches by similarity to the query, using the
+    /// embeddings written by the embedding job
+    pub fn with_embeddings(mut self, backend: Arc<dyn EmbeddingBackend>) -> Self {
+        self.embeddings = Some(backend);
+        self
+    }
+
+    /// Thresholds and weights the built-in signals use
+    pub fn market_config(&self) -> MarketConfig {
+        self.market_config.read().unwrap().clone()
+    }
+
+    /// Replace the thresholds and weights for signals generated from now on
+    pub fn set_market_config(&self, market_config: MarketConfig) {
+        *self.market_config.write().unwrap() = market_config;
     }
 
-    fn log_operation_performance(&self, operation: &str, start_time: Instant, success: bool) {
+    /// Read older history from the rollups kept under `policy`
+    pub fn with_retention(mut self, policy: RetentionPolicy) -> Self {
+        self.retention = policy;
+        self
+    }
+
+    /// Load the stored per-signal-type calibration, replacing the one in use.
+    /// Returns the number of signal types with a calibration.
+    pub async fn reload_calibration(&self) -> AgentResult<usize> {
+        let loaded: HashMap<_, _> = self
+            .signals
+            .calibrations()
+            .await?
+            .into_iter()
+            .map(|calibration| (calibration.signal_type, calibration.calibrator))
+            .collect();
+
+        let count = loaded.len();
+        self.set_calibration(loaded);
+        info!("Loaded signal calibration for {} signal types", count);
+        Ok(count)
+    }
+
+    /// Replace the calibration used for new signals
+    pub fn set_calibration(&self, calibration: HashMap<SignalType, IsotonicCalibrator>) {
+        *self.calibration.write().unwrap() = calibration;
+    }
+
+    fn log_operation_performance(
+        &self,
+        step: &str,
+        operation: &str,
+        start_time: Instant,
+        success: bool,
+    ) {
+        metrics().observe_step(step, start_time.elapsed());
         log_performance(PerformanceMetrics {
             operation: operation.to_string(),
             duration_ms: start_time.elapsed().as_millis() as u64,
@@ -67,18 +209,6 @@ impl TokenAnalyticsService {
         });
     }
 
-    async fn collect_cursor_results(
-        &self,
-        cursor: mongodb::Cursor<TokenAnalytics>,
-    ) -> AgentResult<Vec<TokenAnalytics>> {
-        let mut results = Vec::new();
-        let mut cursor = cursor;
-        while let Some(result) = cursor.next().await {
-            results.push(result.map_err(AgentError::Database)?);
-        }
-        Ok(results)
-    }
-
     pub async fn fetch_and_store_token_info(
         &self,
         symbol: &str,
@@ -100,7 +230,7 @@ impl TokenAnalyticsService {
                     e
                 ));
                 logger.error(&err.to_string());
-                self.log_operation_performance(&operation, start_time, false);
+                self.log_operation_performance("fetch_token_info", &operation, start_time, false);
                 return Err(err);
             }
         };
@@ -109,7 +239,7 @@ impl TokenAnalyticsService {
         if overview.price <= 0.0 {
             let err = AgentError::validation("Token price must be positive");
             logger.error(&err.to_string());
-            self.log_operation_performance(&operation, start_time, false);
+            self.log_operation_performance("fetch_token_info", &operation, start_time, false);
             return Err(err);
         }
 
@@ -127,12 +257,9 @@ impl TokenAnalyticsService {
         let analytics = self.convert_to_analytics(address, symbol, overview).await?;
 
         // Store in database
-        self.collection
-            .insert_one(&analytics)
-            .await
-            .map_err(AgentError::Database)?;
+        self.analytics.insert(&analytics).await?;
 
-        self.log_operation_performance(&operation, start_time, true);
+        self.log_operation_performance("fetch_token_info", &operation, start_time, true);
         Ok(analytics)
     }
 
@@ -158,32 +285,25 @@ impl TokenAnalyticsService {
             Err(_) => vec![],
         };
 
-        let (rsi, macd, macd_signal, bollinger_upper, bollinger_lower) =
-            if !price_history.is_empty() {
-                let prices: Vec<f64> = price_history
-                    .iter()
-                    .map(|h| h.price.to_f64().unwrap_or_default())
-                    .collect();
-
-                // Calculate RSI (14 periods)
-                let rsi = self.calculate_rsi(&prices, 14);
-
-                // Calculate MACD (12, 26, 9)
-                let (macd, signal) = self.calculate_macd(&prices, 12, 26, 9);
-
-                // Calculate Bollinger Bands (20 periods, 2 standard deviations)
-                let (upper, lower) = self.calculate_bollinger_bands(&prices, 20, 2.0);
-
-                (
-                    Some(f64_to_decimal(rsi)),
-                    Some(f64_to_decimal(macd)),
-                    Some(f64_to_decimal(signal)),
-                    Some(f64_to_decimal(upper)),
-                    Some(f64_to_decimal(lower)),
-                )
-            } else {
-                (None, None, None, None, None)
-            };
+        let prices: Vec<f64> = price_history
+            .iter()
+            .filter_map(|h| h.price.to_f64())
+            .chain(std::iter::once(overview.price))
+            .collect();
+
+        let rsi = indicators::rsi(&prices, 14)
+            .map(f64_to_decimal)
+            .transpose()?;
+        let macd = indicators::macd(&prices, 12, 26, 9);
+        let bands = indicators::bollinger(&prices, 20, 2.0);
+        let (macd, macd_signal) = (
+            macd.map(|m| f64_to_decimal(m.line)).transpose()?,
+            macd.map(|m| f64_to_decimal(m.signal)).transpose()?,
+        );
+        let (bollinger_upper, bollinger_lower) = (
+            bands.map(|b| f64_to_decimal(b.upper)).transpose()?,
+            bands.map(|b| f64_to_decimal(b.lower)).transpose()?,
+        );
 
         Ok(TokenAnalytics {
             id: None,
@@ -195,30 +315,39 @@ impl TokenAnalyticsService {
             logo_uri: Some(overview.logo_uri),
 
             // Price metrics
-            price: f64_to_decimal(overview.price),
-            price_change_24h: Some(f64_to_decimal(overview.price_change_24h_percent)),
-            price_change_7d: Some(f64_to_decimal(
-                (overview.price - overview.history_24h_price) / overview.history_24h_price * 100.0,
-            )),
+            price: Price::from_f64(overview.price)?.value().clone(),
+            price_change_24h: Some(f64_to_decimal(overview.price_change_24h_percent)?),
+            // Undefined without an earlier price
+            price_change_7d: (overview.history_24h_price > 0.0)
+                .then(|| {
+                    f64_to_decimal(
+                        (overview.price - overview.history_24h_price) / overview.history_24h_price
+                            * 100.0,
+                    )
+                })
+                .transpose()?,
 
             // Volume metrics
-            volume_24h: Some(f64_to_decimal(overview.v24h)),
-            volume_change_24h: Some(f64_to_decimal(overview.v24h_change_percent)),
-            volume_by_price_24h: Some(f64_to_decimal(overview.v24h_usd)),
+            volume_24h: Some(f64_to_decimal(overview.v24h)?),
+            volume_change_24h: Some(f64_to_decimal(overview.v24h_change_percent)?),
+            volume_by_price_24h: Some(f64_to_decimal(overview.v24h_usd)?),
 
             // Market metrics
-            market_cap: Some(f64_to_decimal(overview.real_mc)),
-            fully_diluted_market_cap: Some(f64_to_decimal(overview.fdv)),
-            circulating_supply: Some(f64_to_decimal(overview.circulating_supply)),
-            total_supply: Some(f64_to_decimal(overview.total_supply)),
+            market_cap: Some(f64_to_decimal(overview.real_mc)?),
+            fully_diluted_market_cap: Some(f64_to_decimal(overview.fdv)?),
+            circulating_supply: Some(f64_to_decimal(overview.circulating_supply)?),
+            total_supply: Some(f64_to_decimal(overview.total_supply)?),
 
             // Liquidity metrics
-            liquidity: Some(f64_to_decimal(overview.liquidity)),
-            liquidity_change_24h: Some(f64_to_decimal(overview.v24h_change_percent)),
+            liquidity: Some(f64_to_decimal(overview.liquidity)?),
+            liquidity_change_24h: Some(f64_to_decimal(overview.v24h_change_percent)?),
 
             // Trading metrics
             trades_24h: Some(overview.trade24h),
-            average_trade_size: Some(f64_to_decimal(overview.v24h_usd / overview.trade24h as f64)),
+            // Undefined without trades
+            average_trade_size: (overview.trade24h > 0)
+                .then(|| f64_to_decimal(overview.v24h_usd / overview.trade24h as f64))
+                .transpose()?,
 
             // Holder metrics
             holder_count: Some(overview.holder as i32),
@@ -254,148 +383,46 @@ impl TokenAnalyticsService {
                 "price_change_24h_percent": overview.price_change_24h_percent,
             }),
             embedding: None,
+            embedding_version: None,
         })
     }
 
-    fn calculate_rsi(&self, prices: &[f64], period: usize) -> f64 {
-        if prices.len() < period + 1 {
-            return 50.0; // Default value if not enough data
-        }
-
-        let mut gains = Vec::new();
-        let mut losses = Vec::new();
-
-        for i in 1..prices.len() {
-            let diff = prices[i] - prices[i - 1];
-            if diff >= 0.0 {
-                gains.push(diff);
-                losses.push(0.0);
-            } else {
-                gains.push(0.0);
-                losses.push(-diff);
-            }
-        }
-
-        let avg_gain = gains.iter().take(period).sum::<f64>() / period as f64;
-        let avg_loss = losses.iter().take(period).sum::<f64>() / period as f64;
-
-        if avg_loss == 0.0 {
-            return 100.0;
-        }
-
-        let rs = avg_gain / avg_loss;
-        100.0 - (100.0 / (1.0 + rs))
-    }
-
-    fn calculate_macd(
-        &self,
-        prices: &[f64],
-        fast_period: usize,
-        slow_period: usize,
-        signal_period: usize,
-    ) -> (f64, f64) {
-        if prices.len() < slow_period {
-            return (0.0, 0.0);
-        }
-
-        let fast_ema = self.calculate_ema(prices, fast_period);
-        let slow_ema = self.calculate_ema(prices, slow_period);
-        let macd_line = fast_ema - slow_ema;
-
-        let signal_line = self.calculate_ema(&vec![macd_line], signal_period);
-
-        (macd_line, signal_line)
-    }
-
-    fn calculate_ema(&self, prices: &[f64], period: usize) -> f64 {
-        if prices.is_empty() {
-            return 0.0;
-        }
-
-        let multiplier = 2.0 / (period as f64 + 1.0);
-        let mut ema = prices[0];
-
-        for price in prices.iter().skip(1) {
-            ema = (price - ema) * multiplier + ema;
-        }
-
-        ema
-    }
-
-    fn calculate_bollinger_bands(
-        &self,
-        prices: &[f64],
-        period: usize,
-        num_std_dev: f64,
-    ) -> (f64, f64) {
-        if prices.len() < period {
-            return (prices[prices.len() - 1], prices[prices.len() - 1]);
-        }
-
-        let sma = prices.iter().take(period).sum::<f64>() / period as f64;
-
-        let variance = prices
-            .iter()
-            .take(period)
-            .map(|price| {
-                let diff = price - sma;
-                diff * diff
-            })
-            .sum::<f64>()
-            / period as f64;
-
-        let std_dev = variance.sqrt();
-
-        let upper_band = sma + (std_dev * num_std_dev);
-        let lower_band = sma - (std_dev * num_std_dev);
-
-        (upper_band, lower_band)
-    }
-
     pub async fn get_previous_analytics(
         &self,
         address: &str,
     ) -> AgentResult<Option<TokenAnalytics>> {
-        let filter = doc! {
-            "token_address": address,
-            "timestamp": { "$lt": DateTime::now() }
-        };
-
-        let options = FindOneOptions::builder()
-            .sort(doc! { "timestamp": -1 })
-            .build();
-
-        self.collection
-            .find_one(filter)
-            .with_options(options)
-            .await
-            .map_err(AgentError::Database)
+        let query = AnalyticsQuery::token(address).until(DateTime::now());
+        self.analytics.find_one(&query).await
     }
 
+    /// Snapshots of a token between two times, oldest first. Parts of the
+    /// range past the raw retention are read from hourly or daily rollups.
     pub async fn get_token_history(
         &self,
         address: &str,
         start_time: DateTime,
         end_time: DateTime,
     ) -> AgentResult<Vec<TokenAnalytics>> {
-        let filter = doc! {
-            "token_address": address,
-            "timestamp": {
-                "$gte": start_time,
-                "$lte": end_time
+        let mut history = Vec::new();
+        for (resolution, since, until) in
+            history_segments(&self.retention, DateTime::now(), start_time, end_time)
+        {
+            let query = AnalyticsQuery::token(address)
+                .since(since)
+                .until(until)
+                .sort(AnalyticsSort::Oldest)
+                .resolution(resolution);
+            let mut segment = self.analytics.find(&query).await?;
+            // Snapshots stay raw until the retention job first rolls them up
+            if segment.is_empty() && resolution != Resolution::Raw {
+                segment = self
+                    .analytics
+                    .find(&query.resolution(Resolution::Raw))
+                    .await?;
             }
-        };
-
-        let options = FindOptions::builder().sort(doc! { "timestamp": 1 }).build();
-
-        let cursor = self
-            .collection
-            .find(filter)
-            .with_options(options)
-            .await
-            .map_err(AgentError::Database)?;
-
-        self.collect_cursor_results(cursor).await
+            history.extend(segment);
+        }
+        Ok(history)
     }
 
     // Helper method for retrying API calls
@@ -426,62 +453,105 @@ impl TokenAnalyticsService {
         Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Unknown error during retry")))
     }
 
+    /// Score a fresh snapshot with the multi-factor signal engine and store
+    /// the resulting signal, if any.
+    ///
+    /// A Buy/Sell/StrongBuy/StrongSell signal is emitted when the composite
+    /// detector score is strong enough. Otherwise the snapshot-over-snapshot
+    /// price and volume rules produce a PriceSpike, PriceDrop or VolumeSurge.
     pub async fn generate_market_signals(
         &self,
         analytics: &TokenAnalytics,
     ) -> AgentResult<Option<MarketSignal>> {
         let logger = RequestLogger::new("token_analytics", "generate_market_signals");
 
-        // Get previous analytics for comparison
-        let previous = match self.get_previous_analytics(&analytics.token_address).await {
-            Ok(prev) => prev,
+        let window_start = analytics.timestamp.timestamp_millis() - SIGNAL_HISTORY_SECS * 1000;
+        let history: Vec<TokenAnalytics> = match self
+            .get_token_history(
+                &analytics.token_address,
+                DateTime::from_millis(window_start),
+                analytics.timestamp,
+            )
+            .await
+        {
+            Ok(history) => history
+                .into_iter()
+                .filter(|h| h.timestamp < analytics.timestamp)
+                .collect(),
             Err(e) => {
                 logger.error(&e.to_string());
                 return Err(e);
             }
         };
+        let kol_positions = self.kol_positions(&analytics.token_address).await?;
+
+        let ctx = SignalContext {
+            current: analytics,
+            history: &history,
+            kol_positions: &kol_positions,
+            now: Utc::now(),
+        };
+        let composite = self.combiner.evaluate(&ctx);
+
+        let zero = BigDecimal::from(0);
+        let price_change = ctx
+            .previous()
+            .filter(|prev| prev.price > zero)
+            .map(|prev| (analytics.price.clone() - prev.price.clone()) / prev.price.clone());
+        let volume_change = ctx.previous().and_then(|prev| {
+            let prev_volume = prev.volume_24h.as_ref().filter(|v| **v > zero)?;
+            let current = analytics.volume_24h.as_ref()?;
+            Some((current.clone() - prev_volume.clone()) / prev_volume.clone())
+        });
 
-        if let Some(prev) = previous {
-            let price_change = (analytics.price.clone() - prev.price.clone()) / prev.price.clone();
-            let volume_change = analytics.volume_24h.as_ref().map(|current| {
-                let binding = BigDecimal::from(0);
-                let prev_volume = prev.volume_24h.as_ref().unwrap_or(&binding);
-                (current.clone() - prev_volume.clone()) / prev_volume.clone()
-            });
-
-            let mut signal_opt = None;
-
-            if price_change.abs() > self.market_config.price_change_threshold {
-                info!(
-                    "Price spike detected: change={:.2}%, volume_change={:?}",
-                    price_change.abs(),
-                    volume_change.clone(),
-                );
-                let signal = self.create_market_signal(
-                    analytics,
-                    SignalType::PriceSpike,
-                    price_change,
-                    volume_change.clone(),
-                );
-                self.log_signal(&signal, analytics);
-                signal_opt = Some(signal);
-            } else if let Some(vol_change) = volume_change {
-                if vol_change > self.market_config.volume_surge_threshold {
-                    let signal = self.create_market_signal(
-                        analytics,
-                        SignalType::VolumeSurge,
-                        price_change,
-                        Some(vol_change),
+        let market_config = self.market_config();
+        let signal_type = self.combiner.signal_type(&composite).or_else(|| {
+            match (&price_change, &volume_change) {
+                (Some(change), _) if change.abs() > market_config.price_change_threshold => {
+                    info!(
+                        "Price move detected: change={:.2}%, volume_change={:?}",
+                        change, volume_change,
                     );
-                    self.log_signal(&signal, analytics);
-                    signal_opt = Some(signal);
+                    Some(if *change > zero {
+                        SignalType::PriceSpike
+                    } else {
+                        SignalType::PriceDrop
+                    })
+                }
+                (_, Some(change)) if *change > market_config.volume_surge_threshold => {
+                    Some(SignalType::VolumeSurge)
                 }
+                _ => None,
             }
+        });
 
-            Ok(signal_opt)
-        } else {
-            Ok(None)
-        }
+        let Some(signal_type) = signal_type else {
+            debug!(
+                "No signal for {} (composite {:.3} from {} factors)",
+                analytics.token_symbol,
+                composite.score,
+                composite.factors.len()
+            );
+            return Ok(None);
+        };
+
+        let signal = self.create_market_signal(
+            analytics,
+            signal_type,
+            price_change.unwrap_or_else(|| zero.clone()),
+            volume_change,
+            &composite,
+        )?;
+        self.log_signal(&signal, analytics);
+        Ok(Some(self.store_signal(signal).await?))
+    }
+
+    /// KOL positions recorded on the token's recommendation, if any
+    pub async fn kol_positions(&self, address: &str) -> AgentResult<Vec<KolOwnership>> {
+        let recommendation = self.recommendations.get(address).await?;
+        Ok(recommendation
+            .and_then(|r| r.kol_ownership)
+            .unwrap_or_default())
     }
 
     fn create_market_signal(
@@ -490,29 +560,40 @@ impl TokenAnalyticsService {
         signal_type: SignalType,
         price_change: BigDecimal,
         volume_change: Option<BigDecimal>,
-    ) -> MarketSignal {
+        composite: &Composite,
+    ) -> AgentResult<MarketSignal> {
         let vol_change = volume_change.unwrap_or_else(|| BigDecimal::from(0));
-        let confidence = self.calculate_confidence(price_change.clone(), vol_change.clone());
+        let raw_confidence = match signal_type {
+            // Composite signals are as confident as the detectors agree
+            SignalType::Buy | SignalType::StrongBuy | SignalType::Sell | SignalType::StrongSell => {
+                f64_to_decimal(composite.score.abs())?
+            }
+            _ => self.calculate_confidence(price_change.clone(), vol_change.clone()),
+        };
+        let confidence = self.calibrate(&signal_type, &raw_confidence)?;
 
         let metadata = json!({
             "token_symbol": analytics.token_symbol.clone(),
-            "token_name": analytics.token_name.clone()
+            "token_name": analytics.token_name.clone(),
+            "composite_score": composite.score,
+            "factors": composite.factors,
         });
 
-        MarketSignalBuilder::new(
+        Ok(MarketSignalBuilder::new(
             analytics.token_address.clone(),
             signal_type,
             analytics.price.clone(),
         )
         .confidence(confidence)
-        .risk_score(f64_to_decimal(0.5))
-        .sentiment_score(f64_to_decimal(0.5))
+        .raw_confidence(raw_confidence)
+        .risk_score(f64_to_decimal(composite.risk)?)
+        .sentiment_score(f64_to_decimal(composite.sentiment)?)
         .price_change_24h(price_change)
         .volume_change_24h(vol_change.clone())
         .volume_change(vol_change)
         .timestamp(analytics.timestamp)
         .metadata(metadata)
-        .build()
+        .build())
     }
 
     fn log_signal(&self, signal: &MarketSignal, analytics: &TokenAnalytics) {
@@ -521,111 +602,371 @@ impl TokenAnalyticsService {
         log_market_signal(&signal_log);
     }
 
+    /// Persist a generated signal to `market_signals` so its outcome can be measured
+    pub async fn store_signal(&self, mut signal: MarketSignal) -> AgentResult<MarketSignal> {
+        signal.id = self.signals.insert(&signal).await?;
+        metrics().record_signal(&signal.signal_type.to_string());
+        Ok(signal)
+    }
+
+    /// Raw signal score: the configured linear blend, unclamped so the
+    /// calibration can tell strong moves apart
     fn calculate_confidence(
         &self,
         price_change: BigDecimal,
         volume_change: BigDecimal,
     ) -> BigDecimal {
-        self.market_config.base_confidence.clone()
-            + (price_change * self.market_config.price_weight.clone())
-            + (volume_change * self.market_config.volume_weight.clone())
+        let market_config = self.market_config();
+        market_config.base_confidence
+            + (price_change * market_config.price_weight)
+            + (volume_change * market_config.volume_weight)
+    }
+
+    /// Map a raw score to the probability that the signal's direction is
+    /// right, using the fitted calibration for its type when there is one.
+    /// Without one the raw score is clamped to [0, 1].
+    fn calibrate(
+        &self,
+        signal_type: &SignalType,
+        raw_confidence: &BigDecimal,
+    ) -> AgentResult<BigDecimal> {
+        match self.calibration.read().unwrap().get(signal_type) {
+            Some(calibrator) => f64_to_decimal(calibrator.predict(decimal_to_f64(raw_confidence)?)),
+            None => Ok(raw_confidence
+                .clone()
+                .max(BigDecimal::from(0))
+                .min(BigDecimal::from(1))),
+        }
     }
 
     /// Get analytics data relevant to a specific query.
     /// This method is used by the LLM service for semantic analysis and by the CLI for showing recent trading activity.
-    /// 
+    /// Without embeddings, the most recent snapshots of the last 24 hours are returned.
+    ///
     /// # Arguments
     /// * `query` - The search query to find relevant analytics
     pub async fn get_relevant_analytics(&self, query: &str) -> AgentResult<Vec<TokenAnalytics>> {
-        debug!("Finding relevant analytics for query: {}", query);
+        self.search_analytics(query, &AnalyticsFilter::last_hours(24), 10)
+            .await
+    }
 
-        let twenty_four_hours_ago = Utc::now() - ChronoDuration::hours(24);
-        let filter = doc! {
-            "timestamp": {
-                "$gte": DateTime::from_millis(twenty_four_hours_ago.timestamp_millis())
+    /// Find the snapshots most similar to `query` that pass `filter`, one
+    /// per token. Falls back to the most recent snapshots when no embedding
+    /// backend is configured, nothing is embedded yet or the search fails.
+    ///
+    /// # Arguments
+    /// * `query` - Free text describing what to look for
+    /// * `filter` - Time window, token and metric restrictions
+    /// * `limit` - Maximum number of tokens to return
+    pub async fn search_analytics(
+        &self,
+        query: &str,
+        filter: &AnalyticsFilter,
+        limit: usize,
+    ) -> AgentResult<Vec<TokenAnalytics>> {
+        debug!("Finding relevant analytics for query: {}", query);
+        let limit = limit.max(1);
+
+        if let Some(backend) = &self.embeddings {
+            match self
+                .nearest_analytics(backend.as_ref(), query, filter, limit)
+                .await
+            {
+                Ok(ranked) if !ranked.is_empty() => return Ok(ranked),
+                Ok(_) => debug!("No embedded snapshots match, using recent snapshots"),
+                Err(e) => warn!(
+                    "Semantic analytics search failed, using recent snapshots: {}",
+                    e
+                ),
             }
-        };
+        }
 
-        let options = FindOptions::builder()
-            .sort(doc! { "timestamp": -1 })
-            .limit(10)
-            .build();
+        let query = filter.to_query().limit(limit * SEARCH_OVERSAMPLE);
+        let recent = self.analytics.find(&query).await?;
+        Ok(distinct_tokens(recent.into_iter(), filter, limit))
+    }
 
-        let cursor = self
-            .collection
-            .find(filter)
-            .with_options(options)
+    async fn nearest_analytics(
+        &self,
+        backend: &dyn EmbeddingBackend,
+        query: &str,
+        filter: &AnalyticsFilter,
+        limit: usize,
+    ) -> AgentResult<Vec<TokenAnalytics>> {
+        let vector = backend
+            .embed(&[query.to_string()])
             .await
-            .map_err(AgentError::Database)?;
+            .map_err(AgentError::Other)?
+            .pop()
+            .ok_or_else(|| AgentError::VectorStore("No embedding returned for the query".into()))?;
+        let conditions = AnalyticsQuery {
+            embedding_version: Some(embedding_version(backend.model())),
+            ..filter.to_query()
+        };
+        let results = self
+            .analytics
+            .nearest(&vector, &conditions, limit * SEARCH_OVERSAMPLE)
+            .await?;
+        let ranked = results.into_iter().map(|(_, analytics)| analytics);
+        Ok(distinct_tokens(ranked, filter, limit))
+    }
+
+    /// Find the tokens whose latest profile is closest to the latest profile
+    /// of `address`, nearest first.
+    ///
+    /// # Arguments
+    /// * `address` - Reference token, e.g. one that did well
+    /// * `k` - Maximum number of neighbours to return
+    /// * `filter` - Window the candidate snapshots must fall in (also the
+    ///   span holder growth is measured over, 24 hours by default) and metric
+    ///   thresholds they must pass. The token restriction is ignored.
+    pub async fn find_similar_tokens(
+        &self,
+        address: &str,
+        k: usize,
+        filter: &AnalyticsFilter,
+    ) -> AgentResult<Vec<SimilarToken>> {
+        let window = filter.window.unwrap_or_else(|| ChronoDuration::hours(24));
+        let reference = self
+            .analytics
+            .find_one(&AnalyticsQuery::token(address))
+            .await?
+            .ok_or_else(|| {
+                AgentError::InvalidInput(format!("No analytics stored for token {}", address))
+            })?;
+        let reference_start = reference.timestamp.timestamp_millis() - window.num_milliseconds();
+        let reference_history = self
+            .get_token_history(
+                address,
+                DateTime::from_millis(reference_start),
+                reference.timestamp,
+            )
+            .await?;
+        let reference_features = token_features(&reference, reference_history.first());
 
-        self.collect_cursor_results(cursor).await
+        let candidates_filter = AnalyticsFilter {
+            window: Some(window),
+            token_address: None,
+            ..filter.clone()
+        };
+        let conditions = AnalyticsQuery {
+            exclude_token: Some(address.to_string()),
+            ..candidates_filter.to_query()
+        }
+        .limit(SIMILARITY_SCAN_LIMIT);
+        let snapshots = self.analytics.find(&conditions).await?;
+
+        // Newest and oldest snapshot of each token in the window
+        let mut tokens: Vec<(TokenAnalytics, Option<TokenAnalytics>)> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for snapshot in snapshots {
+            match positions.get(&snapshot.token_address) {
+                Some(&i) => tokens[i].1 = Some(snapshot),
+                None => {
+                    positions.insert(snapshot.token_address.clone(), tokens.len());
+                    tokens.push((snapshot, None));
+                }
+            }
+        }
+
+        let candidates = tokens
+            .into_iter()
+            .filter(|(latest, _)| candidates_filter.matches(latest))
+            .map(|(latest, oldest)| {
+                let features = token_features(&latest, oldest.as_ref());
+                (latest, features)
+            })
+            .collect();
+
+        let similar = rank_similar(&reference_features, candidates, k);
+        debug!("Found {} tokens similar to {}", similar.len(), address);
+        Ok(similar)
     }
 
     /// Get the top trending tokens based on volume and price changes.
     /// Used by both the LLM service for market insights and the CLI for monitoring.
-    /// 
+    ///
     /// # Arguments
     /// * `limit` - Maximum number of trending tokens to return
     pub async fn get_trending_tokens(&self, limit: i64) -> AgentResult<Vec<TokenAnalytics>> {
         debug!("Getting top {} trending tokens", limit);
 
-        let twenty_four_hours_ago = Utc::now() - ChronoDuration::hours(24);
-        let filter = doc! {
-            "timestamp": {
-                "$gte": DateTime::from_millis(twenty_four_hours_ago.timestamp_millis())
-            }
-        };
-
-        let options = FindOptions::builder()
-            .sort(doc! {
-                "volume_24h": -1,
-                "price_change_24h": -1
-            })
-            .limit(limit)
-            .build();
-
-        let cursor = self
-            .collection
-            .find(filter)
-            .with_options(options)
-            .await
-            .map_err(AgentError::Database)?;
-
-        self.collect_cursor_results(cursor).await
+        let query = AnalyticsFilter::last_hours(24)
+            .to_query()
+            .sort(AnalyticsSort::Trending)
+            .limit(limit.max(0) as usize);
+        self.analytics.find(&query).await
     }
 
     /// Get the most recent analytics data for a specific token.
     /// Used for token comparison in the LLM service and for monitoring in the CLI.
-    /// 
+    ///
     /// # Arguments
     /// * `address` - The token's address
     pub async fn get_token_analytics(&self, address: &str) -> AgentResult<Option<TokenAnalytics>> {
         debug!("Getting analytics for token: {}", address);
 
-        let twenty_four_hours_ago = Utc::now() - ChronoDuration::hours(24);
-        let filter = doc! {
-            "token_address": address,
-            "timestamp": {
-                "$gte": DateTime::from_millis(twenty_four_hours_ago.timestamp_millis())
-            }
+        let query = AnalyticsFilter {
+            token_address: Some(address.to_string()),
+            ..AnalyticsFilter::last_hours(24)
+        }
+        .to_query();
+        self.analytics.find_one(&query).await
+    }
+
+    /// Get the most recent snapshot of a token by symbol, ignoring case.
+    /// Used by the analyst chat to resolve tokens mentioned by name.
+    ///
+    /// # Arguments
+    /// * `symbol` - The token's symbol, e.g. "BONK"
+    pub async fn find_by_symbol(&self, symbol: &str) -> AgentResult<Option<TokenAnalytics>> {
+        if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Ok(None);
+        }
+        debug!("Looking up token by symbol: {}", symbol);
+
+        let query = AnalyticsQuery {
+            token_symbol: Some(symbol.to_string()),
+            ..Default::default()
         };
+        self.analytics.find_one(&query).await
+    }
+}
 
-        let options = FindOptions::builder()
-            .sort(doc! { "timestamp": -1 })
-            .limit(1)
-            .build();
+/// The first `limit` snapshots passing `filter`, keeping one per token
+fn distinct_tokens(
+    snapshots: impl Iterator<Item = TokenAnalytics>,
+    filter: &AnalyticsFilter,
+    limit: usize,
+) -> Vec<TokenAnalytics> {
+    let mut seen = HashSet::new();
+    snapshots
+        .filter(|a| filter.matches(a))
+        .filter(|a| seen.insert(a.token_address.clone()))
+        .take(limit)
+        .collect()
+}
 
-        let mut cursor = self
-            .collection
-            .find(filter)
-            .with_options(options)
-            .await
-            .map_err(AgentError::Database)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::birdeye::api::MockBirdeyeApi;
+    use std::str::FromStr;
+
+    fn snapshot(address: &str, liquidity: Option<&str>) -> TokenAnalytics {
+        TokenAnalytics {
+            token_address: address.to_string(),
+            token_name: address.to_string(),
+            token_symbol: address.to_uppercase(),
+            liquidity: liquidity.map(|l| BigDecimal::from_str(l).unwrap()),
+            timestamp: DateTime::now(),
+            ..TokenAnalytics::test_snapshot(0, 1.0)
+        }
+    }
+
+    #[test]
+    fn test_filter_query() {
+        let query = AnalyticsFilter::default().to_query();
+        assert!(query.token_address.is_none() && query.since.is_none());
+
+        let filter = AnalyticsFilter {
+            token_address: Some("mint".to_string()),
+            min_liquidity: Some(1000.0),
+            ..AnalyticsFilter::last_hours(24)
+        };
+        let query = filter.to_query();
+        assert_eq!(query.token_address.as_deref(), Some("mint"));
+        let since = query.since.unwrap().timestamp_millis();
+        let expected = (Utc::now() - ChronoDuration::hours(24)).timestamp_millis();
+        assert!((since - expected).abs() < 60_000);
+    }
 
-        if let Some(result) = cursor.next().await {
-            Ok(Some(result.map_err(AgentError::Database)?))
-        } else {
-            Ok(None)
+    #[tokio::test]
+    async fn test_relevant_analytics_without_embeddings() {
+        let storage = Storage::memory();
+        let service =
+            TokenAnalyticsService::from_storage(&storage, Arc::new(MockBirdeyeApi::new()), None)
+                .await
+                .unwrap();
+
+        let mut stale = snapshot("old", Some("5000"));
+        stale.timestamp =
+            DateTime::from_millis(DateTime::now().timestamp_millis() - 48 * 3_600_000);
+        for analytics in [
+            stale,
+            snapshot("a", Some("5000")),
+            snapshot("b", Some("10")),
+            snapshot("a", Some("6000")),
+        ] {
+            storage.analytics.insert(&analytics).await.unwrap();
         }
+
+        let relevant = service.get_relevant_analytics("anything").await.unwrap();
+        let addresses: Vec<_> = relevant.iter().map(|a| a.token_address.as_str()).collect();
+        assert_eq!(addresses, vec!["a", "b"]);
+        assert_eq!(relevant[0].liquidity, Some(BigDecimal::from(6000)));
+
+        let liquid = AnalyticsFilter {
+            min_liquidity: Some(1000.0),
+            ..AnalyticsFilter::last_hours(72)
+        };
+        let found = service.search_analytics("", &liquid, 10).await.unwrap();
+        let addresses: Vec<_> = found.iter().map(|a| a.token_address.as_str()).collect();
+        assert_eq!(addresses, vec!["a", "old"]);
+    }
+
+    #[tokio::test]
+    async fn test_raw_confidence_is_only_clamped_when_uncalibrated() {
+        let storage = Storage::memory();
+        let service =
+            TokenAnalyticsService::from_storage(&storage, Arc::new(MockBirdeyeApi::new()), None)
+                .await
+                .unwrap();
+        let strong = service.calculate_confidence(BigDecimal::from(20), BigDecimal::from(0));
+        let stronger = service.calculate_confidence(BigDecimal::from(40), BigDecimal::from(0));
+        assert!(strong > BigDecimal::from(1) && stronger > strong);
+        assert_eq!(
+            service
+                .calibrate(&SignalType::PriceSpike, &stronger)
+                .unwrap(),
+            BigDecimal::from(1)
+        );
+
+        // A calibration fitted on the raw scores still tells them apart
+        let samples = [
+            (decimal_to_f64(&strong).unwrap(), false),
+            (decimal_to_f64(&stronger).unwrap(), true),
+        ];
+        service.set_calibration(HashMap::from([(
+            SignalType::PriceSpike,
+            IsotonicCalibrator::fit(&samples),
+        )]));
+        assert!(
+            service.calibrate(&SignalType::PriceSpike, &strong).unwrap()
+                < service
+                    .calibrate(&SignalType::PriceSpike, &stronger)
+                    .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_distinct_tokens_applies_metric_filter() {
+        let filter = AnalyticsFilter {
+            min_liquidity: Some(1000.0),
+            ..Default::default()
+        };
+        let snapshots = vec![
+            snapshot("a", Some("5000")),
+            snapshot("b", Some("10")),
+            snapshot("a", Some("4000")),
+            snapshot("c", None),
+            snapshot("d", Some("2000")),
+            snapshot("e", Some("3000")),
+        ];
+
+        let kept = distinct_tokens(snapshots.into_iter(), &filter, 2);
+        let addresses: Vec<_> = kept.iter().map(|a| a.token_address.as_str()).collect();
+        assert_eq!(addresses, vec!["a", "d"]);
+        assert_eq!(kept[0].liquidity, Some(BigDecimal::from(5000)));
     }
 }
@@ -1,16 +1,18 @@
 use crate::birdeye::api::TokenV3Response;
 use crate::error::{AgentError, AgentResult};
 use crate::models::token_analytics::TokenAnalytics;
+use crate::services::analyst_chat::AnalystChat;
 use crate::services::token_analytics::TokenAnalyticsService;
+use crate::tools::{prompt_with_tools, AnalystTools, MAX_TOOL_TURNS};
 use anyhow::Result;
 use rig::{
     agent::Agent as RigAgent,
     completion::Prompt,
     providers::openai::{Client as OpenAIClient, CompletionModel},
 };
+use serde_json;
 use std::sync::Arc;
 use tracing::{debug, error};
-use serde_json;
 
 const INITIAL_ANALYSIS_PROMPT: &str = include_str!("../prompts/token_filter_initial.txt");
 const METADATA_ANALYSIS_PROMPT: &str = include_str!("../prompts/token_filter_market.txt");
@@ -20,6 +22,8 @@ pub struct TokenAnalyticsLLM {
     analytics_service: Arc<TokenAnalyticsService>,
     openai_client: OpenAIClient,
     agent: RigAgent<CompletionModel>,
+    /// Tools the agent may call
+    tools: AnalystTools,
 }
 
 impl TokenAnalyticsLLM {
@@ -34,36 +38,80 @@ impl TokenAnalyticsLLM {
             analytics_service,
             openai_client,
             agent,
+            tools: AnalystTools::new(),
         }
     }
 
+    /// Let the agent look data up through tools instead of relying only on
+    /// pre-fetched analytics
+    pub fn with_tools(mut self, tools: AnalystTools) -> Self {
+        let builder = self
+            .openai_client
+            .agent(crate::config::get_openai_model())
+            .preamble(include_str!("../prompts/analytics_system.txt"));
+        self.agent = tools.register(builder).build();
+        debug!("Analytics agent tools: {:?}", tools.names());
+        self.tools = tools;
+        self
+    }
+
+    /// Multi-turn chat with the same tools as this agent
+    pub fn chat(&self) -> AnalystChat<CompletionModel> {
+        let model = crate::config::get_openai_model();
+        let agent = self.tools.register(
+            self.openai_client
+                .agent(model)
+                .preamble(include_str!("../prompts/analytics_system.txt"))
+                .append_preamble(include_str!("../prompts/analyst_chat.txt")),
+        );
+        let summarizer = self
+            .openai_client
+            .agent(model)
+            .preamble(include_str!("../prompts/chat_summary.txt"));
+        AnalystChat::new(agent.build(), summarizer.build())
+            .with_analytics(self.analytics_service.clone())
+    }
+
+    /// Prompt the agent, running any tools it calls
+    async fn ask(&self, prompt: String) -> Result<String> {
+        prompt_with_tools(&self.agent, prompt, vec![], MAX_TOOL_TURNS).await
+    }
+
     pub async fn analyze_query(&self, query: &str) -> AgentResult<String> {
         debug!("Processing analytics query: {}", query);
 
-        // Get relevant token analytics based on the query
-        let analytics = match self.analytics_service.get_relevant_analytics(query).await {
-            Ok(data) => data,
-            Err(e) => {
-                error!("Failed to get relevant analytics: {}", e);
-                return Err(AgentError::MarketAnalysis(format!(
-                    "Failed to get analytics: {}",
-                    e
-                )));
-            }
+        let tools = self.tools.names();
+        let prompt = if tools.is_empty() {
+            // Get relevant token analytics based on the query
+            let analytics = match self.analytics_service.get_relevant_analytics(query).await {
+                Ok(data) => data,
+                Err(e) => {
+                    error!("Failed to get relevant analytics: {}", e);
+                    return Err(AgentError::MarketAnalysis(format!(
+                        "Failed to get analytics: {}",
+                        e
+                    )));
+                }
+            };
+
+            // Format analytics data for LLM
+            let formatted_data = self.format_analytics_data(&analytics)?;
+
+            format!(
+                "Based on the following token analytics data, please answer this question: {}\n\nData:\n{}",
+                query,
+                formatted_data
+            )
+        } else {
+            format!(
+                "Please answer this question, using the available tools ({}) to look up the data you need: {}",
+                tools.join(", "),
+                query
+            )
         };
 
-        // Format analytics data for LLM
-        let formatted_data = self.format_analytics_data(&analytics)?;
-
-        // Create prompt for analysis
-        let prompt = format!(
-            "Based on the following token analytics data, please answer this question: {}\n\nData:\n{}",
-            query,
-            formatted_data
-        );
-
         // Get LLM analysis
-        match self.agent.prompt(prompt).await {
+        match self.ask(prompt).await {
             Ok(analysis) => Ok(analysis),
             Err(e) => {
                 error!("Failed to get LLM analysis: {}", e);
@@ -91,7 +139,7 @@ impl TokenAnalyticsLLM {
         );
 
         // Get LLM analysis
-        let insights = self.agent.prompt(prompt).await.map_err(|e| {
+        let insights = self.ask(prompt).await.map_err(|e| {
             AgentError::MarketAnalysis(format!("Failed to get market insights: {}", e))
         })?;
 
@@ -119,10 +167,10 @@ impl TokenAnalyticsLLM {
         );
 
         // Get LLM analysis
-        let comparison =
-            self.agent.prompt(prompt).await.map_err(|e| {
-                AgentError::MarketAnalysis(format!("Failed to compare tokens: {}", e))
-            })?;
+        let comparison = self
+            .ask(prompt)
+            .await
+            .map_err(|e| AgentError::MarketAnalysis(format!("Failed to compare tokens: {}", e)))?;
 
         Ok(comparison)
     }
@@ -182,7 +230,11 @@ impl TokenAnalyticsLLM {
         }
     }
 
-    pub async fn analyze_token_metadata(&self, token: &TokenAnalytics, metadata: &TokenV3Response) -> Result<TokenAnalytics> {
+    pub async fn analyze_token_metadata(
+        &self,
+        token: &TokenAnalytics,
+        metadata: &TokenV3Response,
+    ) -> Result<TokenAnalytics> {
         let prompt = format!(
             "{}\n\nToken Analysis: {}\nMetadata: {}",
             METADATA_ANALYSIS_PROMPT,
@@ -202,7 +254,11 @@ impl TokenAnalyticsLLM {
         })
     }
 
-    pub async fn analyze_token_sentiment(&self, token: &TokenAnalytics, sentiment_data: &str) -> Result<TokenAnalytics> {
+    pub async fn analyze_token_sentiment(
+        &self,
+        token: &TokenAnalytics,
+        sentiment_data: &str,
+    ) -> Result<TokenAnalytics> {
         let prompt = format!(
             "{}\n\nToken Analysis: {}\nSentiment Data: {}",
             SENTIMENT_ANALYSIS_PROMPT,
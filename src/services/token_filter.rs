@@ -1,22 +1,24 @@
 use crate::birdeye::api::{BirdeyeApi, TokenV3Response};
-use crate::config::mongodb::{MongoDbPool, TokenAnalyticsDataExt};
+use crate::config::mongodb::MongoDbPool;
+use crate::llm::{LlmBackend, OpenAIBackend};
+use crate::logging::metrics::timed_step;
 use anyhow::{Context, Result};
-use mongodb::bson::{self, doc, Document};
-use rig::{
-    completion::{CompletionModel, CompletionRequest},
-    message::{Message, UserContent},
-    one_or_many::OneOrMany,
-    providers::openai::{self, Client as OpenAIClient},
-};
+use mongodb::bson::doc;
+use rig::providers::openai;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::{debug, error, info};
-use serde_json::json;
 use crate::services::wallet_tracker::{WalletTrackerService, TokenRecommendation, DecisionReasoning};
+use crate::storage::{RecommendationRepository, Storage};
+use crate::vector_store::DEFAULT_INDEX_NAME;
 use chrono::Utc;
 
 const INITIAL_FILTER_PROMPT: &str = include_str!("../prompts/token_filter_initial.txt");
-const MODEL: &str = openai::O3_MINI;  // Using O3_MINI which is now available in RIG
+pub const MODEL: &str = openai::O3_MINI;  // Using O3_MINI which is now available in RIG
+
+/// Version of the market/metadata analysis prompts. Bump whenever the prompt
+/// text changes so stored results and eval reports can be told apart.
+pub const PROMPT_VERSION: &str = "v1";
 
 const PIPELINE_STEP_1: &str = "PIPELINE STEP 1: BirdEye Filter Selection";
 const PIPELINE_STEP_2: &str = "PIPELINE STEP 2: Token List Retrieval";
@@ -93,10 +95,21 @@ pub struct FilterResponse {
     pub summary: FilterSummary,
 }
 
+/// Output of the two LLM analysis stages for one batch of tokens
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PipelineAnalysis {
+    /// Result of the market metrics stage (step 3)
+    pub market: FilterResponse,
+    /// Result of the metadata stage (step 4), i.e. the final selection
+    pub metadata: FilterResponse,
+    /// Tokens that were sent to the analysis stages
+    pub tokens: Vec<TokenV3Response>,
+}
+
 pub struct TokenFilterService {
     birdeye: Arc<dyn BirdeyeApi>,
-    db_pool: Arc<MongoDbPool>,
-    openai_client: OpenAIClient,
+    recommendations: Arc<dyn RecommendationRepository>,
+    llm: Arc<dyn LlmBackend>,
     wallet_tracker: Option<WalletTrackerService>,
 }
 
@@ -106,74 +119,54 @@ impl TokenFilterService {
         db_pool: Arc<MongoDbPool>,
         openai_api_key: &str,
     ) -> Self {
-        let openai_client = OpenAIClient::new(openai_api_key);
-        let wallet_tracker = Some(WalletTrackerService::new(db_pool.clone()));
+        let llm = Arc::new(OpenAIBackend::new(openai_api_key, MODEL));
+        let storage = Storage::mongo(&db_pool, DEFAULT_INDEX_NAME);
+        Self::with_backend(birdeye, &storage, llm)
+    }
+
+    /// Create the service with explicit repositories and completion backend
+    pub fn with_backend(
+        birdeye: Arc<dyn BirdeyeApi>,
+        storage: &Storage,
+        llm: Arc<dyn LlmBackend>,
+    ) -> Self {
+        let wallet_tracker = Some(WalletTrackerService::from_storage(storage));
 
         Self {
             birdeye,
-            db_pool,
-            openai_client,
+            recommendations: storage.recommendations.clone(),
+            llm,
             wallet_tracker,
         }
     }
 
-    async fn get_completion(&self, prompt: &str) -> Result<String> {
-        debug!("Using model: {}", MODEL);
-        
-        let request = CompletionRequest {
-            prompt: Message::User {
-                content: OneOrMany::one(UserContent::text(prompt.to_string())),
-            },
-            chat_history: vec![],
-            preamble: None,
-            tools: vec![],
-            temperature: None,
-            additional_params: None,
-            documents: vec![],
-            max_tokens: None,
-        };
-        
-        let completion = match self.openai_client
-            .completion_model(MODEL)
-            .completion(request)
-            .await {
-                Ok(c) => {
-                    debug!("Got successful completion response");
-                    c
-                },
-                Err(e) => {
-                    error!("Completion request failed: {:?}", e);
-                    return Err(anyhow::anyhow!("Failed to get completion: {}", e));
-                }
-            };
-
-        let result = completion.choice
-            .into_iter()
-            .find_map(|content| match content {
-                rig::message::AssistantContent::Text(text) => Some(text.text),
-                _other => None
-            })
-            .ok_or_else(|| anyhow::anyhow!("No text response from completion"))?;
+    /// Model used by the configured completion backend
+    pub fn model(&self) -> &str {
+        self.llm.model()
+    }
 
-        Ok(result)
+    async fn get_completion(&self, prompt: &str) -> Result<String> {
+        self.llm.complete(prompt).await
     }
 
     pub async fn filter_tokens(&self, page: i64, _limit: Option<i64>) -> Result<FilterResponse> {
         info!("\n{}\n{}", PIPELINE_STEP_1, "=".repeat(50));
         
         // Get BirdEye filters
-        let filters = self.get_birdeye_filters().await?;
+        let filters = timed_step("birdeye_filters", self.get_birdeye_filters()).await?;
         
         info!("\n{}\n{}", PIPELINE_STEP_2, "=".repeat(50));
         
         // Get token list using v3 endpoint with correct parameters
-        let tokens = self.birdeye
-            .get_token_list_v3(
+        let tokens = timed_step(
+            "token_list",
+            self.birdeye.get_token_list_v3(
                 page,
                 filters.limit,
                 Some(&filters.filters)
-            )
-            .await?;
+            ),
+        )
+        .await?;
             
         // Handle edge case of empty tokens list
         if tokens.data.items.is_empty() {
@@ -195,53 +188,13 @@ impl TokenFilterService {
             
         info!("Retrieved {} tokens from BirdEye API", tokens.data.items.len());
 
-        info!("\n{}\n{}", PIPELINE_STEP_3, "=".repeat(50));
-        
-        // Filter out tokens with null fields
-        let valid_tokens: Vec<_> = tokens.data.items.into_iter()
-            .filter(|token| {
-                // Check for null or invalid values in essential fields
-                let is_valid = 
-                    token.address.is_some() && 
-                    !token.address.as_ref().unwrap().is_empty() &&
-                    token.symbol.is_some() && 
-                    !token.symbol.as_ref().unwrap().is_empty() &&
-                    token.liquidity.is_some();
-                
-                if !is_valid {
-                    debug!("Filtering out token with missing essential data");
-                }
-                
-                is_valid
-            })
-            .collect();
-            
-        info!("Filtered to {} valid tokens after removing entries with missing data", valid_tokens.len());
-        
-        // Analyze market data with valid tokens only
-        let market_analysis = self.analyze_market_data(&valid_tokens).await?;
-        
-        // Get metadata for filtered tokens
-        let token_pairs: Vec<(TokenAnalysis, TokenV3Response)> = market_analysis
-            .filtered_tokens
-            .into_iter()
-            .filter_map(|analysis| {
-                valid_tokens
-                    .iter()
-                    .find(|t| t.address == analysis.address)
-                    .map(|t| (analysis, t.clone()))
-            })
-            .collect();
-
-        info!("\n{}\n{}", PIPELINE_STEP_4, "=".repeat(50));
-        
-        // Analyze metadata
-        let metadata_analysis = self.analyze_metadata(&token_pairs).await?;
+        let analysis = self.analyze_tokens(tokens.data.items).await?;
+        let metadata_analysis = analysis.metadata;
 
         info!("\n{}\n{}", PIPELINE_STEP_5, "=".repeat(50));
         
         // Store results
-        self.store_analysis_results(&metadata_analysis).await?;
+        timed_step("store_results", self.store_analysis_results(&metadata_analysis, &analysis.tokens)).await?;
         info!("Analysis complete - {} tokens stored in recommendations", metadata_analysis.filtered_tokens.len());
         
         // Check KOL wallet ownership
@@ -255,7 +208,8 @@ impl TokenFilterService {
                 info!("Checking KOL ownership for token {}/{}: {} ({})", 
                      i+1, token_count, token.symbol, token.address);
                      
-                match wallet_tracker.update_token_recommendation_with_kol_data(&token.address).await {
+                let update = wallet_tracker.update_token_recommendation_with_kol_data(&token.address);
+                match timed_step("kol_ownership", update).await {
                     Ok(_) => info!("KOL ownership check complete for {}", token.symbol),
                     Err(e) => error!("Error checking KOL ownership for {}: {}", token.symbol, e),
                 }
@@ -267,6 +221,57 @@ impl TokenFilterService {
         Ok(metadata_analysis)
     }
 
+    /// Run the market and metadata analysis stages on a batch of tokens.
+    ///
+    /// Nothing is stored; this is the part of the pipeline that depends on the
+    /// prompts and the model, so it is also what the eval harness replays.
+    pub async fn analyze_tokens(&self, tokens: Vec<TokenV3Response>) -> Result<PipelineAnalysis> {
+        info!("\n{}\n{}", PIPELINE_STEP_3, "=".repeat(50));
+
+        // Filter out tokens with missing essential fields
+        let valid_tokens: Vec<_> = tokens
+            .into_iter()
+            .filter(|token| {
+                let is_valid = !token.address.is_empty()
+                    && !token.symbol.is_empty()
+                    && token.liquidity.is_some();
+
+                if !is_valid {
+                    debug!("Filtering out token with missing essential data");
+                }
+
+                is_valid
+            })
+            .collect();
+
+        info!("Filtered to {} valid tokens after removing entries with missing data", valid_tokens.len());
+
+        // Analyze market data with valid tokens only
+        let market_analysis = timed_step("market_analysis", self.analyze_market_data(&valid_tokens)).await?;
+
+        // Get metadata for filtered tokens
+        let token_pairs: Vec<(TokenAnalysis, TokenV3Response)> = market_analysis
+            .filtered_tokens
+            .iter()
+            .filter_map(|analysis| {
+                valid_tokens
+                    .iter()
+                    .find(|t| t.address == analysis.address)
+                    .map(|t| (analysis.clone(), t.clone()))
+            })
+            .collect();
+
+        info!("\n{}\n{}", PIPELINE_STEP_4, "=".repeat(50));
+
+        let metadata_analysis = timed_step("metadata_analysis", self.analyze_metadata(&token_pairs)).await?;
+
+        Ok(PipelineAnalysis {
+            market: market_analysis,
+            metadata: metadata_analysis,
+            tokens: valid_tokens,
+        })
+    }
+
     /// Get BirdEye filter parameters using LLM analysis
     pub async fn get_birdeye_filters(&self) -> Result<BirdeyeFilters> {
         let prompt = format!(
@@ -541,51 +546,51 @@ Token pairs to analyze: {}"#,
         Ok(analysis)
     }
 
-    async fn store_analysis_results(&self, analysis: &FilterResponse) -> Result<()> {
-        let db = self.db_pool.get_database()?;
-        let collection = db.collection::<Document>("token_recommendations");
-        
+    async fn store_analysis_results(
+        &self,
+        analysis: &FilterResponse,
+        tokens: &[TokenV3Response],
+    ) -> Result<()> {
         for token in &analysis.filtered_tokens {
             // Generate detailed decision reasoning
             let decision_reasoning = self.generate_decision_reasoning(token).await?;
             
+            // Market data as of the analysis, used as the entry point for outcome tracking
+            let market = tokens.iter().find(|t| t.address == token.address);
+            
             // Convert to TokenRecommendation
             let recommendation = TokenRecommendation {
                 id: None,
                 token_address: token.address.clone(),
                 symbol: token.symbol.clone(),
-                name: String::new(), // Would need to get from token metadata
-                decimals: 0,         // Would need to get from token metadata
-                logo_uri: None,      // Would need to get from token metadata
+                name: market.map(|t| t.name.clone()).unwrap_or_default(),
+                decimals: market.map(|t| t.decimals as i32).unwrap_or_default(),
+                logo_uri: market.and_then(|t| t.logo_uri.clone()),
                 analysis_date: Utc::now(),
                 overall_score: token.score,
                 market_score: token.analysis.market_score,
                 social_score: token.analysis.social_score,
                 dev_score: token.analysis.dev_score,
                 risk_score: token.analysis.risk_score,
-                price: 0.0,           // Would need to get from token data
-                liquidity: 0.0,       // Would need to get from token data
-                market_cap: 0.0,      // Would need to get from token data
-                volume_24h: 0.0,      // Would need to get from token data
-                holders: 0,           // Would need to get from token data
+                price: market.map(|t| t.price).unwrap_or_default(),
+                liquidity: market.and_then(|t| t.liquidity).unwrap_or_default(),
+                market_cap: market.and_then(|t| t.market_cap).unwrap_or_default(),
+                volume_24h: market.and_then(|t| t.volume_24h).unwrap_or_default(),
+                holders: market.and_then(|t| t.holder).unwrap_or_default(),
                 strengths: token.analysis.key_strengths.clone(),
                 risks: token.analysis.key_risks.clone(),
                 recommendation: token.analysis.final_recommendation.clone(),
                 kol_ownership: None,  // Will be populated by wallet tracker
                 decision_reasoning: Some(decision_reasoning),
+                prompt_version: Some(PROMPT_VERSION.to_string()),
+                outcomes: None,       // Filled in by the recommendation tracker
                 embedding: None,      // Would be generated if vector search is used
+                embedding_version: None,
             };
             
-            // Convert to BSON document
-            let doc = mongodb::bson::to_document(&recommendation)?;
-            
-            // Insert or update
-            let filter = doc! { "token_address": &token.address };
-            let options = mongodb::options::UpdateOptions::builder()
-                .upsert(true)
-                .build();
-                
-            collection.update_one(filter, doc! { "$set": doc }, options)
+            // One document per recommendation, so earlier ones keep their outcomes
+            self.recommendations
+                .save(&recommendation)
                 .await
                 .context("Failed to update token recommendation")?;
                 
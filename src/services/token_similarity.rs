@@ -0,0 +1,249 @@
+//! Numeric token profiles and nearest-neighbour ranking.
+//!
+//! Each snapshot is reduced to a fixed set of features (size, activity,
+//! momentum, holder growth and indicators). Features are standardized over
+//! the compared population so that no single scale dominates, and tokens are
+//! ranked by their root-mean-square difference to the reference.
+
+use crate::models::token_analytics::TokenAnalytics;
+use bigdecimal::{BigDecimal, ToPrimitive};
+
+/// Names of the profile features, in vector order
+pub const FEATURES: [&str; 12] = [
+    "liquidity",
+    "market_cap",
+    "volume_24h",
+    "turnover",
+    "price_change_24h",
+    "volume_change_24h",
+    "liquidity_change_24h",
+    "holders",
+    "holder_growth",
+    "rsi_14",
+    "macd_histogram",
+    "bollinger_position",
+];
+
+/// Features both tokens must have for a comparison to count
+pub const MIN_SHARED_FEATURES: usize = 4;
+
+/// Features reported as driving a match
+const DRIVERS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenFeatures(pub [Option<f64>; FEATURES.len()]);
+
+/// A feature on which the candidate resembles the reference
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureMatch {
+    pub feature: &'static str,
+    pub reference: f64,
+    pub value: f64,
+    /// Difference in standard deviations of the compared population
+    pub difference: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimilarToken {
+    /// Latest snapshot of the candidate
+    pub analytics: TokenAnalytics,
+    /// Root-mean-square standardized difference, 0 for an identical profile
+    pub distance: f64,
+    /// Closest features, best first
+    pub drivers: Vec<FeatureMatch>,
+}
+
+fn to_f64(value: &Option<BigDecimal>) -> Option<f64> {
+    value
+        .as_ref()
+        .and_then(|v| v.to_f64())
+        .filter(|v| v.is_finite())
+}
+
+fn log_scale(value: Option<f64>) -> Option<f64> {
+    value.filter(|v| *v >= 0.0).map(|v| (v + 1.0).log10())
+}
+
+/// Profile of `latest`. Holder growth is measured against `earlier`, an older
+/// snapshot of the same token.
+pub fn token_features(latest: &TokenAnalytics, earlier: Option<&TokenAnalytics>) -> TokenFeatures {
+    let price = latest.price.to_f64().filter(|p| *p > 0.0);
+    let liquidity = to_f64(&latest.liquidity);
+    let volume = to_f64(&latest.volume_24h);
+
+    let turnover = match (volume, liquidity) {
+        (Some(v), Some(l)) if l > 0.0 => Some(v / l),
+        _ => None,
+    };
+    let holder_growth = earlier
+        .filter(|e| e.timestamp < latest.timestamp)
+        .and_then(|e| e.holder_count)
+        .filter(|before| *before > 0)
+        .zip(latest.holder_count)
+        .map(|(before, now)| (now - before) as f64 / before as f64);
+    let macd_histogram = match (to_f64(&latest.macd), to_f64(&latest.macd_signal), price) {
+        (Some(macd), Some(signal), Some(price)) => Some((macd - signal) / price),
+        _ => None,
+    };
+    let bollinger_position = match (
+        to_f64(&latest.bollinger_lower),
+        to_f64(&latest.bollinger_upper),
+        price,
+    ) {
+        (Some(lower), Some(upper), Some(price)) if upper > lower => {
+            Some((price - lower) / (upper - lower))
+        }
+        _ => None,
+    };
+
+    TokenFeatures([
+        log_scale(liquidity),
+        log_scale(to_f64(&latest.market_cap)),
+        log_scale(volume),
+        turnover,
+        to_f64(&latest.price_change_24h),
+        to_f64(&latest.volume_change_24h),
+        to_f64(&latest.liquidity_change_24h),
+        log_scale(latest.holder_count.map(f64::from)),
+        holder_growth,
+        to_f64(&latest.rsi_14),
+        macd_histogram,
+        bollinger_position,
+    ])
+}
+
+/// Standard deviation of each feature, `None` where fewer than two values
+/// exist or all values are equal
+fn feature_scales<'a>(
+    profiles: impl Iterator<Item = &'a TokenFeatures> + Clone,
+) -> Vec<Option<f64>> {
+    (0..FEATURES.len())
+        .map(|i| {
+            let values: Vec<f64> = profiles.clone().filter_map(|p| p.0[i]).collect();
+            if values.len() < 2 {
+                return None;
+            }
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            let variance =
+                values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+            let std = variance.sqrt();
+            (std > f64::EPSILON).then_some(std)
+        })
+        .collect()
+}
+
+/// The `k` candidates closest to `reference`, nearest first. Candidates
+/// sharing fewer than `MIN_SHARED_FEATURES` usable features are skipped.
+pub fn rank_similar(
+    reference: &TokenFeatures,
+    candidates: Vec<(TokenAnalytics, TokenFeatures)>,
+    k: usize,
+) -> Vec<SimilarToken> {
+    let scales = feature_scales(
+        std::iter::once(reference).chain(candidates.iter().map(|(_, features)| features)),
+    );
+
+    let mut ranked: Vec<SimilarToken> = candidates
+        .into_iter()
+        .filter_map(|(analytics, features)| {
+            let mut matches: Vec<FeatureMatch> = (0..FEATURES.len())
+                .filter_map(|i| {
+                    let std = scales[i]?;
+                    let (reference, value) = (reference.0[i]?, features.0[i]?);
+                    Some(FeatureMatch {
+                        feature: FEATURES[i],
+                        reference,
+                        value,
+                        difference: (value - reference).abs() / std,
+                    })
+                })
+                .collect();
+            if matches.len() < MIN_SHARED_FEATURES {
+                return None;
+            }
+
+            let distance = (matches.iter().map(|m| m.difference.powi(2)).sum::<f64>()
+                / matches.len() as f64)
+                .sqrt();
+            matches.sort_by(|a, b| a.difference.total_cmp(&b.difference));
+            matches.truncate(DRIVERS);
+            Some(SimilarToken {
+                analytics,
+                distance,
+                drivers: matches,
+            })
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    ranked.truncate(k);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn profile(values: [f64; 5]) -> TokenFeatures {
+        let mut features = [None; FEATURES.len()];
+        for (slot, value) in features.iter_mut().zip(values) {
+            *slot = Some(value);
+        }
+        TokenFeatures(features)
+    }
+
+    fn snapshot(address: &str) -> TokenAnalytics {
+        let decimal = |v: &str| Some(BigDecimal::from_str(v).unwrap());
+        TokenAnalytics {
+            token_address: address.to_string(),
+            volume_24h: decimal("5000"),
+            liquidity: decimal("1000"),
+            holder_count: Some(110),
+            bollinger_upper: decimal("3"),
+            bollinger_lower: decimal("1"),
+            ..TokenAnalytics::test_snapshot(2, 2.0)
+        }
+    }
+
+    #[test]
+    fn test_token_features() {
+        let latest = snapshot("a");
+        let mut earlier = snapshot("a");
+        earlier.holder_count = Some(100);
+        earlier.timestamp = bson::DateTime::from_millis(1_000);
+
+        let features = token_features(&latest, Some(&earlier));
+        assert!((features.0[0].unwrap() - 1001f64.log10()).abs() < 1e-9);
+        assert_eq!(features.0[3], Some(5.0));
+        assert!((features.0[8].unwrap() - 0.1).abs() < 1e-9);
+        assert_eq!(features.0[11], Some(0.5));
+        assert_eq!(features.0[9], None);
+
+        // Growth needs an older snapshot
+        assert_eq!(token_features(&latest, Some(&latest)).0[8], None);
+    }
+
+    #[test]
+    fn test_rank_similar() {
+        let reference = profile([1.0, 1.0, 1.0, 1.0, 1.0]);
+        let mut sparse = TokenFeatures([None; FEATURES.len()]);
+        sparse.0[0] = Some(1.0);
+        let candidates = vec![
+            (snapshot("far"), profile([5.0, 5.0, 5.0, 5.0, 5.0])),
+            (snapshot("near"), profile([1.1, 1.0, 0.9, 1.0, 2.0])),
+            (snapshot("sparse"), sparse),
+        ];
+
+        let ranked = rank_similar(&reference, candidates, 5);
+        let addresses: Vec<_> = ranked
+            .iter()
+            .map(|s| s.analytics.token_address.as_str())
+            .collect();
+        assert_eq!(addresses, vec!["near", "far"]);
+        assert!(ranked[0].distance < ranked[1].distance);
+        assert_eq!(ranked[0].drivers.len(), DRIVERS);
+        assert_eq!(ranked[0].drivers[0].difference, 0.0);
+        assert_ne!(ranked[0].drivers[0].feature, "price_change_24h");
+    }
+}
@@ -1,40 +1,37 @@
 use anyhow::{Context, Result};
-use mongodb::{bson::{doc, Document}, Collection};
-use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::{debug, error, info};
+use tracing::info;
 
 use crate::config::mongodb::MongoDbPool;
+use crate::storage::{KolWalletRepository, RecommendationRepository, Storage};
+use crate::vector_store::DEFAULT_INDEX_NAME;
 
 pub mod models;
 pub use models::*;
 
 /// Service for tracking KOL (Key Opinion Leader) wallets and their token holdings
 pub struct WalletTrackerService {
-    db_pool: Arc<MongoDbPool>,
+    kol_wallets: Arc<dyn KolWalletRepository>,
+    recommendations: Arc<dyn RecommendationRepository>,
 }
 
 impl WalletTrackerService {
     pub fn new(db_pool: Arc<MongoDbPool>) -> Self {
-        Self { db_pool }
+        Self::from_storage(&Storage::mongo(&db_pool, DEFAULT_INDEX_NAME))
     }
 
-    /// Get collection of KOL wallets
-    fn get_kol_wallets_collection(&self) -> Result<Collection<KolWallet>> {
-        let db = self.db_pool.get_database()?;
-        Ok(db.collection::<KolWallet>("kol_wallets"))
-    }
-    
-    /// Get collection of token recommendations
-    fn get_token_recommendations_collection(&self) -> Result<Collection<TokenRecommendation>> {
-        let db = self.db_pool.get_database()?;
-        Ok(db.collection::<TokenRecommendation>("token_recommendations"))
+    /// Create the service over explicit repositories, e.g. `Storage::memory()`
+    pub fn from_storage(storage: &Storage) -> Self {
+        Self {
+            kol_wallets: storage.kol_wallets.clone(),
+            recommendations: storage.recommendations.clone(),
+        }
     }
     
     /// Add a new KOL wallet to the database
     pub async fn add_kol_wallet(&self, kol_wallet: KolWallet) -> Result<()> {
-        let collection = self.get_kol_wallets_collection()?;
-        collection.insert_one(kol_wallet, None)
+        self.kol_wallets
+            .insert(&kol_wallet)
             .await
             .context("Failed to insert KOL wallet")?;
         Ok(())
@@ -42,14 +39,11 @@ impl WalletTrackerService {
     
     /// Get all active KOL wallets
     pub async fn get_active_kol_wallets(&self) -> Result<Vec<KolWallet>> {
-        let collection = self.get_kol_wallets_collection()?;
-        let filter = doc! { "active": true };
-        let wallets = collection.find(filter, None)
-            .await
-            .context("Failed to find active KOL wallets")?
-            .try_collect()
+        let wallets = self
+            .kol_wallets
+            .active()
             .await
-            .context("Failed to collect KOL wallets")?;
+            .context("Failed to find active KOL wallets")?;
         Ok(wallets)
     }
     
@@ -80,13 +74,8 @@ impl WalletTrackerService {
         
         // Update token recommendation if KOL ownership exists
         if !kol_ownership.is_empty() {
-            let collection = self.get_token_recommendations_collection()?;
-            let filter = doc! { "token_address": token_address };
-            let update = doc! { 
-                "$set": { "kol_ownership": bson::to_bson(&kol_ownership)? } 
-            };
-            
-            collection.update_one(filter, update, None)
+            self.recommendations
+                .set_kol_ownership(token_address, &kol_ownership)
                 .await
                 .context("Failed to update token recommendation with KOL data")?;
                 
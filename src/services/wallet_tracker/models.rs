@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// KOL (Key Opinion Leader) wallet information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,9 +128,40 @@ pub struct TokenRecommendation {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub decision_reasoning: Option<DecisionReasoning>,
     
+    /// Version of the filter prompts that produced this recommendation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>,
+    
+    /// Realized performance keyed by horizon label ("1h", "24h", "7d", "30d")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcomes: Option<BTreeMap<String, RecommendationOutcome>>,
+    
     /// Vector embedding for similarity search
     #[serde(skip_serializing_if = "Option::is_none")]
     pub embedding: Option<Vec<f32>>,
+    
+    /// Representation and model the embedding was built with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_version: Option<String>,
+}
+
+/// Forward performance of a recommendation measured at a fixed horizon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecommendationOutcome {
+    /// When the outcome was measured
+    pub evaluated_at: DateTime<Utc>,
+    
+    /// Token price in USD at the horizon
+    pub price: f64,
+    
+    /// Return from the recommendation price to the horizon price (0.1 = +10%)
+    pub forward_return: f64,
+    
+    /// Largest peak-to-trough decline between recommendation and horizon (0.2 = -20%)
+    pub max_drawdown: f64,
+    
+    /// Number of price points the drawdown was computed from
+    pub samples: usize,
 }
 
 /// Detailed decision reasoning from the LLM
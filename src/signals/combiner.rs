@@ -0,0 +1,179 @@
+use super::detectors::{
+    BollingerBreakoutDetector, HolderGrowthDetector, KolInflowDetector, LiquidityDrainDetector,
+    MacdCrossoverDetector, MomentumDetector, RsiDetector,
+};
+use super::indicators::volatility;
+use super::{Detector, Factor, SignalContext};
+use crate::models::market_signal::SignalType;
+use serde::{Deserialize, Serialize};
+
+/// Risk reported when neither a detector nor the price history says anything
+const DEFAULT_RISK: f64 = 0.5;
+
+/// Per-snapshot return volatility that maps to full risk
+const MAX_VOLATILITY: f64 = 0.1;
+
+/// Weighted reading of all detectors for one token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Composite {
+    /// Factors of the detectors that fired
+    pub factors: Vec<Factor>,
+    /// Weighted mean of the factor scores, from -1.0 (bearish) to 1.0 (bullish)
+    pub score: f64,
+    /// Mean of the factor risks and price volatility, in [0, 1]
+    pub risk: f64,
+    /// `score` mapped to [0, 1]
+    pub sentiment: f64,
+}
+
+impl Composite {
+    /// Signal type for the composite score, or `None` when it is too weak
+    pub fn signal_type(&self, buy_threshold: f64, strong_threshold: f64) -> Option<SignalType> {
+        match self.score {
+            s if s >= strong_threshold => Some(SignalType::StrongBuy),
+            s if s >= buy_threshold => Some(SignalType::Buy),
+            s if s <= -strong_threshold => Some(SignalType::StrongSell),
+            s if s <= -buy_threshold => Some(SignalType::Sell),
+            _ => None,
+        }
+    }
+}
+
+/// Runs a weighted set of detectors and merges their factors
+pub struct SignalCombiner {
+    detectors: Vec<(Box<dyn Detector>, f64)>,
+    /// Minimum absolute composite score for a Buy/Sell signal
+    pub buy_threshold: f64,
+    /// Minimum absolute composite score for a StrongBuy/StrongSell signal
+    pub strong_threshold: f64,
+}
+
+impl SignalCombiner {
+    /// A combiner without detectors
+    pub fn new() -> Self {
+        Self {
+            detectors: Vec::new(),
+            buy_threshold: 0.25,
+            strong_threshold: 0.6,
+        }
+    }
+
+    pub fn with_detector(mut self, detector: impl Detector + 'static, weight: f64) -> Self {
+        self.detectors.push((Box::new(detector), weight));
+        self
+    }
+
+    pub fn thresholds(mut self, buy: f64, strong: f64) -> Self {
+        self.buy_threshold = buy;
+        self.strong_threshold = strong;
+        self
+    }
+
+    pub fn evaluate(&self, ctx: &SignalContext) -> Composite {
+        let factors = self
+            .detectors
+            .iter()
+            .filter_map(|(detector, weight)| {
+                detector.detect(ctx).map(|mut factor| {
+                    factor.weight = *weight;
+                    factor
+                })
+            })
+            .collect();
+
+        combine(factors, volatility(&ctx.prices()))
+    }
+
+    pub fn signal_type(&self, composite: &Composite) -> Option<SignalType> {
+        composite.signal_type(self.buy_threshold, self.strong_threshold)
+    }
+}
+
+impl Default for SignalCombiner {
+    /// All built-in detectors. Liquidity drains and KOL entries are weighted
+    /// up because they are rarer and more decisive than indicator readings.
+    fn default() -> Self {
+        Self::new()
+            .with_detector(MomentumDetector::default(), 1.0)
+            .with_detector(RsiDetector::default(), 0.8)
+            .with_detector(MacdCrossoverDetector::default(), 0.8)
+            .with_detector(BollingerBreakoutDetector::default(), 0.8)
+            .with_detector(LiquidityDrainDetector::default(), 1.5)
+            .with_detector(HolderGrowthDetector::default(), 0.7)
+            .with_detector(KolInflowDetector::default(), 1.2)
+    }
+}
+
+/// Merge weighted factors into a composite. `volatility` is the return
+/// volatility of the price history, if there is enough of it.
+pub fn combine(factors: Vec<Factor>, volatility: Option<f64>) -> Composite {
+    let total_weight: f64 = factors.iter().map(|f| f.weight).sum();
+    let score = if total_weight > 0.0 {
+        (factors.iter().map(|f| f.score * f.weight).sum::<f64>() / total_weight).clamp(-1.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let risks: Vec<f64> = factors
+        .iter()
+        .filter_map(|f| f.risk)
+        .chain(volatility.map(|v| (v / MAX_VOLATILITY).min(1.0)))
+        .collect();
+    let risk = if risks.is_empty() {
+        DEFAULT_RISK
+    } else {
+        risks.iter().sum::<f64>() / risks.len() as f64
+    };
+
+    Composite {
+        factors,
+        score,
+        risk,
+        sentiment: (score + 1.0) / 2.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signals::factor;
+
+    fn weighted(name: &str, score: f64, weight: f64, risk: Option<f64>) -> Factor {
+        let mut f = factor(name, score, risk, String::new());
+        f.weight = weight;
+        f
+    }
+
+    #[test]
+    fn test_combine_weights_scores() {
+        let composite = combine(
+            vec![
+                weighted("a", 1.0, 3.0, None),
+                weighted("b", -1.0, 1.0, Some(0.8)),
+            ],
+            Some(0.02),
+        );
+
+        assert!((composite.score - 0.5).abs() < 1e-12);
+        assert!((composite.sentiment - 0.75).abs() < 1e-12);
+        assert!((composite.risk - 0.5).abs() < 1e-12);
+        assert_eq!(composite.signal_type(0.25, 0.6), Some(SignalType::Buy));
+    }
+
+    #[test]
+    fn test_combine_without_factors_is_neutral() {
+        let composite = combine(Vec::new(), None);
+        assert_eq!(composite.score, 0.0);
+        assert_eq!(composite.risk, DEFAULT_RISK);
+        assert_eq!(composite.signal_type(0.25, 0.6), None);
+    }
+
+    #[test]
+    fn test_signal_type_thresholds() {
+        let at = |score| combine(vec![weighted("a", score, 1.0, None)], None);
+        assert_eq!(at(0.7).signal_type(0.25, 0.6), Some(SignalType::StrongBuy));
+        assert_eq!(at(-0.3).signal_type(0.25, 0.6), Some(SignalType::Sell));
+        assert_eq!(at(-0.9).signal_type(0.25, 0.6), Some(SignalType::StrongSell));
+        assert_eq!(at(0.1).signal_type(0.25, 0.6), None);
+    }
+}
@@ -0,0 +1,446 @@
+use super::indicators::{bollinger, macd_series, rsi};
+use super::{factor, Detector, Factor, SignalContext};
+use bigdecimal::ToPrimitive;
+use chrono::Duration as ChronoDuration;
+
+/// Price change over the last `lookback` snapshots
+pub struct MomentumDetector {
+    pub lookback: usize,
+    /// Smallest absolute change (fraction) that counts as momentum
+    pub min_change: f64,
+    /// Change at which the score reaches ~0.76 (tanh(1))
+    pub scale: f64,
+}
+
+impl Default for MomentumDetector {
+    fn default() -> Self {
+        Self {
+            lookback: 12,
+            min_change: 0.03,
+            scale: 0.15,
+        }
+    }
+}
+
+impl Detector for MomentumDetector {
+    fn name(&self) -> &'static str {
+        "momentum"
+    }
+
+    fn detect(&self, ctx: &SignalContext) -> Option<Factor> {
+        let prices = ctx.prices();
+        if prices.len() < 2 {
+            return None;
+        }
+
+        let start = prices[prices.len().saturating_sub(self.lookback + 1)];
+        if start <= 0.0 {
+            return None;
+        }
+        let change = ctx.current_price() / start - 1.0;
+        if change.abs() < self.min_change {
+            return None;
+        }
+
+        Some(factor(
+            self.name(),
+            (change / self.scale).tanh(),
+            None,
+            format!(
+                "price {:+.2}% over {} snapshots",
+                change * 100.0,
+                prices.len().min(self.lookback + 1) - 1
+            ),
+        ))
+    }
+}
+
+/// Mean reversion at RSI extremes: oversold is bullish, overbought bearish
+pub struct RsiDetector {
+    pub period: usize,
+    pub oversold: f64,
+    pub overbought: f64,
+}
+
+impl Default for RsiDetector {
+    fn default() -> Self {
+        Self {
+            period: 14,
+            oversold: 30.0,
+            overbought: 70.0,
+        }
+    }
+}
+
+impl Detector for RsiDetector {
+    fn name(&self) -> &'static str {
+        "rsi"
+    }
+
+    fn detect(&self, ctx: &SignalContext) -> Option<Factor> {
+        let value = rsi(&ctx.prices(), self.period)
+            .or_else(|| ctx.current.rsi_14.as_ref().and_then(|r| r.to_f64()))?;
+
+        let score = if value <= self.oversold {
+            (self.oversold - value) / self.oversold
+        } else if value >= self.overbought {
+            -(value - self.overbought) / (100.0 - self.overbought)
+        } else {
+            return None;
+        };
+
+        Some(factor(
+            self.name(),
+            score,
+            None,
+            format!(
+                "RSI {:.1} {}",
+                value,
+                if score > 0.0 {
+                    "oversold"
+                } else {
+                    "overbought"
+                }
+            ),
+        ))
+    }
+}
+
+/// MACD line crossing its signal line between the last two snapshots
+pub struct MacdCrossoverDetector {
+    pub fast: usize,
+    pub slow: usize,
+    pub signal: usize,
+    pub strength: f64,
+}
+
+impl Default for MacdCrossoverDetector {
+    fn default() -> Self {
+        Self {
+            fast: 12,
+            slow: 26,
+            signal: 9,
+            strength: 0.7,
+        }
+    }
+}
+
+impl Detector for MacdCrossoverDetector {
+    fn name(&self) -> &'static str {
+        "macd_crossover"
+    }
+
+    fn detect(&self, ctx: &SignalContext) -> Option<Factor> {
+        let series = macd_series(&ctx.prices(), self.fast, self.slow, self.signal);
+        let [.., prev, last] = series.as_slice() else {
+            return None;
+        };
+
+        let score = if prev.histogram() <= 0.0 && last.histogram() > 0.0 {
+            self.strength
+        } else if prev.histogram() >= 0.0 && last.histogram() < 0.0 {
+            -self.strength
+        } else {
+            return None;
+        };
+
+        Some(factor(
+            self.name(),
+            score,
+            None,
+            format!(
+                "MACD {} crossover (line {:.6}, signal {:.6})",
+                if score > 0.0 { "bullish" } else { "bearish" },
+                last.line,
+                last.signal
+            ),
+        ))
+    }
+}
+
+/// Current price closing outside the Bollinger bands of the preceding window
+pub struct BollingerBreakoutDetector {
+    pub period: usize,
+    pub num_std_dev: f64,
+    pub strength: f64,
+}
+
+impl Default for BollingerBreakoutDetector {
+    fn default() -> Self {
+        Self {
+            period: 20,
+            num_std_dev: 2.0,
+            strength: 0.6,
+        }
+    }
+}
+
+impl Detector for BollingerBreakoutDetector {
+    fn name(&self) -> &'static str {
+        "bollinger_breakout"
+    }
+
+    fn detect(&self, ctx: &SignalContext) -> Option<Factor> {
+        let prices = ctx.prices();
+        let (_, previous) = prices.split_last()?;
+        let bands = bollinger(previous, self.period, self.num_std_dev)?;
+        let price = ctx.current_price();
+
+        let score = if price > bands.upper {
+            self.strength
+        } else if price < bands.lower {
+            -self.strength
+        } else {
+            return None;
+        };
+
+        Some(factor(
+            self.name(),
+            score,
+            // Wide bands mean a volatile token; 50% width maps to full risk
+            Some(bands.width() * 2.0),
+            format!(
+                "price {:.8} {} band {:.8}",
+                price,
+                if score > 0.0 {
+                    "above upper"
+                } else {
+                    "below lower"
+                },
+                if score > 0.0 {
+                    bands.upper
+                } else {
+                    bands.lower
+                }
+            ),
+        ))
+    }
+}
+
+/// Liquidity falling across the history window
+pub struct LiquidityDrainDetector {
+    /// Smallest drop (fraction) that counts as a drain
+    pub min_drop: f64,
+}
+
+impl Default for LiquidityDrainDetector {
+    fn default() -> Self {
+        Self { min_drop: 0.2 }
+    }
+}
+
+impl Detector for LiquidityDrainDetector {
+    fn name(&self) -> &'static str {
+        "liquidity_drain"
+    }
+
+    fn detect(&self, ctx: &SignalContext) -> Option<Factor> {
+        let current = ctx.current.liquidity.as_ref()?.to_f64()?;
+        let start = ctx
+            .history
+            .iter()
+            .find_map(|a| a.liquidity.as_ref()?.to_f64())
+            .filter(|l| *l > 0.0)?;
+
+        let drop = 1.0 - current / start;
+        if drop < self.min_drop {
+            return None;
+        }
+
+        Some(factor(
+            self.name(),
+            -(drop * 2.0),
+            Some(drop * 2.0),
+            format!(
+                "liquidity down {:.1}% (${:.0} -> ${:.0})",
+                drop * 100.0,
+                start,
+                current
+            ),
+        ))
+    }
+}
+
+/// Change in holder count across the history window
+pub struct HolderGrowthDetector {
+    /// Smallest absolute change (fraction) that is reported
+    pub min_change: f64,
+    pub scale: f64,
+}
+
+impl Default for HolderGrowthDetector {
+    fn default() -> Self {
+        Self {
+            min_change: 0.05,
+            scale: 0.2,
+        }
+    }
+}
+
+impl Detector for HolderGrowthDetector {
+    fn name(&self) -> &'static str {
+        "holder_growth"
+    }
+
+    fn detect(&self, ctx: &SignalContext) -> Option<Factor> {
+        let current = ctx.current.holder_count? as f64;
+        let start = ctx
+            .history
+            .iter()
+            .find_map(|a| a.holder_count)
+            .filter(|h| *h > 0)? as f64;
+
+        let change = current / start - 1.0;
+        if change.abs() < self.min_change {
+            return None;
+        }
+
+        Some(factor(
+            self.name(),
+            (change / self.scale).tanh(),
+            None,
+            format!("holders {:+.1}% ({} -> {})", change * 100.0, start, current),
+        ))
+    }
+}
+
+/// KOL wallets that opened a position recently
+pub struct KolInflowDetector {
+    pub window: ChronoDuration,
+    /// Score added per new KOL position
+    pub per_position: f64,
+}
+
+impl Default for KolInflowDetector {
+    fn default() -> Self {
+        Self {
+            window: ChronoDuration::hours(24),
+            per_position: 0.3,
+        }
+    }
+}
+
+impl Detector for KolInflowDetector {
+    fn name(&self) -> &'static str {
+        "kol_inflow"
+    }
+
+    fn detect(&self, ctx: &SignalContext) -> Option<Factor> {
+        let recent: Vec<&str> = ctx
+            .kol_positions
+            .iter()
+            .filter(|p| ctx.now - p.entry_time <= self.window)
+            .map(|p| p.name.as_str())
+            .collect();
+        if recent.is_empty() {
+            return None;
+        }
+
+        Some(factor(
+            self.name(),
+            recent.len() as f64 * self.per_position,
+            None,
+            format!(
+                "{} new KOL position(s): {}",
+                recent.len(),
+                recent.join(", ")
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::token_analytics::TokenAnalytics;
+    use crate::services::wallet_tracker::KolOwnership;
+    use crate::utils::f64_to_decimal;
+    use chrono::Utc;
+
+    fn snapshot(price: f64, liquidity: f64, holders: i32) -> TokenAnalytics {
+        TokenAnalytics {
+            liquidity: Some(f64_to_decimal(liquidity).unwrap()),
+            holder_count: Some(holders),
+            timestamp: bson::DateTime::now(),
+            ..TokenAnalytics::test_snapshot(0, price)
+        }
+    }
+
+    fn ctx<'a>(
+        current: &'a TokenAnalytics,
+        history: &'a [TokenAnalytics],
+        kol: &'a [KolOwnership],
+    ) -> SignalContext<'a> {
+        SignalContext {
+            current,
+            history,
+            kol_positions: kol,
+            now: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_momentum() {
+        let history = vec![snapshot(1.0, 0.0, 0), snapshot(1.05, 0.0, 0)];
+        let current = snapshot(1.2, 0.0, 0);
+        let factor = MomentumDetector::default()
+            .detect(&ctx(&current, &history, &[]))
+            .unwrap();
+        assert!(factor.score > 0.8);
+
+        let flat = snapshot(1.01, 0.0, 0);
+        assert!(MomentumDetector::default()
+            .detect(&ctx(&flat, &history[..1], &[]))
+            .is_none());
+    }
+
+    #[test]
+    fn test_rsi_overbought_is_bearish() {
+        let history: Vec<_> = (1..=20).map(|i| snapshot(i as f64, 0.0, 0)).collect();
+        let current = snapshot(21.0, 0.0, 0);
+        let factor = RsiDetector::default()
+            .detect(&ctx(&current, &history, &[]))
+            .unwrap();
+        assert_eq!(factor.score, -1.0);
+    }
+
+    #[test]
+    fn test_liquidity_drain_reports_risk() {
+        let history = vec![snapshot(1.0, 100_000.0, 0)];
+        let current = snapshot(1.0, 60_000.0, 0);
+        let factor = LiquidityDrainDetector::default()
+            .detect(&ctx(&current, &history, &[]))
+            .unwrap();
+        assert!((factor.score + 0.8).abs() < 1e-9);
+        assert!((factor.risk.unwrap() - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_holder_growth() {
+        let history = vec![snapshot(1.0, 0.0, 1000)];
+        let current = snapshot(1.0, 0.0, 1200);
+        let factor = HolderGrowthDetector::default()
+            .detect(&ctx(&current, &history, &[]))
+            .unwrap();
+        assert!(factor.score > 0.7);
+    }
+
+    #[test]
+    fn test_kol_inflow_ignores_old_positions() {
+        let position = |hours: i64| KolOwnership {
+            kol_id: "kol".to_string(),
+            name: format!("kol-{}", hours),
+            wallet_address: "wallet".to_string(),
+            position_size: 1.0,
+            entry_time: Utc::now() - ChronoDuration::hours(hours),
+        };
+        let positions = vec![position(2), position(48)];
+        let current = snapshot(1.0, 0.0, 0);
+
+        let factor = KolInflowDetector::default()
+            .detect(&ctx(&current, &[], &positions))
+            .unwrap();
+        assert!((factor.score - 0.3).abs() < 1e-9);
+        assert!(factor.reason.contains("kol-2"));
+    }
+}
@@ -0,0 +1,179 @@
+//! Technical indicators over a price series ordered oldest to newest.
+//!
+//! Functions return `None` when the series is too short for the requested
+//! period instead of a neutral placeholder, so callers can tell "no data"
+//! from "neutral reading".
+
+/// Exponential moving average for every point, seeded with the first value
+pub fn ema_series(values: &[f64], period: usize) -> Vec<f64> {
+    let Some(first) = values.first() else {
+        return Vec::new();
+    };
+
+    let multiplier = 2.0 / (period as f64 + 1.0);
+    let mut ema = *first;
+    let mut series = Vec::with_capacity(values.len());
+    series.push(ema);
+    for value in &values[1..] {
+        ema += (value - ema) * multiplier;
+        series.push(ema);
+    }
+    series
+}
+
+/// Relative strength index with Wilder smoothing
+pub fn rsi(prices: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || prices.len() < period + 1 {
+        return None;
+    }
+
+    let changes: Vec<f64> = prices.windows(2).map(|w| w[1] - w[0]).collect();
+    let mut avg_gain = changes[..period].iter().map(|c| c.max(0.0)).sum::<f64>() / period as f64;
+    let mut avg_loss = changes[..period].iter().map(|c| (-c).max(0.0)).sum::<f64>() / period as f64;
+
+    for change in &changes[period..] {
+        avg_gain = (avg_gain * (period - 1) as f64 + change.max(0.0)) / period as f64;
+        avg_loss = (avg_loss * (period - 1) as f64 + (-change).max(0.0)) / period as f64;
+    }
+
+    if avg_loss == 0.0 {
+        return Some(if avg_gain == 0.0 { 50.0 } else { 100.0 });
+    }
+
+    Some(100.0 - 100.0 / (1.0 + avg_gain / avg_loss))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Macd {
+    pub line: f64,
+    pub signal: f64,
+}
+
+impl Macd {
+    pub fn histogram(&self) -> f64 {
+        self.line - self.signal
+    }
+}
+
+/// MACD line and signal line for every point once the slow EMA has
+/// `slow` values behind it
+pub fn macd_series(prices: &[f64], fast: usize, slow: usize, signal: usize) -> Vec<Macd> {
+    if prices.len() < slow {
+        return Vec::new();
+    }
+
+    let fast_ema = ema_series(prices, fast);
+    let slow_ema = ema_series(prices, slow);
+    let lines: Vec<f64> = fast_ema
+        .iter()
+        .zip(&slow_ema)
+        .skip(slow - 1)
+        .map(|(f, s)| f - s)
+        .collect();
+    let signals = ema_series(&lines, signal);
+
+    lines
+        .into_iter()
+        .zip(signals)
+        .map(|(line, signal)| Macd { line, signal })
+        .collect()
+}
+
+pub fn macd(prices: &[f64], fast: usize, slow: usize, signal: usize) -> Option<Macd> {
+    macd_series(prices, fast, slow, signal).last().copied()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bands {
+    pub upper: f64,
+    pub middle: f64,
+    pub lower: f64,
+}
+
+impl Bands {
+    /// Band width relative to the middle band
+    pub fn width(&self) -> f64 {
+        if self.middle == 0.0 {
+            0.0
+        } else {
+            (self.upper - self.lower) / self.middle
+        }
+    }
+}
+
+/// Bollinger bands over the most recent `period` prices
+pub fn bollinger(prices: &[f64], period: usize, num_std_dev: f64) -> Option<Bands> {
+    if period == 0 || prices.len() < period {
+        return None;
+    }
+
+    let window = &prices[prices.len() - period..];
+    let middle = window.iter().sum::<f64>() / period as f64;
+    let variance = window.iter().map(|p| (p - middle).powi(2)).sum::<f64>() / period as f64;
+    let std_dev = variance.sqrt();
+
+    Some(Bands {
+        upper: middle + std_dev * num_std_dev,
+        middle,
+        lower: middle - std_dev * num_std_dev,
+    })
+}
+
+/// Standard deviation of simple returns between consecutive prices
+pub fn volatility(prices: &[f64]) -> Option<f64> {
+    let returns: Vec<f64> = prices
+        .windows(2)
+        .filter(|w| w[0] > 0.0)
+        .map(|w| w[1] / w[0] - 1.0)
+        .collect();
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance =
+        returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    Some(variance.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rsi_bounds() {
+        let rising: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(rsi(&rising, 14), Some(100.0));
+
+        let falling: Vec<f64> = rising.iter().rev().copied().collect();
+        assert_eq!(rsi(&falling, 14), Some(0.0));
+
+        assert_eq!(rsi(&rising[..10], 14), None);
+    }
+
+    #[test]
+    fn test_macd_turns_positive_on_uptrend() {
+        let mut prices = vec![10.0; 30];
+        prices.extend((1..=10).map(|i| 10.0 + i as f64));
+
+        let series = macd_series(&prices, 12, 26, 9);
+        assert_eq!(series.len(), prices.len() - 25);
+        assert_eq!(series[0].histogram(), 0.0);
+        assert!(series.last().unwrap().histogram() > 0.0);
+    }
+
+    #[test]
+    fn test_bollinger_uses_latest_window() {
+        let prices = [100.0, 1.0, 1.0, 1.0];
+        let bands = bollinger(&prices, 3, 2.0).unwrap();
+        assert_eq!(
+            bands,
+            Bands {
+                upper: 1.0,
+                middle: 1.0,
+                lower: 1.0
+            }
+        );
+        assert!(bollinger(&prices, 5, 2.0).is_none());
+    }
+}
@@ -0,0 +1,90 @@
+//! Multi-factor market signal engine.
+//!
+//! Independent [`Detector`]s each look at a token's current snapshot, its
+//! recent history and KOL activity and may report a [`Factor`]: a directional
+//! score in [-1, 1] (bearish to bullish) with an optional risk reading. The
+//! [`SignalCombiner`] weighs the factors into one composite reading that
+//! [`TokenAnalyticsService`](crate::services::TokenAnalyticsService) turns
+//! into a `MarketSignal`.
+
+pub mod combiner;
+pub mod detectors;
+pub mod indicators;
+pub mod state;
+
+pub use combiner::{Composite, SignalCombiner};
+pub use detectors::{
+    BollingerBreakoutDetector, HolderGrowthDetector, KolInflowDetector, LiquidityDrainDetector,
+    MacdCrossoverDetector, MomentumDetector, RsiDetector,
+};
+pub use state::{SignalDecision, SignalEpisode, SignalPolicy, SignalStateStore};
+
+use crate::models::token_analytics::TokenAnalytics;
+use crate::services::wallet_tracker::KolOwnership;
+use bigdecimal::ToPrimitive;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Everything a detector may look at for one token
+pub struct SignalContext<'a> {
+    pub current: &'a TokenAnalytics,
+    /// Earlier snapshots of the same token, oldest first
+    pub history: &'a [TokenAnalytics],
+    /// Known KOL positions in the token
+    pub kol_positions: &'a [KolOwnership],
+    pub now: DateTime<Utc>,
+}
+
+impl SignalContext<'_> {
+    /// Prices of the history followed by the current price
+    pub fn prices(&self) -> Vec<f64> {
+        self.history
+            .iter()
+            .chain(std::iter::once(self.current))
+            .filter_map(|a| a.price.to_f64())
+            .collect()
+    }
+
+    pub fn previous(&self) -> Option<&TokenAnalytics> {
+        self.history.last()
+    }
+
+    pub fn current_price(&self) -> f64 {
+        self.current.price.to_f64().unwrap_or_default()
+    }
+}
+
+/// One detector's reading for a token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Factor {
+    pub detector: String,
+    /// Direction and strength, from -1.0 (bearish) to 1.0 (bullish)
+    pub score: f64,
+    /// Weight the combiner gave this detector
+    pub weight: f64,
+    /// Risk reading in [0, 1], if the detector measures one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub risk: Option<f64>,
+    pub reason: String,
+}
+
+/// A single independent scoring rule
+pub trait Detector: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Score the token, or `None` when the detector has nothing to say
+    /// (not enough data, or the condition it looks for is absent)
+    fn detect(&self, ctx: &SignalContext) -> Option<Factor>;
+}
+
+/// Build a factor with the score clamped to [-1, 1] and risk to [0, 1].
+/// The weight is filled in by the combiner.
+pub(crate) fn factor(detector: &str, score: f64, risk: Option<f64>, reason: String) -> Factor {
+    Factor {
+        detector: detector.to_string(),
+        score: score.clamp(-1.0, 1.0),
+        weight: 1.0,
+        risk: risk.map(|r| r.clamp(0.0, 1.0)),
+        reason,
+    }
+}
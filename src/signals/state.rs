@@ -0,0 +1,351 @@
+//! Deduplication of repeated signals into episodes.
+//!
+//! Monitoring loops re-score the same token every cycle, so a single market
+//! move produces the same signal many times. [`SignalStateStore`] folds
+//! signals into per-token episodes: the first signal of an episode is
+//! actionable, later signals in the same direction are merged into it, a
+//! direction flip needs several consecutive confirmations (hysteresis), and
+//! a new episode of a signal type that just fired is held back until its
+//! cooldown has passed.
+
+use crate::error::{AgentError, AgentResult};
+use crate::models::market_signal::{MarketSignal, SignalType};
+use bigdecimal::ToPrimitive;
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Bullish,
+    Bearish,
+}
+
+impl Direction {
+    pub fn of(signal: &MarketSignal) -> Self {
+        if signal.expects_rise() {
+            Direction::Bullish
+        } else {
+            Direction::Bearish
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignalPolicy {
+    /// Minimum time between two episodes of the same type for a token
+    pub cooldown: Duration,
+    /// An episode ends when no signal has extended it for this long
+    pub episode_gap: Duration,
+    /// Consecutive opposite-direction signals needed to end an episode early
+    /// and start one in the other direction
+    pub flip_confirmations: usize,
+}
+
+impl SignalPolicy {
+    pub fn new_from_env() -> AgentResult<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            cooldown: parse_secs_env("SIGNAL_COOLDOWN_SECS", defaults.cooldown)?,
+            episode_gap: parse_secs_env("SIGNAL_EPISODE_GAP_SECS", defaults.episode_gap)?,
+            flip_confirmations: match std::env::var("SIGNAL_FLIP_CONFIRMATIONS") {
+                Ok(val) => val.parse().map_err(|_| {
+                    AgentError::InvalidConfig(
+                        "SIGNAL_FLIP_CONFIRMATIONS".to_string(),
+                        "must be a positive integer".to_string(),
+                    )
+                })?,
+                Err(_) => defaults.flip_confirmations,
+            },
+        })
+    }
+}
+
+fn parse_secs_env(key: &str, default: Duration) -> AgentResult<Duration> {
+    match std::env::var(key) {
+        Ok(val) => val.parse().map(Duration::from_secs).map_err(|_| {
+            AgentError::InvalidConfig(key.to_string(), "must be a number of seconds".to_string())
+        }),
+        Err(_) => Ok(default),
+    }
+}
+
+impl Default for SignalPolicy {
+    fn default() -> Self {
+        Self {
+            cooldown: Duration::from_secs(60 * 60),
+            episode_gap: Duration::from_secs(2 * 60 * 60),
+            flip_confirmations: 2,
+        }
+    }
+}
+
+/// A run of same-direction signals for one token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignalEpisode {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    pub token_address: String,
+    /// Type of the signal that opened the episode
+    pub signal_type: SignalType,
+    pub direction: Direction,
+    pub started_at: DateTime,
+    pub last_seen_at: DateTime,
+    /// Set once the episode is over
+    pub ended_at: Option<DateTime>,
+    pub signal_count: u32,
+    pub peak_confidence: f64,
+    /// Stored signal that opened the episode
+    pub first_signal_id: Option<ObjectId>,
+    pub last_signal_id: Option<ObjectId>,
+}
+
+impl SignalEpisode {
+    pub fn collection_name() -> &'static str {
+        "signal_episodes"
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.ended_at.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignalDecision {
+    /// The signal opened a new episode and should be acted on
+    Actionable(SignalEpisode),
+    /// The signal extended an ongoing episode
+    Merged(SignalEpisode),
+    /// The signal was dropped
+    Suppressed(String),
+}
+
+impl SignalDecision {
+    pub fn is_actionable(&self) -> bool {
+        matches!(self, SignalDecision::Actionable(_))
+    }
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    active: Option<SignalEpisode>,
+    /// Opposite-direction signals seen in a row while an episode is active
+    pending_flip: Option<(Direction, usize)>,
+    /// When each signal type last opened an episode
+    last_opened: HashMap<SignalType, DateTime>,
+}
+
+#[derive(Debug, Default)]
+pub struct SignalStateStore {
+    policy: SignalPolicy,
+    tokens: HashMap<String, TokenState>,
+    /// Episodes ended since the last call to `take_closed`
+    closed: Vec<SignalEpisode>,
+}
+
+impl SignalStateStore {
+    pub fn new(policy: SignalPolicy) -> Self {
+        Self {
+            policy,
+            tokens: HashMap::new(),
+            closed: Vec::new(),
+        }
+    }
+
+    /// Resume an episode that was open before a restart
+    pub fn restore(&mut self, episode: SignalEpisode) {
+        let state = self
+            .tokens
+            .entry(episode.token_address.clone())
+            .or_default();
+        state
+            .last_opened
+            .insert(episode.signal_type.clone(), episode.started_at);
+        if episode.is_open() {
+            state.active = Some(episode);
+        }
+    }
+
+    pub fn active(&self, token_address: &str) -> Option<&SignalEpisode> {
+        self.tokens.get(token_address)?.active.as_ref()
+    }
+
+    /// Episodes that ended since the last call
+    pub fn take_closed(&mut self) -> Vec<SignalEpisode> {
+        std::mem::take(&mut self.closed)
+    }
+
+    /// End every episode that has not been extended within the episode gap
+    pub fn expire(&mut self, now: DateTime) {
+        let gap = self.policy.episode_gap.as_millis() as i64;
+        for state in self.tokens.values_mut() {
+            let stale = state
+                .active
+                .as_ref()
+                .is_some_and(|e| now.timestamp_millis() - e.last_seen_at.timestamp_millis() > gap);
+            if stale {
+                let mut episode = state.active.take().unwrap();
+                episode.ended_at = Some(episode.last_seen_at);
+                state.pending_flip = None;
+                self.closed.push(episode);
+            }
+        }
+    }
+
+    pub fn observe(&mut self, signal: &MarketSignal, now: DateTime) -> SignalDecision {
+        self.expire(now);
+
+        let direction = Direction::of(signal);
+        let confidence = signal.confidence.to_f64().unwrap_or_default();
+        let confirmations = self.policy.flip_confirmations.max(1);
+        let cooldown = self.policy.cooldown.as_millis() as i64;
+        let state = self.tokens.entry(signal.asset_address.clone()).or_default();
+
+        if let Some(active) = state.active.as_mut() {
+            if active.direction == direction {
+                active.last_seen_at = now;
+                active.signal_count += 1;
+                active.peak_confidence = active.peak_confidence.max(confidence);
+                active.last_signal_id = signal.id;
+                state.pending_flip = None;
+                return SignalDecision::Merged(active.clone());
+            }
+
+            let seen = match state.pending_flip {
+                Some((pending, count)) if pending == direction => count + 1,
+                _ => 1,
+            };
+            if seen < confirmations {
+                state.pending_flip = Some((direction, seen));
+                return SignalDecision::Suppressed(format!(
+                    "{:?} flip awaiting confirmation ({}/{})",
+                    direction, seen, confirmations
+                ));
+            }
+
+            let mut ended = state.active.take().unwrap();
+            ended.ended_at = Some(now);
+            self.closed.push(ended);
+            state.pending_flip = None;
+        }
+
+        if let Some(last) = state.last_opened.get(&signal.signal_type) {
+            let since = now.timestamp_millis() - last.timestamp_millis();
+            if since < cooldown {
+                return SignalDecision::Suppressed(format!(
+                    "{:?} cooldown, {}s left",
+                    signal.signal_type,
+                    (cooldown - since) / 1000
+                ));
+            }
+        }
+
+        let episode = SignalEpisode {
+            id: ObjectId::new(),
+            token_address: signal.asset_address.clone(),
+            signal_type: signal.signal_type.clone(),
+            direction,
+            started_at: now,
+            last_seen_at: now,
+            ended_at: None,
+            signal_count: 1,
+            peak_confidence: confidence,
+            first_signal_id: signal.id,
+            last_signal_id: signal.id,
+        };
+        state.last_opened.insert(signal.signal_type.clone(), now);
+        state.active = Some(episode.clone());
+        SignalDecision::Actionable(episode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::market_signal::MarketSignalBuilder;
+    use crate::utils::f64_to_decimal;
+
+    fn signal(signal_type: SignalType) -> MarketSignal {
+        MarketSignalBuilder::new(
+            "token".to_string(),
+            signal_type,
+            f64_to_decimal(1.0).unwrap(),
+        )
+        .confidence(f64_to_decimal(0.7).unwrap())
+        .build()
+    }
+
+    fn at(minutes: i64) -> DateTime {
+        DateTime::from_millis(minutes * 60 * 1000)
+    }
+
+    #[test]
+    fn test_repeats_merge_into_one_episode() {
+        let mut store = SignalStateStore::new(SignalPolicy::default());
+
+        assert!(store
+            .observe(&signal(SignalType::Buy), at(0))
+            .is_actionable());
+        let merged = store.observe(&signal(SignalType::Buy), at(5));
+        let SignalDecision::Merged(episode) = merged else {
+            panic!("expected merge, got {:?}", merged);
+        };
+        assert_eq!(episode.signal_count, 2);
+        assert_eq!(episode.started_at, at(0));
+        assert_eq!(episode.last_seen_at, at(5));
+    }
+
+    #[test]
+    fn test_flip_requires_confirmation() {
+        let mut store = SignalStateStore::new(SignalPolicy::default());
+        store.observe(&signal(SignalType::Buy), at(0));
+
+        let first = store.observe(&signal(SignalType::Sell), at(5));
+        assert!(matches!(first, SignalDecision::Suppressed(_)));
+        assert_eq!(store.active("token").unwrap().direction, Direction::Bullish);
+
+        assert!(store
+            .observe(&signal(SignalType::Sell), at(10))
+            .is_actionable());
+        let closed = store.take_closed();
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].ended_at, Some(at(10)));
+        assert_eq!(store.active("token").unwrap().direction, Direction::Bearish);
+    }
+
+    #[test]
+    fn test_interrupted_flip_resets() {
+        let mut store = SignalStateStore::new(SignalPolicy::default());
+        store.observe(&signal(SignalType::Buy), at(0));
+        store.observe(&signal(SignalType::Sell), at(5));
+        store.observe(&signal(SignalType::Buy), at(10));
+
+        let again = store.observe(&signal(SignalType::Sell), at(15));
+        assert!(matches!(again, SignalDecision::Suppressed(_)));
+    }
+
+    #[test]
+    fn test_gap_ends_episode_and_cooldown_applies() {
+        let policy = SignalPolicy {
+            cooldown: Duration::from_secs(4 * 60 * 60),
+            episode_gap: Duration::from_secs(60 * 60),
+            flip_confirmations: 2,
+        };
+        let mut store = SignalStateStore::new(policy);
+        store.observe(&signal(SignalType::PriceSpike), at(0));
+
+        // Past the gap: the episode ends, but the type is still cooling down
+        let decision = store.observe(&signal(SignalType::PriceSpike), at(90));
+        assert!(matches!(decision, SignalDecision::Suppressed(_)));
+        assert_eq!(store.take_closed()[0].ended_at, Some(at(0)));
+
+        // A different type is not affected by that cooldown
+        assert!(store
+            .observe(&signal(SignalType::VolumeSurge), at(95))
+            .is_actionable());
+        assert!(store
+            .observe(&signal(SignalType::PriceSpike), at(400))
+            .is_actionable());
+    }
+}
@@ -0,0 +1,547 @@
+use super::{
+    AnalyticsQuery, AnalyticsRepository, AnalyticsSort, KolWalletRepository,
+    RecommendationRepository, SettingsRepository, SignalQuery, SignalRepository, TradeRepository,
+};
+use crate::error::{AgentError, AgentResult};
+use crate::models::config_change::ConfigChange;
+use crate::models::market_signal::MarketSignal;
+use crate::models::signal_calibration::SignalCalibration;
+use crate::models::token_analytics::TokenAnalytics;
+use crate::services::wallet_tracker::{KolOwnership, KolWallet, TokenRecommendation};
+use crate::trading::transactions::{TradeRecord, TradeStatus};
+use crate::vector_store::rank;
+use async_trait::async_trait;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use bson::{oid::ObjectId, DateTime};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+fn in_range(time: DateTime, since: Option<DateTime>, until: Option<DateTime>) -> bool {
+    since.is_none_or(|since| time >= since) && until.is_none_or(|until| time <= until)
+}
+
+fn analytics_matches(query: &AnalyticsQuery, analytics: &TokenAnalytics) -> bool {
+    query
+        .token_address
+        .as_ref()
+        .is_none_or(|address| analytics.token_address == *address)
+        && query
+            .exclude_token
+            .as_ref()
+            .is_none_or(|excluded| analytics.token_address != *excluded)
+        && query
+            .token_symbol
+            .as_ref()
+            .is_none_or(|symbol| analytics.token_symbol.eq_ignore_ascii_case(symbol))
+        && in_range(analytics.timestamp, query.since, query.until)
+        && query
+            .embedding_version
+            .as_ref()
+            .is_none_or(|version| analytics.embedding_version.as_ref() == Some(version))
+}
+
+/// Descending by value, missing values last
+fn descending(a: &Option<BigDecimal>, b: &Option<BigDecimal>) -> Ordering {
+    let value = |v: &Option<BigDecimal>| v.as_ref().and_then(|v| v.to_f64());
+    match (value(a), value(b)) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Token snapshots kept in insertion order. Nothing expires, so queries at
+/// any resolution read the raw snapshots.
+#[derive(Default)]
+pub struct MemoryAnalyticsRepository {
+    snapshots: RwLock<Vec<TokenAnalytics>>,
+}
+
+#[async_trait]
+impl AnalyticsRepository for MemoryAnalyticsRepository {
+    async fn insert(&self, analytics: &TokenAnalytics) -> AgentResult<Option<ObjectId>> {
+        let mut analytics = analytics.clone();
+        let id = *analytics.id.get_or_insert_with(ObjectId::new);
+        self.snapshots.write().unwrap().push(analytics);
+        Ok(Some(id))
+    }
+
+    async fn find(&self, query: &AnalyticsQuery) -> AgentResult<Vec<TokenAnalytics>> {
+        // Later inserts win ties, as with ObjectId order
+        let mut found: Vec<TokenAnalytics> = self
+            .snapshots
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|a| analytics_matches(query, a))
+            .cloned()
+            .collect();
+        match query.sort {
+            AnalyticsSort::Newest => found.sort_by_key(|a| Reverse(a.timestamp)),
+            AnalyticsSort::Oldest => found.sort_by_key(|a| a.timestamp),
+            AnalyticsSort::Trending => found.sort_by(|a, b| {
+                descending(&a.volume_24h, &b.volume_24h)
+                    .then_with(|| descending(&a.price_change_24h, &b.price_change_24h))
+            }),
+        }
+        if let Some(limit) = query.limit {
+            found.truncate(limit);
+        }
+        Ok(found)
+    }
+
+    async fn nearest(
+        &self,
+        vector: &[f32],
+        query: &AnalyticsQuery,
+        limit: usize,
+    ) -> AgentResult<Vec<(f32, TokenAnalytics)>> {
+        let candidates = self
+            .snapshots
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|a| analytics_matches(query, a))
+            .filter_map(|a| Some((a.embedding.clone()?, a.clone())))
+            .collect();
+        Ok(rank(vector, candidates, limit.max(1)))
+    }
+}
+
+#[derive(Default)]
+pub struct MemorySignalRepository {
+    signals: RwLock<Vec<MarketSignal>>,
+    calibrations: RwLock<Vec<SignalCalibration>>,
+}
+
+#[async_trait]
+impl SignalRepository for MemorySignalRepository {
+    async fn insert(&self, signal: &MarketSignal) -> AgentResult<Option<ObjectId>> {
+        let mut signal = signal.clone();
+        let id = *signal.id.get_or_insert_with(ObjectId::new);
+        self.signals.write().unwrap().push(signal);
+        Ok(Some(id))
+    }
+
+    async fn find(&self, query: &SignalQuery) -> AgentResult<Vec<MarketSignal>> {
+        let mut found: Vec<MarketSignal> = self
+            .signals
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|s| {
+                query
+                    .token_address
+                    .as_ref()
+                    .is_none_or(|address| s.asset_address == *address)
+                    && in_range(s.timestamp, query.since, query.until)
+            })
+            .cloned()
+            .collect();
+        found.sort_by_key(|a| Reverse(a.timestamp));
+        if let Some(limit) = query.limit {
+            found.truncate(limit);
+        }
+        Ok(found)
+    }
+
+    async fn calibrations(&self) -> AgentResult<Vec<SignalCalibration>> {
+        Ok(self.calibrations.read().unwrap().clone())
+    }
+
+    async fn save_calibration(&self, calibration: &SignalCalibration) -> AgentResult<()> {
+        let mut calibrations = self.calibrations.write().unwrap();
+        calibrations.retain(|c| c.signal_type != calibration.signal_type);
+        calibrations.push(calibration.clone());
+        Ok(())
+    }
+}
+
+/// One recommendation per token
+#[derive(Default)]
+pub struct MemoryRecommendationRepository {
+    recommendations: RwLock<Vec<TokenRecommendation>>,
+}
+
+#[async_trait]
+impl RecommendationRepository for MemoryRecommendationRepository {
+    async fn save(&self, recommendation: &TokenRecommendation) -> AgentResult<()> {
+        let mut recommendations = self.recommendations.write().unwrap();
+        let mut recommendation = recommendation.clone();
+        match recommendations.iter_mut().find(|r| {
+            r.token_address == recommendation.token_address
+                && r.analysis_date == recommendation.analysis_date
+        }) {
+            Some(existing) => {
+                recommendation.id = existing.id;
+                recommendation.outcomes = existing.outcomes.take();
+                *existing = recommendation;
+            }
+            None => {
+                recommendation.id = Some(ObjectId::new());
+                recommendation.outcomes = None;
+                recommendations.push(recommendation);
+            }
+        }
+        Ok(())
+    }
+
+    async fn get(&self, token_address: &str) -> AgentResult<Option<TokenRecommendation>> {
+        Ok(self
+            .recommendations
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| r.token_address == token_address)
+            .max_by_key(|r| r.analysis_date)
+            .cloned())
+    }
+
+    async fn recent(&self, limit: usize) -> AgentResult<Vec<TokenRecommendation>> {
+        let mut recent = self.recommendations.read().unwrap().clone();
+        recent.sort_by_key(|r| Reverse(r.analysis_date));
+        recent.truncate(limit);
+        Ok(recent)
+    }
+
+    async fn set_kol_ownership(
+        &self,
+        token_address: &str,
+        ownership: &[KolOwnership],
+    ) -> AgentResult<()> {
+        if let Some(recommendation) = self
+            .recommendations
+            .write()
+            .unwrap()
+            .iter_mut()
+            .filter(|r| r.token_address == token_address)
+            .max_by_key(|r| r.analysis_date)
+        {
+            recommendation.kol_ownership = Some(ownership.to_vec());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryKolWalletRepository {
+    wallets: RwLock<Vec<KolWallet>>,
+}
+
+#[async_trait]
+impl KolWalletRepository for MemoryKolWalletRepository {
+    async fn insert(&self, wallet: &KolWallet) -> AgentResult<Option<ObjectId>> {
+        let mut wallet = wallet.clone();
+        let id = *wallet.id.get_or_insert_with(ObjectId::new);
+        self.wallets.write().unwrap().push(wallet);
+        Ok(Some(id))
+    }
+
+    async fn active(&self) -> AgentResult<Vec<KolWallet>> {
+        Ok(self
+            .wallets
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|w| w.active)
+            .cloned()
+            .collect())
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryTradeRepository {
+    trades: RwLock<Vec<TradeRecord>>,
+}
+
+#[async_trait]
+impl TradeRepository for MemoryTradeRepository {
+    async fn insert(&self, record: &mut TradeRecord) -> AgentResult<()> {
+        record.id = Some(ObjectId::new());
+        self.trades.write().unwrap().push(record.clone());
+        Ok(())
+    }
+
+    async fn save(&self, record: &TradeRecord) -> AgentResult<()> {
+        let mut trades = self.trades.write().unwrap();
+        let existing = trades
+            .iter_mut()
+            .find(|t| t.id.is_some() && t.id == record.id)
+            .ok_or_else(|| {
+                AgentError::InvalidInput("Cannot save a trade that was never recorded".into())
+            })?;
+        *existing = record.clone();
+        Ok(())
+    }
+
+    async fn with_status(&self, status: TradeStatus) -> AgentResult<Vec<TradeRecord>> {
+        let mut found: Vec<TradeRecord> = self
+            .trades
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|t| t.status == status)
+            .cloned()
+            .collect();
+        found.sort_by_key(|t| Reverse(t.created_at));
+        Ok(found)
+    }
+}
+
+#[derive(Default)]
+pub struct MemorySettingsRepository {
+    overrides: RwLock<BTreeMap<String, String>>,
+    changes: RwLock<Vec<ConfigChange>>,
+}
+
+#[async_trait]
+impl SettingsRepository for MemorySettingsRepository {
+    async fn overrides(&self) -> AgentResult<BTreeMap<String, String>> {
+        Ok(self.overrides.read().unwrap().clone())
+    }
+
+    async fn set_override(&self, setting: &str, value: Option<&str>) -> AgentResult<()> {
+        let mut overrides = self.overrides.write().unwrap();
+        match value {
+            Some(value) => overrides.insert(setting.to_ascii_uppercase(), value.to_string()),
+            None => overrides.remove(&setting.to_ascii_uppercase()),
+        };
+        Ok(())
+    }
+
+    async fn record_change(&self, change: &ConfigChange) -> AgentResult<()> {
+        let mut change = change.clone();
+        change.id.get_or_insert_with(ObjectId::new);
+        self.changes.write().unwrap().push(change);
+        Ok(())
+    }
+
+    async fn changes(&self, limit: usize) -> AgentResult<Vec<ConfigChange>> {
+        Ok(self
+            .changes
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::money::TokenAmount;
+    use crate::services::wallet_tracker::RecommendationOutcome;
+    use crate::trading::order::OrderSide;
+    use crate::trading::order::TradeOrder;
+    use std::str::FromStr;
+
+    fn snapshot(address: &str, millis: i64, volume: Option<&str>) -> TokenAnalytics {
+        TokenAnalytics {
+            token_address: address.to_string(),
+            token_symbol: address.to_uppercase(),
+            volume_24h: volume.map(|v| BigDecimal::from_str(v).unwrap()),
+            timestamp: DateTime::from_millis(millis),
+            ..TokenAnalytics::test_snapshot(0, 1.0)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_analytics_queries() {
+        let repo = MemoryAnalyticsRepository::default();
+        for analytics in [
+            snapshot("bonk", 1_000, Some("50")),
+            snapshot("wif", 2_000, None),
+            snapshot("bonk", 3_000, Some("900")),
+            snapshot("jup", 4_000, Some("100")),
+        ] {
+            assert!(repo.insert(&analytics).await.unwrap().is_some());
+        }
+
+        let latest = repo
+            .find_one(&AnalyticsQuery::token("bonk"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.timestamp.timestamp_millis(), 3_000);
+
+        let history = repo
+            .find(
+                &AnalyticsQuery::default()
+                    .since(DateTime::from_millis(2_000))
+                    .until(DateTime::from_millis(3_000))
+                    .sort(AnalyticsSort::Oldest),
+            )
+            .await
+            .unwrap();
+        let times: Vec<_> = history
+            .iter()
+            .map(|a| a.timestamp.timestamp_millis())
+            .collect();
+        assert_eq!(times, vec![2_000, 3_000]);
+
+        let trending = repo
+            .find(
+                &AnalyticsQuery::default()
+                    .sort(AnalyticsSort::Trending)
+                    .limit(3),
+            )
+            .await
+            .unwrap();
+        let volumes: Vec<_> = trending.iter().map(|a| a.volume_24h.clone()).collect();
+        assert_eq!(
+            volumes,
+            vec![
+                Some(BigDecimal::from(900)),
+                Some(BigDecimal::from(100)),
+                Some(BigDecimal::from(50))
+            ]
+        );
+
+        let by_symbol = AnalyticsQuery {
+            token_symbol: Some("Wif".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(repo.find(&by_symbol).await.unwrap().len(), 1);
+
+        let others = AnalyticsQuery {
+            exclude_token: Some("bonk".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(repo.find(&others).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_nearest_uses_matching_embeddings() {
+        let repo = MemoryAnalyticsRepository::default();
+        let mut close = snapshot("close", 1_000, None);
+        close.embedding = Some(vec![1.0, 0.1]);
+        close.embedding_version = Some("v1:test".to_string());
+        let mut stale = snapshot("stale", 1_000, None);
+        stale.embedding = Some(vec![1.0, 0.0]);
+        stale.embedding_version = Some("v0:test".to_string());
+        let mut far = snapshot("far", 1_000, None);
+        far.embedding = Some(vec![0.0, 1.0]);
+        far.embedding_version = Some("v1:test".to_string());
+        for analytics in [&close, &stale, &far, &snapshot("plain", 1_000, None)] {
+            repo.insert(analytics).await.unwrap();
+        }
+
+        let query = AnalyticsQuery {
+            embedding_version: Some("v1:test".to_string()),
+            ..Default::default()
+        };
+        let nearest = repo.nearest(&[1.0, 0.0], &query, 5).await.unwrap();
+        let addresses: Vec<_> = nearest
+            .iter()
+            .map(|(_, a)| a.token_address.as_str())
+            .collect();
+        assert_eq!(addresses, vec!["close", "far"]);
+    }
+
+    #[tokio::test]
+    async fn test_trades_and_recommendations() {
+        let trades = MemoryTradeRepository::default();
+        let order = TradeOrder {
+            token_address: "mint".to_string(),
+            side: OrderSide::Buy,
+            quantity: TokenAmount::new(1_000_000_000, 9),
+            reason: "test".to_string(),
+        };
+        let mut record = TradeRecord::pending(&order);
+        assert!(trades.save(&record).await.is_err());
+        trades.insert(&mut record).await.unwrap();
+        record.mark_failed("rejected");
+        trades.save(&record).await.unwrap();
+        assert!(trades
+            .with_status(TradeStatus::Pending)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            trades.with_status(TradeStatus::Failed).await.unwrap()[0].error,
+            Some("rejected".to_string())
+        );
+
+        let recommendations = MemoryRecommendationRepository::default();
+        assert!(recommendations.get("mint").await.unwrap().is_none());
+        recommendations
+            .set_kol_ownership("mint", &[])
+            .await
+            .unwrap();
+        assert!(recommendations.recent(10).await.unwrap().is_empty());
+    }
+
+    fn recommendation(address: &str, secs: i64) -> TokenRecommendation {
+        TokenRecommendation {
+            id: None,
+            token_address: address.to_string(),
+            symbol: address.to_uppercase(),
+            name: address.to_string(),
+            decimals: 9,
+            logo_uri: None,
+            analysis_date: chrono::DateTime::from_timestamp(secs, 0).unwrap(),
+            overall_score: 0.8,
+            market_score: 0.8,
+            social_score: 0.8,
+            dev_score: 0.8,
+            risk_score: 0.2,
+            price: 1.0,
+            liquidity: 0.0,
+            market_cap: 0.0,
+            volume_24h: 0.0,
+            holders: 0,
+            strengths: vec![],
+            risks: vec![],
+            recommendation: "BUY".to_string(),
+            kol_ownership: None,
+            decision_reasoning: None,
+            prompt_version: None,
+            outcomes: None,
+            embedding: None,
+            embedding_version: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recommending_again_keeps_earlier_outcomes() {
+        let repo = MemoryRecommendationRepository::default();
+        repo.save(&recommendation("mint", 1_000)).await.unwrap();
+        let outcome = RecommendationOutcome {
+            evaluated_at: chrono::DateTime::from_timestamp(605_800, 0).unwrap(),
+            price: 1.5,
+            forward_return: 0.5,
+            max_drawdown: 0.1,
+            samples: 3,
+        };
+        repo.recommendations.write().unwrap()[0].outcomes =
+            Some(BTreeMap::from([("7d".to_string(), outcome)]));
+
+        // Saving the same recommendation again doesn't clear its outcome
+        repo.save(&recommendation("mint", 1_000)).await.unwrap();
+        let mut later = recommendation("mint", 2_000);
+        later.outcomes = Some(BTreeMap::new());
+        repo.save(&later).await.unwrap();
+        repo.set_kol_ownership("mint", &[]).await.unwrap();
+
+        let recent = repo.recent(10).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].analysis_date.timestamp(), 2_000);
+        assert!(recent[0].outcomes.is_none());
+        assert!(recent[0].kol_ownership.is_some());
+        let earlier = &recent[1];
+        assert_eq!(earlier.outcomes.as_ref().unwrap()["7d"].forward_return, 0.5);
+        assert!(earlier.kol_ownership.is_none());
+        assert_eq!(
+            repo.get("mint").await.unwrap().unwrap().analysis_date,
+            later.analysis_date
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Versioned schema migrations for the MongoDB database.
+//!
+//! Migrations are registered in code, in version order, and every one of
+//! them is safe to run again on a database that already has its changes.
+//! Applied versions are recorded in the `_migrations` collection, so
+//! `migrate` only runs what is missing and `rollback` undoes the newest.
+
+mod schema;
+
+use crate::config::{mongodb::MongoDbPool, EmbeddingConfig};
+use crate::error::{AgentError, AgentResult};
+use async_trait::async_trait;
+use bson::{doc, DateTime};
+use futures::TryStreamExt;
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use tracing::info;
+
+/// Collection recording applied migrations
+pub const MIGRATIONS_COLLECTION: &str = "_migrations";
+
+#[async_trait]
+pub trait Migration: Send + Sync {
+    /// Position in the migration order, unique and increasing
+    fn version(&self) -> u32;
+
+    fn name(&self) -> &'static str;
+
+    /// Apply the change. Must succeed when the change is already present.
+    async fn up(&self, db: &Database) -> AgentResult<()>;
+
+    /// Undo the change. Must succeed when the change is already absent.
+    async fn down(&self, db: &Database) -> AgentResult<()>;
+}
+
+/// Migrations of this version of the code, in order. The vector search
+/// index is sized from `embedding`.
+pub fn registry(embedding: &EmbeddingConfig) -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(schema::CreateCollections),
+        Box::new(schema::CoreIndexes),
+        Box::new(schema::Validators),
+        Box::new(schema::VectorSearchIndex {
+            index_name: embedding.index_name.clone(),
+            dimensions: embedding.dimensions,
+        }),
+        Box::new(schema::RollupIndexes),
+        Box::new(schema::RecommendationHistory),
+        Box::new(schema::SignalOutcomesByHorizon),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AppliedMigration {
+    #[serde(rename = "_id")]
+    version: u32,
+    name: String,
+    applied_at: DateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: u32,
+    pub name: String,
+    /// When the migration was applied, `None` if pending
+    pub applied_at: Option<DateTime>,
+}
+
+impl fmt::Display for MigrationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.applied_at {
+            Some(at) => match chrono::DateTime::from_timestamp_millis(at.timestamp_millis()) {
+                Some(at) => format!("applied {}", at.format("%Y-%m-%d %H:%M:%S UTC")),
+                None => "applied".to_string(),
+            },
+            None => "pending".to_string(),
+        };
+        write!(f, "{:>4}  {:<24} {}", self.version, self.name, state)
+    }
+}
+
+/// Check that versions are unique and in increasing order
+fn validate(migrations: &[Box<dyn Migration>]) -> AgentResult<()> {
+    for pair in migrations.windows(2) {
+        if pair[0].version() >= pair[1].version() {
+            return Err(AgentError::InvalidConfig(
+                "migrations".to_string(),
+                format!(
+                    "version {} ({}) is not after {} ({})",
+                    pair[1].version(),
+                    pair[1].name(),
+                    pair[0].version(),
+                    pair[0].name()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Registered migrations not yet applied, oldest first
+fn pending<'a>(
+    migrations: &'a [Box<dyn Migration>],
+    applied: &HashSet<u32>,
+) -> Vec<&'a dyn Migration> {
+    migrations
+        .iter()
+        .filter(|m| !applied.contains(&m.version()))
+        .map(|m| m.as_ref())
+        .collect()
+}
+
+/// The newest `steps` applied migrations, newest first. Fails when one of
+/// them is not registered, as it can't be undone by this code.
+fn rollback_plan<'a>(
+    migrations: &'a [Box<dyn Migration>],
+    applied: &HashSet<u32>,
+    steps: usize,
+) -> AgentResult<Vec<&'a dyn Migration>> {
+    let mut versions: Vec<u32> = applied.iter().copied().collect();
+    versions.sort_unstable_by(|a, b| b.cmp(a));
+    versions
+        .into_iter()
+        .take(steps)
+        .map(|version| {
+            migrations
+                .iter()
+                .find(|m| m.version() == version)
+                .map(|m| m.as_ref())
+                .ok_or_else(|| {
+                    AgentError::InvalidInput(format!(
+                        "migration {} is applied but not known to this build",
+                        version
+                    ))
+                })
+        })
+        .collect()
+}
+
+pub struct Migrator {
+    db: Database,
+    migrations: Vec<Box<dyn Migration>>,
+    applied: Collection<AppliedMigration>,
+}
+
+impl Migrator {
+    /// Runner over the configured database with the registered migrations
+    pub fn new(pool: &MongoDbPool, embedding: &EmbeddingConfig) -> AgentResult<Self> {
+        let db = pool.database(&pool.get_config().database);
+        Self::with_migrations(db, registry(embedding))
+    }
+
+    pub fn with_migrations(db: Database, migrations: Vec<Box<dyn Migration>>) -> AgentResult<Self> {
+        validate(&migrations)?;
+        Ok(Self {
+            applied: db.collection(MIGRATIONS_COLLECTION),
+            db,
+            migrations,
+        })
+    }
+
+    async fn applied(&self) -> AgentResult<Vec<AppliedMigration>> {
+        self.applied
+            .find(doc! {})
+            .sort(doc! { "_id": 1 })
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)
+    }
+
+    fn versions(applied: &[AppliedMigration]) -> HashSet<u32> {
+        applied.iter().map(|a| a.version).collect()
+    }
+
+    /// Every registered migration, and applied ones this build doesn't know
+    pub async fn status(&self) -> AgentResult<Vec<MigrationStatus>> {
+        let applied = self.applied().await?;
+        let mut status: Vec<MigrationStatus> = self
+            .migrations
+            .iter()
+            .map(|m| MigrationStatus {
+                version: m.version(),
+                name: m.name().to_string(),
+                applied_at: applied
+                    .iter()
+                    .find(|a| a.version == m.version())
+                    .map(|a| a.applied_at),
+            })
+            .collect();
+        status.extend(
+            applied
+                .into_iter()
+                .filter(|a| self.migrations.iter().all(|m| m.version() != a.version))
+                .map(|a| MigrationStatus {
+                    version: a.version,
+                    name: a.name,
+                    applied_at: Some(a.applied_at),
+                }),
+        );
+        status.sort_by_key(|s| s.version);
+        Ok(status)
+    }
+
+    /// Apply pending migrations in order, stopping at the first failure.
+    /// Returns the versions applied.
+    pub async fn migrate(&self) -> AgentResult<Vec<u32>> {
+        let applied = Self::versions(&self.applied().await?);
+        let mut done = Vec::new();
+        for migration in pending(&self.migrations, &applied) {
+            info!(
+                "Applying migration {} {}",
+                migration.version(),
+                migration.name()
+            );
+            migration.up(&self.db).await?;
+            self.applied
+                .insert_one(AppliedMigration {
+                    version: migration.version(),
+                    name: migration.name().to_string(),
+                    applied_at: DateTime::now(),
+                })
+                .await
+                .map_err(AgentError::Database)?;
+            done.push(migration.version());
+        }
+        Ok(done)
+    }
+
+    /// Undo the newest `steps` applied migrations. Returns the versions
+    /// rolled back, newest first.
+    pub async fn rollback(&self, steps: usize) -> AgentResult<Vec<u32>> {
+        let applied = Self::versions(&self.applied().await?);
+        let mut done = Vec::new();
+        for migration in rollback_plan(&self.migrations, &applied, steps)? {
+            info!(
+                "Rolling back migration {} {}",
+                migration.version(),
+                migration.name()
+            );
+            migration.down(&self.db).await?;
+            self.applied
+                .delete_one(doc! { "_id": migration.version() })
+                .await
+                .map_err(AgentError::Database)?;
+            done.push(migration.version());
+        }
+        Ok(done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Noop(u32);
+
+    #[async_trait]
+    impl Migration for Noop {
+        fn version(&self) -> u32 {
+            self.0
+        }
+
+        fn name(&self) -> &'static str {
+            "noop"
+        }
+
+        async fn up(&self, _db: &Database) -> AgentResult<()> {
+            Ok(())
+        }
+
+        async fn down(&self, _db: &Database) -> AgentResult<()> {
+            Ok(())
+        }
+    }
+
+    fn migrations(versions: &[u32]) -> Vec<Box<dyn Migration>> {
+        versions
+            .iter()
+            .map(|v| Box::new(Noop(*v)) as Box<dyn Migration>)
+            .collect()
+    }
+
+    #[test]
+    fn test_registry_order() {
+        assert!(validate(&registry(&EmbeddingConfig::default())).is_ok());
+        assert!(validate(&migrations(&[1, 3, 2])).is_err());
+        assert!(validate(&migrations(&[1, 1])).is_err());
+    }
+
+    #[test]
+    fn test_pending_and_rollback_plan() {
+        let all = migrations(&[1, 2, 3, 4]);
+        let applied: HashSet<u32> = [1, 3].into_iter().collect();
+
+        let versions =
+            |plan: Vec<&dyn Migration>| -> Vec<u32> { plan.iter().map(|m| m.version()).collect() };
+        assert_eq!(versions(pending(&all, &applied)), vec![2, 4]);
+        assert_eq!(versions(rollback_plan(&all, &applied, 1).unwrap()), vec![3]);
+        assert_eq!(
+            versions(rollback_plan(&all, &applied, 5).unwrap()),
+            vec![3, 1]
+        );
+
+        let unknown: HashSet<u32> = [1, 9].into_iter().collect();
+        assert!(rollback_plan(&all, &unknown, 1).is_err());
+    }
+}
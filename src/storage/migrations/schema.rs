@@ -0,0 +1,597 @@
+//! The registered migrations: collections, indexes, validators and the
+//! Atlas vector search index.
+
+use super::Migration;
+use crate::error::{AgentError, AgentResult};
+use async_trait::async_trait;
+use bson::{doc, Document};
+use mongodb::{error::ErrorKind, Database};
+use tracing::{info, warn};
+
+const NAMESPACE_NOT_FOUND: i32 = 26;
+const INDEX_NOT_FOUND: i32 = 27;
+const NAMESPACE_EXISTS: i32 = 48;
+const COMMAND_NOT_FOUND: i32 = 59;
+const INDEX_ALREADY_EXISTS: i32 = 68;
+
+fn error_code(e: &mongodb::error::Error) -> Option<i32> {
+    match e.kind.as_ref() {
+        ErrorKind::Command(c) => Some(c.code),
+        _ => None,
+    }
+}
+
+/// Run `command`, treating the listed error codes as success
+async fn run_tolerating(db: &Database, command: Document, codes: &[i32]) -> AgentResult<bool> {
+    match db.run_command(command).await {
+        Ok(_) => Ok(true),
+        Err(e) if error_code(&e).is_some_and(|c| codes.contains(&c)) => Ok(false),
+        Err(e) => Err(AgentError::Database(e)),
+    }
+}
+
+/// Whether search index commands are missing, as on a self-hosted mongod
+fn is_search_unsupported(e: &mongodb::error::Error) -> bool {
+    match e.kind.as_ref() {
+        ErrorKind::Command(c) => {
+            c.code == COMMAND_NOT_FOUND
+                || c.code == 31082
+                || c.message.contains("Search index commands")
+                || c.message.contains("Atlas")
+        }
+        _ => false,
+    }
+}
+
+/// Collections the services read and write
+const COLLECTIONS: [&str; 18] = [
+    "token_analytics",
+    "token_trending",
+    "market_signals",
+    "signal_calibration",
+    "signal_episodes",
+    "token_recommendations",
+    "kol_wallets",
+    "trades",
+    "trade_updates",
+    "positions",
+    "exit_rules",
+    "limit_orders",
+    "parent_orders",
+    "allocations",
+    "token_states",
+    "chat_sessions",
+    "token_metrics",
+    "vectors",
+];
+
+pub struct CreateCollections;
+
+#[async_trait]
+impl Migration for CreateCollections {
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "create_collections"
+    }
+
+    async fn up(&self, db: &Database) -> AgentResult<()> {
+        for name in COLLECTIONS {
+            if run_tolerating(db, doc! { "create": name }, &[NAMESPACE_EXISTS]).await? {
+                info!("Created collection {}", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Collections are kept, so that rolling back never deletes data
+    async fn down(&self, _db: &Database) -> AgentResult<()> {
+        Ok(())
+    }
+}
+
+struct IndexSpec {
+    collection: &'static str,
+    name: &'static str,
+    keys: Document,
+    unique: bool,
+}
+
+impl IndexSpec {
+    fn new(collection: &'static str, name: &'static str, keys: Document) -> Self {
+        Self {
+            collection,
+            name,
+            keys,
+            unique: false,
+        }
+    }
+
+    fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+}
+
+/// Indexes backing the queries of the services. Names already used by
+/// earlier setup scripts are kept so existing databases are matched.
+fn core_indexes() -> Vec<IndexSpec> {
+    vec![
+        IndexSpec::new(
+            "token_analytics",
+            "token_time_desc",
+            doc! { "token_address": 1, "timestamp": -1 },
+        ),
+        IndexSpec::new(
+            "token_analytics",
+            "timestamp_desc",
+            doc! { "timestamp": -1 },
+        ),
+        IndexSpec::new(
+            "token_analytics",
+            "embedding_version",
+            doc! { "embedding_version": 1 },
+        ),
+        IndexSpec::new("token_trending", "timestamp_desc", doc! { "timestamp": -1 }),
+        IndexSpec::new(
+            "token_trending",
+            "address_1_timestamp_-1",
+            doc! { "address": 1, "timestamp": -1 },
+        ),
+        IndexSpec::new(
+            "market_signals",
+            "asset_time_desc",
+            doc! { "asset_address": 1, "timestamp": -1 },
+        ),
+        IndexSpec::new("market_signals", "timestamp_desc", doc! { "timestamp": -1 }),
+        IndexSpec::new("signal_episodes", "started_at", doc! { "started_at": 1 }),
+        IndexSpec::new(
+            "token_recommendations",
+            "token_address_unique",
+            doc! { "token_address": 1 },
+        )
+        .unique(),
+        IndexSpec::new(
+            "token_recommendations",
+            "analysis_date_desc",
+            doc! { "analysis_date": -1 },
+        ),
+        IndexSpec::new("kol_wallets", "active", doc! { "active": 1 }),
+        IndexSpec::new(
+            "trades",
+            "status_created_desc",
+            doc! { "status": 1, "created_at": -1 },
+        ),
+        IndexSpec::new(
+            "positions",
+            "status_opened",
+            doc! { "status": 1, "opened_at": 1 },
+        ),
+        IndexSpec::new(
+            "limit_orders",
+            "status_created",
+            doc! { "status": 1, "created_at": 1 },
+        ),
+        IndexSpec::new(
+            "parent_orders",
+            "status_created",
+            doc! { "status": 1, "created_at": 1 },
+        ),
+        IndexSpec::new(
+            "chat_sessions",
+            "updated_at_desc",
+            doc! { "updated_at": -1 },
+        ),
+    ]
+}
+
+pub struct CoreIndexes;
+
+#[async_trait]
+impl Migration for CoreIndexes {
+    fn version(&self) -> u32 {
+        2
+    }
+
+    fn name(&self) -> &'static str {
+        "core_indexes"
+    }
+
+    /// Creating an index that exists with the same keys and options is a
+    /// no-op on the server
+    async fn up(&self, db: &Database) -> AgentResult<()> {
+        for spec in core_indexes() {
+            let mut index = doc! { "key": spec.keys, "name": spec.name };
+            if spec.unique {
+                index.insert("unique", true);
+            }
+            db.run_command(doc! { "createIndexes": spec.collection, "indexes": [index] })
+                .await
+                .map_err(AgentError::Database)?;
+            info!("Ensured index {}.{}", spec.collection, spec.name);
+        }
+        Ok(())
+    }
+
+    async fn down(&self, db: &Database) -> AgentResult<()> {
+        for spec in core_indexes() {
+            run_tolerating(
+                db,
+                doc! { "dropIndexes": spec.collection, "index": spec.name },
+                &[NAMESPACE_NOT_FOUND, INDEX_NOT_FOUND],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// JSON schemas of the collections written by the repositories. Only the
+/// fields every writer sets are required. Decimal amounts are stored as
+/// strings and left unconstrained.
+fn validators() -> Vec<(&'static str, Document)> {
+    vec![
+        (
+            "token_analytics",
+            doc! {
+                "bsonType": "object",
+                "required": ["token_address", "token_symbol", "price", "timestamp"],
+                "properties": {
+                    "token_address": { "bsonType": "string" },
+                    "token_symbol": { "bsonType": "string" },
+                    "timestamp": { "bsonType": "date" },
+                    "embedding": { "bsonType": "array" },
+                }
+            },
+        ),
+        (
+            "market_signals",
+            doc! {
+                "bsonType": "object",
+                "required": ["asset_address", "signal_type", "timestamp"],
+                "properties": {
+                    "asset_address": { "bsonType": "string" },
+                    "signal_type": { "bsonType": "string" },
+                    "timestamp": { "bsonType": "date" },
+                }
+            },
+        ),
+        (
+            "token_recommendations",
+            doc! {
+                "bsonType": "object",
+                "required": ["token_address", "symbol"],
+                "properties": {
+                    "token_address": { "bsonType": "string" },
+                    "symbol": { "bsonType": "string" },
+                    "embedding": { "bsonType": "array" },
+                }
+            },
+        ),
+        (
+            "trades",
+            doc! {
+                "bsonType": "object",
+                "required": ["token_address", "status", "created_at", "updated_at"],
+                "properties": {
+                    "token_address": { "bsonType": "string" },
+                    "status": { "enum": ["Pending", "Executed", "Failed"] },
+                    "created_at": { "bsonType": "date" },
+                    "updated_at": { "bsonType": "date" },
+                }
+            },
+        ),
+    ]
+}
+
+pub struct Validators;
+
+#[async_trait]
+impl Migration for Validators {
+    fn version(&self) -> u32 {
+        3
+    }
+
+    fn name(&self) -> &'static str {
+        "validators"
+    }
+
+    /// Validation is `moderate`, so documents written before the validator
+    /// can still be updated
+    async fn up(&self, db: &Database) -> AgentResult<()> {
+        for (collection, schema) in validators() {
+            db.run_command(doc! {
+                "collMod": collection,
+                "validator": { "$jsonSchema": schema },
+                "validationLevel": "moderate",
+                "validationAction": "error",
+            })
+            .await
+            .map_err(AgentError::Database)?;
+            info!("Set validator on {}", collection);
+        }
+        Ok(())
+    }
+
+    async fn down(&self, db: &Database) -> AgentResult<()> {
+        for (collection, _) in validators() {
+            run_tolerating(
+                db,
+                doc! { "collMod": collection, "validator": {}, "validationLevel": "off" },
+                &[NAMESPACE_NOT_FOUND],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Rolled-up collections with the field identifying the token. The retention
+/// job merges buckets on the token and bucket start, which needs a unique
+/// index on both.
+const ROLLUPS: [(&str, &str); 4] = [
+    ("token_analytics_hourly", "token_address"),
+    ("token_analytics_daily", "token_address"),
+    ("token_trending_hourly", "address"),
+    ("token_trending_daily", "address"),
+];
+
+pub struct RollupIndexes;
+
+#[async_trait]
+impl Migration for RollupIndexes {
+    fn version(&self) -> u32 {
+        5
+    }
+
+    fn name(&self) -> &'static str {
+        "rollup_indexes"
+    }
+
+    async fn up(&self, db: &Database) -> AgentResult<()> {
+        for (collection, key) in ROLLUPS {
+            db.run_command(doc! {
+                "createIndexes": collection,
+                "indexes": [{
+                    "key": { key: 1, "timestamp": 1 },
+                    "name": "bucket_unique",
+                    "unique": true,
+                }]
+            })
+            .await
+            .map_err(AgentError::Database)?;
+            info!("Ensured index {}.bucket_unique", collection);
+        }
+        Ok(())
+    }
+
+    async fn down(&self, db: &Database) -> AgentResult<()> {
+        for (collection, _) in ROLLUPS {
+            run_tolerating(
+                db,
+                doc! { "dropIndexes": collection, "index": "bucket_unique" },
+                &[NAMESPACE_NOT_FOUND, INDEX_NOT_FOUND],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps every recommendation of a token instead of one per token, so
+/// earlier recommendations keep their outcomes. The unique key moves from
+/// the token to the token and analysis date.
+pub struct RecommendationHistory;
+
+#[async_trait]
+impl Migration for RecommendationHistory {
+    fn version(&self) -> u32 {
+        6
+    }
+
+    fn name(&self) -> &'static str {
+        "recommendation_history"
+    }
+
+    async fn up(&self, db: &Database) -> AgentResult<()> {
+        run_tolerating(
+            db,
+            doc! { "dropIndexes": "token_recommendations", "index": "token_address_unique" },
+            &[NAMESPACE_NOT_FOUND, INDEX_NOT_FOUND],
+        )
+        .await?;
+        db.run_command(doc! {
+            "createIndexes": "token_recommendations",
+            "indexes": [{
+                "key": { "token_address": 1, "analysis_date": -1 },
+                "name": "token_date_unique",
+                "unique": true,
+            }]
+        })
+        .await
+        .map_err(AgentError::Database)?;
+        info!("Ensured index token_recommendations.token_date_unique");
+        Ok(())
+    }
+
+    /// Fails while a token has more than one recommendation
+    async fn down(&self, db: &Database) -> AgentResult<()> {
+        db.run_command(doc! {
+            "createIndexes": "token_recommendations",
+            "indexes": [{
+                "key": { "token_address": 1 },
+                "name": "token_address_unique",
+                "unique": true,
+            }]
+        })
+        .await
+        .map_err(AgentError::Database)?;
+        run_tolerating(
+            db,
+            doc! { "dropIndexes": "token_recommendations", "index": "token_date_unique" },
+            &[NAMESPACE_NOT_FOUND, INDEX_NOT_FOUND],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Moves the single `outcome` of market signals under `outcomes.<horizon>`,
+/// so a signal can be measured at every horizon
+pub struct SignalOutcomesByHorizon;
+
+#[async_trait]
+impl Migration for SignalOutcomesByHorizon {
+    fn version(&self) -> u32 {
+        7
+    }
+
+    fn name(&self) -> &'static str {
+        "signal_outcomes_by_horizon"
+    }
+
+    async fn up(&self, db: &Database) -> AgentResult<()> {
+        let result = db
+            .collection::<Document>("market_signals")
+            .update_many(
+                doc! { "outcome": { "$exists": true } },
+                vec![
+                    doc! { "$set": { "outcomes": { "$arrayToObject": [[{
+                        "k": "$outcome.horizon",
+                        "v": {
+                            "evaluated_at": "$outcome.evaluated_at",
+                            "price": "$outcome.price",
+                            "forward_return": "$outcome.forward_return",
+                            "hit": "$outcome.hit",
+                        },
+                    }]] } } },
+                    doc! { "$unset": "outcome" },
+                ],
+            )
+            .await
+            .map_err(AgentError::Database)?;
+        info!(
+            "Moved {} market signal outcomes under their horizon",
+            result.modified_count
+        );
+        Ok(())
+    }
+
+    /// Keeps one outcome per signal, the first horizon stored
+    async fn down(&self, db: &Database) -> AgentResult<()> {
+        db.collection::<Document>("market_signals")
+            .update_many(
+                doc! { "outcomes": { "$exists": true, "$ne": {} } },
+                vec![
+                    doc! { "$set": { "outcome": { "$let": {
+                        "vars": { "first": { "$first": { "$objectToArray": "$outcomes" } } },
+                        "in": { "$mergeObjects": [{ "horizon": "$$first.k" }, "$$first.v"] },
+                    } } } },
+                    doc! { "$unset": "outcomes" },
+                ],
+            )
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(())
+    }
+}
+
+/// Collections whose documents the embedding job embeds
+const EMBEDDED_COLLECTIONS: [&str; 2] = ["token_analytics", "token_recommendations"];
+
+/// Atlas vector search index over `embedding`. Deployments without Atlas
+/// Search skip it and rely on the in-process similarity scan.
+pub struct VectorSearchIndex {
+    pub index_name: String,
+    pub dimensions: usize,
+}
+
+impl VectorSearchIndex {
+    fn definition(&self) -> Document {
+        let mut fields = vec![doc! {
+            "type": "vector",
+            "path": crate::vector_store::EMBEDDING_FIELD,
+            "numDimensions": self.dimensions as i64,
+            "similarity": "cosine",
+        }];
+        fields.extend(
+            crate::vector_store::FILTER_FIELDS
+                .iter()
+                .map(|path| doc! { "type": "filter", "path": *path }),
+        );
+        doc! { "fields": fields }
+    }
+}
+
+#[async_trait]
+impl Migration for VectorSearchIndex {
+    fn version(&self) -> u32 {
+        4
+    }
+
+    fn name(&self) -> &'static str {
+        "vector_search_index"
+    }
+
+    async fn up(&self, db: &Database) -> AgentResult<()> {
+        for collection in EMBEDDED_COLLECTIONS {
+            let command = doc! {
+                "createSearchIndexes": collection,
+                "indexes": [{
+                    "name": &self.index_name,
+                    "type": "vectorSearch",
+                    "definition": self.definition(),
+                }]
+            };
+            match db.run_command(command).await {
+                Ok(_) => info!("Created vector index {} on {}", self.index_name, collection),
+                Err(e) if error_code(&e) == Some(INDEX_ALREADY_EXISTS) => {}
+                Err(e) if is_search_unsupported(&e) => {
+                    warn!(
+                        "Atlas Search unavailable, skipping vector index on {}: {}",
+                        collection, e
+                    );
+                }
+                Err(e) => return Err(AgentError::Database(e)),
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, db: &Database) -> AgentResult<()> {
+        for collection in EMBEDDED_COLLECTIONS {
+            match db
+                .run_command(doc! { "dropSearchIndex": collection, "name": &self.index_name })
+                .await
+            {
+                Ok(_) => info!("Dropped vector index {} on {}", self.index_name, collection),
+                Err(e)
+                    if is_search_unsupported(&e)
+                        || matches!(
+                            error_code(&e),
+                            Some(NAMESPACE_NOT_FOUND | INDEX_NOT_FOUND)
+                        ) => {}
+                Err(e) => return Err(AgentError::Database(e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_names_unique_per_collection() {
+        let indexes = core_indexes();
+        for (i, a) in indexes.iter().enumerate() {
+            assert!(COLLECTIONS.contains(&a.collection), "{}", a.collection);
+            assert!(!indexes[i + 1..]
+                .iter()
+                .any(|b| a.collection == b.collection && (a.name == b.name || a.keys == b.keys)));
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Repository traits over the collections the services read and write, with
+//! a MongoDB implementation and an in-memory one for tests and local runs.
+
+pub mod memory;
+pub mod migrations;
+pub mod mongo;
+
+use crate::config::mongodb::MongoDbPool;
+use crate::error::{AgentError, AgentResult};
+use crate::models::config_change::ConfigChange;
+use crate::models::market_signal::MarketSignal;
+use crate::models::signal_calibration::SignalCalibration;
+use crate::models::token_analytics::TokenAnalytics;
+use crate::services::wallet_tracker::{KolOwnership, KolWallet, TokenRecommendation};
+use crate::trading::transactions::{TradeRecord, TradeStatus};
+use async_trait::async_trait;
+use bson::{oid::ObjectId, DateTime};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Order of analytics query results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnalyticsSort {
+    #[default]
+    Newest,
+    Oldest,
+    /// Highest 24h volume, then highest 24h price change
+    Trending,
+}
+
+/// Granularity of a time-series read. Rollups keep the last snapshot of
+/// each bucket, stamped with the bucket start.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Resolution {
+    #[default]
+    Raw,
+    Hourly,
+    Daily,
+}
+
+impl Resolution {
+    /// Collection holding `source` at this resolution
+    pub fn collection(&self, source: &str) -> String {
+        match self {
+            Resolution::Raw => source.to_string(),
+            Resolution::Hourly => format!("{}_hourly", source),
+            Resolution::Daily => format!("{}_daily", source),
+        }
+    }
+
+    /// Length of a bucket in milliseconds, 0 for raw snapshots
+    pub fn bucket_millis(&self) -> i64 {
+        match self {
+            Resolution::Raw => 0,
+            Resolution::Hourly => 3_600_000,
+            Resolution::Daily => 86_400_000,
+        }
+    }
+}
+
+/// Conditions on stored token snapshots. Unset fields don't restrict.
+#[derive(Debug, Clone, Default)]
+pub struct AnalyticsQuery {
+    pub token_address: Option<String>,
+    /// Skip snapshots of this token
+    pub exclude_token: Option<String>,
+    /// Exact symbol, ignoring case
+    pub token_symbol: Option<String>,
+    /// Snapshots taken at or after this time
+    pub since: Option<DateTime>,
+    /// Snapshots taken at or before this time
+    pub until: Option<DateTime>,
+    pub embedding_version: Option<String>,
+    pub sort: AnalyticsSort,
+    pub limit: Option<usize>,
+    /// Raw snapshots or rollups. Backends without rollups read raw snapshots.
+    pub resolution: Resolution,
+}
+
+impl AnalyticsQuery {
+    pub fn token(address: &str) -> Self {
+        Self {
+            token_address: Some(address.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn since(mut self, since: DateTime) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn until(mut self, until: DateTime) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    pub fn sort(mut self, sort: AnalyticsSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn resolution(mut self, resolution: Resolution) -> Self {
+        self.resolution = resolution;
+        self
+    }
+}
+
+/// Conditions on stored market signals, returned newest first
+#[derive(Debug, Clone, Default)]
+pub struct SignalQuery {
+    pub token_address: Option<String>,
+    pub since: Option<DateTime>,
+    pub until: Option<DateTime>,
+    pub limit: Option<usize>,
+}
+
+#[async_trait]
+pub trait AnalyticsRepository: Send + Sync {
+    /// Store a snapshot, returning its id
+    async fn insert(&self, analytics: &TokenAnalytics) -> AgentResult<Option<ObjectId>>;
+
+    async fn find(&self, query: &AnalyticsQuery) -> AgentResult<Vec<TokenAnalytics>>;
+
+    async fn find_one(&self, query: &AnalyticsQuery) -> AgentResult<Option<TokenAnalytics>> {
+        let query = query.clone().limit(1);
+        Ok(self.find(&query).await?.into_iter().next())
+    }
+
+    /// Snapshots matching `query` whose embedding is closest to `vector`,
+    /// best first with their cosine similarity. Sort and limit of `query`
+    /// are ignored.
+    async fn nearest(
+        &self,
+        vector: &[f32],
+        query: &AnalyticsQuery,
+        limit: usize,
+    ) -> AgentResult<Vec<(f32, TokenAnalytics)>>;
+}
+
+#[async_trait]
+pub trait SignalRepository: Send + Sync {
+    /// Store a signal, returning its id
+    async fn insert(&self, signal: &MarketSignal) -> AgentResult<Option<ObjectId>>;
+
+    async fn find(&self, query: &SignalQuery) -> AgentResult<Vec<MarketSignal>>;
+
+    /// Stored calibration of every signal type
+    async fn calibrations(&self) -> AgentResult<Vec<SignalCalibration>>;
+
+    /// Replace the calibration of the calibration's signal type
+    async fn save_calibration(&self, calibration: &SignalCalibration) -> AgentResult<()>;
+}
+
+#[async_trait]
+pub trait RecommendationRepository: Send + Sync {
+    /// Store a recommendation, one per token and analysis date. Earlier
+    /// recommendations of the token keep their measured outcomes, and saving
+    /// the same one again doesn't clear its own.
+    async fn save(&self, recommendation: &TokenRecommendation) -> AgentResult<()>;
+
+    /// Latest recommendation of the token
+    async fn get(&self, token_address: &str) -> AgentResult<Option<TokenRecommendation>>;
+
+    /// Most recent recommendations by analysis date
+    async fn recent(&self, limit: usize) -> AgentResult<Vec<TokenRecommendation>>;
+
+    /// Record KOL positions on the latest recommendation of the token
+    async fn set_kol_ownership(
+        &self,
+        token_address: &str,
+        ownership: &[KolOwnership],
+    ) -> AgentResult<()>;
+}
+
+#[async_trait]
+pub trait KolWalletRepository: Send + Sync {
+    async fn insert(&self, wallet: &KolWallet) -> AgentResult<Option<ObjectId>>;
+
+    async fn active(&self) -> AgentResult<Vec<KolWallet>>;
+}
+
+#[async_trait]
+pub trait TradeRepository: Send + Sync {
+    /// Record a new trade and set its id
+    async fn insert(&self, record: &mut TradeRecord) -> AgentResult<()>;
+
+    /// Overwrite a recorded trade
+    async fn save(&self, record: &TradeRecord) -> AgentResult<()>;
+
+    /// Trades with the given status, newest first
+    async fn with_status(&self, status: TradeStatus) -> AgentResult<Vec<TradeRecord>>;
+}
+
+#[async_trait]
+pub trait SettingsRepository: Send + Sync {
+    /// Values of the `settings` document, by setting name
+    async fn overrides(&self) -> AgentResult<BTreeMap<String, String>>;
+
+    /// Set one value of the `settings` document, or remove it with `None`
+    async fn set_override(&self, setting: &str, value: Option<&str>) -> AgentResult<()>;
+
+    /// Append a reload to the audit log
+    async fn record_change(&self, change: &ConfigChange) -> AgentResult<()>;
+
+    /// Most recent audited reloads, newest first
+    async fn changes(&self, limit: usize) -> AgentResult<Vec<ConfigChange>>;
+}
+
+/// Where services keep their data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    Mongo,
+    Memory,
+}
+
+impl FromStr for StorageKind {
+    type Err = AgentError;
+
+    fn from_str(s: &str) -> AgentResult<Self> {
+        match s.to_lowercase().as_str() {
+            "mongo" | "mongodb" => Ok(StorageKind::Mongo),
+            "memory" => Ok(StorageKind::Memory),
+            _ => Err(AgentError::InvalidInput(format!(
+                "unknown storage '{}', expected mongo or memory",
+                s
+            ))),
+        }
+    }
+}
+
+/// One repository per collection, sharing a backend
+#[derive(Clone)]
+pub struct Storage {
+    pub analytics: Arc<dyn AnalyticsRepository>,
+    pub signals: Arc<dyn SignalRepository>,
+    pub recommendations: Arc<dyn RecommendationRepository>,
+    pub kol_wallets: Arc<dyn KolWalletRepository>,
+    pub trades: Arc<dyn TradeRepository>,
+    pub settings: Arc<dyn SettingsRepository>,
+}
+
+impl Storage {
+    /// Repositories over the configured database. `vector_index` is the Atlas
+    /// vector search index over `token_analytics.embedding`.
+    pub fn mongo(pool: &MongoDbPool, vector_index: &str) -> Self {
+        let db = pool.database(&pool.get_config().database);
+        Self {
+            analytics: Arc::new(mongo::MongoAnalyticsRepository::new(&db, vector_index)),
+            signals: Arc::new(mongo::MongoSignalRepository::new(&db)),
+            recommendations: Arc::new(mongo::MongoRecommendationRepository::new(&db)),
+            kol_wallets: Arc::new(mongo::MongoKolWalletRepository::new(&db)),
+            trades: Arc::new(mongo::MongoTradeRepository::new(&db)),
+            settings: Arc::new(mongo::MongoSettingsRepository::new(&db)),
+        }
+    }
+
+    /// Empty repositories kept in process memory
+    pub fn memory() -> Self {
+        Self {
+            analytics: Arc::new(memory::MemoryAnalyticsRepository::default()),
+            signals: Arc::new(memory::MemorySignalRepository::default()),
+            recommendations: Arc::new(memory::MemoryRecommendationRepository::default()),
+            kol_wallets: Arc::new(memory::MemoryKolWalletRepository::default()),
+            trades: Arc::new(memory::MemoryTradeRepository::default()),
+            settings: Arc::new(memory::MemorySettingsRepository::default()),
+        }
+    }
+}
@@ -0,0 +1,600 @@
+use super::{
+    AnalyticsQuery, AnalyticsRepository, AnalyticsSort, KolWalletRepository,
+    RecommendationRepository, Resolution, SettingsRepository, SignalQuery, SignalRepository,
+    TradeRepository,
+};
+use crate::error::{AgentError, AgentResult};
+use crate::models::config_change::ConfigChange;
+use crate::models::market_signal::MarketSignal;
+use crate::models::signal_calibration::SignalCalibration;
+use crate::models::token_analytics::TokenAnalytics;
+use crate::services::wallet_tracker::{KolOwnership, KolWallet, TokenRecommendation};
+use crate::trading::transactions::{TradeRecord, TradeStatus};
+use crate::vector_store::VectorStore;
+use async_trait::async_trait;
+use bson::{doc, oid::ObjectId, Bson, Document};
+use futures::TryStreamExt;
+use mongodb::{options::FindOptions, Collection, Database};
+use std::collections::BTreeMap;
+
+fn time_range(since: Option<bson::DateTime>, until: Option<bson::DateTime>) -> Option<Document> {
+    let mut range = doc! {};
+    if let Some(since) = since {
+        range.insert("$gte", since);
+    }
+    if let Some(until) = until {
+        range.insert("$lte", until);
+    }
+    (!range.is_empty()).then_some(range)
+}
+
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Filter document for the conditions of an analytics query
+pub fn analytics_filter(query: &AnalyticsQuery) -> Document {
+    let mut filter = doc! {};
+    if let Some(address) = &query.token_address {
+        filter.insert("token_address", address);
+    } else if let Some(excluded) = &query.exclude_token {
+        filter.insert("token_address", doc! { "$ne": excluded });
+    }
+    if let Some(symbol) = &query.token_symbol {
+        filter.insert(
+            "token_symbol",
+            doc! { "$regex": format!("^{}$", escape_regex(symbol)), "$options": "i" },
+        );
+    }
+    if let Some(range) = time_range(query.since, query.until) {
+        filter.insert("timestamp", range);
+    }
+    if let Some(version) = &query.embedding_version {
+        filter.insert("embedding_version", version);
+    }
+    filter
+}
+
+/// Decimal value of a field stored as a decimal string, null when missing
+/// or unparseable
+fn to_decimal(field: &str) -> Document {
+    doc! {
+        "$convert": {
+            "input": format!("${}", field),
+            "to": "decimal",
+            "onError": Bson::Null,
+            "onNull": Bson::Null,
+        }
+    }
+}
+
+/// Trending needs an aggregation: amounts are stored as decimal strings,
+/// which a plain sort would order alphabetically
+fn trending_pipeline(query: &AnalyticsQuery) -> Vec<Document> {
+    let mut pipeline = vec![
+        doc! { "$match": analytics_filter(query) },
+        doc! { "$addFields": {
+            "_trending_volume": to_decimal("volume_24h"),
+            "_trending_change": to_decimal("price_change_24h"),
+        } },
+        doc! { "$sort": { "_trending_volume": -1, "_trending_change": -1, "_id": -1 } },
+    ];
+    if let Some(limit) = query.limit {
+        pipeline.push(doc! { "$limit": limit as i64 });
+    }
+    pipeline.push(doc! { "$unset": ["_trending_volume", "_trending_change"] });
+    pipeline
+}
+
+pub struct MongoAnalyticsRepository {
+    collection: Collection<TokenAnalytics>,
+    hourly: Collection<TokenAnalytics>,
+    daily: Collection<TokenAnalytics>,
+    vectors: VectorStore<TokenAnalytics>,
+}
+
+impl MongoAnalyticsRepository {
+    pub fn new(db: &Database, vector_index: &str) -> Self {
+        let collection = db.collection("token_analytics");
+        Self {
+            vectors: VectorStore::new(collection.clone(), vector_index),
+            hourly: db.collection(&Resolution::Hourly.collection("token_analytics")),
+            daily: db.collection(&Resolution::Daily.collection("token_analytics")),
+            collection,
+        }
+    }
+
+    fn at(&self, resolution: Resolution) -> &Collection<TokenAnalytics> {
+        match resolution {
+            Resolution::Raw => &self.collection,
+            Resolution::Hourly => &self.hourly,
+            Resolution::Daily => &self.daily,
+        }
+    }
+}
+
+#[async_trait]
+impl AnalyticsRepository for MongoAnalyticsRepository {
+    async fn insert(&self, analytics: &TokenAnalytics) -> AgentResult<Option<ObjectId>> {
+        let result = self
+            .collection
+            .insert_one(analytics)
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(result.inserted_id.as_object_id())
+    }
+
+    async fn find(&self, query: &AnalyticsQuery) -> AgentResult<Vec<TokenAnalytics>> {
+        let sort = match query.sort {
+            AnalyticsSort::Newest => doc! { "timestamp": -1 },
+            AnalyticsSort::Oldest => doc! { "timestamp": 1 },
+            AnalyticsSort::Trending => {
+                return self
+                    .at(query.resolution)
+                    .aggregate(trending_pipeline(query))
+                    .await
+                    .map_err(AgentError::Database)?
+                    .with_type::<TokenAnalytics>()
+                    .try_collect()
+                    .await
+                    .map_err(AgentError::Database)
+            }
+        };
+        let options = FindOptions::builder()
+            .sort(sort)
+            .limit(query.limit.map(|l| l as i64))
+            .build();
+        self.at(query.resolution)
+            .find(analytics_filter(query))
+            .with_options(options)
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)
+    }
+
+    async fn nearest(
+        &self,
+        vector: &[f32],
+        query: &AnalyticsQuery,
+        limit: usize,
+    ) -> AgentResult<Vec<(f32, TokenAnalytics)>> {
+        self.vectors
+            .top_n_by_vector(vector, limit, analytics_filter(query))
+            .await
+            .map_err(|e| AgentError::VectorStore(e.to_string()))
+    }
+}
+
+pub struct MongoSignalRepository {
+    signals: Collection<MarketSignal>,
+    calibrations: Collection<SignalCalibration>,
+}
+
+impl MongoSignalRepository {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            signals: db.collection("market_signals"),
+            calibrations: db.collection(SignalCalibration::collection_name()),
+        }
+    }
+}
+
+#[async_trait]
+impl SignalRepository for MongoSignalRepository {
+    async fn insert(&self, signal: &MarketSignal) -> AgentResult<Option<ObjectId>> {
+        let result = self
+            .signals
+            .insert_one(signal)
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(result.inserted_id.as_object_id())
+    }
+
+    async fn find(&self, query: &SignalQuery) -> AgentResult<Vec<MarketSignal>> {
+        let mut filter = doc! {};
+        if let Some(address) = &query.token_address {
+            filter.insert("asset_address", address);
+        }
+        if let Some(range) = time_range(query.since, query.until) {
+            filter.insert("timestamp", range);
+        }
+        let options = FindOptions::builder()
+            .sort(doc! { "timestamp": -1 })
+            .limit(query.limit.map(|l| l as i64))
+            .build();
+        self.signals
+            .find(filter)
+            .with_options(options)
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)
+    }
+
+    async fn calibrations(&self) -> AgentResult<Vec<SignalCalibration>> {
+        self.calibrations
+            .find(doc! {})
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)
+    }
+
+    async fn save_calibration(&self, calibration: &SignalCalibration) -> AgentResult<()> {
+        self.calibrations
+            .replace_one(
+                doc! { "signal_type": bson::to_bson(&calibration.signal_type)? },
+                calibration,
+            )
+            .upsert(true)
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(())
+    }
+}
+
+pub struct MongoRecommendationRepository {
+    collection: Collection<TokenRecommendation>,
+}
+
+impl MongoRecommendationRepository {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            collection: db.collection("token_recommendations"),
+        }
+    }
+}
+
+#[async_trait]
+impl RecommendationRepository for MongoRecommendationRepository {
+    async fn save(&self, recommendation: &TokenRecommendation) -> AgentResult<()> {
+        let mut document = bson::to_document(recommendation)?;
+        document.remove("_id");
+        document.remove("outcomes");
+        self.collection
+            .update_one(
+                doc! {
+                    "token_address": &recommendation.token_address,
+                    "analysis_date": bson::to_bson(&recommendation.analysis_date)?,
+                },
+                doc! { "$set": document },
+            )
+            .upsert(true)
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(())
+    }
+
+    async fn get(&self, token_address: &str) -> AgentResult<Option<TokenRecommendation>> {
+        self.collection
+            .find_one(doc! { "token_address": token_address })
+            .sort(doc! { "analysis_date": -1 })
+            .await
+            .map_err(AgentError::Database)
+    }
+
+    async fn recent(&self, limit: usize) -> AgentResult<Vec<TokenRecommendation>> {
+        self.collection
+            .find(doc! {})
+            .sort(doc! { "analysis_date": -1 })
+            .limit(limit as i64)
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)
+    }
+
+    async fn set_kol_ownership(
+        &self,
+        token_address: &str,
+        ownership: &[KolOwnership],
+    ) -> AgentResult<()> {
+        self.collection
+            .find_one_and_update(
+                doc! { "token_address": token_address },
+                doc! { "$set": { "kol_ownership": bson::to_bson(ownership)? } },
+            )
+            .sort(doc! { "analysis_date": -1 })
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(())
+    }
+}
+
+pub struct MongoKolWalletRepository {
+    collection: Collection<KolWallet>,
+}
+
+impl MongoKolWalletRepository {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            collection: db.collection("kol_wallets"),
+        }
+    }
+}
+
+#[async_trait]
+impl KolWalletRepository for MongoKolWalletRepository {
+    async fn insert(&self, wallet: &KolWallet) -> AgentResult<Option<ObjectId>> {
+        let result = self
+            .collection
+            .insert_one(wallet)
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(result.inserted_id.as_object_id())
+    }
+
+    async fn active(&self) -> AgentResult<Vec<KolWallet>> {
+        self.collection
+            .find(doc! { "active": true })
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)
+    }
+}
+
+pub struct MongoTradeRepository {
+    trades: Collection<TradeRecord>,
+}
+
+impl MongoTradeRepository {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            trades: db.collection("trades"),
+        }
+    }
+}
+
+#[async_trait]
+impl TradeRepository for MongoTradeRepository {
+    async fn insert(&self, record: &mut TradeRecord) -> AgentResult<()> {
+        let result = self
+            .trades
+            .insert_one(&*record)
+            .await
+            .map_err(AgentError::Database)?;
+        record.id = result.inserted_id.as_object_id();
+        Ok(())
+    }
+
+    async fn save(&self, record: &TradeRecord) -> AgentResult<()> {
+        let id = record.id.ok_or_else(|| {
+            AgentError::InvalidInput("Cannot save a trade that was never recorded".into())
+        })?;
+        self.trades
+            .replace_one(doc! { "_id": id }, record)
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(())
+    }
+
+    async fn with_status(&self, status: TradeStatus) -> AgentResult<Vec<TradeRecord>> {
+        self.trades
+            .find(doc! { "status": bson::to_bson(&status)? })
+            .sort(doc! { "created_at": -1 })
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)
+    }
+}
+
+/// `_id` of the document in `settings` holding the live overrides
+const LIVE_SETTINGS_ID: &str = "live";
+
+/// Setting value in the form the environment variable takes. Lists become
+/// comma separated.
+fn setting_value(value: &Bson) -> Option<String> {
+    match value {
+        Bson::String(s) => Some(s.clone()),
+        Bson::Double(x) => Some(x.to_string()),
+        Bson::Int32(i) => Some(i.to_string()),
+        Bson::Int64(i) => Some(i.to_string()),
+        Bson::Decimal128(d) => Some(d.to_string()),
+        Bson::Boolean(b) => Some(b.to_string()),
+        Bson::Array(items) => items
+            .iter()
+            .map(setting_value)
+            .collect::<Option<Vec<_>>>()
+            .map(|items| items.join(",")),
+        _ => None,
+    }
+}
+
+pub struct MongoSettingsRepository {
+    settings: Collection<Document>,
+    audit: Collection<ConfigChange>,
+}
+
+impl MongoSettingsRepository {
+    pub fn new(db: &Database) -> Self {
+        Self {
+            settings: db.collection("settings"),
+            audit: db.collection(ConfigChange::collection_name()),
+        }
+    }
+}
+
+#[async_trait]
+impl SettingsRepository for MongoSettingsRepository {
+    async fn overrides(&self) -> AgentResult<BTreeMap<String, String>> {
+        let Some(document) = self
+            .settings
+            .find_one(doc! { "_id": LIVE_SETTINGS_ID })
+            .await
+            .map_err(AgentError::Database)?
+        else {
+            return Ok(BTreeMap::new());
+        };
+        let Ok(values) = document.get_document("values") else {
+            return Ok(BTreeMap::new());
+        };
+
+        let mut overrides = BTreeMap::new();
+        for (name, value) in values {
+            let value = setting_value(value).ok_or_else(|| {
+                AgentError::InvalidConfig(
+                    format!("settings.{}", name),
+                    "must be a string, number, bool or list".into(),
+                )
+            })?;
+            overrides.insert(name.to_ascii_uppercase(), value);
+        }
+        Ok(overrides)
+    }
+
+    async fn set_override(&self, setting: &str, value: Option<&str>) -> AgentResult<()> {
+        let field = format!("values.{}", setting);
+        let update = match value {
+            Some(value) => doc! {
+                "$set": { field: value, "updated_at": bson::DateTime::now() },
+            },
+            None => doc! {
+                "$unset": { field: "" },
+                "$set": { "updated_at": bson::DateTime::now() },
+            },
+        };
+        self.settings
+            .update_one(doc! { "_id": LIVE_SETTINGS_ID }, update)
+            .upsert(true)
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(())
+    }
+
+    async fn record_change(&self, change: &ConfigChange) -> AgentResult<()> {
+        self.audit
+            .insert_one(change)
+            .await
+            .map_err(AgentError::Database)?;
+        Ok(())
+    }
+
+    async fn changes(&self, limit: usize) -> AgentResult<Vec<ConfigChange>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "timestamp": -1 })
+            .limit(limit as i64)
+            .build();
+        self.audit
+            .find(doc! {})
+            .with_options(options)
+            .await
+            .map_err(AgentError::Database)?
+            .try_collect()
+            .await
+            .map_err(AgentError::Database)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analytics_filter() {
+        let query = AnalyticsQuery {
+            exclude_token: Some("mint".to_string()),
+            token_symbol: Some("bonk".to_string()),
+            ..Default::default()
+        }
+        .since(bson::DateTime::from_millis(1_000));
+
+        let filter = analytics_filter(&query);
+        assert_eq!(
+            filter.get_document("token_address").unwrap(),
+            &doc! { "$ne": "mint" }
+        );
+        assert_eq!(
+            filter.get_document("token_symbol").unwrap(),
+            &doc! { "$regex": "^bonk$", "$options": "i" }
+        );
+        assert_eq!(
+            filter.get_document("timestamp").unwrap(),
+            &doc! { "$gte": bson::DateTime::from_millis(1_000) }
+        );
+        assert!(analytics_filter(&AnalyticsQuery::default()).is_empty());
+        assert_eq!(escape_regex("a.b$"), "a\\.b\\$");
+    }
+
+    #[test]
+    fn test_trending_sorts_on_decimal_values() {
+        let pipeline = trending_pipeline(&AnalyticsQuery::default().limit(5));
+        let stages: Vec<_> = pipeline.iter().map(|s| s.keys().next().unwrap()).collect();
+        assert_eq!(
+            stages,
+            vec!["$match", "$addFields", "$sort", "$limit", "$unset"]
+        );
+        assert_eq!(
+            pipeline[1]
+                .get_document("$addFields")
+                .unwrap()
+                .get_document("_trending_volume")
+                .unwrap(),
+            &to_decimal("volume_24h")
+        );
+    }
+
+    fn snapshot(address: &str, volume: Option<&str>, change: &str) -> TokenAnalytics {
+        use std::str::FromStr;
+
+        TokenAnalytics {
+            token_address: address.to_string(),
+            volume_24h: volume.map(|v| bigdecimal::BigDecimal::from_str(v).unwrap()),
+            price_change_24h: Some(bigdecimal::BigDecimal::from_str(change).unwrap()),
+            ..TokenAnalytics::test_snapshot(0, 1.0)
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs MongoDB at MONGODB_URI or localhost:27017"]
+    async fn test_trending_matches_memory_backend() {
+        use crate::storage::memory::MemoryAnalyticsRepository;
+
+        let uri =
+            std::env::var("MONGODB_URI").unwrap_or_else(|_| "mongodb://localhost:27017".into());
+        let client = mongodb::Client::with_uri_str(&uri).await.unwrap();
+        let db = client.database(&format!("cainam_test_{}", std::process::id()));
+        let mongo = MongoAnalyticsRepository::new(&db, "vector_index");
+        let memory = MemoryAnalyticsRepository::default();
+
+        for analytics in [
+            snapshot("a", Some("50"), "1"),
+            snapshot("b", None, "9"),
+            snapshot("c", Some("900"), "1"),
+            snapshot("d", Some("100"), "1"),
+            snapshot("e", Some("100"), "2.5"),
+            snapshot("f", Some("9.5"), "1"),
+        ] {
+            mongo.insert(&analytics).await.unwrap();
+            memory.insert(&analytics).await.unwrap();
+        }
+
+        let query = AnalyticsQuery::default()
+            .sort(AnalyticsSort::Trending)
+            .limit(5);
+        let tokens = |found: Vec<TokenAnalytics>| -> Vec<String> {
+            found.into_iter().map(|a| a.token_address).collect()
+        };
+        let from_mongo = tokens(mongo.find(&query).await.unwrap());
+        let from_memory = tokens(memory.find(&query).await.unwrap());
+        db.drop().await.unwrap();
+
+        assert_eq!(from_mongo, vec!["c", "e", "d", "a", "f"]);
+        assert_eq!(from_mongo, from_memory);
+    }
+}
@@ -0,0 +1,232 @@
+use super::{rule_signal, Strategy, StrategyContext};
+use crate::config::strategy_config::EnsembleMode;
+use crate::models::market_signal::{MarketSignal, SignalType};
+use anyhow::Result;
+use async_trait::async_trait;
+use bigdecimal::ToPrimitive;
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::warn;
+
+/// One member's opinion in an ensemble decision
+#[derive(Debug, Clone, Serialize)]
+pub struct MemberVote {
+    pub strategy: String,
+    pub weight: f64,
+    /// 1.0 bullish, -1.0 bearish, 0.0 abstained
+    pub direction: f64,
+    pub confidence: f64,
+}
+
+/// Combines several strategies into one signal per token.
+///
+/// Each member contributes its strongest directional signal. A member that
+/// has no opinion or fails abstains but still counts towards the total
+/// weight, so the ensemble only acts when enough of it agrees.
+pub struct EnsembleStrategy {
+    members: Vec<(Arc<dyn Strategy>, f64)>,
+    pub mode: EnsembleMode,
+    /// Minimum agreeing weight share (vote) or absolute score (weighted)
+    pub threshold: f64,
+}
+
+impl EnsembleStrategy {
+    pub fn new(mode: EnsembleMode, threshold: f64) -> Self {
+        Self {
+            members: Vec::new(),
+            mode,
+            threshold,
+        }
+    }
+
+    pub fn with_member(mut self, strategy: Arc<dyn Strategy>, weight: f64) -> Self {
+        self.members.push((strategy, weight));
+        self
+    }
+
+    /// Bullish (positive) or bearish (negative) score and confidence, or
+    /// `None` when the members do not agree enough
+    fn decide(&self, votes: &[MemberVote]) -> Option<(f64, f64)> {
+        let total: f64 = votes.iter().map(|v| v.weight).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        match self.mode {
+            EnsembleMode::Vote => {
+                let side = |dir: f64| votes.iter().filter(move |v| v.direction == dir);
+                let bull: f64 = side(1.0).map(|v| v.weight).sum();
+                let bear: f64 = side(-1.0).map(|v| v.weight).sum();
+                if bull == bear {
+                    return None;
+                }
+                let direction = if bull > bear { 1.0 } else { -1.0 };
+                let share = bull.max(bear) / total;
+                if share < self.threshold {
+                    return None;
+                }
+                let confidence = side(direction)
+                    .map(|v| v.weight * v.confidence)
+                    .sum::<f64>()
+                    / total;
+                Some((direction, confidence))
+            }
+            EnsembleMode::Weighted => {
+                let score = votes
+                    .iter()
+                    .map(|v| v.weight * v.direction * v.confidence)
+                    .sum::<f64>()
+                    / total;
+                (score != 0.0 && score.abs() >= self.threshold)
+                    .then_some((score.signum(), score.abs()))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Strategy for EnsembleStrategy {
+    fn name(&self) -> &str {
+        "ensemble"
+    }
+
+    async fn evaluate(&self, ctx: &StrategyContext) -> Result<Vec<MarketSignal>> {
+        let mut votes = Vec::with_capacity(self.members.len());
+        let mut strongest: Vec<Option<MarketSignal>> = Vec::with_capacity(self.members.len());
+
+        for (strategy, weight) in &self.members {
+            let signal = match strategy.evaluate(ctx).await {
+                Ok(signals) => signals
+                    .into_iter()
+                    .filter(|s| s.signal_type != SignalType::Hold)
+                    .max_by(|a, b| a.confidence.cmp(&b.confidence)),
+                Err(e) => {
+                    warn!("Strategy {} failed, abstaining: {}", strategy.name(), e);
+                    None
+                }
+            };
+            votes.push(MemberVote {
+                strategy: strategy.name().to_string(),
+                weight: *weight,
+                direction: match &signal {
+                    Some(s) if s.expects_rise() => 1.0,
+                    Some(_) => -1.0,
+                    None => 0.0,
+                },
+                confidence: signal
+                    .as_ref()
+                    .and_then(|s| s.confidence.to_f64())
+                    .unwrap_or_default(),
+            });
+            strongest.push(signal);
+        }
+
+        let Some((direction, confidence)) = self.decide(&votes) else {
+            return Ok(Vec::new());
+        };
+
+        let reason = votes
+            .iter()
+            .filter(|v| v.direction != 0.0)
+            .map(|v| format!("{} {:+}", v.strategy, v.direction * v.confidence))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut signal = rule_signal(self.name(), ctx, direction > 0.0, confidence, reason)?;
+
+        if let Some(metadata) = signal.metadata.as_mut() {
+            metadata.insert("votes", bson::to_bson(&votes)?);
+            // Keep the execution plan of the most confident agreeing member
+            let plan = votes
+                .iter()
+                .zip(&strongest)
+                .filter(|(v, _)| v.direction == direction)
+                .max_by(|(a, _), (b, _)| a.confidence.total_cmp(&b.confidence))
+                .and_then(|(_, s)| s.as_ref()?.metadata.as_ref()?.get("recommendation"));
+            if let Some(plan) = plan {
+                metadata.insert("recommendation", plan.clone());
+            }
+        }
+        Ok(vec![signal])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::token_analytics::TokenAnalytics;
+    use anyhow::anyhow;
+
+    /// Member that always says the same thing
+    struct Fixed(&'static str, Option<(bool, f64)>);
+
+    #[async_trait]
+    impl Strategy for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        async fn evaluate(&self, ctx: &StrategyContext) -> Result<Vec<MarketSignal>> {
+            match self.1 {
+                Some((bullish, confidence)) => Ok(vec![rule_signal(
+                    self.0,
+                    ctx,
+                    bullish,
+                    confidence,
+                    String::new(),
+                )?]),
+                None => Err(anyhow!("no data")),
+            }
+        }
+    }
+
+    fn context() -> StrategyContext {
+        StrategyContext::new(TokenAnalytics::test_snapshot(0, 1.0), Vec::new(), 10.0)
+    }
+
+    fn member(name: &'static str, opinion: Option<(bool, f64)>) -> Arc<dyn Strategy> {
+        Arc::new(Fixed(name, opinion))
+    }
+
+    #[tokio::test]
+    async fn test_vote() {
+        let ensemble = EnsembleStrategy::new(EnsembleMode::Vote, 0.5)
+            .with_member(member("a", Some((true, 0.6))), 1.0)
+            .with_member(member("b", Some((true, 0.8))), 1.0)
+            .with_member(member("c", Some((false, 0.9))), 1.0);
+
+        let signals = ensemble.evaluate(&context()).await.unwrap();
+        assert_eq!(signals.len(), 1);
+        assert!(signals[0].expects_rise());
+        let votes = signals[0]
+            .metadata
+            .as_ref()
+            .unwrap()
+            .get_array("votes")
+            .unwrap();
+        assert_eq!(votes.len(), 3);
+
+        // A failing member abstains and the rest no longer carry the majority
+        let ensemble = EnsembleStrategy::new(EnsembleMode::Vote, 0.5)
+            .with_member(member("a", Some((true, 0.6))), 1.0)
+            .with_member(member("b", None), 1.0)
+            .with_member(member("c", Some((false, 0.9))), 1.0);
+        assert!(ensemble.evaluate(&context()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_weighted() {
+        let ensemble = EnsembleStrategy::new(EnsembleMode::Weighted, 0.3)
+            .with_member(member("a", Some((true, 0.5))), 1.0)
+            .with_member(member("b", Some((false, 0.9))), 3.0);
+
+        let signals = ensemble.evaluate(&context()).await.unwrap();
+        assert!(!signals[0].expects_rise());
+        let confidence = signals[0].confidence.to_f64().unwrap();
+        assert!((confidence - 0.55).abs() < 1e-6);
+
+        let ensemble = EnsembleStrategy::new(EnsembleMode::Weighted, 0.3)
+            .with_member(member("a", Some((true, 0.5))), 1.0)
+            .with_member(member("b", Some((false, 0.5))), 1.0);
+        assert!(ensemble.evaluate(&context()).await.unwrap().is_empty());
+    }
+}
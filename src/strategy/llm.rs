@@ -1,28 +1,265 @@
-use crate::market_data::{birdeye::BirdEyeProvider, DataProvider};
+use super::recommendation::TradeRecommendation;
+use super::{Strategy, StrategyContext};
+use crate::llm::LlmBackend;
+use crate::models::market_signal::MarketSignal;
 use anyhow::Result;
+use async_trait::async_trait;
+use bigdecimal::ToPrimitive;
+use std::fmt::Write;
 use std::sync::Arc;
-use tracing::{debug, instrument};
+use tracing::{debug, info, instrument};
 
+/// Most recent candles included in the prompt
+const PROMPT_CANDLES: usize = 24;
+
+const RESPONSE_FORMAT: &str = r#"{
+    "action": "Buy|Sell|Hold",
+    "token_address": "string",
+    "amount_in_sol": number,
+    "reasoning": "string",
+    "confidence": number (0.0-1.0),
+    "risk_assessment": "string",
+    "market_analysis": {
+        "volume_analysis": {
+            "current_volume_usd": number,
+            "volume_change_24h": number,
+            "is_volume_bullish": boolean,
+            "analysis": "string"
+        },
+        "price_trend": {
+            "current_trend": "string",
+            "support_levels": [number],
+            "resistance_levels": [number],
+            "trend_strength": number (0.0-1.0)
+        },
+        "liquidity_assessment": {
+            "liquidity_score": number (0.0-1.0),
+            "slippage_estimate": number,
+            "is_liquid_enough": boolean
+        },
+        "momentum_indicators": {
+            "rsi_14": number,
+            "macd": {
+                "value": number,
+                "signal": "bullish|bearish|neutral"
+            },
+            "overall_momentum": "strong_buy|buy|neutral|sell|strong_sell"
+        },
+        "on_chain_metrics": {
+            "unique_holders": number,
+            "holder_concentration": number (0.0-1.0),
+            "smart_money_flow": "inflow|outflow|neutral"
+        }
+    },
+    "execution_strategy": {
+        "entry_type": "market|limit",
+        "limit_price": number (required for limit entries),
+        "position_size_sol": number,
+        "stop_loss_pct": number (fraction below entry, e.g. 0.1 for 10%),
+        "take_profit_levels": [{
+            "price_target": number,
+            "size_pct": number (fraction of the position, levels sum to at most 1.0)
+        }],
+        "time_horizon": "short|medium|long",
+        "dca_strategy": {
+            "should_dca": boolean,
+            "interval_hours": number,
+            "num_entries": number
+        }
+    }
+}"#;
+
+const CRITERIA: &str = "\
+1. Volume should show significant increase (>50% 24h change) with sustainable growth
+2. Price action should show clear trend with identifiable support/resistance levels
+3. Liquidity should be sufficient to enter/exit position with <2% slippage
+4. Momentum indicators should align with the overall trend
+5. Smart money flow should indicate institutional interest
+6. Risk:reward ratio should be at least 1:3 for any trade";
+
+/// Strategy that asks an LLM for a full trade plan
 pub struct LLMStrategy {
-    birdeye: Arc<BirdEyeProvider>,
+    backend: Arc<dyn LlmBackend>,
+    /// Confidence at which a recommendation becomes a StrongBuy/StrongSell
+    pub strong_confidence: f64,
 }
 
 impl LLMStrategy {
-    pub fn new(birdeye: Arc<BirdEyeProvider>) -> Self {
-        Self { birdeye }
-    }
-
-    #[instrument(skip(self))]
-    pub async fn analyze_trading_opportunity(&self, prompt: &str, sol_balance: f64) -> Result<String> {
-        debug!("Analyzing trading opportunity with prompt: {}", prompt);
-        
-        // Format the analysis with the available SOL balance
-        let analysis = format!(
-            "Available SOL: {}\n{}",
-            sol_balance,
-            prompt
+    pub fn new(backend: Arc<dyn LlmBackend>) -> Self {
+        Self {
+            backend,
+            strong_confidence: 0.8,
+        }
+    }
+
+    pub fn build_prompt(&self, ctx: &StrategyContext) -> String {
+        let a = &ctx.analytics;
+        let num = |v: &Option<bigdecimal::BigDecimal>| {
+            v.as_ref()
+                .and_then(|d| d.to_f64())
+                .map(|d| format!("{:.2}", d))
+                .unwrap_or_else(|| "n/a".to_string())
+        };
+
+        let mut prompt = String::new();
+        let _ = writeln!(
+            prompt,
+            "Token: {} ({})\nAddress: {}\nPrice: ${}\n24h price change: {}%\n24h volume: ${}\n\
+             Liquidity: ${}\nMarket cap: ${}\nHolders: {}",
+            a.token_name,
+            a.token_symbol,
+            a.token_address,
+            a.price,
+            num(&a.price_change_24h),
+            num(&a.volume_24h),
+            num(&a.liquidity),
+            num(&a.market_cap),
+            a.holder_count
+                .map(|h| h.to_string())
+                .unwrap_or_else(|| "n/a".to_string()),
+        );
+
+        let _ = writeln!(
+            prompt,
+            "\nHourly candles (oldest first, open/high/low/close):"
+        );
+        let skip = ctx.candles.len().saturating_sub(PROMPT_CANDLES);
+        for c in &ctx.candles[skip..] {
+            let _ = writeln!(
+                prompt,
+                "  {} {:.8} {:.8} {:.8} {:.8}",
+                c.timestamp, c.open, c.high, c.low, c.close
+            );
+        }
+
+        let _ = writeln!(prompt, "\nOur open positions:");
+        if ctx.positions.is_empty() {
+            let _ = writeln!(prompt, "  none");
+        }
+        for p in &ctx.positions {
+            let _ = writeln!(
+                prompt,
+                "  {} tokens at ${:.8} entry, realized PnL ${:.2}",
+                p.quantity, p.entry_price, p.realized_pnl
+            );
+        }
+
+        let _ = writeln!(prompt, "\nKOL positions:");
+        if ctx.kol_positions.is_empty() {
+            let _ = writeln!(prompt, "  none");
+        }
+        for k in &ctx.kol_positions {
+            let _ = writeln!(
+                prompt,
+                "  {} entered {} with {:.2}",
+                k.name,
+                k.entry_time.format("%Y-%m-%d %H:%M"),
+                k.position_size
+            );
+        }
+
+        let _ = write!(
+            prompt,
+            "\nAnalyze this trading opportunity and provide a detailed recommendation in the \
+             following JSON format:\n{}\n\nAvailable SOL balance: {} SOL\n\n\
+             Consider the following criteria for the analysis:\n{}",
+            RESPONSE_FORMAT, ctx.sol_balance, CRITERIA
+        );
+        prompt
+    }
+
+    #[instrument(skip(self, ctx), fields(token = %ctx.analytics.token_symbol))]
+    pub async fn recommend(&self, ctx: &StrategyContext) -> Result<TradeRecommendation> {
+        let prompt = self.build_prompt(ctx);
+        debug!(
+            "Requesting trade recommendation from {}",
+            self.backend.model()
         );
-        
-        Ok(analysis)
+
+        let response = self.backend.complete(&prompt).await?;
+        let mut recommendation = TradeRecommendation::parse(&response)?;
+        recommendation.token_address = ctx.analytics.token_address.clone();
+
+        info!(
+            "{:?} {} (confidence {:.2}): {}",
+            recommendation.action,
+            ctx.analytics.token_symbol,
+            recommendation.confidence,
+            recommendation.reasoning
+        );
+        Ok(recommendation)
     }
-} 
\ No newline at end of file
+}
+
+#[async_trait]
+impl Strategy for LLMStrategy {
+    fn name(&self) -> &str {
+        "llm"
+    }
+
+    async fn evaluate(&self, ctx: &StrategyContext) -> Result<Vec<MarketSignal>> {
+        let recommendation = self.recommend(ctx).await?;
+        Ok(recommendation
+            .to_signal(self.name(), &ctx.analytics, self.strong_confidence)?
+            .into_iter()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::ScriptedBackend;
+    use crate::models::market_signal::SignalType;
+    use crate::models::token_analytics::TokenAnalytics;
+    use crate::strategy::TradeAction;
+
+    fn snapshot(secs: i64, price: f64) -> TokenAnalytics {
+        TokenAnalytics {
+            holder_count: Some(1200),
+            ..TokenAnalytics::test_snapshot(secs, price)
+        }
+    }
+
+    fn context() -> StrategyContext {
+        let history = vec![snapshot(0, 1.0), snapshot(3600, 1.1)];
+        StrategyContext::new(snapshot(7200, 1.25), history, 10.0)
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_emits_signal_with_plan() {
+        let backend = Arc::new(ScriptedBackend::new([r#"{
+            "action": "Buy",
+            "reasoning": "breakout",
+            "confidence": 0.9,
+            "execution_strategy": {"stop_loss_pct": 0.12}
+        }"#]));
+        let strategy = LLMStrategy::new(backend.clone());
+
+        let signals = strategy.evaluate(&context()).await.unwrap();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].signal_type, SignalType::StrongBuy);
+
+        let rec = TradeRecommendation::from_signal(&signals[0]).unwrap();
+        assert_eq!(rec.action, TradeAction::Buy);
+        assert_eq!(rec.token_address, "addr");
+        assert_eq!(rec.exit_plan().stop_loss_pct, Some(0.12));
+
+        let prompt = &backend.prompts()[0];
+        assert!(prompt.contains("Token: Token (TKN)"));
+        assert!(prompt.contains("7200 1.25000000"));
+        assert!(prompt.contains("Available SOL balance: 10 SOL"));
+    }
+
+    #[tokio::test]
+    async fn test_hold_and_bad_responses() {
+        let backend = Arc::new(ScriptedBackend::new([
+            r#"{"action": "Hold", "reasoning": "chop", "confidence": 0.4}"#,
+            "not json",
+        ]));
+        let strategy = LLMStrategy::new(backend);
+
+        assert!(strategy.evaluate(&context()).await.unwrap().is_empty());
+        assert!(strategy.evaluate(&context()).await.is_err());
+    }
+}
@@ -1,71 +1,218 @@
-#[instrument(skip(self))]
-pub async fn analyze_trading_opportunity(&self, prompt: String, sol_balance: f64) -> Result<String> {
-    info!("Analyzing trading opportunity with prompt: {}", prompt);
-    
-    // Format the prompt with market analysis requirements
-    let formatted_prompt = format!(
-        "{}\n\nAnalyze this trading opportunity and provide a detailed recommendation in the following JSON format:\n{{
-            \"action\": \"Buy|Sell|Hold\",
-            \"token_address\": \"string\",
-            \"amount_in_sol\": number,
-            \"reasoning\": \"string\",
-            \"confidence\": number (0.0-1.0),
-            \"risk_assessment\": \"string\",
-            \"market_analysis\": {{
-                \"volume_analysis\": {{
-                    \"current_volume_usd\": number,
-                    \"volume_change_24h\": number,
-                    \"is_volume_bullish\": boolean,
-                    \"analysis\": \"string\"
-                }},
-                \"price_trend\": {{
-                    \"current_trend\": \"string\",
-                    \"support_levels\": [number],
-                    \"resistance_levels\": [number],
-                    \"trend_strength\": number (0.0-1.0)
-                }},
-                \"liquidity_assessment\": {{
-                    \"liquidity_score\": number (0.0-1.0),
-                    \"slippage_estimate\": number,
-                    \"is_liquid_enough\": boolean
-                }},
-                \"momentum_indicators\": {{
-                    \"rsi_14\": number,
-                    \"macd\": {{
-                        \"value\": number,
-                        \"signal\": \"bullish|bearish|neutral\"
-                    }},
-                    \"overall_momentum\": \"strong_buy|buy|neutral|sell|strong_sell\"
-                }},
-                \"on_chain_metrics\": {{
-                    \"unique_holders\": number,
-                    \"holder_concentration\": number (0.0-1.0),
-                    \"smart_money_flow\": \"inflow|outflow|neutral\"
-                }}
-            }},
-            \"execution_strategy\": {{
-                \"entry_type\": \"market|limit\",
-                \"position_size_sol\": number,
-                \"stop_loss_pct\": number,
-                \"take_profit_levels\": [{{
-                    \"price_target\": number,
-                    \"size_pct\": number
-                }}],
-                \"time_horizon\": \"short|medium|long\",
-                \"dca_strategy\": {{
-                    \"should_dca\": boolean,
-                    \"interval_hours\": number,
-                    \"num_entries\": number
-                }}
-            }}
-        }}\n\nAvailable SOL balance: {} SOL\n\nConsider the following criteria for the analysis:\n1. Volume should show significant increase (>50% 24h change) with sustainable growth\n2. Price action should show clear trend with identifiable support/resistance levels\n3. Liquidity should be sufficient to enter/exit position with <2% slippage\n4. Momentum indicators should align with the overall trend\n5. Smart money flow should indicate institutional interest\n6. Risk:reward ratio should be at least 1:3 for any trade", 
-        prompt,
-        sol_balance
+//! Trading strategies.
+//!
+//! A [`Strategy`] looks at a [`StrategyContext`] for one token and emits the
+//! `MarketSignal`s it would trade on. Strategies that produce a full
+//! execution plan attach it to the signal (see [`TradeRecommendation`]).
+//! Rule-based strategies can be combined in an [`EnsembleStrategy`], and any
+//! strategy can be replayed over stored history with the [`Backtester`].
+
+pub mod backtest;
+pub mod ensemble;
+pub mod llm;
+pub mod recommendation;
+pub mod rules;
+
+pub use backtest::{BacktestReport, Backtester};
+pub use ensemble::EnsembleStrategy;
+pub use llm::LLMStrategy;
+pub use recommendation::{TradeAction, TradeRecommendation};
+pub use rules::{BreakoutStrategy, CompositeStrategy, MeanReversionStrategy, MomentumStrategy};
+
+use crate::config::StrategyConfig;
+use crate::llm::LlmBackend;
+use crate::models::candle::Candle;
+use crate::models::market_signal::{MarketSignal, MarketSignalBuilder, SignalType};
+use crate::models::token_analytics::TokenAnalytics;
+use crate::services::token_analytics::TokenAnalyticsService;
+use crate::services::wallet_tracker::KolOwnership;
+use crate::trading::positions::{Position, PositionLedger};
+use crate::utils::f64_to_decimal;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use bigdecimal::ToPrimitive;
+use bson::DateTime;
+use serde_json::json;
+use std::sync::Arc;
+
+/// How far back the context history reaches
+const CONTEXT_HISTORY_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Candle width built from the history
+const CANDLE_SECS: i64 = 60 * 60;
+
+/// Confidence at which a rule-based signal becomes a StrongBuy/StrongSell
+const STRONG_CONFIDENCE: f64 = 0.8;
+
+/// Everything a strategy may look at for one token
+#[derive(Debug, Clone)]
+pub struct StrategyContext {
+    pub analytics: TokenAnalytics,
+    /// Earlier snapshots of the token, oldest first
+    pub history: Vec<TokenAnalytics>,
+    /// Hourly candles of the history and the current snapshot
+    pub candles: Vec<Candle>,
+    /// Our open positions in the token
+    pub positions: Vec<Position>,
+    pub kol_positions: Vec<KolOwnership>,
+    pub sol_balance: f64,
+}
+
+impl StrategyContext {
+    pub fn new(analytics: TokenAnalytics, history: Vec<TokenAnalytics>, sol_balance: f64) -> Self {
+        let mut snapshots = history.clone();
+        snapshots.push(analytics.clone());
+        Self {
+            candles: Candle::from_snapshots(&snapshots, CANDLE_SECS),
+            analytics,
+            history,
+            positions: Vec::new(),
+            kol_positions: Vec::new(),
+            sol_balance,
+        }
+    }
+
+    /// Load history, positions and KOL data for a fresh snapshot
+    pub async fn load(
+        analytics_service: &TokenAnalyticsService,
+        ledger: &PositionLedger,
+        analytics: TokenAnalytics,
+        sol_balance: f64,
+    ) -> Result<Self> {
+        let address = analytics.token_address.clone();
+        let start = analytics.timestamp.timestamp_millis() - CONTEXT_HISTORY_SECS * 1000;
+        let history = analytics_service
+            .get_token_history(&address, DateTime::from_millis(start), analytics.timestamp)
+            .await?
+            .into_iter()
+            .filter(|h| h.timestamp < analytics.timestamp)
+            .collect();
+
+        let mut ctx = Self::new(analytics, history, sol_balance);
+        ctx.positions = ledger.open_positions(Some(&address)).await?;
+        ctx.kol_positions = analytics_service.kol_positions(&address).await?;
+        Ok(ctx)
+    }
+
+    /// Prices of the history followed by the current price
+    pub fn prices(&self) -> Vec<f64> {
+        self.history
+            .iter()
+            .chain(std::iter::once(&self.analytics))
+            .filter_map(|a| a.price.to_f64())
+            .collect()
+    }
+
+    pub fn current_price(&self) -> f64 {
+        self.analytics.price.to_f64().unwrap_or_default()
+    }
+}
+
+#[async_trait]
+pub trait Strategy: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Signals the strategy would trade on; empty when it has no opinion
+    async fn evaluate(&self, ctx: &StrategyContext) -> Result<Vec<MarketSignal>>;
+}
+
+/// Build the strategy set named in the config: nothing when no strategy is
+/// configured, the strategy itself when there is one, and an ensemble of all
+/// of them otherwise. `llm` is required when the set includes "llm".
+pub fn from_config(
+    config: &StrategyConfig,
+    llm: Option<Arc<dyn LlmBackend>>,
+) -> Result<Option<Arc<dyn Strategy>>> {
+    let mut strategies = config
+        .strategies
+        .iter()
+        .map(|name| {
+            let strategy: Arc<dyn Strategy> = match name.as_str() {
+                "momentum" => Arc::new(MomentumStrategy::from_config(config)),
+                "mean_reversion" => Arc::new(MeanReversionStrategy::from_config(config)),
+                "breakout" => Arc::new(BreakoutStrategy::from_config(config)),
+                "composite" => Arc::new(CompositeStrategy::default()),
+                "llm" => {
+                    Arc::new(LLMStrategy::new(llm.clone().ok_or_else(|| {
+                        anyhow!("The llm strategy needs an LLM backend")
+                    })?))
+                }
+                other => return Err(anyhow!("Unknown strategy '{}'", other)),
+            };
+            Ok(strategy)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if strategies.len() <= 1 {
+        return Ok(strategies.pop());
+    }
+    let ensemble = strategies.into_iter().fold(
+        EnsembleStrategy::new(config.ensemble_mode, config.ensemble_threshold),
+        |ensemble, strategy| {
+            let weight = config.weight(strategy.name());
+            ensemble.with_member(strategy, weight)
+        },
     );
+    Ok(Some(Arc::new(ensemble)))
+}
+
+/// Directional signal for a rule-based strategy. `confidence` is clamped to
+/// [0, 1] and decides between the plain and strong signal types; NaN is an
+/// error.
+pub(crate) fn rule_signal(
+    strategy: &str,
+    ctx: &StrategyContext,
+    bullish: bool,
+    confidence: f64,
+    reason: String,
+) -> Result<MarketSignal> {
+    let confidence = confidence.clamp(0.0, 1.0);
+    let signal_type = match (bullish, confidence >= STRONG_CONFIDENCE) {
+        (true, true) => SignalType::StrongBuy,
+        (true, false) => SignalType::Buy,
+        (false, true) => SignalType::StrongSell,
+        (false, false) => SignalType::Sell,
+    };
+
+    let analytics = &ctx.analytics;
+    let mut builder = MarketSignalBuilder::new(
+        analytics.token_address.clone(),
+        signal_type,
+        analytics.price.clone(),
+    )
+    .confidence(f64_to_decimal(confidence)?)
+    .raw_confidence(f64_to_decimal(confidence)?)
+    .timestamp(analytics.timestamp)
+    .metadata(json!({
+        "strategy": strategy,
+        "token_symbol": analytics.token_symbol.clone(),
+        "reason": reason,
+    }));
+    if let Some(change) = &analytics.price_change_24h {
+        builder = builder.price_change_24h(change.clone());
+    }
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config() {
+        let mut config = StrategyConfig::default();
+        assert!(from_config(&config, None).unwrap().is_none());
+
+        config.strategies = vec!["breakout".to_string()];
+        let strategy = from_config(&config, None).unwrap().unwrap();
+        assert_eq!(strategy.name(), "breakout");
+
+        config.strategies = vec!["momentum".to_string(), "mean_reversion".to_string()];
+        let strategy = from_config(&config, None).unwrap().unwrap();
+        assert_eq!(strategy.name(), "ensemble");
 
-    // Get analysis from LLM
-    let analysis = self.agent.complete(&formatted_prompt).await?;
-    
-    info!("Received analysis from LLM");
-    Ok(analysis)
-}
\ No newline at end of file
+        config.strategies = vec!["llm".to_string()];
+        assert!(from_config(&config, None).is_err());
+        config.strategies = vec!["martingale".to_string()];
+        assert!(from_config(&config, None).is_err());
+    }
+}
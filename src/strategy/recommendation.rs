@@ -0,0 +1,269 @@
+//! Typed trade recommendation returned by the LLM strategy.
+
+use crate::llm::strip_code_fences;
+use crate::models::market_signal::{MarketSignal, MarketSignalBuilder, SignalType};
+use crate::models::token_analytics::TokenAnalytics;
+use crate::trading::exits::{ExitPlan, TakeProfitLevel};
+use crate::trading::scheduler::Schedule;
+use crate::utils::f64_to_decimal;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Key under `metadata` that carries the full recommendation on a signal
+const METADATA_KEY: &str = "recommendation";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradeAction {
+    Buy,
+    Sell,
+    Hold,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryType {
+    #[default]
+    Market,
+    Limit,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TakeProfitTarget {
+    pub price_target: f64,
+    /// Fraction of the position sold at the target
+    pub size_pct: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DcaPlan {
+    pub should_dca: bool,
+    pub interval_hours: f64,
+    pub num_entries: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionStrategy {
+    #[serde(default)]
+    pub entry_type: EntryType,
+    /// Required for limit entries
+    #[serde(default)]
+    pub limit_price: Option<f64>,
+    #[serde(default)]
+    pub position_size_sol: f64,
+    /// Stop below entry, as a fraction
+    #[serde(default)]
+    pub stop_loss_pct: Option<f64>,
+    #[serde(default)]
+    pub take_profit_levels: Vec<TakeProfitTarget>,
+    #[serde(default)]
+    pub time_horizon: Option<String>,
+    #[serde(default)]
+    pub dca_strategy: Option<DcaPlan>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeRecommendation {
+    pub action: TradeAction,
+    #[serde(default)]
+    pub token_address: String,
+    #[serde(default)]
+    pub amount_in_sol: f64,
+    pub reasoning: String,
+    pub confidence: f64,
+    #[serde(default)]
+    pub risk_assessment: String,
+    /// Free-form analysis sections; kept for logging, not interpreted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub market_analysis: Option<serde_json::Value>,
+    #[serde(default)]
+    pub execution_strategy: ExecutionStrategy,
+}
+
+impl TradeRecommendation {
+    /// Parse and validate a model response
+    pub fn parse(response: &str) -> Result<Self> {
+        let recommendation: Self = serde_json::from_str(strip_code_fences(response))
+            .context("Response is not a valid trade recommendation")?;
+        recommendation.validate()?;
+        Ok(recommendation)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !(0.0..=1.0).contains(&self.confidence) {
+            bail!("confidence {} is outside [0, 1]", self.confidence);
+        }
+        if self.amount_in_sol < 0.0 {
+            bail!("amount_in_sol {} is negative", self.amount_in_sol);
+        }
+
+        let exec = &self.execution_strategy;
+        if let Some(stop) = exec.stop_loss_pct {
+            if !(0.0..1.0).contains(&stop) {
+                bail!("stop_loss_pct {} must be a fraction below 1", stop);
+            }
+        }
+        if exec.entry_type == EntryType::Limit && exec.limit_price.is_none_or(|p| p <= 0.0) {
+            bail!("limit entry without a positive limit_price");
+        }
+        let mut total = 0.0;
+        for level in &exec.take_profit_levels {
+            if level.price_target <= 0.0 || !(0.0..=1.0).contains(&level.size_pct) {
+                bail!(
+                    "invalid take-profit level {} @ {}",
+                    level.size_pct,
+                    level.price_target
+                );
+            }
+            total += level.size_pct;
+        }
+        if total > 1.0 + 1e-6 {
+            bail!(
+                "take-profit levels sell {:.0}% of the position",
+                total * 100.0
+            );
+        }
+        if let Some(dca) = exec.dca_strategy.as_ref().filter(|d| d.should_dca) {
+            if dca.num_entries == 0 || dca.interval_hours <= 0.0 {
+                bail!("DCA plan needs entries and a positive interval");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn exit_plan(&self) -> ExitPlan {
+        let exec = &self.execution_strategy;
+        ExitPlan {
+            stop_loss_pct: exec.stop_loss_pct,
+            trailing_stop_pct: None,
+            take_profit: exec
+                .take_profit_levels
+                .iter()
+                .map(|t| TakeProfitLevel {
+                    price: t.price_target,
+                    size_pct: t.size_pct,
+                    filled: false,
+                })
+                .collect(),
+        }
+    }
+
+    /// Entry split into DCA children, if the plan asks for it
+    pub fn dca_schedule(&self) -> Option<Schedule> {
+        let dca = self.execution_strategy.dca_strategy.as_ref()?;
+        (dca.should_dca && dca.num_entries > 1)
+            .then(|| Schedule::dca(dca.interval_hours, dca.num_entries))
+    }
+
+    pub fn limit_price(&self) -> Option<f64> {
+        let exec = &self.execution_strategy;
+        match exec.entry_type {
+            EntryType::Limit => exec.limit_price,
+            EntryType::Market => None,
+        }
+    }
+
+    /// Signal for the recommendation, or `None` for a hold. Confidence at or
+    /// above `strong_confidence` makes it a StrongBuy/StrongSell. The
+    /// recommendation rides along in the signal metadata.
+    pub fn to_signal(
+        &self,
+        strategy: &str,
+        analytics: &TokenAnalytics,
+        strong_confidence: f64,
+    ) -> Result<Option<MarketSignal>> {
+        let strong = self.confidence >= strong_confidence;
+        let signal_type = match (self.action, strong) {
+            (TradeAction::Buy, true) => SignalType::StrongBuy,
+            (TradeAction::Buy, false) => SignalType::Buy,
+            (TradeAction::Sell, true) => SignalType::StrongSell,
+            (TradeAction::Sell, false) => SignalType::Sell,
+            (TradeAction::Hold, _) => return Ok(None),
+        };
+
+        let mut builder = MarketSignalBuilder::new(
+            analytics.token_address.clone(),
+            signal_type,
+            analytics.price.clone(),
+        )
+        .confidence(f64_to_decimal(self.confidence)?)
+        .raw_confidence(f64_to_decimal(self.confidence)?)
+        .timestamp(analytics.timestamp)
+        .metadata(json!({
+            "strategy": strategy,
+            "token_symbol": analytics.token_symbol.clone(),
+            "reasoning": self.reasoning.clone(),
+            METADATA_KEY: self,
+        }));
+        if let Some(change) = &analytics.price_change_24h {
+            builder = builder.price_change_24h(change.clone());
+        }
+        Ok(Some(builder.build()))
+    }
+
+    /// Recommendation carried by a signal built with [`Self::to_signal`]
+    pub fn from_signal(signal: &MarketSignal) -> Option<Self> {
+        let value = signal.metadata.as_ref()?.get(METADATA_KEY)?;
+        bson::from_bson(value.clone()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"```json
+    {
+        "action": "Buy",
+        "token_address": "addr",
+        "amount_in_sol": 1.5,
+        "reasoning": "volume breakout",
+        "confidence": 0.85,
+        "risk_assessment": "medium",
+        "execution_strategy": {
+            "entry_type": "limit",
+            "limit_price": 0.9,
+            "position_size_sol": 1.5,
+            "stop_loss_pct": 0.1,
+            "take_profit_levels": [
+                {"price_target": 1.5, "size_pct": 0.5},
+                {"price_target": 2.0, "size_pct": 0.5}
+            ],
+            "time_horizon": "short",
+            "dca_strategy": {"should_dca": true, "interval_hours": 4, "num_entries": 3}
+        }
+    }
+    ```"#;
+
+    #[test]
+    fn test_parse_full_plan() {
+        let rec = TradeRecommendation::parse(RESPONSE).unwrap();
+
+        assert_eq!(rec.action, TradeAction::Buy);
+        assert_eq!(rec.limit_price(), Some(0.9));
+        assert_eq!(
+            rec.dca_schedule(),
+            Some(Schedule::Dca {
+                interval_secs: 4 * 3600,
+                entries: 3
+            })
+        );
+        let plan = rec.exit_plan();
+        assert_eq!(plan.stop_loss_pct, Some(0.1));
+        assert_eq!(plan.take_profit.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_plans() {
+        let percent_stop = RESPONSE.replace("\"stop_loss_pct\": 0.1", "\"stop_loss_pct\": 10");
+        assert!(TradeRecommendation::parse(&percent_stop).is_err());
+
+        let oversold = RESPONSE.replace("\"size_pct\": 0.5}\n", "\"size_pct\": 0.9}\n");
+        assert!(TradeRecommendation::parse(&oversold).is_err());
+
+        let no_limit = RESPONSE.replace("\"limit_price\": 0.9,", "");
+        assert!(TradeRecommendation::parse(&no_limit).is_err());
+
+        assert!(TradeRecommendation::parse("I think you should buy").is_err());
+    }
+}
@@ -0,0 +1,325 @@
+//! Rule-based strategies over the price history of a token.
+
+use super::{rule_signal, Strategy, StrategyContext};
+use crate::config::StrategyConfig;
+use crate::models::market_signal::MarketSignal;
+use crate::signals::{SignalCombiner, SignalContext};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+
+/// Buys when the price rose more than `threshold` over the last `lookback`
+/// snapshots and sells when it fell as much
+pub struct MomentumStrategy {
+    pub lookback: usize,
+    pub threshold: f64,
+}
+
+impl MomentumStrategy {
+    pub fn from_config(config: &StrategyConfig) -> Self {
+        Self {
+            lookback: config.momentum_lookback,
+            threshold: config.momentum_threshold,
+        }
+    }
+}
+
+#[async_trait]
+impl Strategy for MomentumStrategy {
+    fn name(&self) -> &str {
+        "momentum"
+    }
+
+    async fn evaluate(&self, ctx: &StrategyContext) -> Result<Vec<MarketSignal>> {
+        let prices = ctx.prices();
+        if prices.len() <= self.lookback {
+            return Ok(Vec::new());
+        }
+        let base = prices[prices.len() - 1 - self.lookback];
+        let current = ctx.current_price();
+        if base <= 0.0 {
+            return Ok(Vec::new());
+        }
+
+        let change = (current - base) / base;
+        if change.abs() < self.threshold {
+            return Ok(Vec::new());
+        }
+        // Full confidence at three times the threshold
+        let confidence = (change.abs() / (self.threshold * 3.0)).min(1.0);
+        let reason = format!("{:+.2}% over {} snapshots", change * 100.0, self.lookback);
+        Ok(vec![rule_signal(
+            self.name(),
+            ctx,
+            change > 0.0,
+            confidence,
+            reason,
+        )?])
+    }
+}
+
+/// Buys when the price is `z_entry` standard deviations below its
+/// `period`-snapshot average and sells when it is as far above
+pub struct MeanReversionStrategy {
+    pub period: usize,
+    pub z_entry: f64,
+}
+
+impl MeanReversionStrategy {
+    pub fn from_config(config: &StrategyConfig) -> Self {
+        Self {
+            period: config.mean_reversion_period,
+            z_entry: config.mean_reversion_z,
+        }
+    }
+}
+
+#[async_trait]
+impl Strategy for MeanReversionStrategy {
+    fn name(&self) -> &str {
+        "mean_reversion"
+    }
+
+    async fn evaluate(&self, ctx: &StrategyContext) -> Result<Vec<MarketSignal>> {
+        let prices = ctx.prices();
+        if prices.len() < self.period {
+            return Ok(Vec::new());
+        }
+        let window = &prices[prices.len() - self.period..];
+        let mean = window.iter().sum::<f64>() / window.len() as f64;
+        let variance = window.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / window.len() as f64;
+        let std_dev = variance.sqrt();
+        if std_dev <= 0.0 {
+            return Ok(Vec::new());
+        }
+
+        let z = (ctx.current_price() - mean) / std_dev;
+        if z.abs() < self.z_entry {
+            return Ok(Vec::new());
+        }
+        // Full confidence one standard deviation past the entry band
+        let confidence = (0.5 + (z.abs() - self.z_entry) / 2.0).min(1.0);
+        let reason = format!("z-score {:.2} against the {}-snapshot mean", z, self.period);
+        Ok(vec![rule_signal(
+            self.name(),
+            ctx,
+            z < 0.0,
+            confidence,
+            reason,
+        )?])
+    }
+}
+
+/// Buys when the price closes above the high of the previous `lookback`
+/// hourly candles by `buffer` and sells when it breaks below their low
+pub struct BreakoutStrategy {
+    pub lookback: usize,
+    pub buffer: f64,
+}
+
+impl BreakoutStrategy {
+    pub fn from_config(config: &StrategyConfig) -> Self {
+        Self {
+            lookback: config.breakout_lookback,
+            buffer: config.breakout_buffer,
+        }
+    }
+}
+
+#[async_trait]
+impl Strategy for BreakoutStrategy {
+    fn name(&self) -> &str {
+        "breakout"
+    }
+
+    async fn evaluate(&self, ctx: &StrategyContext) -> Result<Vec<MarketSignal>> {
+        // The last candle holds the current snapshot
+        let Some((_, previous)) = ctx.candles.split_last() else {
+            return Ok(Vec::new());
+        };
+        if previous.len() < self.lookback {
+            return Ok(Vec::new());
+        }
+        let range = &previous[previous.len() - self.lookback..];
+        let high = range.iter().map(|c| c.high).fold(f64::MIN, f64::max);
+        let low = range.iter().map(|c| c.low).fold(f64::MAX, f64::min);
+        let price = ctx.current_price();
+
+        let (bullish, distance, level) = if price > high * (1.0 + self.buffer) {
+            (true, price / high - 1.0, high)
+        } else if price < low * (1.0 - self.buffer) {
+            (false, 1.0 - price / low, low)
+        } else {
+            return Ok(Vec::new());
+        };
+        // Full confidence once the move is four buffers past the level
+        let confidence = (0.5 + distance / (self.buffer * 8.0)).min(1.0);
+        let reason = format!(
+            "price {:.8} broke the {}-candle {} of {:.8}",
+            price,
+            self.lookback,
+            if bullish { "high" } else { "low" },
+            level
+        );
+        Ok(vec![rule_signal(
+            self.name(),
+            ctx,
+            bullish,
+            confidence,
+            reason,
+        )?])
+    }
+}
+
+/// The multi-factor signal engine as a strategy
+pub struct CompositeStrategy {
+    combiner: SignalCombiner,
+}
+
+impl CompositeStrategy {
+    pub fn new(combiner: SignalCombiner) -> Self {
+        Self { combiner }
+    }
+}
+
+impl Default for CompositeStrategy {
+    fn default() -> Self {
+        Self::new(SignalCombiner::default())
+    }
+}
+
+#[async_trait]
+impl Strategy for CompositeStrategy {
+    fn name(&self) -> &str {
+        "composite"
+    }
+
+    async fn evaluate(&self, ctx: &StrategyContext) -> Result<Vec<MarketSignal>> {
+        let signal_ctx = SignalContext {
+            current: &ctx.analytics,
+            history: &ctx.history,
+            kol_positions: &ctx.kol_positions,
+            now: Utc::now(),
+        };
+        let composite = self.combiner.evaluate(&signal_ctx);
+        if self.combiner.signal_type(&composite).is_none() {
+            return Ok(Vec::new());
+        }
+        let reason = format!(
+            "composite score {:.3} from {} factors",
+            composite.score,
+            composite.factors.len()
+        );
+        Ok(vec![rule_signal(
+            self.name(),
+            ctx,
+            composite.score > 0.0,
+            composite.score.abs().min(1.0),
+            reason,
+        )?])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::market_signal::SignalType;
+    use crate::models::token_analytics::TokenAnalytics;
+
+    /// Hourly snapshots at the given prices; the last one is current
+    fn context(prices: &[f64]) -> StrategyContext {
+        let mut snapshots: Vec<_> = prices
+            .iter()
+            .enumerate()
+            .map(|(i, p)| TokenAnalytics::test_snapshot(i as i64 * 3600, *p))
+            .collect();
+        let current = snapshots.pop().unwrap();
+        StrategyContext::new(current, snapshots, 10.0)
+    }
+
+    #[tokio::test]
+    async fn test_momentum() {
+        let strategy = MomentumStrategy {
+            lookback: 3,
+            threshold: 0.05,
+        };
+
+        let signals = strategy
+            .evaluate(&context(&[1.0, 1.0, 1.02, 1.05, 1.2]))
+            .await
+            .unwrap();
+        assert_eq!(signals.len(), 1);
+        assert!(signals[0].expects_rise());
+
+        let signals = strategy
+            .evaluate(&context(&[1.0, 1.0, 0.9, 0.85, 0.8]))
+            .await
+            .unwrap();
+        assert_eq!(signals[0].signal_type, SignalType::StrongSell);
+
+        assert!(strategy
+            .evaluate(&context(&[1.0, 1.0, 1.01, 1.02]))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(strategy
+            .evaluate(&context(&[1.0, 2.0]))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mean_reversion() {
+        let strategy = MeanReversionStrategy {
+            period: 6,
+            z_entry: 1.5,
+        };
+
+        let signals = strategy
+            .evaluate(&context(&[1.0, 1.01, 0.99, 1.0, 1.01, 0.8]))
+            .await
+            .unwrap();
+        assert_eq!(signals.len(), 1);
+        assert!(signals[0].expects_rise());
+
+        let signals = strategy
+            .evaluate(&context(&[1.0, 1.01, 0.99, 1.0, 1.01, 1.2]))
+            .await
+            .unwrap();
+        assert!(!signals[0].expects_rise());
+
+        assert!(strategy
+            .evaluate(&context(&[1.0, 1.01, 0.99, 1.0, 1.01, 1.0]))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_breakout() {
+        let strategy = BreakoutStrategy {
+            lookback: 3,
+            buffer: 0.01,
+        };
+
+        let signals = strategy
+            .evaluate(&context(&[1.0, 1.05, 0.98, 1.02, 1.1]))
+            .await
+            .unwrap();
+        assert_eq!(signals.len(), 1);
+        assert!(signals[0].expects_rise());
+
+        let signals = strategy
+            .evaluate(&context(&[1.0, 1.05, 0.98, 1.02, 0.9]))
+            .await
+            .unwrap();
+        assert!(!signals[0].expects_rise());
+
+        assert!(strategy
+            .evaluate(&context(&[1.0, 1.05, 0.98, 1.02, 1.04]))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,261 @@
+use super::spawn_call;
+use crate::error::AgentError;
+use crate::models::token_analytics::TokenAnalytics;
+use crate::services::{TokenAnalyticsService, WalletTrackerService};
+use crate::utils::decimal_to_f64;
+use bson::DateTime;
+use rig::{completion::ToolDefinition, tool::Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Most snapshots a history lookup returns to the model
+const MAX_HISTORY_POINTS: usize = 48;
+
+/// Compact view of a snapshot handed to the model
+#[derive(Debug, Serialize)]
+pub struct TokenSummary {
+    pub address: String,
+    pub name: String,
+    pub symbol: String,
+    pub price: f64,
+    pub price_change_24h: Option<f64>,
+    pub volume_24h: Option<f64>,
+    pub liquidity: Option<f64>,
+    pub market_cap: Option<f64>,
+    pub holders: Option<i32>,
+    pub rsi_14: Option<f64>,
+    pub timestamp: String,
+}
+
+impl TryFrom<&TokenAnalytics> for TokenSummary {
+    type Error = AgentError;
+
+    fn try_from(a: &TokenAnalytics) -> Result<Self, AgentError> {
+        let num = |v: &Option<bigdecimal::BigDecimal>| v.as_ref().map(decimal_to_f64).transpose();
+        Ok(Self {
+            address: a.token_address.clone(),
+            name: a.token_name.clone(),
+            symbol: a.token_symbol.clone(),
+            price: decimal_to_f64(&a.price)?,
+            price_change_24h: num(&a.price_change_24h)?,
+            volume_24h: num(&a.volume_24h)?,
+            liquidity: num(&a.liquidity)?,
+            market_cap: num(&a.market_cap)?,
+            holders: a.holder_count,
+            rsi_14: num(&a.rsi_14)?,
+            timestamp: a.timestamp.try_to_rfc3339_string().unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AddressArgs {
+    pub address: String,
+}
+
+fn address_schema(description: &str) -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "address": { "type": "string", "description": description }
+        },
+        "required": ["address"]
+    })
+}
+
+/// Latest stored analytics snapshot of a token
+pub struct GetTokenAnalytics {
+    service: Arc<TokenAnalyticsService>,
+}
+
+impl GetTokenAnalytics {
+    pub fn new(service: Arc<TokenAnalyticsService>) -> Self {
+        Self { service }
+    }
+}
+
+impl Tool for GetTokenAnalytics {
+    const NAME: &'static str = "get_token_analytics";
+
+    type Error = AgentError;
+    type Args = AddressArgs;
+    type Output = Option<TokenSummary>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Latest analytics snapshot (price, volume, liquidity, holders, RSI) \
+                          of a token from the last 24 hours, or null if none is stored"
+                .to_string(),
+            parameters: address_schema("Token mint address"),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let service = self.service.clone();
+        spawn_call(async move {
+            let analytics = service.get_token_analytics(&args.address).await?;
+            analytics.as_ref().map(TokenSummary::try_from).transpose()
+        })
+        .await
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TrendingArgs {
+    pub limit: Option<i64>,
+}
+
+/// Highest volume tokens of the last 24 hours
+pub struct GetTrendingTokens {
+    service: Arc<TokenAnalyticsService>,
+}
+
+impl GetTrendingTokens {
+    pub fn new(service: Arc<TokenAnalyticsService>) -> Self {
+        Self { service }
+    }
+}
+
+impl Tool for GetTrendingTokens {
+    const NAME: &'static str = "get_trending_tokens";
+
+    type Error = AgentError;
+    type Args = TrendingArgs;
+    type Output = Vec<TokenSummary>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Tokens with the highest 24h volume and price change".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "limit": {
+                        "type": "integer",
+                        "description": "Number of tokens to return (1-50, default 10)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let limit = args.limit.unwrap_or(10).clamp(1, 50);
+        let service = self.service.clone();
+        spawn_call(async move {
+            let trending = service.get_trending_tokens(limit).await?;
+            trending.iter().map(TokenSummary::try_from).collect()
+        })
+        .await
+    }
+}
+
+#[derive(Deserialize)]
+pub struct HistoryArgs {
+    pub address: String,
+    pub hours: Option<i64>,
+}
+
+/// Stored snapshots of a token over a time window
+pub struct GetTokenHistory {
+    service: Arc<TokenAnalyticsService>,
+}
+
+impl GetTokenHistory {
+    pub fn new(service: Arc<TokenAnalyticsService>) -> Self {
+        Self { service }
+    }
+}
+
+impl Tool for GetTokenHistory {
+    const NAME: &'static str = "get_token_history";
+
+    type Error = AgentError;
+    type Args = HistoryArgs;
+    type Output = Vec<TokenSummary>;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: format!(
+                "Stored snapshots of a token, oldest first, thinned to at most {} points",
+                MAX_HISTORY_POINTS
+            ),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "address": { "type": "string", "description": "Token mint address" },
+                    "hours": {
+                        "type": "integer",
+                        "description": "How far back to look (1-720, default 24)"
+                    }
+                },
+                "required": ["address"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let hours = args.hours.unwrap_or(24).clamp(1, 720);
+        let end = DateTime::now();
+        let start = DateTime::from_millis(end.timestamp_millis() - hours * 60 * 60 * 1000);
+        let service = self.service.clone();
+        let history =
+            spawn_call(async move { service.get_token_history(&args.address, start, end).await })
+                .await?;
+
+        let step = history.len().div_ceil(MAX_HISTORY_POINTS).max(1);
+        history
+            .iter()
+            .rev()
+            .step_by(step)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .map(TokenSummary::try_from)
+            .collect()
+    }
+}
+
+/// Tracked KOL wallets holding a token
+pub struct CheckKolOwnership {
+    tracker: Arc<WalletTrackerService>,
+}
+
+impl CheckKolOwnership {
+    pub fn new(tracker: Arc<WalletTrackerService>) -> Self {
+        Self { tracker }
+    }
+}
+
+impl Tool for CheckKolOwnership {
+    const NAME: &'static str = "check_kol_ownership";
+
+    type Error = AgentError;
+    type Args = AddressArgs;
+    type Output = serde_json::Value;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Tracked KOL (key opinion leader) wallets holding a token, with \
+                          position size and entry time"
+                .to_string(),
+            parameters: address_schema("Token mint address"),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let tracker = self.tracker.clone();
+        let ownership = spawn_call(async move {
+            tracker
+                .check_kol_ownership(&args.address)
+                .await
+                .map_err(AgentError::Other)
+        })
+        .await?;
+        serde_json::to_value(ownership).map_err(|e| AgentError::Conversion(e.to_string()))
+    }
+}
@@ -0,0 +1,335 @@
+//! On-chain actions and analytics queries exposed to the LLM as rig tools.
+//!
+//! Read-only tools are registered whenever their service is available.
+//! Tools that change state, like `transfer`, are only registered after an
+//! explicit [`AnalystTools::allow_transfers`].
+
+pub mod analytics;
+pub mod solana;
+
+pub use analytics::{CheckKolOwnership, GetTokenAnalytics, GetTokenHistory, GetTrendingTokens};
+pub use solana::{GetAssetsByOwner, GetBalance, Transfer};
+
+use crate::error::AgentError;
+use crate::services::{TokenAnalyticsService, WalletTrackerService};
+use crate::trading::SolanaAgentKit;
+use anyhow::{anyhow, Result};
+use rig::{
+    agent::{Agent, AgentBuilder},
+    completion::{Completion, CompletionModel, Message},
+    message::{AssistantContent, UserContent},
+    one_or_many::OneOrMany,
+    tool::Tool,
+};
+use std::future::Future;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// Tool-call round trips allowed before the model must answer
+pub const MAX_TOOL_TURNS: usize = 5;
+
+/// Set of tools handed to the analytics agent
+#[derive(Default, Clone)]
+pub struct AnalystTools {
+    analytics: Option<Arc<TokenAnalyticsService>>,
+    wallet_tracker: Option<Arc<WalletTrackerService>>,
+    solana: Option<Arc<SolanaAgentKit>>,
+    allow_transfers: bool,
+}
+
+impl AnalystTools {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token analytics, trending and history lookups
+    pub fn with_analytics(mut self, service: Arc<TokenAnalyticsService>) -> Self {
+        self.analytics = Some(service);
+        self
+    }
+
+    /// KOL ownership lookups
+    pub fn with_wallet_tracker(mut self, tracker: Arc<WalletTrackerService>) -> Self {
+        self.wallet_tracker = Some(tracker);
+        self
+    }
+
+    /// Wallet balance and asset lookups
+    pub fn with_solana(mut self, agent: Arc<SolanaAgentKit>) -> Self {
+        self.solana = Some(agent);
+        self
+    }
+
+    /// Let the model send funds from the wallet. Off by default.
+    pub fn allow_transfers(mut self, allow: bool) -> Self {
+        self.allow_transfers = allow;
+        self
+    }
+
+    /// Names of the tools [`register`](Self::register) adds
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.analytics.is_some() {
+            names.extend([
+                GetTokenAnalytics::NAME,
+                GetTrendingTokens::NAME,
+                GetTokenHistory::NAME,
+            ]);
+        }
+        if self.wallet_tracker.is_some() {
+            names.push(CheckKolOwnership::NAME);
+        }
+        if self.solana.is_some() {
+            names.extend([GetBalance::NAME, GetAssetsByOwner::NAME]);
+            if self.allow_transfers {
+                names.push(Transfer::NAME);
+            }
+        }
+        names
+    }
+
+    /// Add the available tools to an agent under construction
+    pub fn register<M: CompletionModel>(&self, mut builder: AgentBuilder<M>) -> AgentBuilder<M> {
+        if let Some(service) = &self.analytics {
+            builder = builder
+                .tool(GetTokenAnalytics::new(service.clone()))
+                .tool(GetTrendingTokens::new(service.clone()))
+                .tool(GetTokenHistory::new(service.clone()));
+        }
+        if let Some(tracker) = &self.wallet_tracker {
+            builder = builder.tool(CheckKolOwnership::new(tracker.clone()));
+        }
+        if let Some(agent) = &self.solana {
+            builder = builder
+                .tool(GetBalance::new(agent.clone()))
+                .tool(GetAssetsByOwner::new(agent.clone()));
+            if self.allow_transfers {
+                warn!("Transfer tool enabled; the model can send funds from the wallet");
+                builder = builder.tool(Transfer::new(agent.clone()));
+            }
+        }
+        builder
+    }
+}
+
+/// Run a tool body on its own task.
+///
+/// rig requires tool futures to be `Sync`, which the MongoDB and RPC client
+/// futures are not; the handle of a spawned task is.
+fn spawn_call<T, F>(call: F) -> impl Future<Output = Result<T, AgentError>>
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T, AgentError>> + Send + 'static,
+{
+    let handle = tokio::spawn(call);
+    async move { handle.await.map_err(|e| AgentError::Other(e.into()))? }
+}
+
+/// Prompt an agent, running the tools it calls and feeding their results
+/// back until it answers in text.
+///
+/// rig's `Agent::prompt` returns the output of the first tool call as the
+/// answer; this loop gives the model up to `max_turns` round trips instead.
+/// Tool failures are reported to the model rather than aborting the prompt.
+pub async fn prompt_with_tools<M: CompletionModel>(
+    agent: &Agent<M>,
+    prompt: impl Into<Message>,
+    mut history: Vec<Message>,
+    max_turns: usize,
+) -> Result<String> {
+    let mut prompt: Message = prompt.into();
+
+    for _ in 0..=max_turns {
+        let response = agent
+            .completion(prompt.clone(), history.clone())
+            .await?
+            .send()
+            .await?;
+
+        let mut text = Vec::new();
+        let mut results = Vec::new();
+        for content in response.choice.iter() {
+            match content {
+                AssistantContent::Text(t) => text.push(t.text.clone()),
+                AssistantContent::ToolCall(call) => {
+                    debug!(
+                        "Model called {} with {}",
+                        call.function.name, call.function.arguments
+                    );
+                    let output = agent
+                        .tools
+                        .call(&call.function.name, call.function.arguments.to_string())
+                        .await
+                        .unwrap_or_else(|e| format!("Error: {}", e));
+                    results.push(UserContent::tool_result(
+                        call.id.clone(),
+                        OneOrMany::one(output.into()),
+                    ));
+                }
+            }
+        }
+
+        if results.is_empty() {
+            return Ok(text.join("\n"));
+        }
+
+        history.push(prompt);
+        history.push(Message::Assistant {
+            content: response.choice,
+        });
+        prompt = Message::User {
+            content: OneOrMany::many(results).expect("tool results are not empty"),
+        };
+    }
+
+    Err(anyhow!(
+        "Model kept calling tools after {} round trips",
+        max_turns
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use rig::completion::{
+        CompletionError, CompletionModel, CompletionRequest, CompletionResponse, Message,
+    };
+    use rig::message::AssistantContent;
+    use rig::one_or_many::OneOrMany;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    /// Prompt, chat history and tool names of a request
+    pub type RecordedRequest = (Message, Vec<Message>, Vec<String>);
+
+    /// Completion model replaying scripted choices and recording requests
+    #[derive(Clone, Default)]
+    pub struct ScriptedModel {
+        pub choices: Arc<Mutex<VecDeque<AssistantContent>>>,
+        pub requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    }
+
+    impl CompletionModel for ScriptedModel {
+        type Response = ();
+
+        async fn completion(
+            &self,
+            request: CompletionRequest,
+        ) -> Result<CompletionResponse<()>, CompletionError> {
+            let tools = request.tools.iter().map(|t| t.name.clone()).collect();
+            self.requests
+                .lock()
+                .unwrap()
+                .push((request.prompt, request.chat_history, tools));
+            let choice = self
+                .choices
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or_else(|| CompletionError::ProviderError("no choices left".to_string()))?;
+            Ok(CompletionResponse {
+                choice: OneOrMany::one(choice),
+                raw_response: (),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::ScriptedModel;
+    use super::*;
+    use crate::config::SolanaConfig;
+    use rig::message::ToolResultContent;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_sdk::signature::Keypair;
+
+    fn solana_agent() -> Arc<SolanaAgentKit> {
+        Arc::new(SolanaAgentKit::with_client(
+            RpcClient::new_mock("succeeds".to_string()),
+            Keypair::new(),
+            SolanaConfig::default(),
+        ))
+    }
+
+    #[test]
+    fn test_transfer_requires_opt_in() {
+        let tools = AnalystTools::new().with_solana(solana_agent());
+        assert_eq!(tools.names(), vec!["get_balance", "get_assets_by_owner"]);
+
+        let tools = tools.allow_transfers(true);
+        assert!(tools.names().contains(&"transfer"));
+    }
+
+    #[tokio::test]
+    async fn test_prompt_runs_tool_calls() {
+        let model = ScriptedModel::default();
+        model.choices.lock().unwrap().extend([
+            AssistantContent::tool_call("call-1", "get_balance", serde_json::json!({})),
+            AssistantContent::text("You hold 0.00000005 SOL"),
+        ]);
+        let tools = AnalystTools::new().with_solana(solana_agent());
+        let agent = tools.register(AgentBuilder::new(model.clone())).build();
+
+        let answer = prompt_with_tools(&agent, "What is my balance?", vec![], MAX_TOOL_TURNS)
+            .await
+            .unwrap();
+        assert_eq!(answer, "You hold 0.00000005 SOL");
+
+        let requests = model.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        // Both tools are offered, transfer is not
+        assert_eq!(requests[0].2.len(), 2);
+        assert!(!requests[0].2.contains(&"transfer".to_string()));
+
+        // The second request carries the question, the call and its result
+        let (prompt, history, _) = &requests[1];
+        assert_eq!(history.len(), 2);
+        let Message::User { content } = prompt else {
+            panic!("expected the tool result as the next prompt");
+        };
+        let UserContent::ToolResult(result) = content.first() else {
+            panic!("expected a tool result");
+        };
+        assert_eq!(result.id, "call-1");
+        let ToolResultContent::Text(text) = result.content.first() else {
+            panic!("expected a text result");
+        };
+        assert!(text.text.contains("\"balance\":5e-8"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_tool_and_turn_limit() {
+        let model = ScriptedModel::default();
+        model.choices.lock().unwrap().extend([
+            AssistantContent::tool_call(
+                "1",
+                "transfer",
+                serde_json::json!({"to": "x", "amount": 1}),
+            ),
+            AssistantContent::tool_call(
+                "2",
+                "transfer",
+                serde_json::json!({"to": "x", "amount": 1}),
+            ),
+        ]);
+        let agent = AgentBuilder::new(model.clone()).build();
+
+        let err = prompt_with_tools(&agent, "Send 1 SOL to x", vec![], 1)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("kept calling tools"));
+
+        // The model is told the tool does not exist
+        let requests = model.requests.lock().unwrap();
+        let Message::User { content } = &requests[1].0 else {
+            panic!("expected a user message");
+        };
+        let UserContent::ToolResult(result) = content.first() else {
+            panic!("expected a tool result");
+        };
+        let ToolResultContent::Text(text) = result.content.first() else {
+            panic!("expected a text result");
+        };
+        assert!(text.text.contains("ToolNotFoundError"));
+    }
+}
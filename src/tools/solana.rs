@@ -0,0 +1,196 @@
+use super::spawn_call;
+use crate::actions::{helius, solana};
+use crate::error::AgentError;
+use crate::trading::SolanaAgentKit;
+use rig::{completion::ToolDefinition, tool::Tool};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct BalanceArgs {
+    pub mint: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Balance {
+    pub wallet: String,
+    pub mint: Option<String>,
+    pub balance: f64,
+}
+
+/// SOL or token balance of the agent's wallet
+pub struct GetBalance {
+    agent: Arc<SolanaAgentKit>,
+}
+
+impl GetBalance {
+    pub fn new(agent: Arc<SolanaAgentKit>) -> Self {
+        Self { agent }
+    }
+}
+
+impl Tool for GetBalance {
+    const NAME: &'static str = "get_balance";
+
+    type Error = AgentError;
+    type Args = BalanceArgs;
+    type Output = Balance;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Balance of the trading wallet in SOL, or in whole tokens when a \
+                          mint is given"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "mint": {
+                        "type": "string",
+                        "description": "Token mint address, omit for SOL"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let agent = self.agent.clone();
+        spawn_call(async move {
+            let balance = solana::get_balance(&agent, args.mint.as_deref())
+                .await
+                .map_err(AgentError::Other)?;
+            Ok(Balance {
+                wallet: agent.wallet.address.to_string(),
+                mint: args.mint,
+                balance,
+            })
+        })
+        .await
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AssetsArgs {
+    pub owner: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// Assets held by a wallet, through Helius
+pub struct GetAssetsByOwner {
+    agent: Arc<SolanaAgentKit>,
+}
+
+impl GetAssetsByOwner {
+    pub fn new(agent: Arc<SolanaAgentKit>) -> Self {
+        Self { agent }
+    }
+}
+
+impl Tool for GetAssetsByOwner {
+    const NAME: &'static str = "get_assets_by_owner";
+
+    type Error = AgentError;
+    type Args = AssetsArgs;
+    type Output = serde_json::Value;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "NFTs and fungible tokens held by a wallet".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "owner": {
+                        "type": "string",
+                        "description": "Wallet address, omit for the trading wallet"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Number of assets to return (1-100, default 20)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let owner = args
+            .owner
+            .unwrap_or_else(|| self.agent.wallet.address.to_string());
+        let limit = args.limit.unwrap_or(20).clamp(1, 100);
+        helius::get_assets_by_owner(&self.agent, &owner, limit)
+            .await
+            .map_err(AgentError::Other)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TransferArgs {
+    pub to: String,
+    pub amount: f64,
+    pub mint: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransferResult {
+    pub signature: String,
+}
+
+/// Sends SOL or tokens from the trading wallet.
+///
+/// Moves funds, so it is only handed to a model when the caller opts in
+/// through [`super::AnalystTools::allow_transfers`].
+pub struct Transfer {
+    agent: Arc<SolanaAgentKit>,
+}
+
+impl Transfer {
+    pub fn new(agent: Arc<SolanaAgentKit>) -> Self {
+        Self { agent }
+    }
+}
+
+impl Tool for Transfer {
+    const NAME: &'static str = "transfer";
+
+    type Error = AgentError;
+    type Args = TransferArgs;
+    type Output = TransferResult;
+
+    async fn definition(&self, _prompt: String) -> ToolDefinition {
+        ToolDefinition {
+            name: Self::NAME.to_string(),
+            description: "Send SOL or tokens from the trading wallet. Irreversible; only use \
+                          when the user explicitly asks for a transfer"
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "to": { "type": "string", "description": "Recipient wallet address" },
+                    "amount": {
+                        "type": "number",
+                        "description": "Amount in SOL, or in whole tokens when a mint is given"
+                    },
+                    "mint": {
+                        "type": "string",
+                        "description": "Token mint address, omit for SOL"
+                    }
+                },
+                "required": ["to", "amount"]
+            }),
+        }
+    }
+
+    async fn call(&self, args: Self::Args) -> Result<Self::Output, Self::Error> {
+        let agent = self.agent.clone();
+        spawn_call(async move {
+            let signature = solana::transfer(&agent, &args.to, args.amount, args.mint.as_deref())
+                .await
+                .map_err(|e| AgentError::Transaction(e.to_string()))?;
+            Ok(TransferResult { signature })
+        })
+        .await
+    }
+}
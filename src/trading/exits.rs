@@ -0,0 +1,467 @@
+//! Exit rules for open positions.
+//!
+//! Each open position can carry a fixed stop, a trailing stop and tiered
+//! take-profit levels. Rules are stored in `exit_rules` keyed by position id,
+//! so they survive restarts and can be edited with `cainam exits`. The
+//! [`ExitManager`] evaluates them on every price update and sells through the
+//! [`TradingEngine`].
+
+use super::order::{Fill, OrderSide, TradeOrder};
+use super::positions::{Position, PositionLedger};
+use super::trading_engine::TradingEngine;
+use crate::config::mongodb::MongoDbPool;
+use crate::models::money::{Price, TokenAmount};
+use crate::utils::f64_to_decimal;
+use anyhow::{anyhow, Result};
+use bson::{doc, oid::ObjectId, DateTime};
+use futures::TryStreamExt;
+use mongodb::Collection;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TakeProfitLevel {
+    pub price: f64,
+    /// Fraction of the initial position sold at this level
+    pub size_pct: f64,
+    #[serde(default)]
+    pub filled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExitRules {
+    #[serde(rename = "_id")]
+    pub position_id: ObjectId,
+    pub token_address: String,
+    pub stop_price: Option<f64>,
+    /// Fraction below the highest price seen that closes the position
+    pub trailing_stop_pct: Option<f64>,
+    /// Highest price seen since the position was opened
+    pub high_water_price: f64,
+    /// Levels ordered by price
+    pub take_profit: Vec<TakeProfitLevel>,
+    pub updated_at: DateTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitReason {
+    StopLoss,
+    TrailingStop,
+    /// Indices of the take-profit levels that were hit
+    TakeProfit(Vec<usize>),
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitReason::StopLoss => write!(f, "stop loss"),
+            ExitReason::TrailingStop => write!(f, "trailing stop"),
+            ExitReason::TakeProfit(levels) => {
+                let levels: Vec<String> = levels.iter().map(|l| (l + 1).to_string()).collect();
+                write!(f, "take profit level {}", levels.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExitAction {
+    pub reason: ExitReason,
+    pub quantity: TokenAmount,
+}
+
+impl ExitRules {
+    /// Empty rule set for a position
+    pub fn new(position_id: ObjectId, position: &Position) -> Result<Self> {
+        Ok(Self {
+            position_id,
+            token_address: position.token_address.clone(),
+            stop_price: None,
+            trailing_stop_pct: None,
+            high_water_price: position.entry_price.to_f64()?,
+            take_profit: Vec::new(),
+            updated_at: DateTime::now(),
+        })
+    }
+
+    /// Stop `pct` (fraction) below the entry price
+    pub fn with_stop_loss_pct(mut self, entry_price: f64, pct: f64) -> Self {
+        self.stop_price = Some(entry_price * (1.0 - pct));
+        self
+    }
+
+    pub fn with_trailing_stop_pct(mut self, pct: f64) -> Self {
+        self.trailing_stop_pct = Some(pct);
+        self
+    }
+
+    pub fn with_take_profit(mut self, price: f64, size_pct: f64) -> Self {
+        self.take_profit.push(TakeProfitLevel {
+            price,
+            size_pct,
+            filled: false,
+        });
+        self.take_profit.sort_by(|a, b| a.price.total_cmp(&b.price));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stop_price.is_none()
+            && self.trailing_stop_pct.is_none()
+            && self.take_profit.iter().all(|l| l.filled)
+    }
+
+    /// Apply a price update. Stops sell everything that is left; take-profit
+    /// levels sell their share of the initial size, once each.
+    pub fn evaluate(&mut self, position: &Position, price: f64) -> Result<Option<ExitAction>> {
+        self.high_water_price = self.high_water_price.max(price);
+
+        if self.stop_price.is_some_and(|stop| price <= stop) {
+            return Ok(Some(ExitAction {
+                reason: ExitReason::StopLoss,
+                quantity: position.quantity,
+            }));
+        }
+        if self
+            .trailing_stop_pct
+            .is_some_and(|pct| price <= self.high_water_price * (1.0 - pct))
+        {
+            return Ok(Some(ExitAction {
+                reason: ExitReason::TrailingStop,
+                quantity: position.quantity,
+            }));
+        }
+
+        let mut hit = Vec::new();
+        let mut quantity = TokenAmount::new(0, position.initial_quantity.decimals());
+        for (i, level) in self.take_profit.iter_mut().enumerate() {
+            if !level.filled && price >= level.price {
+                level.filled = true;
+                let share = position
+                    .initial_quantity
+                    .share(&f64_to_decimal(level.size_pct)?)?;
+                quantity = quantity.checked_add(&share)?;
+                hit.push(i);
+            }
+        }
+        if hit.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(ExitAction {
+            reason: ExitReason::TakeProfit(hit),
+            quantity: quantity.checked_min(&position.quantity)?,
+        }))
+    }
+}
+
+/// Storage for exit rules, usable without a trading engine
+pub struct ExitRuleStore {
+    rules: Collection<ExitRules>,
+}
+
+impl ExitRuleStore {
+    pub fn new(db_pool: Arc<MongoDbPool>) -> Self {
+        let db = db_pool.database(&db_pool.get_config().database);
+        Self {
+            rules: db.collection("exit_rules"),
+        }
+    }
+
+    pub async fn get(&self, position_id: ObjectId) -> Result<Option<ExitRules>> {
+        Ok(self.rules.find_one(doc! { "_id": position_id }).await?)
+    }
+
+    pub async fn list(&self) -> Result<Vec<ExitRules>> {
+        Ok(self.rules.find(doc! {}).await?.try_collect().await?)
+    }
+
+    pub async fn save(&self, rules: &ExitRules) -> Result<()> {
+        let mut rules = rules.clone();
+        rules.updated_at = DateTime::now();
+        self.rules
+            .replace_one(doc! { "_id": rules.position_id }, &rules)
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, position_id: ObjectId) -> Result<bool> {
+        let result = self.rules.delete_one(doc! { "_id": position_id }).await?;
+        Ok(result.deleted_count > 0)
+    }
+}
+
+/// Exit rules requested for a position before it is opened, e.g. by a
+/// strategy recommendation. Unset stops fall back to the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExitPlan {
+    /// Stop below the entry price, as a fraction
+    pub stop_loss_pct: Option<f64>,
+    pub trailing_stop_pct: Option<f64>,
+    #[serde(default)]
+    pub take_profit: Vec<TakeProfitLevel>,
+}
+
+/// Rules attached to every newly opened position. A value of 0 disables it.
+#[derive(Debug, Clone, Default)]
+pub struct DefaultExits {
+    pub stop_loss_pct: f64,
+    pub trailing_stop_pct: f64,
+}
+
+pub struct ExitManager {
+    ledger: PositionLedger,
+    rules: ExitRuleStore,
+    engine: Arc<TradingEngine>,
+    defaults: RwLock<DefaultExits>,
+}
+
+impl ExitManager {
+    pub fn new(
+        db_pool: Arc<MongoDbPool>,
+        engine: Arc<TradingEngine>,
+        defaults: DefaultExits,
+    ) -> Self {
+        Self {
+            ledger: PositionLedger::new(db_pool.clone()),
+            rules: ExitRuleStore::new(db_pool),
+            engine,
+            defaults: RwLock::new(defaults),
+        }
+    }
+
+    /// Replace the rules attached to positions opened from now on
+    pub fn set_defaults(&self, defaults: DefaultExits) {
+        *self.defaults.write().unwrap() = defaults;
+    }
+
+    /// Record a filled buy as a position and attach the planned rules, or
+    /// the defaults where the plan leaves them out
+    pub async fn open_position(
+        &self,
+        token_address: &str,
+        quantity: TokenAmount,
+        entry_price: &Price,
+        plan: Option<&ExitPlan>,
+    ) -> Result<(Position, ExitRules)> {
+        let position = self
+            .ledger
+            .open(token_address, quantity, entry_price.clone())
+            .await?;
+        let id = position
+            .id
+            .ok_or_else(|| anyhow!("Position for {} was stored without an id", token_address))?;
+
+        let plan = plan.cloned().unwrap_or_default();
+        let defaults = self.defaults.read().unwrap().clone();
+        let stop_loss_pct = plan.stop_loss_pct.unwrap_or(defaults.stop_loss_pct);
+        let trailing_stop_pct = plan.trailing_stop_pct.unwrap_or(defaults.trailing_stop_pct);
+
+        let mut rules = ExitRules::new(id, &position)?;
+        if stop_loss_pct > 0.0 {
+            rules = rules.with_stop_loss_pct(entry_price.to_f64()?, stop_loss_pct);
+        }
+        if trailing_stop_pct > 0.0 {
+            rules = rules.with_trailing_stop_pct(trailing_stop_pct);
+        }
+        for level in plan.take_profit {
+            rules = rules.with_take_profit(level.price, level.size_pct);
+        }
+        self.rules.save(&rules).await?;
+
+        Ok((position, rules))
+    }
+
+    /// Roll an order fill into the ledger. A buy without a position opens
+    /// one with the planned rules; a buy with one adds to it at the average
+    /// price; a sell reduces the position it names. Returns the position id.
+    pub async fn apply_fill(
+        &self,
+        side: OrderSide,
+        token_address: &str,
+        position_id: Option<ObjectId>,
+        fill: &Fill,
+        plan: Option<&ExitPlan>,
+    ) -> Result<Option<ObjectId>> {
+        match (side, position_id) {
+            (OrderSide::Buy, None) => {
+                let (position, _) = self
+                    .open_position(token_address, fill.quantity, &fill.price, plan)
+                    .await?;
+                Ok(position.id)
+            }
+            (OrderSide::Buy, Some(id)) => {
+                let mut position = self.ledger.get(id).await?;
+                position.add_fill(fill.quantity, &fill.price)?;
+                self.ledger.save(&position).await?;
+                Ok(Some(id))
+            }
+            (OrderSide::Sell, Some(id)) => {
+                let mut position = self.ledger.get(id).await?;
+                self.book_exit(&mut position, fill).await?;
+                Ok(Some(id))
+            }
+            // Not tied to a tracked position
+            (OrderSide::Sell, None) => Ok(None),
+        }
+    }
+
+    /// Tokens held across the open positions in a token, or `None` without
+    /// any
+    pub async fn held(&self, token_address: &str) -> Result<Option<TokenAmount>> {
+        let mut held: Option<TokenAmount> = None;
+        for position in self.ledger.open_positions(Some(token_address)).await? {
+            held = Some(match held {
+                Some(total) => total.checked_add(&position.quantity)?,
+                None => position.quantity,
+            });
+        }
+        Ok(held)
+    }
+
+    /// Book a sell that isn't tied to one position against the open
+    /// positions in its token, oldest first
+    pub async fn reduce_positions(&self, token_address: &str, fill: &Fill) -> Result<()> {
+        let mut remaining = fill.quantity;
+        for mut position in self.ledger.open_positions(Some(token_address)).await? {
+            if remaining.is_zero() {
+                break;
+            }
+            let sold = self
+                .book_exit(
+                    &mut position,
+                    &Fill {
+                        quantity: remaining,
+                        price: fill.price.clone(),
+                    },
+                )
+                .await?;
+            remaining = remaining.checked_sub(&sold)?;
+        }
+        Ok(())
+    }
+
+    /// Sell up to the fill quantity out of `position` and drop its rules once
+    /// it closes. Returns the quantity sold.
+    async fn book_exit(&self, position: &mut Position, fill: &Fill) -> Result<TokenAmount> {
+        let sold = position.apply_exit(fill.quantity, &fill.price, DateTime::now())?;
+        self.ledger.save(position).await?;
+        if !position.is_open() {
+            if let Some(id) = position.id {
+                self.rules.remove(id).await?;
+            }
+        }
+        Ok(sold)
+    }
+
+    /// Evaluate the rules of every open position in a token against a new
+    /// price and submit the resulting sells. Returns the order signatures.
+    pub async fn on_price(&self, token_address: &str, price: f64) -> Result<Vec<String>> {
+        let exit_price = Price::from_f64(price)?;
+        let mut signatures = Vec::new();
+
+        for mut position in self.ledger.open_positions(Some(token_address)).await? {
+            let Some(id) = position.id else { continue };
+            let Some(mut rules) = self.rules.get(id).await? else {
+                continue;
+            };
+
+            let high_water = rules.high_water_price;
+            let Some(action) = rules.evaluate(&position, price)? else {
+                if rules.high_water_price > high_water {
+                    self.rules.save(&rules).await?;
+                }
+                continue;
+            };
+
+            info!(
+                "Exit triggered for position {} ({}): {} at {:.8}",
+                id, token_address, action.reason, price
+            );
+            let order = TradeOrder::sell(token_address, action.quantity, action.reason.to_string());
+            match self.engine.submit_order(&order).await {
+                Ok(execution) => {
+                    let fill = execution.fill_or(action.quantity, &exit_price);
+                    position.apply_exit(fill.quantity, &fill.price, DateTime::now())?;
+                    self.ledger.save(&position).await?;
+                    if position.is_open() {
+                        self.rules.save(&rules).await?;
+                    } else {
+                        self.rules.remove(id).await?;
+                    }
+                    signatures.push(execution.signature);
+                }
+                // Rules are left untouched so the exit is retried on the next price
+                Err(e) => warn!("Exit order for position {} failed: {}", id, e),
+            }
+        }
+
+        Ok(signatures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(value: u64) -> TokenAmount {
+        TokenAmount::new(value * 1_000_000, 6)
+    }
+
+    fn position() -> Position {
+        Position::new("token", tokens(100), Price::from_f64(1.0).unwrap())
+    }
+
+    fn rules() -> ExitRules {
+        ExitRules::new(ObjectId::new(), &position()).unwrap()
+    }
+
+    #[test]
+    fn test_stop_loss() {
+        let mut rules = rules().with_stop_loss_pct(1.0, 0.1);
+        assert_eq!(rules.evaluate(&position(), 0.95).unwrap(), None);
+
+        let action = rules.evaluate(&position(), 0.9).unwrap().unwrap();
+        assert_eq!(action.reason, ExitReason::StopLoss);
+        assert_eq!(action.quantity, tokens(100));
+    }
+
+    #[test]
+    fn test_trailing_stop_follows_high() {
+        let mut rules = rules().with_trailing_stop_pct(0.2);
+        assert_eq!(rules.evaluate(&position(), 2.0).unwrap(), None);
+        assert_eq!(rules.evaluate(&position(), 1.7).unwrap(), None);
+
+        let action = rules.evaluate(&position(), 1.6).unwrap().unwrap();
+        assert_eq!(action.reason, ExitReason::TrailingStop);
+    }
+
+    #[test]
+    fn test_take_profit_levels_fill_once() {
+        let mut rules = rules()
+            .with_take_profit(2.0, 0.5)
+            .with_take_profit(1.5, 0.25);
+        let mut position = position();
+
+        let action = rules.evaluate(&position, 1.6).unwrap().unwrap();
+        assert_eq!(action.reason, ExitReason::TakeProfit(vec![0]));
+        assert_eq!(action.quantity, tokens(25));
+        position
+            .apply_exit(
+                action.quantity,
+                &Price::from_f64(1.6).unwrap(),
+                DateTime::now(),
+            )
+            .unwrap();
+
+        assert_eq!(rules.evaluate(&position, 1.6).unwrap(), None);
+
+        // A gap over both levels only sells the unfilled one
+        let action = rules.evaluate(&position, 2.5).unwrap().unwrap();
+        assert_eq!(action.reason, ExitReason::TakeProfit(vec![1]));
+        assert_eq!(action.quantity, tokens(50));
+    }
+}
@@ -0,0 +1,433 @@
+//! Limit orders emulated on top of market swaps.
+//!
+//! Pending [`LimitOrder`]s are stored in `limit_orders`. The
+//! [`TriggerEngine`] watches prices from BirdEye and, once an order's limit is
+//! crossed, claims the order, quotes the swap and fires exactly that quote
+//! through the [`TradingEngine`] only if it is still within the order's
+//! slippage tolerance.
+
+use super::exits::{ExitManager, ExitPlan};
+use super::order::{OrderSide, TradeOrder};
+use super::trading_engine::TradingEngine;
+use crate::birdeye::BirdeyeApi;
+use crate::config::mongodb::MongoDbPool;
+use crate::models::money::TokenAmount;
+use anyhow::{anyhow, bail, Result};
+use bson::{doc, oid::ObjectId, DateTime, Document};
+use futures::TryStreamExt;
+use mongodb::{options::ReturnDocument, Collection};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LimitStatus {
+    Pending,
+    /// Claimed by a trigger engine that is quoting or sending its swap
+    Firing,
+    Filled,
+    Expired,
+    Cancelled,
+    /// The swap was submitted and failed
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LimitOrder {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub token_address: String,
+    pub side: OrderSide,
+    /// Size in the token's base units
+    pub quantity: TokenAmount,
+    /// Highest price a buy fills at, lowest price a sell fills at
+    pub limit_price: f64,
+    /// How far past the limit the fire-time quote may be, as a fraction
+    pub max_slippage: f64,
+    pub expires_at: DateTime,
+    pub status: LimitStatus,
+    /// Position a sell reduces
+    pub position_id: Option<ObjectId>,
+    /// Exit rules for the position a buy opens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_plan: Option<ExitPlan>,
+    pub reason: String,
+    pub created_at: DateTime,
+    pub fill_price: Option<f64>,
+    pub signature: Option<String>,
+    pub closed_at: Option<DateTime>,
+    pub error: Option<String>,
+}
+
+impl LimitOrder {
+    pub fn new(
+        token_address: &str,
+        side: OrderSide,
+        quantity: TokenAmount,
+        limit_price: f64,
+        max_slippage: f64,
+        expires_at: DateTime,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: None,
+            token_address: token_address.to_string(),
+            side,
+            quantity,
+            limit_price,
+            max_slippage,
+            expires_at,
+            status: LimitStatus::Pending,
+            position_id: None,
+            exit_plan: None,
+            reason: reason.into(),
+            created_at: DateTime::now(),
+            fill_price: None,
+            signature: None,
+            closed_at: None,
+            error: None,
+        }
+    }
+
+    pub fn with_position(mut self, position_id: ObjectId) -> Self {
+        self.position_id = Some(position_id);
+        self
+    }
+
+    pub fn with_exit_plan(mut self, plan: ExitPlan) -> Self {
+        self.exit_plan = Some(plan);
+        self
+    }
+
+    pub fn is_expired(&self, now: DateTime) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Whether a watched price has reached the limit
+    pub fn crosses(&self, price: f64) -> bool {
+        match self.side {
+            OrderSide::Buy => price <= self.limit_price,
+            OrderSide::Sell => price >= self.limit_price,
+        }
+    }
+
+    /// Whether a fire-time quote is still acceptable
+    pub fn within_slippage(&self, quote: f64) -> bool {
+        match self.side {
+            OrderSide::Buy => quote <= self.limit_price * (1.0 + self.max_slippage),
+            OrderSide::Sell => quote >= self.limit_price * (1.0 - self.max_slippage),
+        }
+    }
+
+    fn close(&mut self, status: LimitStatus, at: DateTime) {
+        self.status = status;
+        self.closed_at = Some(at);
+    }
+}
+
+/// Storage for limit orders, usable without a trading engine
+pub struct LimitOrderStore {
+    orders: Collection<LimitOrder>,
+}
+
+impl LimitOrderStore {
+    pub fn new(db_pool: Arc<MongoDbPool>) -> Self {
+        let db = db_pool.database(&db_pool.get_config().database);
+        Self {
+            orders: db.collection("limit_orders"),
+        }
+    }
+
+    pub async fn insert(&self, order: &LimitOrder) -> Result<LimitOrder> {
+        let mut order = order.clone();
+        let result = self.orders.insert_one(&order).await?;
+        order.id = result.inserted_id.as_object_id();
+        Ok(order)
+    }
+
+    /// Pending orders, optionally only those in one token, oldest first
+    pub async fn pending(&self, token_address: Option<&str>) -> Result<Vec<LimitOrder>> {
+        let mut filter = doc! { "status": "Pending" };
+        if let Some(token_address) = token_address {
+            filter.insert("token_address", token_address);
+        }
+        Ok(self
+            .orders
+            .find(filter)
+            .sort(doc! { "created_at": 1 })
+            .await?
+            .try_collect()
+            .await?)
+    }
+
+    /// Move a pending order to `Firing`. Returns false if it is no longer
+    /// pending, for example because it was cancelled or another engine
+    /// claimed it.
+    pub async fn claim(&self, id: ObjectId) -> Result<bool> {
+        self.transition(id, LimitStatus::Pending, doc! { "status": "Firing" })
+            .await
+    }
+
+    /// Return a claimed order to `Pending` without firing it
+    pub async fn release(&self, id: ObjectId) -> Result<()> {
+        self.transition(id, LimitStatus::Firing, doc! { "status": "Pending" })
+            .await?;
+        Ok(())
+    }
+
+    /// Store how an order closed if it is still in the status `from`.
+    /// Returns false if its status changed in the meantime.
+    pub async fn close(&self, order: &LimitOrder, from: LimitStatus) -> Result<bool> {
+        let id = order
+            .id
+            .ok_or_else(|| anyhow!("Cannot close a limit order that was never stored"))?;
+        self.transition(
+            id,
+            from,
+            doc! {
+                "status": bson::to_bson(&order.status)?,
+                "fill_price": order.fill_price,
+                "signature": order.signature.as_deref(),
+                "closed_at": order.closed_at,
+                "error": order.error.as_deref(),
+            },
+        )
+        .await
+    }
+
+    pub async fn set_position(&self, id: ObjectId, position_id: Option<ObjectId>) -> Result<()> {
+        self.orders
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "position_id": position_id } },
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn cancel(&self, id: ObjectId) -> Result<LimitOrder> {
+        let cancelled = self
+            .orders
+            .find_one_and_update(
+                doc! { "_id": id, "status": "Pending" },
+                doc! { "$set": { "status": "Cancelled", "closed_at": DateTime::now() } },
+            )
+            .return_document(ReturnDocument::After)
+            .await?;
+        if let Some(order) = cancelled {
+            return Ok(order);
+        }
+        match self.orders.find_one(doc! { "_id": id }).await? {
+            Some(order) => bail!("Limit order {} is already {:?}", id, order.status),
+            None => bail!("Limit order {} not found", id),
+        }
+    }
+
+    /// Apply `set` to an order only while it is in the status `from`
+    async fn transition(&self, id: ObjectId, from: LimitStatus, set: Document) -> Result<bool> {
+        let result = self
+            .orders
+            .update_one(
+                doc! { "_id": id, "status": bson::to_bson(&from)? },
+                doc! { "$set": set },
+            )
+            .await?;
+        Ok(result.modified_count == 1)
+    }
+}
+
+pub struct TriggerEngine {
+    store: LimitOrderStore,
+    exits: Arc<ExitManager>,
+    engine: Arc<TradingEngine>,
+    birdeye: Arc<dyn BirdeyeApi>,
+}
+
+impl TriggerEngine {
+    pub fn new(
+        db_pool: Arc<MongoDbPool>,
+        engine: Arc<TradingEngine>,
+        exits: Arc<ExitManager>,
+        birdeye: Arc<dyn BirdeyeApi>,
+    ) -> Self {
+        Self {
+            store: LimitOrderStore::new(db_pool),
+            exits,
+            engine,
+            birdeye,
+        }
+    }
+
+    pub async fn place(&self, order: LimitOrder) -> Result<LimitOrder> {
+        let order = self.store.insert(&order).await?;
+        info!(
+            "Placed limit {} {} {} at {:.8}",
+            order.side, order.quantity, order.token_address, order.limit_price
+        );
+        Ok(order)
+    }
+
+    /// Fetch a price for every token with pending orders and fire the ones
+    /// that crossed. Returns the signatures of the swaps sent.
+    pub async fn watch(&self) -> Result<Vec<String>> {
+        let tokens: BTreeSet<String> = self
+            .store
+            .pending(None)
+            .await?
+            .into_iter()
+            .map(|o| o.token_address)
+            .collect();
+
+        let mut signatures = Vec::new();
+        for token in tokens {
+            match self.price(&token).await {
+                Ok(price) => signatures.extend(self.on_price(&token, price).await?),
+                Err(e) => warn!("No price for {}, limit orders not checked: {}", token, e),
+            }
+        }
+        Ok(signatures)
+    }
+
+    /// Check the pending orders of a token against a watched price
+    pub async fn on_price(&self, token_address: &str, price: f64) -> Result<Vec<String>> {
+        let mut signatures = Vec::new();
+
+        for mut order in self.store.pending(Some(token_address)).await? {
+            let Some(id) = order.id else { continue };
+            let now = DateTime::now();
+            if order.is_expired(now) {
+                order.close(LimitStatus::Expired, now);
+                if self.store.close(&order, LimitStatus::Pending).await? {
+                    info!("Limit order {} expired", id);
+                }
+                continue;
+            }
+            if !order.crosses(price) {
+                continue;
+            }
+            // Only the engine that claims an order fires it, and a cancelled
+            // order can't be claimed
+            if !self.store.claim(id).await? {
+                continue;
+            }
+            if let Some(signature) = self.fire(&mut order, price).await? {
+                signatures.push(signature);
+            }
+        }
+
+        Ok(signatures)
+    }
+
+    /// Quote and send a claimed order. The watched price can be stale by
+    /// the time the swap goes out, so the swap is the quote checked against
+    /// the limit. Returns the signature if the order filled.
+    async fn fire(&self, order: &mut LimitOrder, price: f64) -> Result<Option<String>> {
+        let Some(id) = order.id else { return Ok(None) };
+        let swap = TradeOrder {
+            token_address: order.token_address.clone(),
+            side: order.side,
+            quantity: order.quantity,
+            reason: format!("limit {:.8}: {}", order.limit_price, order.reason),
+        };
+        let quote = match self.engine.quote(&swap, order.max_slippage).await {
+            Ok(quote) => quote,
+            Err(e) => {
+                warn!("No quote for limit order {}, retrying later: {}", id, e);
+                self.store.release(id).await?;
+                return Ok(None);
+            }
+        };
+        let quoted = quote.fill.price.to_f64()?;
+        if !order.within_slippage(quoted) {
+            info!(
+                "Limit order {} crossed at {:.8} but quote {:.8} is past slippage, waiting",
+                id, price, quoted
+            );
+            self.store.release(id).await?;
+            return Ok(None);
+        }
+
+        let execution = match self.engine.execute(&quote).await {
+            Ok(execution) => execution,
+            Err(e) => {
+                warn!("Limit order {} failed to fill: {}", id, e);
+                order.error = Some(e.to_string());
+                order.close(LimitStatus::Failed, DateTime::now());
+                self.store.close(order, LimitStatus::Firing).await?;
+                return Ok(None);
+            }
+        };
+
+        // The fill is stored before the position ledger is touched so a
+        // ledger error can't fire the swap again
+        let fill = execution.fill_or(quote.order.quantity, &quote.fill.price);
+        order.fill_price = Some(fill.price.to_f64()?);
+        order.signature = Some(execution.signature.clone());
+        order.close(LimitStatus::Filled, DateTime::now());
+        self.store.close(order, LimitStatus::Firing).await?;
+
+        match self
+            .exits
+            .apply_fill(
+                order.side,
+                &order.token_address,
+                order.position_id,
+                &fill,
+                order.exit_plan.as_ref(),
+            )
+            .await
+        {
+            Ok(position_id) if position_id != order.position_id => {
+                order.position_id = position_id;
+                self.store.set_position(id, position_id).await?;
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to roll limit order {} into its position: {}", id, e),
+        }
+        Ok(Some(execution.signature))
+    }
+
+    async fn price(&self, token_address: &str) -> Result<f64> {
+        Ok(self.birdeye.get_token_overview(token_address).await?.price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(side: OrderSide) -> LimitOrder {
+        LimitOrder::new(
+            "token",
+            side,
+            TokenAmount::new(10_000_000, 6),
+            1.0,
+            0.02,
+            DateTime::from_millis(1_000),
+            "test",
+        )
+    }
+
+    #[test]
+    fn test_crossing_and_slippage_by_side() {
+        let buy = order(OrderSide::Buy);
+        assert!(buy.crosses(0.99));
+        assert!(!buy.crosses(1.01));
+        assert!(buy.within_slippage(1.019));
+        assert!(!buy.within_slippage(1.03));
+
+        let sell = order(OrderSide::Sell);
+        assert!(sell.crosses(1.01));
+        assert!(!sell.crosses(0.99));
+        assert!(sell.within_slippage(0.981));
+        assert!(!sell.within_slippage(0.97));
+    }
+
+    #[test]
+    fn test_expiry() {
+        let order = order(OrderSide::Buy);
+        assert!(!order.is_expired(DateTime::from_millis(999)));
+        assert!(order.is_expired(DateTime::from_millis(1_000)));
+    }
+}
@@ -1,36 +1,113 @@
+pub mod exits;
+pub mod limit_orders;
+pub mod order;
+pub mod positions;
+pub mod scheduler;
+pub mod swap;
 pub mod trading_engine;
+pub mod transactions;
 
-use anyhow::Result;
-use solana_client::rpc_client::RpcClient;
+use crate::config::SolanaConfig;
+use anyhow::{anyhow, Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+use spl_token_2022::{extension::StateWithExtensions, state::Mint};
+use std::sync::Arc;
+use transactions::TransactionService;
 
+/// The agent's signing wallet
+pub struct Wallet {
+    pub wallet: Arc<Keypair>,
+    pub address: Pubkey,
+}
+
+impl Wallet {
+    pub fn new(keypair: Keypair) -> Self {
+        Self {
+            address: keypair.pubkey(),
+            wallet: Arc::new(keypair),
+        }
+    }
+}
+
+/// RPC connection, wallet and settings shared by the on-chain actions
 pub struct SolanaAgentKit {
-    rpc_client: RpcClient,
-    wallet_keypair: solana_sdk::signer::keypair::Keypair,
+    pub connection: Arc<RpcClient>,
+    pub wallet: Wallet,
+    pub config: SolanaConfig,
 }
 
 impl SolanaAgentKit {
-    pub fn new(rpc_url: &str, wallet_keypair: solana_sdk::signer::keypair::Keypair) -> Self {
-        Self {
-            rpc_client: RpcClient::new(rpc_url.to_string()),
+    pub fn new(rpc_url: &str, wallet_keypair: Keypair) -> Self {
+        Self::with_client(
+            RpcClient::new(rpc_url.to_string()),
             wallet_keypair,
+            SolanaConfig::default(),
+        )
+    }
+
+    /// Toolkit on an existing RPC client, e.g. a mock in tests
+    pub fn with_client(connection: RpcClient, wallet_keypair: Keypair, config: SolanaConfig) -> Self {
+        Self {
+            connection: Arc::new(connection),
+            wallet: Wallet::new(wallet_keypair),
+            config,
         }
     }
 
+    pub fn with_config(mut self, config: SolanaConfig) -> Self {
+        self.config = config;
+        self
+    }
+
     pub fn new_from_env() -> Result<Self> {
         let rpc_url = std::env::var("SOLANA_RPC_URL")?;
         let wallet_key = std::env::var("SOLANA_PRIVATE_KEY")?;
 
         // Parse the base58 private key
-        let wallet_keypair = solana_sdk::signer::keypair::Keypair::from_base58_string(&wallet_key);
+        let wallet_keypair = Keypair::from_base58_string(&wallet_key);
 
-        Ok(Self::new(&rpc_url, wallet_keypair))
+        Ok(Self::new(&rpc_url, wallet_keypair).with_config(SolanaConfig::new_from_env()?))
     }
 
     pub fn get_rpc_client(&self) -> &RpcClient {
-        &self.rpc_client
+        &self.connection
+    }
+
+    pub fn get_wallet_keypair(&self) -> &Keypair {
+        &self.wallet.wallet
     }
 
-    pub fn get_wallet_keypair(&self) -> &solana_sdk::signer::keypair::Keypair {
-        &self.wallet_keypair
+    /// Transaction sender for the wallet, using the configured commitment
+    pub fn transactions(&self) -> TransactionService {
+        TransactionService::new(
+            self.connection.clone(),
+            self.wallet.wallet.clone(),
+            self.config.transactions.clone(),
+        )
+    }
+}
+
+/// Token program that owns `mint` and the mint's decimals
+pub async fn mint_info(connection: &RpcClient, mint: &Pubkey) -> Result<(Pubkey, u8)> {
+    let mint_account = connection
+        .get_account(mint)
+        .await
+        .with_context(|| format!("Failed to get account info for mint {}", mint))?;
+
+    // The mint's owner tells which token program its accounts belong to
+    let token_program = mint_account.owner;
+    if token_program != spl_token::ID && token_program != spl_token_2022::ID {
+        return Err(anyhow!(
+            "{} is not a token mint (owned by {})",
+            mint,
+            token_program
+        ));
     }
+    // Token-2022 mints carry extensions after the base layout
+    let decimals = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+        .map_err(|e| anyhow!("Failed to unpack mint info for {}: {}", mint, e))?
+        .base
+        .decimals;
+    Ok((token_program, decimals))
 }
@@ -0,0 +1,90 @@
+use crate::models::money::{Price, TokenAmount, UsdAmount};
+use anyhow::Result;
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl fmt::Display for OrderSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderSide::Buy => write!(f, "BUY"),
+            OrderSide::Sell => write!(f, "SELL"),
+        }
+    }
+}
+
+/// A market order handed to the [`TradingEngine`](super::trading_engine::TradingEngine)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeOrder {
+    pub token_address: String,
+    pub side: OrderSide,
+    /// Size in the token's base units
+    pub quantity: TokenAmount,
+    /// Why the order was placed, for logs and the trade ledger
+    pub reason: String,
+}
+
+impl TradeOrder {
+    pub fn buy(token_address: &str, quantity: TokenAmount, reason: impl Into<String>) -> Self {
+        Self {
+            token_address: token_address.to_string(),
+            side: OrderSide::Buy,
+            quantity,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn sell(token_address: &str, quantity: TokenAmount, reason: impl Into<String>) -> Self {
+        Self {
+            token_address: token_address.to_string(),
+            side: OrderSide::Sell,
+            quantity,
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Tokens an order swapped and the price per token, in units of the quote
+/// mint
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub quantity: TokenAmount,
+    pub price: Price,
+}
+
+impl Fill {
+    /// `quantity` tokens swapped for `quote_amount` whole units of the quote
+    /// mint
+    pub fn new(quantity: TokenAmount, quote_amount: &BigDecimal) -> Result<Self> {
+        let price = Price::per_unit(
+            &UsdAmount::new(quote_amount.clone()),
+            &quantity.to_decimal(),
+        )?;
+        Ok(Self { quantity, price })
+    }
+}
+
+/// A submitted order
+#[derive(Debug, Clone)]
+pub struct Execution {
+    pub signature: String,
+    /// What the order filled: read from the confirmed transaction, or the
+    /// quote in dry-run mode. `None` for a dry run that wasn't quoted.
+    pub fill: Option<Fill>,
+}
+
+impl Execution {
+    /// The fill, or `quantity` at `price` when nothing was filled on-chain
+    pub fn fill_or(&self, quantity: TokenAmount, price: &Price) -> Fill {
+        self.fill.clone().unwrap_or_else(|| Fill {
+            quantity,
+            price: price.clone(),
+        })
+    }
+}
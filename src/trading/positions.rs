@@ -0,0 +1,225 @@
+//! Ledger of positions opened by the trading agent, stored in `positions`.
+
+use crate::config::mongodb::MongoDbPool;
+use crate::models::money::{Price, TokenAmount, UsdAmount};
+use anyhow::{anyhow, Result};
+use bson::{doc, oid::ObjectId, DateTime};
+use futures::TryStreamExt;
+use mongodb::Collection;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PositionStatus {
+    Open,
+    Closed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub token_address: String,
+    /// Quantity still held
+    pub quantity: TokenAmount,
+    /// Quantity bought when the position was opened
+    pub initial_quantity: TokenAmount,
+    /// Average price paid
+    pub entry_price: Price,
+    pub status: PositionStatus,
+    /// Profit of the exits so far
+    pub realized_pnl: UsdAmount,
+    pub opened_at: DateTime,
+    pub closed_at: Option<DateTime>,
+}
+
+impl Position {
+    pub fn new(token_address: &str, quantity: TokenAmount, entry_price: Price) -> Self {
+        Self {
+            id: None,
+            token_address: token_address.to_string(),
+            quantity,
+            initial_quantity: quantity,
+            entry_price,
+            status: PositionStatus::Open,
+            realized_pnl: UsdAmount::zero(),
+            opened_at: DateTime::now(),
+            closed_at: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.status == PositionStatus::Open
+    }
+
+    /// Add a buy fill, moving the entry price to the average cost
+    pub fn add_fill(&mut self, quantity: TokenAmount, price: &Price) -> Result<()> {
+        let total = self.quantity.checked_add(&quantity)?;
+        if !total.is_zero() {
+            let cost = self.quantity.value_at(&self.entry_price) + quantity.value_at(price);
+            self.entry_price = Price::per_unit(&cost, &total.to_decimal())?;
+        }
+        self.quantity = total;
+        self.initial_quantity = self.initial_quantity.checked_add(&quantity)?;
+        Ok(())
+    }
+
+    /// Book a sale of up to `quantity` at `price`. Returns the quantity sold.
+    pub fn apply_exit(
+        &mut self,
+        quantity: TokenAmount,
+        price: &Price,
+        at: DateTime,
+    ) -> Result<TokenAmount> {
+        let sold = quantity.checked_min(&self.quantity)?;
+        let pnl = sold.value_at(price) - sold.value_at(&self.entry_price);
+        self.realized_pnl = self.realized_pnl.clone() + pnl;
+        self.quantity = self.quantity.checked_sub(&sold)?;
+        if self.quantity.is_zero() {
+            self.status = PositionStatus::Closed;
+            self.closed_at = Some(at);
+        }
+        Ok(sold)
+    }
+}
+
+pub struct PositionLedger {
+    positions: Collection<Position>,
+}
+
+impl PositionLedger {
+    pub fn new(db_pool: Arc<MongoDbPool>) -> Self {
+        let db = db_pool.database(&db_pool.get_config().database);
+        Self {
+            positions: db.collection("positions"),
+        }
+    }
+
+    pub async fn open(
+        &self,
+        token_address: &str,
+        quantity: TokenAmount,
+        entry_price: Price,
+    ) -> Result<Position> {
+        let mut position = Position::new(token_address, quantity, entry_price);
+        let result = self.positions.insert_one(&position).await?;
+        position.id = result.inserted_id.as_object_id();
+        Ok(position)
+    }
+
+    pub async fn get(&self, id: ObjectId) -> Result<Position> {
+        self.positions
+            .find_one(doc! { "_id": id })
+            .await?
+            .ok_or_else(|| anyhow!("Position {} not found", id))
+    }
+
+    /// Open positions, optionally only those in one token
+    pub async fn open_positions(&self, token_address: Option<&str>) -> Result<Vec<Position>> {
+        let mut filter = doc! { "status": "Open" };
+        if let Some(token_address) = token_address {
+            filter.insert("token_address", token_address);
+        }
+        Ok(self
+            .positions
+            .find(filter)
+            .sort(doc! { "opened_at": 1 })
+            .await?
+            .try_collect()
+            .await?)
+    }
+
+    /// Realized profit of every position, open or closed
+    pub async fn realized_pnl(&self) -> Result<UsdAmount> {
+        let positions: Vec<Position> = self.positions.find(doc! {}).await?.try_collect().await?;
+        Ok(positions
+            .into_iter()
+            .fold(UsdAmount::zero(), |total, p| total + p.realized_pnl))
+    }
+
+    pub async fn save(&self, position: &Position) -> Result<()> {
+        let id = position
+            .id
+            .ok_or_else(|| anyhow!("Cannot save a position that was never opened"))?;
+        self.positions
+            .replace_one(doc! { "_id": id }, position)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(value: f64) -> Price {
+        Price::from_f64(value).unwrap()
+    }
+
+    fn tokens(value: u64) -> TokenAmount {
+        TokenAmount::new(value * 1_000_000, 6)
+    }
+
+    #[test]
+    fn test_add_fill_averages_entry() {
+        let mut position = Position::new("token", tokens(10), price(1.0));
+        position.add_fill(tokens(30), &price(2.0)).unwrap();
+
+        assert_eq!(position.quantity, tokens(40));
+        assert_eq!(position.initial_quantity, tokens(40));
+        assert_eq!(position.entry_price, price(1.75));
+    }
+
+    #[test]
+    fn test_averaged_position_saves_to_bson() {
+        let mut position = Position::new("token", tokens(10), price(1.0));
+        position.add_fill(tokens(20), &price(1.1)).unwrap();
+
+        let stored = bson::to_document(&position).unwrap();
+        let loaded: Position = bson::from_document(stored).unwrap();
+        assert_eq!(
+            loaded.entry_price.to_string(),
+            "1.066666666666666666666666666666667"
+        );
+    }
+
+    #[test]
+    fn test_partial_then_full_exit() {
+        let mut position = Position::new("token", tokens(100), price(0.1));
+
+        assert_eq!(
+            position
+                .apply_exit(tokens(40), &price(0.3), DateTime::now())
+                .unwrap(),
+            tokens(40)
+        );
+        assert!(position.is_open());
+        // Exact, where f64 gives 7.999999999999999
+        assert_eq!(position.realized_pnl, UsdAmount::from_f64(8.0).unwrap());
+
+        // Selling more than is held only sells the remainder
+        assert_eq!(
+            position
+                .apply_exit(tokens(100), &price(0.05), DateTime::now())
+                .unwrap(),
+            tokens(60)
+        );
+        assert!(!position.is_open());
+        assert_eq!(position.realized_pnl, UsdAmount::from_f64(5.0).unwrap());
+    }
+
+    #[test]
+    fn test_position_stored_with_f64_quantity() {
+        let mut stored = bson::to_document(&Position::new("token", tokens(1), price(1.0))).unwrap();
+        stored.insert("quantity", 2.5);
+        stored.insert("initial_quantity", 2.5);
+        let mut position: Position = bson::from_document(stored).unwrap();
+
+        // Exits are booked at the larger of the two precisions
+        let sold = position
+            .apply_exit(TokenAmount::new(500_000, 6), &price(1.0), DateTime::now())
+            .unwrap();
+        assert_eq!(sold, TokenAmount::new(500_000, 6));
+        assert_eq!(position.quantity, tokens(2));
+    }
+}
@@ -0,0 +1,773 @@
+//! Execution schedules that split a parent order into child orders over time.
+//!
+//! A [`ParentOrder`] and its children are stored together in `parent_orders`.
+//! The [`ExecutionScheduler`] submits children as they come due and rolls
+//! their fills into the position ledger: buys into one position whose entry
+//! price is the average fill price, sells out of the position they target.
+
+use super::exits::{ExitManager, ExitPlan};
+use super::order::{OrderSide, TradeOrder};
+use super::trading_engine::TradingEngine;
+use crate::config::mongodb::MongoDbPool;
+use crate::models::money::{Price, TokenAmount, UsdAmount};
+use anyhow::{anyhow, bail, Result};
+use bson::{doc, oid::ObjectId, DateTime, Document};
+use futures::TryStreamExt;
+use mongodb::Collection;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Schedule {
+    /// Equal entries at a fixed interval, the first one immediately
+    Dca { interval_secs: u64, entries: usize },
+    /// Equal slices spread evenly over a window, each at the start of its slot
+    Twap { window_secs: u64, slices: usize },
+}
+
+impl Schedule {
+    /// DCA plan as the strategy prompt describes it
+    pub fn dca(interval_hours: f64, num_entries: usize) -> Self {
+        Schedule::Dca {
+            interval_secs: (interval_hours * 3600.0).round() as u64,
+            entries: num_entries,
+        }
+    }
+
+    pub fn twap(window_secs: u64, slices: usize) -> Self {
+        Schedule::Twap {
+            window_secs,
+            slices,
+        }
+    }
+
+    pub fn child_count(&self) -> usize {
+        match *self {
+            Schedule::Dca { entries, .. } => entries.max(1),
+            Schedule::Twap { slices, .. } => slices.max(1),
+        }
+    }
+
+    /// Time between two consecutive children, in milliseconds
+    fn step_millis(&self) -> i64 {
+        match *self {
+            Schedule::Dca { interval_secs, .. } => interval_secs as i64 * 1000,
+            Schedule::Twap {
+                window_secs,
+                slices,
+            } => window_secs as i64 * 1000 / slices.max(1) as i64,
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schedule::Dca {
+                interval_secs,
+                entries,
+            } => write!(f, "DCA {} x every {}s", entries, interval_secs),
+            Schedule::Twap {
+                window_secs,
+                slices,
+            } => write!(f, "TWAP {} slices over {}s", slices, window_secs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParentStatus {
+    Active,
+    Paused,
+    Cancelled,
+    /// Every child filled
+    Completed,
+    /// Every child is done and some of them failed
+    PartiallyFilled,
+    /// Every child failed
+    Failed,
+}
+
+impl ParentStatus {
+    /// No child of the order will be submitted again
+    pub fn is_final(&self) -> bool {
+        !matches!(self, ParentStatus::Active | ParentStatus::Paused)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChildStatus {
+    Pending,
+    /// Claimed by a scheduler run that is quoting or sending it
+    Submitting,
+    Filled,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildOrder {
+    pub due_at: DateTime,
+    pub quantity: TokenAmount,
+    pub status: ChildStatus,
+    pub fill_price: Option<f64>,
+    pub signature: Option<String>,
+    pub filled_at: Option<DateTime>,
+    /// Last submission error, if the child failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParentOrder {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub token_address: String,
+    pub side: OrderSide,
+    /// Total size, split evenly between the children
+    pub quantity: TokenAmount,
+    pub schedule: Schedule,
+    pub status: ParentStatus,
+    pub children: Vec<ChildOrder>,
+    /// Position the fills roll into. Buys open it on their first fill; sells
+    /// must name it up front to reduce it.
+    pub position_id: Option<ObjectId>,
+    /// Exit rules for the position a buy opens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_plan: Option<ExitPlan>,
+    pub reason: String,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl ParentOrder {
+    pub fn new(
+        token_address: &str,
+        side: OrderSide,
+        quantity: TokenAmount,
+        schedule: Schedule,
+        reason: impl Into<String>,
+        start: DateTime,
+    ) -> Self {
+        let step = schedule.step_millis();
+        let children = quantity
+            .split(schedule.child_count())
+            .into_iter()
+            .enumerate()
+            .map(|(i, quantity)| ChildOrder {
+                due_at: DateTime::from_millis(start.timestamp_millis() + step * i as i64),
+                quantity,
+                status: ChildStatus::Pending,
+                fill_price: None,
+                signature: None,
+                filled_at: None,
+                error: None,
+            })
+            .collect();
+
+        Self {
+            id: None,
+            token_address: token_address.to_string(),
+            side,
+            quantity,
+            schedule,
+            status: ParentStatus::Active,
+            children,
+            position_id: None,
+            exit_plan: None,
+            reason: reason.into(),
+            created_at: start,
+            updated_at: start,
+        }
+    }
+
+    pub fn with_position(mut self, position_id: ObjectId) -> Self {
+        self.position_id = Some(position_id);
+        self
+    }
+
+    pub fn with_exit_plan(mut self, plan: ExitPlan) -> Self {
+        self.exit_plan = Some(plan);
+        self
+    }
+
+    /// Indices of pending children that are due. Nothing is due while the
+    /// parent is not active.
+    pub fn due_children(&self, now: DateTime) -> Vec<usize> {
+        if self.status != ParentStatus::Active {
+            return Vec::new();
+        }
+        self.children
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.status == ChildStatus::Pending && c.due_at <= now)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn record_fill(&mut self, index: usize, price: f64, signature: String, at: DateTime) {
+        let child = &mut self.children[index];
+        child.status = ChildStatus::Filled;
+        child.fill_price = Some(price);
+        child.signature = Some(signature);
+        child.filled_at = Some(at);
+        child.error = None;
+        self.finish_if_done(at);
+    }
+
+    fn filled(&self) -> impl Iterator<Item = &ChildOrder> {
+        self.children
+            .iter()
+            .filter(|c| c.status == ChildStatus::Filled)
+    }
+
+    /// Quantity of the filled children
+    pub fn filled_quantity(&self) -> Result<TokenAmount> {
+        let none = TokenAmount::new(0, self.quantity.decimals());
+        Ok(self
+            .filled()
+            .try_fold(none, |total, c| total.checked_add(&c.quantity))?)
+    }
+
+    /// Volume-weighted price of the filled children, once any filled
+    pub fn average_price(&self) -> Result<Option<Price>> {
+        let filled = self.filled_quantity()?;
+        if filled.is_zero() {
+            return Ok(None);
+        }
+        let mut cost = UsdAmount::zero();
+        for child in self.filled() {
+            let price = Price::from_f64(child.fill_price.unwrap_or_default())?;
+            cost = cost + child.quantity.value_at(&price);
+        }
+        Ok(Some(Price::per_unit(&cost, &filled.to_decimal())?))
+    }
+
+    pub fn record_failure(&mut self, index: usize, error: String, at: DateTime) {
+        let child = &mut self.children[index];
+        child.status = ChildStatus::Failed;
+        child.error = Some(error);
+        self.finish_if_done(at);
+    }
+
+    pub fn pause(&mut self, at: DateTime) -> Result<()> {
+        if self.status != ParentStatus::Active {
+            bail!(
+                "Only an active order can be paused (status {:?})",
+                self.status
+            );
+        }
+        self.status = ParentStatus::Paused;
+        self.updated_at = at;
+        Ok(())
+    }
+
+    /// Resume a paused order. A TWAP spreads its remaining slices from the
+    /// resume time on; DCA entries that came due while it was paused are
+    /// submitted on the next run.
+    pub fn resume(&mut self, at: DateTime) -> Result<()> {
+        if self.status != ParentStatus::Paused {
+            bail!(
+                "Only a paused order can be resumed (status {:?})",
+                self.status
+            );
+        }
+        if let Schedule::Twap { .. } = self.schedule {
+            let step = self.schedule.step_millis();
+            for (i, child) in self
+                .children
+                .iter_mut()
+                .filter(|c| c.status == ChildStatus::Pending)
+                .enumerate()
+            {
+                child.due_at = DateTime::from_millis(at.timestamp_millis() + step * i as i64);
+            }
+        }
+        self.status = ParentStatus::Active;
+        self.updated_at = at;
+        Ok(())
+    }
+
+    pub fn cancel(&mut self, at: DateTime) -> Result<()> {
+        if self.status.is_final() {
+            bail!("Order is already {:?}", self.status);
+        }
+        for child in &mut self.children {
+            if child.status == ChildStatus::Pending {
+                child.status = ChildStatus::Cancelled;
+            }
+        }
+        self.status = ParentStatus::Cancelled;
+        self.updated_at = at;
+        Ok(())
+    }
+
+    fn finish_if_done(&mut self, at: DateTime) {
+        self.updated_at = at;
+        if self
+            .children
+            .iter()
+            .any(|c| matches!(c.status, ChildStatus::Pending | ChildStatus::Submitting))
+        {
+            return;
+        }
+        let failed = self
+            .children
+            .iter()
+            .filter(|c| c.status == ChildStatus::Failed)
+            .count();
+        self.status = if failed == 0 {
+            ParentStatus::Completed
+        } else if failed == self.children.len() {
+            ParentStatus::Failed
+        } else {
+            ParentStatus::PartiallyFilled
+        };
+    }
+}
+
+/// Storage for parent orders, usable without a trading engine
+pub struct ParentOrderStore {
+    orders: Collection<ParentOrder>,
+}
+
+impl ParentOrderStore {
+    pub fn new(db_pool: Arc<MongoDbPool>) -> Self {
+        let db = db_pool.database(&db_pool.get_config().database);
+        Self {
+            orders: db.collection("parent_orders"),
+        }
+    }
+
+    pub async fn insert(&self, order: &ParentOrder) -> Result<ParentOrder> {
+        let mut order = order.clone();
+        let result = self.orders.insert_one(&order).await?;
+        order.id = result.inserted_id.as_object_id();
+        Ok(order)
+    }
+
+    pub async fn get(&self, id: ObjectId) -> Result<ParentOrder> {
+        self.orders
+            .find_one(doc! { "_id": id })
+            .await?
+            .ok_or_else(|| anyhow!("Parent order {} not found", id))
+    }
+
+    /// Orders that are active or paused, oldest first
+    pub async fn open(&self) -> Result<Vec<ParentOrder>> {
+        Ok(self
+            .orders
+            .find(doc! { "status": { "$in": ["Active", "Paused"] } })
+            .sort(doc! { "created_at": 1 })
+            .await?
+            .try_collect()
+            .await?)
+    }
+
+    pub async fn pause(&self, id: ObjectId) -> Result<ParentOrder> {
+        let mut order = self.get(id).await?;
+        order.pause(DateTime::now())?;
+        self.transition(
+            &order,
+            ParentStatus::Active,
+            doc! { "status": "Paused", "updated_at": order.updated_at },
+        )
+        .await
+    }
+
+    pub async fn resume(&self, id: ObjectId) -> Result<ParentOrder> {
+        let mut order = self.get(id).await?;
+        order.resume(DateTime::now())?;
+        let mut set = doc! { "status": "Active", "updated_at": order.updated_at };
+        for (i, child) in order.children.iter().enumerate() {
+            if child.status == ChildStatus::Pending {
+                set.insert(format!("children.{}.due_at", i), child.due_at);
+            }
+        }
+        self.transition(&order, ParentStatus::Paused, set).await
+    }
+
+    pub async fn cancel(&self, id: ObjectId) -> Result<ParentOrder> {
+        let mut order = self.get(id).await?;
+        order.cancel(DateTime::now())?;
+        // A child that is being submitted is left to finish
+        let result = self
+            .orders
+            .update_one(
+                doc! { "_id": id, "status": { "$in": ["Active", "Paused"] } },
+                doc! { "$set": {
+                    "status": "Cancelled",
+                    "updated_at": order.updated_at,
+                    "children.$[pending].status": "Cancelled",
+                } },
+            )
+            .array_filters(vec![doc! { "pending.status": "Pending" }])
+            .await?;
+        if result.matched_count == 0 {
+            bail!("Parent order {} changed meanwhile, try again", id);
+        }
+        self.get(id).await
+    }
+
+    /// Move a pending child of an active order to `Submitting`. Returns
+    /// false if the order was paused or cancelled, or the child claimed.
+    pub async fn claim_child(&self, order: &ParentOrder, index: usize) -> Result<bool> {
+        let mut set = Document::new();
+        set.insert(format!("children.{}.status", index), "Submitting");
+        self.update_child(order, index, ChildStatus::Pending, set)
+            .await
+    }
+
+    /// Return a claimed child to `Pending` without submitting it
+    pub async fn release_child(&self, order: &ParentOrder, index: usize) -> Result<()> {
+        let mut set = Document::new();
+        set.insert(format!("children.{}.status", index), "Pending");
+        self.update_child(order, index, ChildStatus::Submitting, set)
+            .await?;
+        Ok(())
+    }
+
+    /// Store how a claimed child finished, and the order's final status
+    /// once no child is left, unless it was cancelled meanwhile
+    pub async fn finish_child(&self, order: &ParentOrder, index: usize) -> Result<()> {
+        let mut set = doc! { "updated_at": order.updated_at };
+        set.insert(
+            format!("children.{}", index),
+            bson::to_bson(&order.children[index])?,
+        );
+        self.update_child(order, index, ChildStatus::Submitting, set)
+            .await?;
+        if order.status.is_final() {
+            self.orders
+                .update_one(
+                    doc! { "_id": order.id, "status": { "$in": ["Active", "Paused"] } },
+                    doc! { "$set": { "status": bson::to_bson(&order.status)? } },
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn set_position(&self, id: ObjectId, position_id: Option<ObjectId>) -> Result<()> {
+        self.orders
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "position_id": position_id } },
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Apply `set` to an order only while it is in the status `from`, and
+    /// return it as stored
+    async fn transition(
+        &self,
+        order: &ParentOrder,
+        from: ParentStatus,
+        set: Document,
+    ) -> Result<ParentOrder> {
+        let id = order
+            .id
+            .ok_or_else(|| anyhow!("Parent order was never stored"))?;
+        let result = self
+            .orders
+            .update_one(
+                doc! { "_id": id, "status": bson::to_bson(&from)? },
+                doc! { "$set": set },
+            )
+            .await?;
+        if result.matched_count == 0 {
+            bail!("Parent order {} changed meanwhile, try again", id);
+        }
+        self.get(id).await
+    }
+
+    /// Apply `set` to an order while its child is in the status `from`.
+    /// Pending children are only changed while the order is active.
+    async fn update_child(
+        &self,
+        order: &ParentOrder,
+        index: usize,
+        from: ChildStatus,
+        set: Document,
+    ) -> Result<bool> {
+        let id = order
+            .id
+            .ok_or_else(|| anyhow!("Parent order was never stored"))?;
+        let mut filter = doc! { "_id": id };
+        filter.insert(format!("children.{}.status", index), bson::to_bson(&from)?);
+        if from == ChildStatus::Pending {
+            filter.insert("status", "Active");
+        }
+        let result = self.orders.update_one(filter, doc! { "$set": set }).await?;
+        Ok(result.modified_count == 1)
+    }
+}
+
+pub struct ExecutionScheduler {
+    store: ParentOrderStore,
+    exits: Arc<ExitManager>,
+    engine: Arc<TradingEngine>,
+}
+
+impl ExecutionScheduler {
+    pub fn new(
+        db_pool: Arc<MongoDbPool>,
+        engine: Arc<TradingEngine>,
+        exits: Arc<ExitManager>,
+    ) -> Self {
+        Self {
+            store: ParentOrderStore::new(db_pool),
+            exits,
+            engine,
+        }
+    }
+
+    pub fn store(&self) -> &ParentOrderStore {
+        &self.store
+    }
+
+    pub async fn schedule(&self, order: ParentOrder) -> Result<ParentOrder> {
+        if order.side == OrderSide::Sell && order.position_id.is_none() {
+            bail!("A scheduled sell must name the position it reduces");
+        }
+        let order = self.store.insert(&order).await?;
+        info!(
+            "Scheduled {} {} {} {} ({})",
+            order.schedule, order.side, order.quantity, order.token_address, order.reason
+        );
+        Ok(order)
+    }
+
+    /// Submit every child that is due. Returns the number of children filled.
+    pub async fn run_due(&self) -> Result<usize> {
+        let mut filled = 0;
+        for mut order in self.store.open().await? {
+            let Some(id) = order.id else { continue };
+            for index in order.due_children(DateTime::now()) {
+                // A child is only submitted by the run that claims it, and
+                // not once its order is paused or cancelled
+                if !self.store.claim_child(&order, index).await? {
+                    continue;
+                }
+                order.children[index].status = ChildStatus::Submitting;
+                let child = TradeOrder {
+                    token_address: order.token_address.clone(),
+                    side: order.side,
+                    quantity: order.children[index].quantity,
+                    reason: format!(
+                        "{} child {}/{}: {}",
+                        order.schedule,
+                        index + 1,
+                        order.children.len(),
+                        order.reason
+                    ),
+                };
+
+                let quote = match self
+                    .engine
+                    .quote(&child, self.engine.get_max_slippage())
+                    .await
+                {
+                    Ok(quote) => quote,
+                    Err(e) => {
+                        warn!(
+                            "No quote for child order {} of {}, retrying later: {}",
+                            index + 1,
+                            id,
+                            e
+                        );
+                        order.children[index].status = ChildStatus::Pending;
+                        self.store.release_child(&order, index).await?;
+                        continue;
+                    }
+                };
+                let execution = match self.engine.execute(&quote).await {
+                    Ok(execution) => execution,
+                    Err(e) => {
+                        warn!("Child order {} of {} failed: {}", index + 1, id, e);
+                        order.record_failure(index, e.to_string(), DateTime::now());
+                        self.store.finish_child(&order, index).await?;
+                        continue;
+                    }
+                };
+
+                // The fill is stored before the position ledger is touched
+                // so a ledger error can't resubmit the child
+                let fill = execution.fill_or(quote.order.quantity, &quote.fill.price);
+                order.record_fill(
+                    index,
+                    fill.price.to_f64()?,
+                    execution.signature,
+                    DateTime::now(),
+                );
+                self.store.finish_child(&order, index).await?;
+                filled += 1;
+
+                match self
+                    .exits
+                    .apply_fill(
+                        order.side,
+                        &order.token_address,
+                        order.position_id,
+                        &fill,
+                        order.exit_plan.as_ref(),
+                    )
+                    .await
+                {
+                    Ok(position_id) if position_id != order.position_id => {
+                        order.position_id = position_id;
+                        self.store.set_position(id, position_id).await?;
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!(
+                        "Failed to roll child order {} of {} into its position: {}",
+                        index + 1,
+                        id,
+                        e
+                    ),
+                }
+            }
+        }
+        Ok(filled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(minutes: i64) -> DateTime {
+        DateTime::from_millis(minutes * 60 * 1000)
+    }
+
+    fn tokens(value: u64) -> TokenAmount {
+        TokenAmount::new(value * 1_000_000, 6)
+    }
+
+    #[test]
+    fn test_twap_spreads_children_over_window() {
+        let order = ParentOrder::new(
+            "token",
+            OrderSide::Buy,
+            tokens(90),
+            Schedule::twap(3 * 60 * 60, 3),
+            "test",
+            at(0),
+        );
+
+        let due: Vec<_> = order.children.iter().map(|c| c.due_at).collect();
+        assert_eq!(due, vec![at(0), at(60), at(120)]);
+        assert!(order.children.iter().all(|c| c.quantity == tokens(30)));
+        assert_eq!(order.due_children(at(61)), vec![0, 1]);
+
+        // Units that don't divide evenly go to the last child
+        let order = ParentOrder::new(
+            "token",
+            OrderSide::Buy,
+            TokenAmount::new(10, 0),
+            Schedule::twap(60, 3),
+            "test",
+            at(0),
+        );
+        let children: Vec<_> = order.children.iter().map(|c| c.quantity.raw()).collect();
+        assert_eq!(children, vec![3, 3, 4]);
+    }
+
+    #[test]
+    fn test_fills_average_and_complete() {
+        let mut order = ParentOrder::new(
+            "token",
+            OrderSide::Buy,
+            tokens(20),
+            Schedule::dca(1.0, 2),
+            "test",
+            at(0),
+        );
+
+        order.record_fill(0, 1.0, "a".to_string(), at(0));
+        assert_eq!(order.status, ParentStatus::Active);
+        order.record_fill(1, 2.0, "b".to_string(), at(60));
+
+        assert_eq!(order.status, ParentStatus::Completed);
+        assert_eq!(order.filled_quantity().unwrap(), tokens(20));
+        assert_eq!(
+            order.average_price().unwrap(),
+            Some(Price::from_f64(1.5).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_failed_children_set_final_status() {
+        let new_order = || {
+            ParentOrder::new(
+                "token",
+                OrderSide::Buy,
+                tokens(20),
+                Schedule::dca(1.0, 2),
+                "test",
+                at(0),
+            )
+        };
+
+        let mut order = new_order();
+        order.record_failure(0, "no route".to_string(), at(0));
+        order.record_failure(1, "no route".to_string(), at(60));
+        assert_eq!(order.status, ParentStatus::Failed);
+        assert!(order.cancel(at(61)).is_err());
+
+        let mut order = new_order();
+        order.record_fill(0, 1.0, "a".to_string(), at(0));
+        order.record_failure(1, "no route".to_string(), at(60));
+        assert_eq!(order.status, ParentStatus::PartiallyFilled);
+        assert_eq!(order.filled_quantity().unwrap(), tokens(10));
+    }
+
+    #[test]
+    fn test_pause_and_cancel() {
+        let mut order = ParentOrder::new(
+            "token",
+            OrderSide::Buy,
+            tokens(20),
+            Schedule::dca(1.0, 2),
+            "test",
+            at(0),
+        );
+
+        order.pause(at(1)).unwrap();
+        assert!(order.due_children(at(120)).is_empty());
+        assert!(order.pause(at(2)).is_err());
+
+        // DCA entries that came due while paused go out on resume
+        order.resume(at(3)).unwrap();
+        assert_eq!(order.due_children(at(120)), vec![0, 1]);
+
+        order.cancel(at(4)).unwrap();
+        assert!(order
+            .children
+            .iter()
+            .all(|c| c.status == ChildStatus::Cancelled));
+        assert!(order.resume(at(5)).is_err());
+    }
+
+    #[test]
+    fn test_resumed_twap_spreads_remaining_slices() {
+        let mut order = ParentOrder::new(
+            "token",
+            OrderSide::Buy,
+            tokens(90),
+            Schedule::twap(3 * 60 * 60, 3),
+            "test",
+            at(0),
+        );
+        order.record_fill(0, 1.0, "a".to_string(), at(0));
+        order.pause(at(10)).unwrap();
+
+        order.resume(at(200)).unwrap();
+        let due: Vec<_> = order.children.iter().map(|c| c.due_at).collect();
+        assert_eq!(due, vec![at(0), at(200), at(260)]);
+        assert_eq!(order.due_children(at(201)), vec![1]);
+    }
+}
@@ -0,0 +1,267 @@
+//! Swap instructions from the Jupiter aggregator. Orders are quoted against
+//! the configured quote mint and built as legacy instructions, so
+//! `TransactionService` can price, sign and resend them like any other.
+
+use super::order::{OrderSide, TradeOrder};
+use crate::logging::metrics::timed;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use std::str::FromStr;
+
+pub const JUPITER_API_URL: &str = "https://lite-api.jup.ag/swap/v1";
+pub const USDC_MINT: &str = "EPjFWJ5xBqNd8tfDiHnDqxwTdvUfcTBjCNGNqWdDuy5V";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SwapInstructions {
+    #[serde(default)]
+    setup_instructions: Vec<JupiterInstruction>,
+    swap_instruction: JupiterInstruction,
+    cleanup_instruction: Option<JupiterInstruction>,
+    #[serde(default)]
+    other_instructions: Vec<JupiterInstruction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JupiterInstruction {
+    program_id: String,
+    accounts: Vec<JupiterAccount>,
+    data: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JupiterAccount {
+    pubkey: String,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+impl TryFrom<JupiterInstruction> for Instruction {
+    type Error = anyhow::Error;
+
+    fn try_from(ix: JupiterInstruction) -> Result<Self> {
+        let accounts = ix
+            .accounts
+            .into_iter()
+            .map(|account| {
+                let pubkey = Pubkey::from_str(&account.pubkey)
+                    .with_context(|| format!("Invalid account {}", account.pubkey))?;
+                Ok(if account.is_writable {
+                    AccountMeta::new(pubkey, account.is_signer)
+                } else {
+                    AccountMeta::new_readonly(pubkey, account.is_signer)
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Instruction {
+            program_id: Pubkey::from_str(&ix.program_id)
+                .with_context(|| format!("Invalid program {}", ix.program_id))?,
+            accounts,
+            data: STANDARD
+                .decode(&ix.data)
+                .context("Invalid instruction data")?,
+        })
+    }
+}
+
+/// Client of the Jupiter quote and swap-instructions API
+pub struct JupiterClient {
+    client: Client,
+    api_url: String,
+    quote_mint: String,
+}
+
+impl JupiterClient {
+    pub fn new(api_url: &str, quote_mint: &str) -> Self {
+        Self {
+            client: Client::new(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            quote_mint: quote_mint.to_string(),
+        }
+    }
+
+    /// Quote for filling `order`: buys receive exactly the order quantity
+    /// and sells spend exactly it, paying or receiving the quote mint, within
+    /// `max_slippage` (a fraction). The quantity must be in the mint's
+    /// decimals.
+    pub async fn quote(&self, order: &TradeOrder, max_slippage: f64) -> Result<SwapQuote> {
+        let amount = order.quantity.raw();
+        let (input_mint, output_mint, swap_mode) = match order.side {
+            OrderSide::Buy => (
+                self.quote_mint.as_str(),
+                order.token_address.as_str(),
+                "ExactOut",
+            ),
+            OrderSide::Sell => (
+                order.token_address.as_str(),
+                self.quote_mint.as_str(),
+                "ExactIn",
+            ),
+        };
+        let slippage_bps = (max_slippage * 10_000.0).round() as u32;
+
+        let response: Value = timed(
+            "jupiter",
+            "quote",
+            self.client
+                .get(format!("{}/quote", self.api_url))
+                .query(&[
+                    ("inputMint", input_mint),
+                    ("outputMint", output_mint),
+                    ("amount", &amount.to_string()),
+                    ("slippageBps", &slippage_bps.to_string()),
+                    ("swapMode", swap_mode),
+                    ("asLegacyTransaction", "true"),
+                ])
+                .send(),
+        )
+        .await
+        .context("Jupiter quote request failed")?
+        .error_for_status()
+        .context("Jupiter quote request failed")?
+        .json()
+        .await
+        .context("Invalid Jupiter quote")?;
+
+        SwapQuote::new(order.side, response)
+    }
+
+    /// Instructions that swap exactly `quote` from `wallet`. Jupiter's
+    /// compute-budget instructions are left out because `TransactionService`
+    /// sets its own.
+    pub async fn swap_instructions(
+        &self,
+        wallet: &Pubkey,
+        quote: &SwapQuote,
+    ) -> Result<Vec<Instruction>> {
+        let swap: SwapInstructions = timed(
+            "jupiter",
+            "swap-instructions",
+            self.client
+                .post(format!("{}/swap-instructions", self.api_url))
+                .json(&json!({
+                    "quoteResponse": quote.response,
+                    "userPublicKey": wallet.to_string(),
+                    "wrapAndUnwrapSol": true,
+                    "asLegacyTransaction": true,
+                }))
+                .send(),
+        )
+        .await
+        .context("Jupiter swap-instructions request failed")?
+        .error_for_status()
+        .context("Jupiter swap-instructions request failed")?
+        .json()
+        .await
+        .context("Invalid Jupiter swap instructions")?;
+
+        swap.into_instructions()
+    }
+}
+
+/// A Jupiter quote, kept whole so the swap is built from exactly it
+#[derive(Debug, Clone)]
+pub struct SwapQuote {
+    response: Value,
+    /// Token base units bought or sold
+    pub token_amount: u64,
+    /// Quote mint base units paid for a buy or received for a sell
+    pub quote_amount: u64,
+}
+
+impl SwapQuote {
+    fn new(side: OrderSide, response: Value) -> Result<Self> {
+        let amount = |key: &str| -> Result<u64> {
+            response[key]
+                .as_str()
+                .and_then(|amount| amount.parse().ok())
+                .with_context(|| format!("Jupiter quote has no {}", key))
+        };
+        let (in_amount, out_amount) = (amount("inAmount")?, amount("outAmount")?);
+        let (token_amount, quote_amount) = match side {
+            OrderSide::Buy => (out_amount, in_amount),
+            OrderSide::Sell => (in_amount, out_amount),
+        };
+        Ok(Self {
+            response,
+            token_amount,
+            quote_amount,
+        })
+    }
+}
+
+impl SwapInstructions {
+    fn into_instructions(self) -> Result<Vec<Instruction>> {
+        self.other_instructions
+            .into_iter()
+            .chain(self.setup_instructions)
+            .chain(std::iter::once(self.swap_instruction))
+            .chain(self.cleanup_instruction)
+            .map(Instruction::try_from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_amounts_follow_side() {
+        let response = json!({ "inAmount": "2500000", "outAmount": "1000000000" });
+        let buy = SwapQuote::new(OrderSide::Buy, response.clone()).unwrap();
+        assert_eq!(
+            (buy.token_amount, buy.quote_amount),
+            (1_000_000_000, 2_500_000)
+        );
+
+        let sell = SwapQuote::new(OrderSide::Sell, response).unwrap();
+        assert_eq!(
+            (sell.token_amount, sell.quote_amount),
+            (2_500_000, 1_000_000_000)
+        );
+
+        assert!(SwapQuote::new(OrderSide::Buy, json!({ "inAmount": "1" })).is_err());
+    }
+
+    #[test]
+    fn test_swap_instructions_skip_compute_budget() {
+        let program = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let ix = |data: &[u8]| {
+            json!({
+                "programId": program.to_string(),
+                "accounts": [{ "pubkey": account.to_string(), "isSigner": false, "isWritable": true }],
+                "data": STANDARD.encode(data),
+            })
+        };
+        let response = json!({
+            "computeBudgetInstructions": [ix(&[9])],
+            "setupInstructions": [ix(&[1])],
+            "swapInstruction": ix(&[2]),
+            "cleanupInstruction": ix(&[3]),
+            "otherInstructions": [],
+            "addressLookupTableAddresses": [],
+        });
+
+        let swap: SwapInstructions = serde_json::from_value(response).unwrap();
+        let instructions = swap.into_instructions().unwrap();
+
+        let data: Vec<_> = instructions.iter().map(|ix| ix.data.clone()).collect();
+        assert_eq!(data, vec![vec![1], vec![2], vec![3]]);
+        assert_eq!(instructions[0].program_id, program);
+        assert_eq!(
+            instructions[0].accounts,
+            vec![AccountMeta::new(account, false)]
+        );
+    }
+}
@@ -1,13 +1,37 @@
-use super::SolanaAgentKit;
+use super::order::{Execution, Fill, OrderSide, TradeOrder};
+use super::swap::{JupiterClient, SwapQuote};
+use super::transactions::TransactionService;
+use super::{mint_info, SolanaAgentKit};
+use crate::logging::metrics::metrics;
 use crate::models::market_signal::{MarketSignal, SignalType};
-use crate::utils::{decimal_to_f64, f64_to_decimal};
-use anyhow::Result;
+use crate::models::money::{Price, TokenAmount, UsdAmount};
+use anyhow::{anyhow, Context, Result};
+use bigdecimal::{BigDecimal, Zero};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::RwLock;
 use tracing::{info, warn};
 
 pub struct TradingEngine {
-    min_confidence: f64,
-    max_trade_size: f64,
+    min_confidence: RwLock<BigDecimal>,
+    max_trade_size: RwLock<UsdAmount>,
+    /// Slippage accepted on swaps, as a fraction
+    max_slippage: RwLock<f64>,
     agent: SolanaAgentKit,
+    swaps: JupiterClient,
+    transactions: Option<TransactionService>,
+    /// Mint decimals by token address, which never change
+    decimals: RwLock<HashMap<String, u8>>,
+}
+
+/// An executable quote for an order and the fill it promises
+#[derive(Debug, Clone)]
+pub struct OrderQuote {
+    /// The order in its mint's decimals
+    pub order: TradeOrder,
+    swap: SwapQuote,
+    pub fill: Fill,
 }
 
 #[derive(Debug)]
@@ -21,55 +45,360 @@ pub struct TradeDecision {
 }
 
 impl TradingEngine {
-    pub fn new(min_confidence: f64, max_trade_size: f64, agent: SolanaAgentKit) -> Self {
+    pub fn new(
+        min_confidence: BigDecimal,
+        max_trade_size: UsdAmount,
+        max_slippage: f64,
+        agent: SolanaAgentKit,
+    ) -> Self {
         Self {
-            min_confidence,
-            max_trade_size,
+            min_confidence: RwLock::new(min_confidence),
+            max_trade_size: RwLock::new(max_trade_size),
+            max_slippage: RwLock::new(max_slippage),
+            swaps: JupiterClient::new(&agent.config.jupiter_api_url, &agent.config.quote_mint),
             agent,
+            transactions: None,
+            decimals: RwLock::new(HashMap::new()),
         }
     }
 
-    pub async fn execute_trade(&self, signal: &MarketSignal) -> Result<String> {
-        let min_conf = f64_to_decimal(self.min_confidence);
+    /// Send on-chain orders through `transactions`
+    pub fn with_transactions(mut self, transactions: TransactionService) -> Self {
+        self.transactions = Some(transactions);
+        self
+    }
 
-        if signal.confidence < min_conf {
+    /// Order for a signal, or `None` when its confidence is too low or it
+    /// holds. Sells are sized like buys; the caller caps them at what is held.
+    pub async fn signal_order(&self, signal: &MarketSignal) -> Result<Option<TradeOrder>> {
+        if signal.confidence < self.get_min_confidence() {
             warn!("Signal confidence too low for trading");
-            return Ok("Signal confidence too low".to_string());
+            return Ok(None);
         }
+        let Some(side) = Self::order_side(signal) else {
+            return Ok(None);
+        };
 
-        let max_size = f64_to_decimal(self.max_trade_size);
-        let _amount = decimal_to_f64(&(max_size.clone() * signal.confidence.clone()).min(max_size));
+        Ok(Some(TradeOrder {
+            token_address: signal.asset_address.clone(),
+            side,
+            quantity: self.quantity(signal).await?,
+            reason: format!("{:?} signal", signal.signal_type),
+        }))
+    }
 
-        let action = match signal.signal_type {
+    /// Side a signal trades on, or `None` for a hold
+    pub fn order_side(signal: &MarketSignal) -> Option<OrderSide> {
+        match signal.signal_type {
             SignalType::Buy
             | SignalType::StrongBuy
             | SignalType::PriceSpike
-            | SignalType::VolumeSurge => "BUY",
-            SignalType::Sell | SignalType::StrongSell | SignalType::PriceDrop => "SELL",
-            SignalType::Hold => "HOLD",
+            | SignalType::VolumeSurge => Some(OrderSide::Buy),
+            SignalType::Sell | SignalType::StrongSell | SignalType::PriceDrop => {
+                Some(OrderSide::Sell)
+            }
+            SignalType::Hold => None,
+        }
+    }
+
+    /// Quote amount to trade on a signal: the max trade size scaled by confidence
+    pub fn trade_size(&self, signal: &MarketSignal) -> UsdAmount {
+        let max_trade_size = self.get_max_trade_size();
+        max_trade_size
+            .scale(&signal.confidence)
+            .min(max_trade_size.clone())
+    }
+
+    /// Tokens the trade size of a signal buys at its price, rounded down to
+    /// the mint's smallest unit. A zero or negative price is an error.
+    pub async fn quantity(&self, signal: &MarketSignal) -> Result<TokenAmount> {
+        let price = Price::new(signal.price.clone())?;
+        let decimals = self.token_decimals(&signal.asset_address).await?;
+        Ok(TokenAmount::from_decimal(
+            &self.trade_size(signal).quantity_at(&price)?,
+            decimals,
+        )?)
+    }
+
+    /// Decimals of a token's mint, fetched once per token
+    pub async fn token_decimals(&self, token_address: &str) -> Result<u8> {
+        let cached = self.decimals.read().unwrap().get(token_address).copied();
+        metrics().record_cache_lookup("mint_decimals", cached.is_some());
+        if let Some(decimals) = cached {
+            return Ok(decimals);
+        }
+        let mint = Pubkey::from_str(token_address)
+            .with_context(|| format!("Invalid token address: {}", token_address))?;
+        let (_, decimals) = mint_info(&self.agent.connection, &mint).await?;
+        self.decimals
+            .write()
+            .unwrap()
+            .insert(token_address.to_string(), decimals);
+        Ok(decimals)
+    }
+
+    /// Executable quote for `order` within `max_slippage` (a fraction)
+    pub async fn quote(&self, order: &TradeOrder, max_slippage: f64) -> Result<OrderQuote> {
+        // Positions stored before quantities were exact can carry other
+        // decimals than the mint
+        let decimals = self.token_decimals(&order.token_address).await?;
+        let order = TradeOrder {
+            quantity: TokenAmount::from_decimal(&order.quantity.to_decimal(), decimals)?,
+            ..order.clone()
         };
+        let swap = self.swaps.quote(&order, max_slippage).await?;
+        let quote_decimals = self.token_decimals(&self.agent.config.quote_mint).await?;
+        let fill = Fill::new(
+            TokenAmount::new(swap.token_amount, decimals),
+            &TokenAmount::new(swap.quote_amount, quote_decimals).to_decimal(),
+        )?;
+        Ok(OrderQuote { order, swap, fill })
+    }
 
+    /// Swap exactly `quote` through the transaction service and wait for
+    /// confirmation. The order is tracked in the trade ledger from pending
+    /// to executed, failed or unknown, and the fill is read from the
+    /// confirmed transaction. In dry-run mode the order is only logged and
+    /// fills as quoted.
+    pub async fn execute(&self, quote: &OrderQuote) -> Result<Execution> {
+        let order = &quote.order;
+        if self.agent.config.dry_run {
+            return Ok(Execution {
+                signature: Self::dry_run(order),
+                fill: Some(quote.fill.clone()),
+            });
+        }
+
+        let transactions = self.transactions()?;
         info!(
-            "Executing {} trade for {} with confidence {:.2}",
-            action,
-            signal.asset_address,
-            decimal_to_f64(&signal.confidence)
+            "Submitting {} order for {} {} at {} ({})",
+            order.side, order.quantity, order.token_address, quote.fill.price, order.reason
         );
+        let instructions = self
+            .swaps
+            .swap_instructions(&self.agent.wallet.address, &quote.swap)
+            .await?;
+        let outcome = transactions.execute(order, &instructions, &[]).await?;
+        let fill = match self.confirmed_fill(order, &outcome.signature).await {
+            Ok(fill) => fill,
+            Err(e) => {
+                warn!(
+                    "Failed to read the fill of {}, booking it as quoted: {}",
+                    outcome.signature, e
+                );
+                quote.fill.clone()
+            }
+        };
+        Ok(Execution {
+            signature: outcome.signature.to_string(),
+            fill: Some(fill),
+        })
+    }
 
-        // TODO: Implement actual Solana transaction execution
-        // For now, just return a mock signature
-        Ok(format!(
+    /// Quote `order` within the configured slippage and execute it. In
+    /// dry-run mode the order is only logged, without a quote or fill.
+    pub async fn submit_order(&self, order: &TradeOrder) -> Result<Execution> {
+        if self.agent.config.dry_run {
+            return Ok(Execution {
+                signature: Self::dry_run(order),
+                fill: None,
+            });
+        }
+        self.transactions()?;
+        let quote = self.quote(order, self.get_max_slippage()).await?;
+        self.execute(&quote).await
+    }
+
+    /// Log an order that isn't sent and return its mock signature
+    fn dry_run(order: &TradeOrder) -> String {
+        info!(
+            "Dry run: not sending {} order for {} {} ({})",
+            order.side, order.quantity, order.token_address, order.reason
+        );
+        format!(
             "mock_tx_{}_{}",
-            action.to_lowercase(),
-            signal.asset_address
-        ))
+            order.side.to_string().to_lowercase(),
+            order.token_address
+        )
     }
 
-    pub fn get_min_confidence(&self) -> f64 {
-        self.min_confidence
+    fn transactions(&self) -> Result<&TransactionService> {
+        self.transactions.as_ref().ok_or_else(|| {
+            anyhow!(
+                "No transaction service configured; set TRADE_DRY_RUN=true to trade without one"
+            )
+        })
     }
 
-    pub fn get_max_trade_size(&self) -> f64 {
-        self.max_trade_size
+    /// The tokens and quote mint the wallet swapped in a confirmed transaction
+    async fn confirmed_fill(&self, order: &TradeOrder, signature: &Signature) -> Result<Fill> {
+        let changes = self.transactions()?.balance_changes(signature).await?;
+        let change = |mint: &str| {
+            changes
+                .get(mint)
+                .map(|change| change.abs())
+                .filter(|change| !change.is_zero())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Transaction {} didn't change the {} balance",
+                        signature,
+                        mint
+                    )
+                })
+        };
+        let tokens = change(&order.token_address)?;
+        let quote = change(&self.agent.config.quote_mint)?;
+        Fill::new(
+            TokenAmount::from_decimal(&tokens, order.quantity.decimals())?,
+            &quote,
+        )
+    }
+
+    pub fn get_min_confidence(&self) -> BigDecimal {
+        self.min_confidence.read().unwrap().clone()
+    }
+
+    pub fn get_max_trade_size(&self) -> UsdAmount {
+        self.max_trade_size.read().unwrap().clone()
+    }
+
+    pub fn get_max_slippage(&self) -> f64 {
+        *self.max_slippage.read().unwrap()
+    }
+
+    /// Replace the limits checked by later trades
+    pub fn set_limits(
+        &self,
+        min_confidence: BigDecimal,
+        max_trade_size: UsdAmount,
+        max_slippage: f64,
+    ) {
+        *self.min_confidence.write().unwrap() = min_confidence;
+        *self.max_trade_size.write().unwrap() = max_trade_size;
+        *self.max_slippage.write().unwrap() = max_slippage;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SolanaConfig;
+    use crate::trading::swap::USDC_MINT;
+    use serde_json::{json, Value};
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_sdk::{signature::Keypair, signer::Signer};
+
+    fn engine(dry_run: bool) -> TradingEngine {
+        let agent = SolanaAgentKit::with_client(
+            RpcClient::new_mock("succeeds".to_string()),
+            Keypair::new(),
+            SolanaConfig {
+                dry_run,
+                ..Default::default()
+            },
+        );
+        TradingEngine::new(
+            BigDecimal::from(0),
+            UsdAmount::from_f64(100.0).unwrap(),
+            0.05,
+            agent,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_does_not_send() {
+        let order = TradeOrder::buy("token", TokenAmount::new(1_000_000_000, 9), "test");
+        let execution = engine(true).submit_order(&order).await.unwrap();
+        assert_eq!(execution.signature, "mock_tx_buy_token");
+        assert_eq!(execution.fill, None);
+    }
+
+    #[tokio::test]
+    async fn test_live_order_needs_transaction_service() {
+        let order = TradeOrder::buy("token", TokenAmount::new(1_000_000_000, 9), "test");
+        let err = engine(false).submit_order(&order).await.unwrap_err();
+        assert!(err.to_string().contains("TRADE_DRY_RUN"));
+    }
+
+    fn token_balance(index: u8, mint: &str, owner: &Pubkey, amount: u64, decimals: u8) -> Value {
+        json!({
+            "accountIndex": index,
+            "mint": mint,
+            "owner": owner.to_string(),
+            "uiTokenAmount": {
+                "amount": amount.to_string(),
+                "decimals": decimals,
+                "uiAmount": null,
+                "uiAmountString": "",
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fill_is_read_from_the_transaction() {
+        let keypair = Keypair::new();
+        let wallet = keypair.pubkey();
+        let other = Pubkey::new_unique();
+        // The wallet paid 24.5 USDC for 980 tokens; another owner's balance
+        // in the same mint is left out
+        let transaction = json!({
+            "slot": 2,
+            "blockTime": null,
+            "version": "legacy",
+            "transaction": {
+                "signatures": [Signature::default().to_string()],
+                "message": {
+                    "header": {
+                        "numRequiredSignatures": 1,
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 0,
+                    },
+                    "accountKeys": [wallet.to_string()],
+                    "recentBlockhash": Pubkey::default().to_string(),
+                    "instructions": [],
+                },
+            },
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [1_000_000_000],
+                "postBalances": [999_995_000],
+                "preTokenBalances": [
+                    token_balance(1, USDC_MINT, &wallet, 100_000_000, 6),
+                    token_balance(3, "token", &other, 5_000_000_000, 6),
+                ],
+                "postTokenBalances": [
+                    token_balance(1, USDC_MINT, &wallet, 75_500_000, 6),
+                    token_balance(2, "token", &wallet, 980_000_000, 6),
+                    token_balance(3, "token", &other, 4_020_000_000, 6),
+                ],
+            },
+        });
+        let agent = SolanaAgentKit::with_client(
+            RpcClient::new_mock_with_mocks(
+                "succeeds".to_string(),
+                [(RpcRequest::GetTransaction, transaction)].into(),
+            ),
+            keypair,
+            SolanaConfig::default(),
+        );
+        let transactions = agent.transactions();
+        let engine = TradingEngine::new(
+            BigDecimal::from(0),
+            UsdAmount::from_f64(100.0).unwrap(),
+            0.05,
+            agent,
+        )
+        .with_transactions(transactions);
+
+        let order = TradeOrder::buy("token", TokenAmount::new(1_000_000_000, 6), "test");
+        let fill = engine
+            .confirmed_fill(&order, &Signature::default())
+            .await
+            .unwrap();
+        assert_eq!(fill.quantity, TokenAmount::new(980_000_000, 6));
+        assert_eq!(fill.price, Price::from_f64(0.025).unwrap());
     }
 }
@@ -0,0 +1,722 @@
+//! Transaction lifecycle: priority fees, compute budget, resends on blockhash
+//! expiry and confirmation tracking, with the outcome recorded in the trade
+//! ledger.
+
+use super::order::{OrderSide, TradeOrder};
+use crate::config::mongodb::MongoDbPool;
+use crate::config::TransactionConfig;
+use crate::logging::metrics::timed;
+use crate::models::money::TokenAmount;
+use crate::storage::{mongo::MongoTradeRepository, TradeRepository};
+use anyhow::{anyhow, Context, Result};
+use bigdecimal::{num_bigint::BigInt, BigDecimal};
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// Wrapped SOL, which swaps may wrap and unwrap within the transaction
+pub const NATIVE_MINT: &str = "So11111111111111111111111111111111111111112";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradeStatus {
+    Pending,
+    Executed,
+    Failed,
+    /// A transaction was sent but its outcome was not observed, because
+    /// confirmation timed out or the RPC failed. Check its signature.
+    Unknown,
+}
+
+/// An order as recorded in the `trades` ledger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeRecord {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub token_address: String,
+    pub side: OrderSide,
+    pub quantity: TokenAmount,
+    pub reason: String,
+    pub status: TradeStatus,
+    /// Signature of the last transaction sent, or of the one that landed
+    pub signature: Option<String>,
+    pub slot: Option<u64>,
+    /// Transactions sent, including resends after blockhash expiry
+    pub attempts: u32,
+    /// Compute-unit price paid, in micro-lamports
+    pub priority_fee: Option<u64>,
+    pub error: Option<String>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl TradeRecord {
+    pub fn pending(order: &TradeOrder) -> Self {
+        let now = DateTime::now();
+        Self {
+            id: None,
+            token_address: order.token_address.clone(),
+            side: order.side,
+            quantity: order.quantity,
+            reason: order.reason.clone(),
+            status: TradeStatus::Pending,
+            signature: None,
+            slot: None,
+            attempts: 0,
+            priority_fee: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Note a transaction sent for the order, before waiting for it
+    pub fn mark_sent(&mut self, signature: &Signature, attempts: u32, priority_fee: u64) {
+        self.signature = Some(signature.to_string());
+        self.attempts = attempts;
+        self.priority_fee = Some(priority_fee);
+        self.updated_at = DateTime::now();
+    }
+
+    pub fn mark_executed(&mut self, outcome: &TxOutcome) {
+        self.status = TradeStatus::Executed;
+        self.signature = Some(outcome.signature.to_string());
+        self.slot = Some(outcome.slot);
+        self.attempts = outcome.attempts;
+        self.priority_fee = Some(outcome.priority_fee);
+        self.error = None;
+        self.updated_at = DateTime::now();
+    }
+
+    pub fn mark_failed(&mut self, error: impl Into<String>) {
+        self.status = TradeStatus::Failed;
+        self.error = Some(error.into());
+        self.updated_at = DateTime::now();
+    }
+
+    /// The sent transaction may still have landed; its signature is kept
+    pub fn mark_unknown(&mut self, error: impl Into<String>) {
+        self.status = TradeStatus::Unknown;
+        self.error = Some(error.into());
+        self.updated_at = DateTime::now();
+    }
+}
+
+pub struct TradeLedger {
+    trades: Arc<dyn TradeRepository>,
+}
+
+impl TradeLedger {
+    pub fn new(db_pool: Arc<MongoDbPool>) -> Self {
+        let db = db_pool.database(&db_pool.get_config().database);
+        Self::with_repository(Arc::new(MongoTradeRepository::new(&db)))
+    }
+
+    pub fn with_repository(trades: Arc<dyn TradeRepository>) -> Self {
+        Self { trades }
+    }
+
+    pub async fn insert(&self, record: &mut TradeRecord) -> Result<()> {
+        Ok(self.trades.insert(record).await?)
+    }
+
+    pub async fn save(&self, record: &TradeRecord) -> Result<()> {
+        Ok(self.trades.save(record).await?)
+    }
+
+    /// Trades with the given status, newest first
+    pub async fn with_status(&self, status: TradeStatus) -> Result<Vec<TradeRecord>> {
+        Ok(self.trades.with_status(status).await?)
+    }
+}
+
+/// A transaction that reached the configured commitment
+#[derive(Debug, Clone)]
+pub struct TxOutcome {
+    pub signature: Signature,
+    pub slot: u64,
+    pub attempts: u32,
+    pub priority_fee: u64,
+}
+
+enum Confirmation {
+    Confirmed(u64),
+    Failed(String),
+    /// The blockhash expired before the transaction landed, so it never will
+    Expired,
+}
+
+/// Why a transaction has no confirmed outcome
+enum SendError {
+    /// Nothing that was sent can still land
+    Failed(anyhow::Error),
+    /// A sent transaction may still land
+    Unknown(anyhow::Error),
+}
+
+impl From<SendError> for anyhow::Error {
+    fn from(e: SendError) -> Self {
+        match e {
+            SendError::Failed(e) | SendError::Unknown(e) => e,
+        }
+    }
+}
+
+/// Sends transactions paid by the wallet until they land or fail.
+///
+/// Each transaction gets compute budget instructions with a unit price taken
+/// from recent prioritization fees. A transaction whose blockhash expires
+/// unconfirmed is re-signed with a fresh blockhash and sent again, up to
+/// `max_resends` times. A transaction that lands with an error is not resent.
+pub struct TransactionService {
+    rpc: Arc<RpcClient>,
+    payer: Arc<Keypair>,
+    config: TransactionConfig,
+    ledger: Option<TradeLedger>,
+}
+
+impl TransactionService {
+    pub fn new(rpc: Arc<RpcClient>, payer: Arc<Keypair>, config: TransactionConfig) -> Self {
+        Self {
+            rpc,
+            payer,
+            config,
+            ledger: None,
+        }
+    }
+
+    /// Record orders sent through [`Self::execute`] in the trade ledger
+    pub fn with_ledger(mut self, ledger: TradeLedger) -> Self {
+        self.ledger = Some(ledger);
+        self
+    }
+
+    /// Compute-unit price at the configured percentile of the fees recently
+    /// paid to write-lock `accounts`, within the configured bounds
+    pub async fn priority_fee(&self, accounts: &[Pubkey]) -> Result<u64> {
+        let mut fees: Vec<u64> = timed(
+            "rpc",
+            "getRecentPrioritizationFees",
+            self.rpc.get_recent_prioritization_fees(accounts),
+        )
+        .await?
+        .into_iter()
+        .map(|f| f.prioritization_fee)
+        .collect();
+        fees.sort_unstable();
+
+        let fee = match fees.len() {
+            0 => 0,
+            n => {
+                let rank = (self.config.priority_fee_percentile * (n - 1) as f64).round();
+                fees[rank as usize]
+            }
+        };
+        Ok(fee.clamp(self.config.min_priority_fee, self.config.max_priority_fee))
+    }
+
+    pub fn budget_instructions(&self, priority_fee: u64) -> [Instruction; 2] {
+        [
+            ComputeBudgetInstruction::set_compute_unit_limit(self.config.compute_unit_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(priority_fee),
+        ]
+    }
+
+    /// Send `instructions` as one transaction and wait until it reaches the
+    /// configured commitment. `signers` are needed besides the wallet.
+    pub async fn send_and_confirm(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<TxOutcome> {
+        Ok(self.send(instructions, signers, None).await?)
+    }
+
+    /// `send_and_confirm`, noting every transaction sent in `record`
+    async fn send(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+        mut record: Option<&mut TradeRecord>,
+    ) -> std::result::Result<TxOutcome, SendError> {
+        let writable: Vec<Pubkey> = instructions
+            .iter()
+            .flat_map(|ix| ix.accounts.iter())
+            .filter(|meta| meta.is_writable)
+            .map(|meta| meta.pubkey)
+            .collect();
+        let priority_fee = match self.priority_fee(&writable).await {
+            Ok(fee) => fee,
+            Err(e) => {
+                warn!("Failed to estimate priority fee, using the minimum: {}", e);
+                self.config.min_priority_fee
+            }
+        };
+
+        let mut all_instructions = self.budget_instructions(priority_fee).to_vec();
+        all_instructions.extend_from_slice(instructions);
+        let mut all_signers: Vec<&Keypair> = vec![self.payer.as_ref()];
+        all_signers.extend_from_slice(signers);
+
+        let max_attempts = self.config.max_resends as u32 + 1;
+        let mut last_error = String::new();
+        for attempt in 1..=max_attempts {
+            let (blockhash, last_valid_block_height) = timed(
+                "rpc",
+                "getLatestBlockhash",
+                self.rpc
+                    .get_latest_blockhash_with_commitment(self.config.commitment),
+            )
+            .await
+            .map_err(|e| SendError::Failed(e.into()))?;
+            let transaction = Transaction::new_signed_with_payer(
+                &all_instructions,
+                Some(&self.payer.pubkey()),
+                &all_signers,
+                blockhash,
+            );
+
+            // Noted before sending: a send that errors, e.g. on an RPC
+            // timeout, may still have reached the leader
+            let signature = transaction.signatures[0];
+            if let Some(record) = record.as_deref_mut() {
+                record.mark_sent(&signature, attempt, priority_fee);
+                if let Some(ledger) = &self.ledger {
+                    if let Err(e) = ledger.save(record).await {
+                        warn!("Failed to record signature {}: {}", signature, e);
+                    }
+                }
+            }
+
+            let send_config = RpcSendTransactionConfig {
+                preflight_commitment: Some(self.config.commitment.commitment),
+                ..Default::default()
+            };
+            if let Err(e) = timed(
+                "rpc",
+                "sendTransaction",
+                self.rpc
+                    .send_transaction_with_config(&transaction, send_config),
+            )
+            .await
+            {
+                // A transaction rejected by preflight was never forwarded
+                if let Some(err) = e.get_transaction_error() {
+                    return Err(SendError::Failed(anyhow!(
+                        "Transaction {} failed preflight: {}",
+                        signature,
+                        err
+                    )));
+                }
+                warn!(
+                    "Attempt {}/{} failed to send {}, watching for it until its blockhash expires: {}",
+                    attempt, max_attempts, signature, e
+                );
+            }
+
+            match self
+                .await_confirmation(&signature, last_valid_block_height)
+                .await
+                .map_err(SendError::Unknown)?
+            {
+                Confirmation::Confirmed(slot) => {
+                    info!(
+                        "Transaction {} confirmed in slot {} after {} attempt(s)",
+                        signature, slot, attempt
+                    );
+                    return Ok(TxOutcome {
+                        signature,
+                        slot,
+                        attempts: attempt,
+                        priority_fee,
+                    });
+                }
+                Confirmation::Failed(err) => {
+                    return Err(SendError::Failed(anyhow!(
+                        "Transaction {} failed: {}",
+                        signature,
+                        err
+                    )));
+                }
+                Confirmation::Expired => {
+                    warn!(
+                        "Transaction {} expired unconfirmed, resending with a fresh blockhash",
+                        signature
+                    );
+                    last_error = format!("blockhash of {} expired", signature);
+                }
+            }
+        }
+
+        Err(SendError::Failed(anyhow!(
+            "Transaction not confirmed after {} attempt(s): {}",
+            max_attempts,
+            last_error
+        )))
+    }
+
+    /// Send an order's instructions and record the result in the trade
+    /// ledger: pending with the signature once sent, then executed or failed
+    /// with the error. A transaction that may still land is left `Unknown`.
+    pub async fn execute(
+        &self,
+        order: &TradeOrder,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<TxOutcome> {
+        let mut record = TradeRecord::pending(order);
+        if let Some(ledger) = &self.ledger {
+            ledger.insert(&mut record).await?;
+        }
+
+        let result = self.send(instructions, signers, Some(&mut record)).await;
+        match &result {
+            Ok(outcome) => record.mark_executed(outcome),
+            Err(SendError::Failed(e)) => record.mark_failed(e.to_string()),
+            Err(SendError::Unknown(e)) => record.mark_unknown(e.to_string()),
+        }
+        if let Some(ledger) = &self.ledger {
+            ledger.save(&record).await?;
+        }
+        Ok(result?)
+    }
+
+    /// How a landed transaction changed the wallet's balances, in whole
+    /// tokens by mint. Native SOL wrapped and unwrapped within the
+    /// transaction leaves no token balance, so it is read from the wallet's
+    /// lamports with the fee added back.
+    pub async fn balance_changes(
+        &self,
+        signature: &Signature,
+    ) -> Result<HashMap<String, BigDecimal>> {
+        let transaction = timed(
+            "rpc",
+            "getTransaction",
+            self.rpc.get_transaction_with_config(
+                signature,
+                RpcTransactionConfig {
+                    encoding: None,
+                    commitment: Some(self.config.commitment),
+                    max_supported_transaction_version: Some(0),
+                },
+            ),
+        )
+        .await?;
+        let meta = transaction
+            .transaction
+            .meta
+            .ok_or_else(|| anyhow!("Transaction {} has no status meta", signature))?;
+
+        let owner = self.payer.pubkey().to_string();
+        let mut changes: HashMap<String, BigDecimal> = HashMap::new();
+        for (balances, sign) in [(meta.pre_token_balances, -1), (meta.post_token_balances, 1)] {
+            for balance in Option::<Vec<_>>::from(balances).unwrap_or_default() {
+                if Option::<String>::from(balance.owner).as_deref() != Some(owner.as_str()) {
+                    continue;
+                }
+                let raw = BigInt::from_str(&balance.ui_token_amount.amount)
+                    .with_context(|| format!("Invalid token balance for {}", balance.mint))?;
+                let amount = BigDecimal::new(raw, balance.ui_token_amount.decimals.into());
+                *changes.entry(balance.mint).or_default() += amount * BigDecimal::from(sign);
+            }
+        }
+
+        if !changes.contains_key(NATIVE_MINT) {
+            // The wallet pays the fee and is the first account
+            if let (Some(pre), Some(post)) = (meta.pre_balances.first(), meta.post_balances.first())
+            {
+                let lamports = i128::from(*post) - i128::from(*pre) + i128::from(meta.fee);
+                if lamports != 0 {
+                    changes.insert(NATIVE_MINT.to_string(), BigDecimal::new(lamports.into(), 9));
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    async fn await_confirmation(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+    ) -> Result<Confirmation> {
+        let deadline = Instant::now() + self.config.attempt_timeout;
+        loop {
+            let status = timed(
+                "rpc",
+                "getSignatureStatuses",
+                self.rpc.get_signature_statuses(&[*signature]),
+            )
+            .await?
+            .value
+            .into_iter()
+            .next()
+            .flatten();
+
+            match status {
+                Some(status) => {
+                    if let Some(err) = status.err {
+                        return Ok(Confirmation::Failed(err.to_string()));
+                    }
+                    if status.satisfies_commitment(self.config.commitment) {
+                        return Ok(Confirmation::Confirmed(status.slot));
+                    }
+                }
+                None => {
+                    let height = timed(
+                        "rpc",
+                        "getBlockHeight",
+                        self.rpc
+                            .get_block_height_with_commitment(self.config.commitment),
+                    )
+                    .await?;
+                    if height > last_valid_block_height {
+                        return Ok(Confirmation::Expired);
+                    }
+                }
+            }
+
+            // The blockhash is still valid, so a resend could land twice
+            if Instant::now() >= deadline {
+                return Err(anyhow!(
+                    "Transaction {} not confirmed within {:?}; it may still land",
+                    signature,
+                    self.config.attempt_timeout
+                ));
+            }
+            sleep(self.config.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::MemoryTradeRepository;
+    use serde_json::json;
+    use solana_client::rpc_request::RpcRequest;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn config() -> TransactionConfig {
+        TransactionConfig {
+            poll_interval: Duration::from_millis(1),
+            ..Default::default()
+        }
+    }
+
+    fn service(rpc: RpcClient) -> TransactionService {
+        TransactionService::new(Arc::new(rpc), Arc::new(Keypair::new()), config())
+    }
+
+    fn transfer(payer: &Pubkey) -> Instruction {
+        solana_system_interface::instruction::transfer(payer, &Pubkey::new_unique(), 1_000)
+    }
+
+    #[tokio::test]
+    async fn test_priority_fee_percentile_and_bounds() {
+        let fees: Vec<_> = [5_000, 100, 2_000, 0, 1_000]
+            .iter()
+            .map(|fee| json!({ "slot": 1, "prioritizationFee": fee }))
+            .collect();
+        let mocks = HashMap::from([(RpcRequest::GetRecentPrioritizationFees, json!(fees))]);
+        let service = service(RpcClient::new_mock_with_mocks("succeeds".into(), mocks));
+        // 0.75 of five sorted fees is the fourth: 2000
+        assert_eq!(service.priority_fee(&[]).await.unwrap(), 2_000);
+
+        let mut service = service;
+        service.config.max_priority_fee = 1_500;
+        service.config.min_priority_fee = 1_500;
+        assert_eq!(service.priority_fee(&[]).await.unwrap(), 1_500);
+    }
+
+    #[tokio::test]
+    async fn test_confirms_with_budget_instructions() {
+        let service = service(RpcClient::new_mock("succeeds".into()));
+        let payer = service.payer.pubkey();
+
+        let outcome = service
+            .send_and_confirm(&[transfer(&payer)], &[])
+            .await
+            .unwrap();
+        assert_eq!(outcome.attempts, 1);
+        // The mock reports recent fees of 10_000 micro-lamports
+        assert_eq!(outcome.priority_fee, 10_000);
+
+        let budget = service.budget_instructions(outcome.priority_fee);
+        assert_eq!(
+            budget[1],
+            ComputeBudgetInstruction::set_compute_unit_price(10_000)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resends_after_blockhash_expiry() {
+        // The first signature is unknown once the chain is past the
+        // blockhash's last valid height (1234 in the mock)
+        let mocks = HashMap::from([
+            (
+                RpcRequest::GetSignatureStatuses,
+                json!({ "context": { "slot": 1 }, "value": [null] }),
+            ),
+            (RpcRequest::GetBlockHeight, json!(2_000)),
+        ]);
+        let service = service(RpcClient::new_mock_with_mocks("succeeds".into(), mocks));
+        let payer = service.payer.pubkey();
+
+        let outcome = service
+            .send_and_confirm(&[transfer(&payer)], &[])
+            .await
+            .unwrap();
+        assert_eq!(outcome.attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_transaction_is_not_resent() {
+        let service = service(RpcClient::new_mock("instruction_error".into()));
+        let payer = service.payer.pubkey();
+        let order = TradeOrder::buy("token", TokenAmount::new(1_000_000_000, 9), "test");
+
+        let err = service
+            .execute(&order, &[transfer(&payer)], &[])
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("requires an initialized account"), "{}", err);
+        assert!(!err.contains("attempt"));
+    }
+
+    #[tokio::test]
+    async fn test_unconfirmed_transaction_is_unknown() {
+        // Not seen on the first poll while the blockhash is still valid (last
+        // height 1234), and the attempt times out right after it
+        let mocks = HashMap::from([
+            (
+                RpcRequest::GetSignatureStatuses,
+                json!({ "context": { "slot": 1 }, "value": [null] }),
+            ),
+            (RpcRequest::GetBlockHeight, json!(100)),
+        ]);
+        let trades = Arc::new(MemoryTradeRepository::default());
+        let mut service = service(RpcClient::new_mock_with_mocks("succeeds".into(), mocks))
+            .with_ledger(TradeLedger::with_repository(trades.clone()));
+        service.config.attempt_timeout = Duration::ZERO;
+        let payer = service.payer.pubkey();
+
+        let order = TradeOrder::buy("token", TokenAmount::new(1_000_000_000, 9), "test");
+        let err = service
+            .execute(&order, &[transfer(&payer)], &[])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("may still land"), "{}", err);
+
+        let record = &trades.with_status(TradeStatus::Unknown).await.unwrap()[0];
+        assert!(record.signature.is_some());
+        assert_eq!(record.attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_send_error_is_not_a_failure() {
+        // The send errors, but the transaction reached the leader and lands
+        let mocks = HashMap::from([(RpcRequest::SendTransaction, json!(null))]);
+        let trades = Arc::new(MemoryTradeRepository::default());
+        let landed = service(RpcClient::new_mock_with_mocks("succeeds".into(), mocks))
+            .with_ledger(TradeLedger::with_repository(trades.clone()));
+        let payer = landed.payer.pubkey();
+        let order = TradeOrder::buy("token", TokenAmount::new(1_000_000_000, 9), "test");
+
+        let outcome = landed
+            .execute(&order, &[transfer(&payer)], &[])
+            .await
+            .unwrap();
+        assert_eq!(outcome.attempts, 1);
+        let record = &trades.with_status(TradeStatus::Executed).await.unwrap()[0];
+        assert_eq!(record.signature, Some(outcome.signature.to_string()));
+
+        // The send errors and the transaction isn't seen before the timeout
+        let mocks = HashMap::from([
+            (RpcRequest::SendTransaction, json!(null)),
+            (
+                RpcRequest::GetSignatureStatuses,
+                json!({ "context": { "slot": 1 }, "value": [null] }),
+            ),
+            (RpcRequest::GetBlockHeight, json!(100)),
+        ]);
+        let mut service = service(RpcClient::new_mock_with_mocks("succeeds".into(), mocks))
+            .with_ledger(TradeLedger::with_repository(trades.clone()));
+        service.config.attempt_timeout = Duration::ZERO;
+        let payer = service.payer.pubkey();
+
+        assert!(service
+            .execute(&order, &[transfer(&payer)], &[])
+            .await
+            .is_err());
+        let record = &trades.with_status(TradeStatus::Unknown).await.unwrap()[0];
+        assert!(record.signature.is_some());
+        assert!(trades
+            .with_status(TradeStatus::Failed)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_record_executed() {
+        let mut record = TradeRecord::pending(&TradeOrder::sell(
+            "token",
+            TokenAmount::new(2_000_000_000, 9),
+            "exit",
+        ));
+        assert_eq!(record.status, TradeStatus::Pending);
+
+        record.mark_executed(&TxOutcome {
+            signature: Signature::default(),
+            slot: 42,
+            attempts: 2,
+            priority_fee: 500,
+        });
+        assert_eq!(record.status, TradeStatus::Executed);
+        assert_eq!(record.slot, Some(42));
+        assert_eq!(record.attempts, 2);
+
+        record.mark_failed("blockhash expired");
+        assert_eq!(record.status, TradeStatus::Failed);
+        assert_eq!(record.error.as_deref(), Some("blockhash expired"));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs solana-test-validator listening on 127.0.0.1:8899"]
+    async fn test_transfer_on_local_validator() {
+        let rpc = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        let payer = Arc::new(Keypair::new());
+        let airdrop = rpc
+            .request_airdrop(&payer.pubkey(), 1_000_000_000)
+            .await
+            .unwrap();
+        while !rpc.confirm_transaction(&airdrop).await.unwrap() {
+            sleep(Duration::from_millis(200)).await;
+        }
+
+        let service = TransactionService::new(rpc.clone(), payer.clone(), config());
+        let recipient = Pubkey::new_unique();
+        let instruction =
+            solana_system_interface::instruction::transfer(&payer.pubkey(), &recipient, 5_000_000);
+        let outcome = service.send_and_confirm(&[instruction], &[]).await.unwrap();
+
+        assert_eq!(outcome.attempts, 1);
+        assert_eq!(rpc.get_balance(&recipient).await.unwrap(), 5_000_000);
+    }
+}
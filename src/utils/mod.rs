@@ -1,36 +1,55 @@
+use crate::error::{AgentError, AgentResult};
 use bigdecimal::FromPrimitive;
 use bigdecimal::{BigDecimal, ToPrimitive};
 
-/// Converts an f64 value to a BigDecimal. Returns 0 if the conversion fails.
-pub fn f64_to_decimal(value: f64) -> BigDecimal {
-    BigDecimal::from_f64(value).unwrap_or_else(|| BigDecimal::from(0))
+/// Converts an f64 value to a BigDecimal. NaN and infinities are errors.
+pub fn f64_to_decimal(value: f64) -> AgentResult<BigDecimal> {
+    if !value.is_finite() {
+        return Err(AgentError::Conversion(format!(
+            "{} has no decimal value",
+            value
+        )));
+    }
+    BigDecimal::from_f64(value)
+        .ok_or_else(|| AgentError::Conversion(format!("{} has no decimal value", value)))
 }
 
-/// Converts a reference to a BigDecimal to an f64. Returns 0.0 if the conversion fails.
-pub fn decimal_to_f64(value: &BigDecimal) -> f64 {
-    value.to_f64().unwrap_or(0.0)
+/// Converts a reference to a BigDecimal to an f64. Values out of the f64
+/// range are errors.
+pub fn decimal_to_f64(value: &BigDecimal) -> AgentResult<f64> {
+    value
+        .to_f64()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| AgentError::Conversion(format!("{} is out of the f64 range", value)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use bigdecimal::BigDecimal;
+    use std::str::FromStr;
 
     #[test]
     fn test_f64_to_decimal() {
-        assert_eq!(f64_to_decimal(1.0), BigDecimal::from(1));
-        assert_eq!(f64_to_decimal(0.0), BigDecimal::from(0));
-        assert_eq!(f64_to_decimal(3.14), BigDecimal::from_f64(3.14).unwrap());
+        assert_eq!(f64_to_decimal(1.0).unwrap(), BigDecimal::from(1));
+        assert_eq!(f64_to_decimal(0.0).unwrap(), BigDecimal::from(0));
+        assert_eq!(
+            f64_to_decimal(2.75).unwrap(),
+            BigDecimal::from_f64(2.75).unwrap()
+        );
+        assert!(f64_to_decimal(f64::NAN).is_err());
+        assert!(f64_to_decimal(f64::NEG_INFINITY).is_err());
     }
 
     #[test]
     fn test_decimal_to_f64() {
         let big_decimal_one = BigDecimal::from(1);
         let big_decimal_zero = BigDecimal::from(0);
-        let big_decimal_pi = BigDecimal::from_f64(3.14).unwrap();
+        let big_decimal_fraction = BigDecimal::from_f64(2.75).unwrap();
 
-        assert_eq!(decimal_to_f64(&big_decimal_one), 1.0);
-        assert_eq!(decimal_to_f64(&big_decimal_zero), 0.0);
-        assert_eq!(decimal_to_f64(&big_decimal_pi), 3.14);
+        assert_eq!(decimal_to_f64(&big_decimal_one).unwrap(), 1.0);
+        assert_eq!(decimal_to_f64(&big_decimal_zero).unwrap(), 0.0);
+        assert_eq!(decimal_to_f64(&big_decimal_fraction).unwrap(), 2.75);
+        assert!(decimal_to_f64(&BigDecimal::from_str("1e400").unwrap()).is_err());
     }
 }
@@ -1,150 +1,347 @@
-use anyhow::{Context, Result};
-use rig_core::vector_store::{Document, Store};
-use rig_mongodb::MongoStore;
-use std::sync::Arc;
-use tracing::{info, warn};
-use crate::config::mongodb::{MongoConfig, MongoDbPool};
-use serde_json;
-
-pub struct VectorStore {
-    store: Arc<MongoStore>,
+//! Semantic search over documents carrying an `embedding` field.
+//!
+//! Queries go through MongoDB Atlas `$vectorSearch` when the deployment
+//! supports it, with conditions on the index's filter fields applied inside
+//! the search. Otherwise the newest matching documents are scanned and
+//! ranked by cosine similarity in process.
+
+use crate::llm::embedding::EmbeddingBackend;
+use anyhow::{anyhow, Context, Result};
+use bson::{doc, Bson, Document};
+use futures::TryStreamExt;
+use mongodb::{error::ErrorKind, options::FindOptions, Collection};
+use serde::de::DeserializeOwned;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, info, warn};
+
+/// Field holding the vectors
+pub const EMBEDDING_FIELD: &str = "embedding";
+
+/// Atlas results fetched per requested result, to leave room for the
+/// post-search filter
+const OVERSAMPLE: usize = 10;
+
+/// Fields the Atlas index declares as filters, which `$vectorSearch` can
+/// apply before ranking
+pub const FILTER_FIELDS: [&str; 3] = ["token_address", "timestamp", "embedding_version"];
+
+/// Query operators `$vectorSearch` accepts in its pre-filter
+const FILTER_OPERATORS: [&str; 8] = ["$eq", "$ne", "$gt", "$gte", "$lt", "$lte", "$in", "$nin"];
+
+/// Atlas vector search index used when none is configured
+pub const DEFAULT_INDEX_NAME: &str = "vector_index";
+
+/// Documents scanned by the in-process fallback
+pub const DEFAULT_MAX_SCAN: usize = 5000;
+
+pub struct VectorStore<T: Send + Sync> {
+    collection: Collection<T>,
+    index_name: String,
+    max_scan: usize,
+    /// Cleared once the deployment rejects `$vectorSearch`
+    atlas: AtomicBool,
 }
 
-impl VectorStore {
-    pub async fn new() -> Result<Self> {
-        // Use centralized MongoDB configuration
-        let config = MongoConfig::from_env();
-        info!("Initializing vector store connection");
-        
-        let pool = MongoDbPool::create_pool(config.clone())
-            .await
-            .context("Failed to create MongoDB pool")?;
-            
-        // Configure vector store with optimized search parameters and fields
-        let fields = serde_json::json!({
-            "fields": [{
-                "path": "embedding",
-                "numDimensions": 1536,
-                "similarity": "cosine"
-            }]
-        });
-            
-        let store = MongoStore::new(
-            pool.client(), 
-            &config.database, 
-            "token_analytics",
-            fields
-        ).await
-            .context("Failed to create vector store")?;
-
-        Ok(Self {
-            store: Arc::new(store),
-        })
-    }
-
-    pub async fn insert_documents<T>(&self, documents: Vec<T>) -> Result<()> 
-    where
-        T: Send + Sync + 'static + serde::Serialize + Document,
-    {
-        info!("Inserting documents into vector store");
-        self.store.insert_documents(&documents)
-            .await
-            .context("Failed to insert documents into vector store")?;
-        Ok(())
-    }
-
-    pub async fn top_n<T>(&self, query: &str, limit: usize) -> Result<Vec<(f32, T)>>
-    where
-        T: Send + Sync + for<'de> serde::de::Deserialize<'de> + 'static,
-    {
-        if limit == 0 {
-            warn!("top_n called with limit=0, defaulting to 1");
-            let limit = 1;
+impl<T> VectorStore<T>
+where
+    T: DeserializeOwned + Send + Sync,
+{
+    pub fn new(collection: Collection<T>, index_name: &str) -> Self {
+        Self {
+            collection,
+            index_name: index_name.to_string(),
+            max_scan: DEFAULT_MAX_SCAN,
+            atlas: AtomicBool::new(true),
         }
-        
+    }
+
+    pub fn with_max_scan(mut self, max_scan: usize) -> Self {
+        self.max_scan = max_scan.max(1);
+        self
+    }
+
+    /// Documents most similar to `query`, embedded with `backend`, that match
+    /// `filter`, best first with their cosine similarity
+    pub async fn top_n(
+        &self,
+        backend: &dyn EmbeddingBackend,
+        query: &str,
+        limit: usize,
+        filter: Document,
+    ) -> Result<Vec<(f32, T)>> {
+        let vector = backend
+            .embed(&[query.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("No embedding returned for the query"))?;
+        self.top_n_by_vector(&vector, limit, filter).await
+    }
+
+    /// Documents most similar to `vector` that match `filter`, best first
+    pub async fn top_n_by_vector(
+        &self,
+        vector: &[f32],
+        limit: usize,
+        filter: Document,
+    ) -> Result<Vec<(f32, T)>> {
+        let limit = if limit == 0 {
+            warn!("top_n called with limit=0, defaulting to 1");
+            1
+        } else {
+            limit
+        };
+
         info!("Performing vector similarity search with limit {}", limit);
-        let results = self.store.search::<T>(query, limit)
-            .await
-            .context("Failed to perform vector similarity search")?;
-            
-        info!("Found {} matching documents", results.len());
+        if self.atlas.load(Ordering::Relaxed) {
+            let (prefilter, postfilter) = split_filter(&filter);
+            let has_postfilter = !postfilter.is_empty();
+            match self
+                .atlas_search(vector, limit, prefilter, postfilter)
+                .await
+            {
+                // The post-search filter may have dropped matches beyond the
+                // oversampled candidates
+                Ok(results) if results.len() < limit && has_postfilter => {
+                    debug!(
+                        "Atlas vector search returned {} of {} after filtering, scanning in process",
+                        results.len(),
+                        limit
+                    );
+                }
+                Ok(results) => return Ok(results),
+                Err(e) if is_search_unsupported(&e) => {
+                    warn!(
+                        "Atlas vector search unavailable, scanning in process: {}",
+                        e
+                    );
+                    self.atlas.store(false, Ordering::Relaxed);
+                }
+                Err(e) => return Err(e).context("Failed to perform vector similarity search"),
+            }
+        }
+        self.scan_search(vector, limit, filter).await
+    }
+
+    async fn atlas_search(
+        &self,
+        vector: &[f32],
+        limit: usize,
+        prefilter: Document,
+        postfilter: Document,
+    ) -> mongodb::error::Result<Vec<(f32, T)>> {
+        let candidates = if postfilter.is_empty() {
+            limit
+        } else {
+            limit * OVERSAMPLE
+        };
+        let mut search = doc! {
+            "index": &self.index_name,
+            "path": EMBEDDING_FIELD,
+            "queryVector": vector.iter().map(|v| Bson::Double(*v as f64)).collect::<Vec<_>>(),
+            "numCandidates": (candidates * 10).min(10_000) as i64,
+            "limit": candidates as i64,
+        };
+        if !prefilter.is_empty() {
+            search.insert("filter", prefilter);
+        }
+        let mut pipeline = vec![doc! { "$vectorSearch": search }];
+        if !postfilter.is_empty() {
+            pipeline.push(doc! { "$match": postfilter });
+            pipeline.push(doc! { "$limit": limit as i64 });
+        }
+        pipeline.push(doc! { "$addFields": { "_score": { "$meta": "vectorSearchScore" } } });
+        pipeline.push(doc! { "$project": { EMBEDDING_FIELD: 0 } });
+
+        let documents: Vec<Document> = self
+            .collection
+            .clone_with_type::<Document>()
+            .aggregate(pipeline)
+            .await?
+            .try_collect()
+            .await?;
+
+        let mut results = Vec::with_capacity(documents.len());
+        for mut document in documents {
+            let score = document
+                .remove("_score")
+                .and_then(|s| s.as_f64())
+                .unwrap_or(0.0);
+            results.push((score as f32, bson::from_document(document)?));
+        }
+        debug!("Atlas vector search returned {} documents", results.len());
         Ok(results)
     }
 
-    #[cfg(test)]
-    pub async fn cleanup_test_data(&self) -> Result<()> {
-        // Implement cleanup logic for MongoDB if necessary
-        Ok(())
+    async fn scan_search(
+        &self,
+        vector: &[f32],
+        limit: usize,
+        mut filter: Document,
+    ) -> Result<Vec<(f32, T)>> {
+        filter.insert(EMBEDDING_FIELD, doc! { "$exists": true });
+        let options = FindOptions::builder()
+            .sort(doc! { "_id": -1 })
+            .limit(self.max_scan as i64)
+            .build();
+        let documents: Vec<Document> = self
+            .collection
+            .clone_with_type::<Document>()
+            .find(filter)
+            .with_options(options)
+            .await?
+            .try_collect()
+            .await?;
+
+        let mut candidates = Vec::with_capacity(documents.len());
+        for mut document in documents {
+            let Some(embedding) = document.remove(EMBEDDING_FIELD).and_then(to_vector) else {
+                continue;
+            };
+            candidates.push((embedding, document));
+        }
+        debug!("Scanned {} embedded documents", candidates.len());
+
+        rank(vector, candidates, limit)
+            .into_iter()
+            .map(|(score, document)| Ok((score, bson::from_document(document)?)))
+            .collect()
+    }
+}
+
+/// Split `filter` into the conditions `$vectorSearch` can apply before
+/// ranking, on the index's filter fields with supported operators, and the
+/// rest, matched after the search
+fn split_filter(filter: &Document) -> (Document, Document) {
+    let mut prefilter = Document::new();
+    let mut postfilter = Document::new();
+    for (key, value) in filter {
+        let supported = FILTER_FIELDS.contains(&key.as_str())
+            && match value {
+                Bson::Document(ops) => ops.keys().all(|op| FILTER_OPERATORS.contains(&op.as_str())),
+                Bson::RegularExpression(_) => false,
+                _ => true,
+            };
+        if supported {
+            prefilter.insert(key, value.clone());
+        } else {
+            postfilter.insert(key, value.clone());
+        }
+    }
+    (prefilter, postfilter)
+}
+
+/// Whether the deployment lacks `$vectorSearch`, e.g. a local mongod
+fn is_search_unsupported(e: &mongodb::error::Error) -> bool {
+    match e.kind.as_ref() {
+        // Unrecognized pipeline stage, or search not enabled
+        ErrorKind::Command(c) => {
+            matches!(c.code, 40324 | 31082) || c.message.contains("$vectorSearch")
+        }
+        _ => false,
+    }
+}
+
+fn to_vector(value: Bson) -> Option<Vec<f32>> {
+    match value {
+        Bson::Array(values) => values
+            .iter()
+            .map(|v| match v {
+                Bson::Double(d) => Some(*d as f32),
+                Bson::Int32(i) => Some(*i as f32),
+                Bson::Int64(i) => Some(*i as f32),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Cosine similarity, 0 for empty, zero or mismatched vectors
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
     }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// The `limit` candidates most similar to `query`, best first
+pub fn rank<T>(query: &[f32], candidates: Vec<(Vec<f32>, T)>, limit: usize) -> Vec<(f32, T)> {
+    let mut scored: Vec<(f32, T)> = candidates
+        .into_iter()
+        .map(|(vector, item)| (cosine_similarity(query, &vector), item))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.truncate(limit);
+    scored
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde::{Deserialize, Serialize};
-    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-    #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
-    struct TestDocument {
-        id: String,
-        content: String,
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
     }
 
-    impl Document for TestDocument {
-        fn text(&self) -> &str {
-            &self.content
-        }
-    }
-
-    fn init_test_logging() {
-        let _ = tracing_subscriber::registry()
-            .with(tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info".into()))
-            .with(tracing_subscriber::fmt::layer())
-            .try_init();
-    }
-
-    #[tokio::test]
-    async fn test_vector_store() -> Result<()> {
-        init_test_logging();
-        dotenvy::dotenv().ok();
-        
-        let store = VectorStore::new()
-            .await
-            .context("Failed to create vector store")?;
-            
-        // Clean up any existing test data
-        store.cleanup_test_data()
-            .await
-            .context("Failed to cleanup existing test data")?;
-        
-        let docs = vec![
-            TestDocument {
-                id: "1".to_string(),
-                content: "Test document one".to_string(),
-            },
-            TestDocument {
-                id: "2".to_string(), 
-                content: "Test document two".to_string(),
-            },
+    #[test]
+    fn test_rank() {
+        let candidates = vec![
+            (vec![0.0, 1.0], "north"),
+            (vec![1.0, 0.1], "east"),
+            (vec![-1.0, 0.0], "west"),
         ];
+        let ranked = rank(&[1.0, 0.0], candidates, 2);
+        let names: Vec<_> = ranked.iter().map(|(_, n)| *n).collect();
+        assert_eq!(names, vec!["east", "north"]);
+    }
 
-        store.insert_documents(docs)
-            .await
-            .context("Failed to insert test documents")?;
-
-        let results = store.top_n::<TestDocument>("test document", 2)
-            .await
-            .context("Failed to perform similarity search")?;
-            
-        assert!(!results.is_empty(), "Expected non-empty search results");
-        assert_eq!(results.len(), 2, "Expected exactly 2 search results");
+    #[test]
+    fn test_split_filter() {
+        let since = bson::DateTime::from_millis(1_000);
+        let filter = doc! {
+            "token_address": { "$ne": "mint" },
+            "token_symbol": { "$regex": "^bonk$", "$options": "i" },
+            "timestamp": { "$gte": since },
+            "embedding_version": "v1",
+        };
+        let (prefilter, postfilter) = split_filter(&filter);
+        assert_eq!(
+            prefilter,
+            doc! {
+                "token_address": { "$ne": "mint" },
+                "timestamp": { "$gte": since },
+                "embedding_version": "v1",
+            }
+        );
+        assert_eq!(
+            postfilter,
+            doc! { "token_symbol": { "$regex": "^bonk$", "$options": "i" } }
+        );
 
-        // Clean up test data
-        store.cleanup_test_data()
-            .await
-            .context("Failed to cleanup test data")?;
+        // Unsupported operators stay after the search even on filter fields
+        let (prefilter, postfilter) = split_filter(&doc! { "token_address": { "$exists": true } });
+        assert!(prefilter.is_empty());
+        assert_eq!(postfilter.len(), 1);
+    }
 
-        Ok(())
+    #[test]
+    fn test_to_vector() {
+        let value = Bson::Array(vec![Bson::Double(0.5), Bson::Int32(1)]);
+        assert_eq!(to_vector(value), Some(vec![0.5, 1.0]));
+        assert_eq!(to_vector(Bson::Array(vec![Bson::String("x".into())])), None);
+        assert_eq!(to_vector(Bson::Null), None);
     }
 }
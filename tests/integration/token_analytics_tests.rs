@@ -74,7 +74,7 @@ mod tests {
             .fetch_and_store_token_info("SOL", "test_address")
             .await?;
         assert_eq!(analytics.token_symbol, "SOL");
-        assert_eq!(analytics.price, f64_to_decimal(100.0));
+        assert_eq!(analytics.price, f64_to_decimal(100.0).unwrap());
         Ok(())
     }
 
@@ -113,7 +113,7 @@ mod tests {
         let mut market_config = MarketConfig::default();
 
         // Set up config to generate invalid confidence
-        market_config.base_confidence = f64_to_decimal(2.0); // Will result in confidence > 1
+        market_config.base_confidence = f64_to_decimal(2.0).unwrap(); // Will result in confidence > 1
 
         let service =
             TokenAnalyticsService::new(db, birdeye, birdeye_extended, Some(market_config));
@@ -141,10 +141,10 @@ mod tests {
             token_address: "test_address".to_string(),
             token_name: "Test Token".to_string(),
             token_symbol: "TEST".to_string(),
-            price: f64_to_decimal(90.0), // Lower price to trigger price spike
-            volume_24h: Some(f64_to_decimal(500000.0)),
-            market_cap: Some(f64_to_decimal(1000000.0)),
-            total_supply: Some(f64_to_decimal(10000.0)),
+            price: f64_to_decimal(90.0).unwrap(), // Lower price to trigger price spike
+            volume_24h: Some(f64_to_decimal(500000.0).unwrap()),
+            market_cap: Some(f64_to_decimal(1000000.0).unwrap()),
+            total_supply: Some(f64_to_decimal(10000.0).unwrap()),
             holder_count: None,
             timestamp: Utc::now() - chrono::Duration::hours(1),
             created_at: None,
@@ -163,8 +163,8 @@ mod tests {
         assert!(signal.is_some());
         let signal = signal.unwrap();
         assert_eq!(signal.signal_type, SignalType::PriceSpike);
-        assert!(signal.confidence > f64_to_decimal(0.0));
-        assert!(signal.confidence <= f64_to_decimal(1.0));
+        assert!(signal.confidence > f64_to_decimal(0.0).unwrap());
+        assert!(signal.confidence <= f64_to_decimal(1.0).unwrap());
         Ok(())
     }
 
@@ -186,10 +186,10 @@ mod tests {
             token_address: "test_address".to_string(),
             token_name: "Test Token".to_string(),
             token_symbol: "TEST".to_string(),
-            price: f64_to_decimal(100.0),
-            volume_24h: Some(f64_to_decimal(1000000.0)),
-            market_cap: Some(f64_to_decimal(10000000.0)),
-            total_supply: Some(f64_to_decimal(100000.0)),
+            price: f64_to_decimal(100.0).unwrap(),
+            volume_24h: Some(f64_to_decimal(1000000.0).unwrap()),
+            market_cap: Some(f64_to_decimal(10000000.0).unwrap()),
+            total_supply: Some(f64_to_decimal(100000.0).unwrap()),
             holder_count: None,
             timestamp: Utc::now(),
             created_at: None,